@@ -0,0 +1,114 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Debug)]
+struct Pending {
+    event_type: String,
+    data: Value,
+    sent_at: f64,
+    attempts: u32,
+}
+
+/// 可靠投递：需要确认的事件带上 event_id，超时未确认时重新发出
+#[derive(Debug)]
+pub struct AckTracker {
+    pending: BTreeMap<i64, Pending>,
+    next_id: i64,
+}
+
+impl Default for AckTracker {
+    fn default() -> Self {
+        Self {
+            pending: BTreeMap::new(),
+            next_id: 1,
+        }
+    }
+}
+
+impl AckTracker {
+    /// 为事件分配 event_id 并开始等待确认，返回带 event_id、attempt 的 data
+    pub fn track(&mut self, event_type: &str, mut data: Value, now: f64) -> Value {
+        let event_id = self.next_id;
+        self.next_id += 1;
+        data["event_id"] = event_id.into();
+        data["attempt"] = 1.into();
+        self.pending.insert(
+            event_id,
+            Pending {
+                event_type: event_type.to_string(),
+                data: data.clone(),
+                sent_at: now,
+                attempts: 1,
+            },
+        );
+        data
+    }
+
+    /// 确认事件，事件不存在或已确认时返回 false
+    pub fn ack(&mut self, event_id: i64) -> bool {
+        self.pending.remove(&event_id).is_some()
+    }
+
+    /// 超过 timeout 秒未确认且发送次数少于 max_attempts 的事件，按 event_id 顺序返回
+    /// (event_type, data)，data 的 attempt 为本次是第几次发出
+    pub fn due(&mut self, now: f64, timeout: f64, max_attempts: u32) -> Vec<(String, Value)> {
+        self.pending
+            .values_mut()
+            .filter(|pending| now - pending.sent_at >= timeout && pending.attempts < max_attempts)
+            .map(|pending| {
+                pending.attempts += 1;
+                pending.sent_at = now;
+                pending.data["attempt"] = pending.attempts.into();
+                (pending.event_type.clone(), pending.data.clone())
+            })
+            .collect()
+    }
+
+    /// 所有未确认的事件，每项为事件 data 另加 event_type
+    pub fn unacked(&self) -> Vec<Value> {
+        self.pending
+            .values()
+            .map(|pending| {
+                let mut data = pending.data.clone();
+                data["event_type"] = pending.event_type.clone().into();
+                data
+            })
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn redelivers_until_acked() {
+        let mut acks = AckTracker::default();
+        let gift = acks.track("gift", json!({ "gift_name": "辣条" }), 0.0);
+        let sc = acks.track("super_chat", json!({ "message": "hi" }), 1.0);
+        assert_eq!(gift["event_id"], 1);
+        assert_eq!(sc["event_id"], 2);
+
+        assert!(acks.due(4.0, 5.0, 3).is_empty());
+        let due = acks.due(5.0, 5.0, 3);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].0, "gift");
+        assert_eq!(due[0].1["attempt"], 2);
+
+        assert!(acks.ack(2));
+        assert!(!acks.ack(2));
+        assert_eq!(acks.due(10.0, 5.0, 3)[0].1["attempt"], 3);
+        // 达到最大次数后不再发出，但仍可查询
+        assert!(acks.due(20.0, 5.0, 3).is_empty());
+        let unacked = acks.unacked();
+        assert_eq!(unacked.len(), 1);
+        assert_eq!(unacked[0]["event_type"], "gift");
+        assert!(acks.ack(1));
+        assert!(acks.unacked().is_empty());
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::HashSet;
+
+/// 公开接口（函数、信号、导出属性）的版本，新增或改名时加一；`get_api_version` 返回该值，
+/// 脚本可据此判断接口是否可用
+pub const API_VERSION: i64 = 1;
+
+/// 旧信号最多转发的参数个数，对应 `_forward_signal_alias_0` … `_forward_signal_alias_4`
+pub const MAX_ALIAS_ARGS: usize = 4;
+
+/// 改名后仍保留旧名的信号：新名发出时以相同参数转发到旧名
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SignalAlias {
+    pub old: &'static str,
+    pub new: &'static str,
+    /// 参数个数，不超过 `MAX_ALIAS_ARGS`
+    pub args: usize,
+    /// 从哪个 API 版本起弃用
+    pub since: i64,
+}
+
+/// 已弃用的信号名；改名时在此登记，至少保留到下一个大版本
+pub const SIGNAL_ALIASES: &[SignalAlias] = &[];
+
+pub fn alias(old: &str) -> Option<&'static SignalAlias> {
+    SIGNAL_ALIASES.iter().find(|alias| alias.old == old)
+}
+
+/// 每个弃用名称只提示一次
+#[derive(Debug, Default)]
+pub struct DeprecationLog {
+    warned: HashSet<String>,
+}
+
+impl DeprecationLog {
+    /// 第一次使用时返回提示文字，之后返回 None
+    pub fn first_use(&mut self, alias: &SignalAlias) -> Option<String> {
+        if !self.warned.insert(alias.old.to_string()) {
+            return None;
+        }
+        Some(format!(
+            "信号 {} 自 API 版本 {} 起已弃用，请改为连接 {}",
+            alias.old, alias.since, alias.new
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warns_once_per_alias() {
+        assert!(SIGNAL_ALIASES
+            .iter()
+            .all(|alias| alias.args <= MAX_ALIAS_ARGS && alias.since <= API_VERSION));
+        let renamed = SignalAlias {
+            old: "old_signal",
+            new: "new_signal",
+            args: 1,
+            since: 1,
+        };
+        let mut log = DeprecationLog::default();
+        assert_eq!(
+            log.first_use(&renamed).as_deref(),
+            Some("信号 old_signal 自 API 版本 1 起已弃用，请改为连接 new_signal")
+        );
+        assert_eq!(log.first_use(&renamed), None);
+        assert_eq!(alias("old_signal"), None);
+    }
+}
@@ -0,0 +1,333 @@
+use godot::classes::{Image, ImageTexture};
+use godot::prelude::*;
+use md5::{Digest, Md5};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 缓存文件的扩展名；写入时先写 `.tmp` 再改名，残留的临时文件在打开时删除
+const FILE_EXTENSION: &str = "img";
+const TEMP_EXTENSION: &str = "tmp";
+
+/// 由文件头判断图片格式，不是 Godot 能解码的格式时返回 None
+pub fn image_format(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("jpg")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("webp")
+    } else {
+        None
+    }
+}
+
+/// 把图片数据解码为纹理，返回纹理和解码后占用的字节数（按 RGBA8 估算）
+pub fn decode_texture(bytes: &[u8]) -> Result<(Gd<ImageTexture>, usize), String> {
+    let format = image_format(bytes).ok_or_else(|| "不支持的图片格式".to_string())?;
+    let buffer = PackedByteArray::from(bytes);
+    let mut image = Image::new_gd();
+    let error = match format {
+        "png" => image.load_png_from_buffer(&buffer),
+        "jpg" => image.load_jpg_from_buffer(&buffer),
+        _ => image.load_webp_from_buffer(&buffer),
+    };
+    if error != godot::global::Error::OK {
+        return Err(format!("图片解码失败: {:?}", error));
+    }
+    let size = image.get_width().max(0) as usize * image.get_height().max(0) as usize * 4;
+    let texture =
+        ImageTexture::create_from_image(&image).ok_or_else(|| "创建纹理失败".to_string())?;
+    Ok((texture, size))
+}
+
+/// 磁盘缓存的当前目录和缓存；dir 与打开时的目录不同时重新打开，打开失败时不使用磁盘缓存
+pub fn switch_dir(state: &mut (Option<PathBuf>, Option<DiskCache>), dir: Option<PathBuf>) {
+    if state.0 == dir {
+        return;
+    }
+    let cache = dir.as_deref().and_then(|dir| match DiskCache::open(dir) {
+        Ok((cache, removed)) => {
+            if removed > 0 {
+                godot_print!("资源缓存中删除了 {} 个无效文件", removed);
+            }
+            Some(cache)
+        }
+        Err(e) => {
+            godot_warn!("资源磁盘缓存不可用: {}", e);
+            None
+        }
+    });
+    *state = (dir, cache);
+}
+
+/// 按总大小淘汰最久未使用条目的内存缓存
+#[derive(Debug)]
+pub struct Lru<V> {
+    entries: HashMap<String, (V, usize, u64)>,
+    total: usize,
+    clock: u64,
+}
+
+impl<V> Default for Lru<V> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            total: 0,
+            clock: 0,
+        }
+    }
+}
+
+impl<V: Clone> Lru<V> {
+    /// 命中时同时标记为最近使用
+    pub fn get(&mut self, key: &str) -> Option<V> {
+        self.clock += 1;
+        let (value, _, used) = self.entries.get_mut(key)?;
+        *used = self.clock;
+        Some(value.clone())
+    }
+
+    /// 放入后淘汰最久未使用的条目直到总大小不超过 capacity（刚放入的条目除外），返回被淘汰的 key
+    pub fn insert(&mut self, key: &str, value: V, size: usize, capacity: usize) -> Vec<String> {
+        self.clock += 1;
+        if let Some((_, old_size, _)) = self
+            .entries
+            .insert(key.to_string(), (value, size, self.clock))
+        {
+            self.total -= old_size;
+        }
+        self.total += size;
+        self.shrink(capacity, Some(key))
+    }
+
+    pub fn shrink(&mut self, capacity: usize, keep: Option<&str>) -> Vec<String> {
+        let mut evicted = Vec::new();
+        while self.total > capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .filter(|(key, _)| Some(key.as_str()) != keep)
+                .min_by_key(|(_, (_, _, used))| *used)
+                .map(|(key, _)| key.clone());
+            let Some(oldest) = oldest else {
+                break;
+            };
+            if let Some((_, size, _)) = self.entries.remove(&oldest) {
+                self.total -= size;
+            }
+            evicted.push(oldest);
+        }
+        evicted
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.total = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn total_size(&self) -> usize {
+        self.total
+    }
+}
+
+/// 磁盘缓存：每个 URL 一个文件（文件名为 URL 的 MD5），按修改时间淘汰最久未使用的文件。
+/// 读取时更新修改时间，内容不是有效图片的文件在打开或读取时删除
+#[derive(Debug)]
+pub struct DiskCache {
+    dir: PathBuf,
+    /// 文件名 -> (大小, 最近使用时间)
+    files: HashMap<String, (u64, SystemTime)>,
+    total: u64,
+}
+
+impl DiskCache {
+    /// 打开（不存在时创建）缓存目录并按文件头校验已有文件，返回缓存和被删除的无效文件数
+    pub fn open(dir: &Path) -> Result<(Self, usize), String> {
+        fs::create_dir_all(dir).map_err(|e| format!("创建缓存目录失败: {}", e))?;
+        let mut cache = Self {
+            dir: dir.to_path_buf(),
+            files: HashMap::new(),
+            total: 0,
+        };
+        let mut removed = 0;
+        let entries = fs::read_dir(dir).map_err(|e| format!("读取缓存目录失败: {}", e))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if !metadata.is_file() {
+                continue;
+            }
+            let cached = path.extension().is_some_and(|ext| ext == FILE_EXTENSION);
+            let valid = cached && Self::has_image_header(&path);
+            if !valid {
+                if cached || path.extension().is_some_and(|ext| ext == TEMP_EXTENSION) {
+                    let _ = fs::remove_file(&path);
+                    removed += 1;
+                }
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let used = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            cache.total += metadata.len();
+            cache.files.insert(name, (metadata.len(), used));
+        }
+        Ok((cache, removed))
+    }
+
+    fn file_name(url: &str) -> String {
+        format!("{:x}.{}", Md5::digest(url.as_bytes()), FILE_EXTENSION)
+    }
+
+    fn has_image_header(path: &Path) -> bool {
+        let mut header = [0u8; 12];
+        fs::File::open(path)
+            .and_then(|mut file| file.read_exact(&mut header))
+            .is_ok_and(|_| image_format(&header).is_some())
+    }
+
+    fn read_valid(path: &Path) -> Option<Vec<u8>> {
+        let bytes = fs::read(path).ok()?;
+        image_format(&bytes)?;
+        Some(bytes)
+    }
+
+    /// 命中时更新最近使用时间；文件已损坏时删除并返回 None
+    pub fn read(&mut self, url: &str) -> Option<Vec<u8>> {
+        let name = Self::file_name(url);
+        if !self.files.contains_key(&name) {
+            return None;
+        }
+        let path = self.dir.join(&name);
+        let Some(bytes) = Self::read_valid(&path) else {
+            self.remove(&name);
+            return None;
+        };
+        let now = SystemTime::now();
+        if let Ok(file) = fs::File::options().append(true).open(&path) {
+            let _ = file.set_modified(now);
+        }
+        if let Some((_, used)) = self.files.get_mut(&name) {
+            *used = now;
+        }
+        Some(bytes)
+    }
+
+    /// 写入后淘汰最久未使用的文件直到总大小不超过 max_bytes，返回淘汰的文件数
+    pub fn write(&mut self, url: &str, bytes: &[u8], max_bytes: u64) -> Result<usize, String> {
+        let name = Self::file_name(url);
+        let path = self.dir.join(&name);
+        let temp = path.with_extension(TEMP_EXTENSION);
+        fs::write(&temp, bytes)
+            .and_then(|_| fs::rename(&temp, &path))
+            .map_err(|e| {
+                let _ = fs::remove_file(&temp);
+                format!("写入缓存文件失败: {}", e)
+            })?;
+        if let Some((old_size, _)) = self
+            .files
+            .insert(name, (bytes.len() as u64, SystemTime::now()))
+        {
+            self.total -= old_size;
+        }
+        self.total += bytes.len() as u64;
+        Ok(self.shrink(max_bytes))
+    }
+
+    pub fn shrink(&mut self, max_bytes: u64) -> usize {
+        let mut by_age: Vec<(String, SystemTime)> = self
+            .files
+            .iter()
+            .map(|(name, (_, used))| (name.clone(), *used))
+            .collect();
+        by_age.sort_by_key(|(_, used)| *used);
+        let mut evicted = 0;
+        for (name, _) in by_age {
+            if self.total <= max_bytes {
+                break;
+            }
+            self.remove(&name);
+            evicted += 1;
+        }
+        evicted
+    }
+
+    fn remove(&mut self, name: &str) {
+        if let Some((size, _)) = self.files.remove(name) {
+            self.total -= size;
+        }
+        let _ = fs::remove_file(self.dir.join(name));
+    }
+
+    pub fn clear(&mut self) {
+        let names: Vec<String> = self.files.keys().cloned().collect();
+        for name in names {
+            self.remove(&name);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n0000IHDR";
+
+    #[test]
+    fn evicts_least_recently_used_by_size() {
+        let mut lru = Lru::default();
+        assert!(lru.insert("a", 1, 40, 100).is_empty());
+        assert!(lru.insert("b", 2, 40, 100).is_empty());
+        assert_eq!(lru.get("a"), Some(1));
+        assert_eq!(lru.insert("c", 3, 40, 100), vec!["b".to_string()]);
+        assert_eq!((lru.len(), lru.total_size()), (2, 80));
+        // 单个条目超过上限时仍保留刚放入的条目
+        assert_eq!(lru.insert("d", 4, 500, 100).len(), 2);
+        assert_eq!(lru.get("d"), Some(4));
+        lru.clear();
+        assert_eq!((lru.len(), lru.total_size()), (0, 0));
+    }
+
+    #[test]
+    fn validates_and_evicts_disk_files() {
+        let dir = std::env::temp_dir().join(format!("gdblive_assets_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let (mut cache, removed) = DiskCache::open(&dir).unwrap();
+        assert_eq!(removed, 0);
+        assert_eq!(cache.write("https://a/1.png", PNG, 1000), Ok(0));
+        assert_eq!(cache.read("https://a/1.png").as_deref(), Some(PNG));
+        assert_eq!(cache.read("https://a/2.png"), None);
+
+        // 损坏的缓存文件和残留的临时文件在重新打开时删除
+        fs::write(dir.join(DiskCache::file_name("https://a/2.png")), b"<html>").unwrap();
+        fs::write(dir.join("partial.tmp"), PNG).unwrap();
+        let (mut cache, removed) = DiskCache::open(&dir).unwrap();
+        assert_eq!(removed, 2);
+        assert_eq!((cache.len(), cache.total_size()), (1, PNG.len() as u64));
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert_eq!(cache.write("https://a/3.png", PNG, PNG.len() as u64), Ok(1));
+        assert_eq!(cache.read("https://a/1.png"), None);
+        assert!(cache.read("https://a/3.png").is_some());
+        cache.clear();
+        assert_eq!(cache.len(), 0);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
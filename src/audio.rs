@@ -0,0 +1,210 @@
+use crate::convert::dictionary_to_json;
+use crate::triggers::{select_trigger, TriggerRule};
+use godot::classes::{AudioStream, AudioStreamPlayer, IResource, Resource};
+use godot::prelude::*;
+use std::collections::HashMap;
+
+/// 一条事件音效：事件满足条件时播放 stream
+#[derive(GodotClass)]
+#[class(base=Resource)]
+pub struct AudioTrigger {
+    base: Base<Resource>,
+
+    /// danmaku / gift / super_chat / guard / like
+    #[export]
+    event_type: GString,
+    /// 只匹配该礼物名，留空表示任意礼物
+    #[export]
+    gift_name: GString,
+    /// 事件总价（千分之一元）达到该值才播放
+    #[export]
+    min_price: i64,
+    #[export]
+    stream: Option<Gd<AudioStream>>,
+    #[export]
+    volume_db: f64,
+    /// 同一条音效两次播放的最短间隔（秒）
+    #[export]
+    cooldown: f64,
+}
+
+#[godot_api]
+impl IResource for AudioTrigger {
+    fn init(base: Base<Resource>) -> Self {
+        Self {
+            base,
+            event_type: GString::from("gift"),
+            gift_name: GString::new(),
+            min_price: 0,
+            stream: None,
+            volume_db: 0.0,
+            cooldown: 0.0,
+        }
+    }
+}
+
+impl AudioTrigger {
+    fn rule(&self) -> TriggerRule {
+        TriggerRule {
+            event_type: self.event_type.to_string(),
+            gift_name: self.gift_name.to_string(),
+            min_price: self.min_price,
+            cooldown: self.cooldown,
+        }
+    }
+}
+
+/// 事件音效表，可保存为 .tres 在多个场景间共用
+#[derive(GodotClass)]
+#[class(base=Resource)]
+pub struct AudioTriggerMap {
+    base: Base<Resource>,
+
+    #[export]
+    triggers: Array<Gd<AudioTrigger>>,
+}
+
+#[godot_api]
+impl IResource for AudioTriggerMap {
+    fn init(base: Base<Resource>) -> Self {
+        Self {
+            base,
+            triggers: Array::new(),
+        }
+    }
+}
+
+/// 按 AudioTriggerMap 播放事件音效，限制每条音效的冷却时间和同时播放的数量
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct AudioAlertPlayer {
+    base: Base<Node>,
+
+    /// 要监听 `live_event` 的 Blive 节点，留空时可手动调用 `handle_live_event`
+    #[export]
+    blive_path: NodePath,
+    #[export]
+    trigger_map: Option<Gd<AudioTriggerMap>>,
+    /// 同时播放的音效上限，达到上限时新事件不再播放
+    #[export]
+    max_concurrent: i64,
+    #[export]
+    bus: StringName,
+
+    players: Vec<Gd<AudioStreamPlayer>>,
+    last_played: HashMap<usize, f64>,
+    elapsed: f64,
+}
+
+#[godot_api]
+impl INode for AudioAlertPlayer {
+    fn init(base: Base<Node>) -> Self {
+        Self {
+            base,
+            blive_path: NodePath::default(),
+            trigger_map: None,
+            max_concurrent: 3,
+            bus: StringName::from("Master"),
+            players: Vec::new(),
+            last_played: HashMap::new(),
+            elapsed: 0.0,
+        }
+    }
+
+    fn ready(&mut self) {
+        if self.blive_path.is_empty() {
+            return;
+        }
+        let path = self.blive_path.clone();
+        let Some(mut blive) = self.base().get_node_or_null(&path) else {
+            godot_warn!("AudioAlertPlayer: 找不到节点 {}", path);
+            return;
+        };
+        let callable = Callable::from_object_method(&self.to_gd(), "handle_live_event");
+        blive.connect("live_event", &callable);
+    }
+
+    fn process(&mut self, delta: f64) {
+        self.elapsed += delta;
+    }
+}
+
+#[godot_api]
+impl AudioAlertPlayer {
+    #[signal]
+    fn audio_triggered(event_type: GString, trigger_index: i64);
+
+    /// 根据事件播放音效，返回是否播放
+    #[func]
+    fn handle_live_event(&mut self, event_type: GString, data: Dictionary) -> bool {
+        let Some(map) = self.trigger_map.clone() else {
+            return false;
+        };
+        let triggers = map.bind().triggers.clone();
+        let rules: Vec<TriggerRule> = triggers.iter_shared().map(|t| t.bind().rule()).collect();
+        let event = event_type.to_string();
+        let Some(index) = select_trigger(
+            &rules,
+            &event,
+            &dictionary_to_json(&data),
+            self.elapsed,
+            &self.last_played,
+        ) else {
+            return false;
+        };
+
+        self.remove_finished_players();
+        if self.players.len() as i64 >= self.max_concurrent {
+            return false;
+        }
+        let Some(trigger) = triggers.get(index) else {
+            return false;
+        };
+        let (stream, volume_db) = {
+            let trigger = trigger.bind();
+            (trigger.stream.clone(), trigger.volume_db)
+        };
+        let Some(stream) = stream else {
+            return false;
+        };
+
+        let mut player = AudioStreamPlayer::new_alloc();
+        player.set_stream(&stream);
+        player.set_volume_db(volume_db as f32);
+        player.set_bus(&self.bus);
+        self.base_mut().add_child(&player);
+        player.play();
+        self.players.push(player);
+        self.last_played.insert(index, self.elapsed);
+
+        self.base_mut().emit_signal(
+            "audio_triggered",
+            &[event.to_variant(), (index as i64).to_variant()],
+        );
+        true
+    }
+
+    #[func]
+    fn stop_all_audio(&mut self) {
+        for mut player in self.players.drain(..) {
+            player.stop();
+            player.queue_free();
+        }
+    }
+
+    #[func]
+    fn get_playing_count(&mut self) -> i64 {
+        self.remove_finished_players();
+        self.players.len() as i64
+    }
+
+    fn remove_finished_players(&mut self) {
+        self.players.retain_mut(|player| {
+            if player.is_playing() {
+                return true;
+            }
+            player.queue_free();
+            false
+        });
+    }
+}
@@ -0,0 +1,158 @@
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// 链首记录的 prev_hash
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// 付费事件审计日志：每行一条 JSON 记录，hash = sha256(prev_hash + 记录去掉 hash 字段后的 JSON)，
+/// 任何一行被修改、删除或插入都会让之后的校验失败
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    file: File,
+    last_hash: String,
+    next_seq: u64,
+}
+
+fn record_hash(prev_hash: &str, record: &Value) -> String {
+    // serde_json 的对象按键排序，同一条记录总是序列化为相同的文本
+    let digest = Sha256::digest(format!("{}{}", prev_hash, record));
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl AuditLog {
+    /// 打开（或创建）日志文件，接在已有记录之后继续写；已有记录校验失败时返回错误，不再追加
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let (entries, last_hash) = if path.exists() {
+            verify(path)?
+        } else {
+            (0, GENESIS_HASH.to_string())
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("打开审计日志失败: {}", e))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            last_hash,
+            next_seq: entries as u64 + 1,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 追加一条记录：entry 的字段加上 seq、time_ms、prev_hash 和 hash
+    pub fn append(&mut self, mut entry: Value, time_ms: i64) -> Result<(), String> {
+        entry["seq"] = self.next_seq.into();
+        entry["time_ms"] = time_ms.into();
+        entry["prev_hash"] = self.last_hash.clone().into();
+        let hash = record_hash(&self.last_hash, &entry);
+        entry["hash"] = hash.clone().into();
+        writeln!(self.file, "{}", entry)
+            .and_then(|_| self.file.flush())
+            .map_err(|e| format!("写入审计日志失败: {}", e))?;
+        self.last_hash = hash;
+        self.next_seq += 1;
+        Ok(())
+    }
+}
+
+/// 付费事件的审计记录：event_type、事件 data 和场次信息
+pub fn paid_event_entry(event_type: &str, data: &Value, session: &Value) -> Value {
+    json!({
+        "kind": "paid_event",
+        "event_type": event_type,
+        "session": session,
+        "data": data,
+    })
+}
+
+/// 是否为付费事件：付费礼物（paid 为 true）、醒目留言和上舰
+pub fn is_paid_event(event_type: &str, data: &Value) -> bool {
+    match event_type {
+        crate::events::EVENT_GIFT => data["paid"].as_bool() == Some(true),
+        crate::events::EVENT_SUPER_CHAT | crate::events::EVENT_GUARD => true,
+        _ => false,
+    }
+}
+
+/// 校验整条哈希链，返回 (记录数, 最后一条的 hash)；失败时指出第几行
+pub fn verify(path: &Path) -> Result<(usize, String), String> {
+    let file = File::open(path).map_err(|e| format!("打开审计日志失败: {}", e))?;
+    let mut prev_hash = GENESIS_HASH.to_string();
+    let mut entries = 0;
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.map_err(|e| format!("读取审计日志失败: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut record: Value = serde_json::from_str(&line)
+            .map_err(|e| format!("第 {} 行不是有效的 JSON: {}", line_number, e))?;
+        let hash = record["hash"].as_str().unwrap_or_default().to_string();
+        if let Some(object) = record.as_object_mut() {
+            object.remove("hash");
+        }
+        if record["prev_hash"].as_str() != Some(prev_hash.as_str())
+            || record["seq"].as_u64() != Some(entries as u64 + 1)
+            || record_hash(&prev_hash, &record) != hash
+        {
+            return Err(format!(
+                "第 {} 行校验失败，记录可能被修改或删除",
+                line_number
+            ));
+        }
+        prev_hash = hash;
+        entries += 1;
+    }
+    Ok((entries, prev_hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chains_and_detects_tampering() {
+        let path = std::env::temp_dir().join(format!("gdblive_audit_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let session = json!({ "game_id": "g1", "app_id": 1 });
+        let gift = json!({ "gift_name": "辣条", "price": 100, "paid": true });
+
+        let mut log = AuditLog::open(&path).unwrap();
+        log.append(paid_event_entry("gift", &gift, &session), 1_000)
+            .unwrap();
+        log.append(paid_event_entry("guard", &json!({}), &session), 2_000)
+            .unwrap();
+        drop(log);
+        // 重新打开后接着原来的链写
+        let mut log = AuditLog::open(&path).unwrap();
+        log.append(paid_event_entry("super_chat", &json!({}), &session), 3_000)
+            .unwrap();
+        assert_eq!(verify(&path).unwrap().0, 3);
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, text.replace(r#""price":100"#, r#""price":1"#)).unwrap();
+        assert_eq!(
+            verify(&path).unwrap_err(),
+            "第 1 行校验失败，记录可能被修改或删除"
+        );
+        assert!(AuditLog::open(&path).is_err());
+
+        let lines: Vec<&str> = text.lines().collect();
+        std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        assert!(verify(&path).unwrap_err().starts_with("第 2 行"));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(is_paid_event("gift", &gift));
+        assert!(!is_paid_event("gift", &json!({ "paid": false })));
+        assert!(is_paid_event("guard", &json!({})));
+    }
+}
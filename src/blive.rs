@@ -0,0 +1,5397 @@
+use crate::ack::AckTracker;
+use crate::api::{self, DeprecationLog};
+use crate::asset_cache::{self, DiskCache, Lru};
+use crate::audit::{self, AuditLog};
+use crate::channel;
+use crate::clock::ClockOffset;
+use crate::combo::ComboTracker;
+use crate::conn_history::{self, ConnectionAttempt, ConnectionHistory};
+use crate::consensus::ConsensusDetector;
+use crate::convert::{dictionary_to_json, globalize_path, json_to_dictionary, json_to_variant};
+use crate::degradation::{Change, DegradationPolicy, MessageDigest, Quality};
+use crate::diagnostics::{self, DiagnosticReport};
+use crate::direct::{self, DirectCredentials};
+use crate::error::{self, BliveError};
+use crate::events::{self, Audience, Interaction};
+use crate::first_seen::{self, FirstInteractions};
+use crate::gating::{InteractionGate, Viewer};
+use crate::gifts::GiftCatalog;
+use crate::handoff::SessionHandle;
+use crate::heartbeat_health::HeartbeatHealth;
+use crate::http_meta::HttpMeta;
+use crate::language::{self, LanguageRoute};
+use crate::load_test::{self, GeneratorCounters, LoadTest, Step};
+use crate::login::{self, PollStatus};
+use crate::parse_pool::ParsePool;
+use crate::protocol::{DecodeError, Protocol};
+use crate::rate_limit::{SharedLimiter, MAX_QUEUE_WAIT_SECS};
+use crate::recorder::{self, RecorderOptions, SessionRecorder};
+use crate::scheduler::EventScheduler;
+use crate::sequence::SequenceTracker;
+use crate::session::{self, SessionLifecycle, StartInfo, Transition};
+use crate::shared::{SharedAttachment, SharedSession};
+use crate::signing;
+use crate::spam::SpamCollapser;
+use crate::subsystems::{self, Subsystems};
+use crate::superchat::SuperChatTimers;
+use crate::tls::{self, TlsOptions};
+use crate::traffic::{RateWindow, TrafficSnapshot, TrafficStats};
+use crate::translation::{self, Prepared, TranslationPipeline};
+use crate::webhook::{self, Webhook};
+use crate::ws_stop::{self, StopSignal, Stopper};
+use futures_util::{SinkExt, StreamExt};
+use godot::classes::image::Format;
+use godot::classes::{Image, ImageTexture, Time};
+use godot::prelude::*;
+use rand::Rng;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, Notify};
+use tokio_tungstenite::{connect_async_tls_with_config, tungstenite::Message};
+
+// 心跳间隔（秒）
+const HEARTBEAT_INTERVAL_SECS: u64 = 20;
+// 两条弹幕之间的最小间隔，过快发送会被平台拒绝
+const DANMAKU_SEND_INTERVAL: Duration = Duration::from_millis(1500);
+// 扫码登录轮询间隔（秒）
+const QR_LOGIN_POLL_SECS: u64 = 2;
+// HTTP 连接超时和请求总超时的默认值（秒）
+const DEFAULT_HTTP_CONNECT_TIMEOUT: f64 = 10.0;
+const DEFAULT_HTTP_TIMEOUT: f64 = 30.0;
+
+/// 后台线程发往主线程的消息，在 `process` 中转换为信号
+#[derive(Debug, Clone)]
+enum ThreadMessage {
+    Signal {
+        name: String,
+        args: Vec<String>,
+    },
+    /// 参数为非字符串的信号，参数在主线程转换为对应的 Godot 类型
+    JsonSignal {
+        name: String,
+        args: Vec<serde_json::Value>,
+    },
+    /// 统一格式的互动事件，data 在主线程转换为 Dictionary
+    LiveEvent {
+        event_type: String,
+        data: serde_json::Value,
+        /// 工作线程收到该消息的时刻，主线程据此换算为引擎时间
+        received_at: Instant,
+    },
+    /// 扫码登录成功，主线程保存完整 Cookie，信号里只给出脱敏摘要
+    LoginSucceeded {
+        cookie: String,
+    },
+    /// 长连接消息或轮询得到的直播状态
+    LiveStatus {
+        live: bool,
+    },
+    RoomInfo {
+        room_id: i64,
+        result: Result<serde_json::Value, String>,
+    },
+    /// 长连接收到下播消息
+    StreamEnded,
+    /// 服务端删除的醒目留言
+    SuperChatDeleted {
+        message_ids: Vec<i64>,
+    },
+    /// 项目心跳成功的场次
+    HeartbeatOk {
+        game_ids: Vec<String>,
+    },
+    /// 礼物目录加载完成
+    GiftCatalog {
+        result: Result<GiftCatalog, String>,
+    },
+    /// 翻译接口返回的译文
+    Translation {
+        request_id: i64,
+        result: Result<String, String>,
+    },
+    /// `initialize` 的后台准备步骤完成
+    Ready {
+        result: Result<(), String>,
+    },
+    /// 后台任务中发生的错误，主线程发出 `error_occurred`
+    Error(BliveError),
+    /// 一次长连接尝试结束（连接失败、断开或主动停止）
+    ConnectionAttempt(ConnectionAttempt),
+    /// `start_async` 的开启项目请求完成
+    StartCompleted {
+        request_id: i64,
+        code: String,
+        response: String,
+        error: Option<BliveError>,
+    },
+    /// `end_async` 的关闭项目请求完成
+    EndCompleted {
+        request_id: i64,
+        game_id: String,
+        response: String,
+        error: Option<BliveError>,
+    },
+    /// 一次开放平台请求收到响应（或失败），先于对应的完成消息发送
+    HttpResponse {
+        path: String,
+        meta: HttpMeta,
+    },
+    /// 开放平台请求被本地限流；wait_secs 为 None 表示被拒绝，否则为排队等待的秒数
+    RateLimited {
+        path: String,
+        wait_secs: Option<f64>,
+    },
+    /// `request` 的通用签名请求完成
+    RequestCompleted {
+        request_id: i64,
+        path: String,
+        response: String,
+        error: Option<BliveError>,
+    },
+    /// 每次项目心跳（含批量心跳）的结果和往返时间
+    HeartbeatResult {
+        ok: bool,
+        rtt_ms: i64,
+        error: String,
+    },
+    /// 长连接收到未处理操作码的包
+    RawPacket {
+        operation: u32,
+        body: Vec<u8>,
+    },
+    /// `get_asset` 的后台加载结果，成功时为图片原始数据
+    AssetFetched {
+        url: String,
+        result: Result<Vec<u8>, String>,
+    },
+    /// `start_websocket_for` 启动的连接发来的消息
+    Connection {
+        conn_id: String,
+        message: Box<ThreadMessage>,
+    },
+}
+
+/// `start_websocket_for` 启动的附加长连接，与主连接互不影响
+#[derive(Debug)]
+struct KeyedConnection {
+    url: String,
+    auth_body: String,
+    running: Arc<AtomicBool>,
+    /// 被丢弃时连接同样停止
+    stopper: Stopper,
+    outbound_tx: mpsc::UnboundedSender<Vec<u8>>,
+    /// 该连接自己的流量计数，超时重连后继续累加
+    traffic: Arc<TrafficStats>,
+    message_window: RateWindow,
+    /// 已收到该连接的 ws_timeout，收到对应的 ws_disconnected 后重新连接
+    reconnect_pending: bool,
+}
+
+/// 长连接目标：开放平台直接给出地址和鉴权包，直连模式需要先查询直播间
+#[derive(Debug, Clone)]
+enum WsTarget {
+    /// 连接失败时按顺序尝试下一个地址
+    OpenPlatform {
+        ws_urls: Vec<String>,
+        auth_body: String,
+    },
+    Room {
+        room_id: i64,
+        credentials: DirectCredentials,
+    },
+}
+
+/// 一次长连接会话的参数
+#[derive(Debug, Clone)]
+struct WsSession {
+    /// 依次尝试的地址，至少一个
+    ws_urls: Vec<String>,
+    auth_body: String,
+    protocol: Protocol,
+    /// 直连模式未登录，用户名等字段被平台截断
+    guest: bool,
+    /// 解析线程数，0 表示在接收任务中直接解析
+    parse_workers: usize,
+    tls: TlsOptions,
+    /// 超过该时长未收到心跳回复时断开，None 表示不检查
+    reply_timeout: Option<Duration>,
+    stop: StopSignal,
+}
+
+/// 解析业务消息所需的共享状态，接收任务和解析线程池共用
+#[derive(Clone)]
+struct MessageContext {
+    gate: Arc<Mutex<InteractionGate>>,
+    sender: mpsc::UnboundedSender<ThreadMessage>,
+    guest: bool,
+}
+
+/// 把指定 cmd 的消息转发给节点组成员：`call_group(group, method, cmd, data)`
+#[derive(Debug, Clone)]
+pub(crate) struct GroupForward {
+    pub(crate) group: String,
+    pub(crate) method: String,
+    pub(crate) cmds: Vec<String>,
+}
+
+impl GroupForward {
+    pub(crate) fn accepts(&self, cmd: &str) -> bool {
+        self.cmds.is_empty() || self.cmds.iter().any(|c| c == cmd)
+    }
+}
+
+/// 项目心跳调度：单场次和批量心跳由同一个定时任务发送
+#[derive(Debug, Default)]
+struct HeartbeatSchedule {
+    /// 单场次心跳的场次，None 表示未启动
+    single: Option<String>,
+    /// 批量心跳的场次列表，None 表示未启动
+    batch: Option<Vec<String>>,
+    /// 启动单场次 / 批量心跳的调用返回的请求 ID，随每次心跳的完成信号发出
+    single_request_id: i64,
+    batch_request_id: i64,
+    /// 调度任务是否在运行，两种心跳都停止后任务退出
+    task_running: bool,
+    /// `pause_heartbeats` 期间跳过发送，场次和计时保持不变
+    paused: bool,
+}
+
+/// 弹幕翻译器：用户提供的 HTTP 接口或 Callable
+#[derive(Debug, Clone)]
+enum Translator {
+    Endpoint(String),
+    Callable(Callable),
+}
+
+/// `initialize` 的进度；从未调用 initialize 时不限制 start
+#[derive(Debug, Clone, PartialEq)]
+enum ReadyState {
+    Uninitialized,
+    Initializing,
+    Ready,
+    Failed(String),
+}
+
+/// 共享的 Tokio 运行时，随 Blive 节点释放而关闭，未完成的后台任务在下一个 await 点被取消
+struct RuntimeManager {
+    runtime: Option<tokio::runtime::Runtime>,
+}
+
+impl RuntimeManager {
+    fn is_alive(&self) -> bool {
+        self.runtime
+            .as_ref()
+            .is_some_and(|runtime| runtime.metrics().num_workers() > 0)
+    }
+
+    fn handle(&self) -> &tokio::runtime::Handle {
+        self.runtime.as_ref().expect("runtime 已关闭").handle()
+    }
+
+    /// 所有后台任务都是网络 IO，一个工作线程足够；阻塞的 HTTP 请求放在 blocking 线程池，空闲时自动回收
+    fn new() -> Self {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("blive-worker")
+            .enable_all()
+            .build()
+            .expect("Failed to create tokio runtime");
+        Self {
+            runtime: Some(runtime),
+        }
+    }
+}
+
+impl Drop for RuntimeManager {
+    fn drop(&mut self) {
+        // 不等待 blocking 线程池中的 HTTP 请求，避免释放节点时卡住主线程
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// 开放平台请求：POST 发送 JSON 请求体，GET 只带查询参数
+#[derive(Debug, Clone)]
+enum ApiCall {
+    Post(String),
+    Get(BTreeMap<String, String>),
+}
+
+/// 在后台任务中发送签名请求所需的开放平台凭据
+#[derive(Debug, Clone)]
+struct ApiCredentials {
+    base_url: String,
+    access_key_id: String,
+    access_key_secret: String,
+    http: reqwest::blocking::Client,
+    traffic: Arc<TrafficStats>,
+    limiter: Arc<SharedLimiter>,
+    /// 超出限流时排队等待（最多 `MAX_QUEUE_WAIT_SECS` 秒），为 false 时直接拒绝
+    queue_excess: bool,
+    /// 把每次请求的状态码和响应头送回主线程
+    sender: Option<mpsc::UnboundedSender<ThreadMessage>>,
+}
+
+/// 从共享令牌桶取得发送许可，返回需要等待的秒数；不排队或排队过久时返回限流错误
+fn admit_request(
+    limiter: &SharedLimiter,
+    queue_excess: bool,
+    path: &str,
+) -> Result<f64, BliveError> {
+    let max_wait = if queue_excess {
+        MAX_QUEUE_WAIT_SECS
+    } else {
+        0.0
+    };
+    limiter.reserve(max_wait).ok_or_else(|| BliveError::Http {
+        status: 429,
+        message: format!("超出本地限流，请求未发送: {}", path),
+    })
+}
+
+impl ApiCredentials {
+    /// 阻塞的 HTTP 请求放到 blocking 线程池执行，避免占用 runtime 工作线程
+    ///
+    /// 返回兼容旧信号的响应文本，以及请求失败或 code 不为 0 时的错误
+    async fn post(&self, path: &str, body: String) -> (String, Option<BliveError>) {
+        self.send(path, ApiCall::Post(body)).await
+    }
+
+    async fn send(&self, path: &str, call: ApiCall) -> (String, Option<BliveError>) {
+        let notify = |wait_secs| {
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(ThreadMessage::RateLimited {
+                    path: path.to_string(),
+                    wait_secs,
+                });
+            }
+        };
+        match admit_request(&self.limiter, self.queue_excess, path) {
+            Ok(wait) if wait > 0.0 => {
+                notify(Some(wait));
+                tokio::time::sleep(Duration::from_secs_f64(wait)).await;
+            }
+            Ok(_) => {}
+            Err(error) => {
+                notify(None);
+                return error::check_response(Err(error));
+            }
+        }
+        let credentials = self.clone();
+        let request_path = path.to_string();
+        let result = tokio::task::spawn_blocking(move || {
+            Blive::blocking_send(
+                &credentials.http,
+                &credentials.base_url,
+                &request_path,
+                &call,
+                &credentials.access_key_id,
+                &credentials.access_key_secret,
+                &credentials.traffic,
+            )
+        })
+        .await;
+        let (result, meta) = result.unwrap_or_else(|e| {
+            let error = BliveError::Http {
+                status: 0,
+                message: format!("请求任务失败: {}", e),
+            };
+            (Err(error), HttpMeta::default())
+        });
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(ThreadMessage::HttpResponse {
+                path: path.to_string(),
+                meta,
+            });
+        }
+        error::check_response(result)
+    }
+}
+
+/// 按超时设置创建 HTTP 客户端，秒数不大于 0 表示不限
+fn build_http_client(
+    connect_timeout: f64,
+    timeout: f64,
+    tls: &TlsOptions,
+) -> reqwest::blocking::Client {
+    let secs = |secs: f64| (secs > 0.0).then(|| Duration::from_secs_f64(secs));
+    let mut builder = reqwest::blocking::Client::builder().timeout(secs(timeout));
+    if let Some(connect_timeout) = secs(connect_timeout) {
+        builder = builder.connect_timeout(connect_timeout);
+    }
+    let builder = match tls.apply_to_http(builder) {
+        Ok(builder) => builder,
+        Err(e) => {
+            godot_error!("TLS 设置无效，使用默认设置: {}", e);
+            return reqwest::blocking::Client::new();
+        }
+    };
+    builder.build().unwrap_or_else(|e| {
+        godot_error!("创建 HTTP 客户端失败，使用默认设置: {}", e);
+        reqwest::blocking::Client::new()
+    })
+}
+
+/// 在 blocking 线程中加载资源：先读磁盘缓存，未命中时下载并写入磁盘缓存
+fn fetch_asset(
+    http: &reqwest::blocking::Client,
+    disk: &Mutex<(Option<PathBuf>, Option<DiskCache>)>,
+    dir: Option<PathBuf>,
+    url: &str,
+    max_disk_bytes: u64,
+) -> Result<Vec<u8>, String> {
+    {
+        let mut disk = disk.lock().unwrap();
+        asset_cache::switch_dir(&mut disk, dir);
+        if let Some(bytes) = disk.1.as_mut().and_then(|cache| cache.read(url)) {
+            return Ok(bytes);
+        }
+    }
+    let response = http
+        .get(url)
+        .send()
+        .map_err(|e| format!("下载失败: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("下载失败: HTTP {}", response.status().as_u16()));
+    }
+    let bytes = response
+        .bytes()
+        .map_err(|e| format!("下载失败: {}", e))?
+        .to_vec();
+    if asset_cache::image_format(&bytes).is_none() {
+        return Err("不支持的图片格式".to_string());
+    }
+    if let Some(cache) = disk.lock().unwrap().1.as_mut() {
+        if let Err(e) = cache.write(url, &bytes, max_disk_bytes) {
+            godot_warn!("{}", e);
+        }
+    }
+    Ok(bytes)
+}
+
+/// 工作线程的到达时刻换算为 `Time.get_ticks_msec()`；engine_now 为同一时刻的 (Instant, 引擎毫秒)
+fn engine_ticks_at(received_at: Instant, engine_now: (Instant, u64)) -> u64 {
+    let (now, ticks) = engine_now;
+    let age = now.saturating_duration_since(received_at).as_millis() as u64;
+    ticks.saturating_sub(age)
+}
+
+fn send_json_signal_to_main(
+    sender: &mpsc::UnboundedSender<ThreadMessage>,
+    name: &str,
+    args: Vec<serde_json::Value>,
+) {
+    let _ = sender.send(ThreadMessage::JsonSignal {
+        name: name.to_string(),
+        args,
+    });
+}
+
+fn send_signal_to_main(
+    sender: &mpsc::UnboundedSender<ThreadMessage>,
+    name: &str,
+    args: Vec<String>,
+) {
+    let _ = sender.send(ThreadMessage::Signal {
+        name: name.to_string(),
+        args,
+    });
+}
+
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct Blive {
+    base: Base<Node>,
+
+    #[export]
+    app_id: i64,
+    #[export]
+    access_key_id: GString,
+    #[export]
+    access_key_secret: GString,
+    #[export]
+    api_base_url: GString,
+    /// 同一场景有多个节点时用于区分来源：非空时信号中的字典参数都带有 channel 键；
+    /// 为空时读取节点元数据 `channel_name`
+    #[export]
+    channel_name: GString,
+    /// 直连模式使用的浏览器 Cookie（至少包含 SESSDATA，建议带上 buvid3 和 DedeUserID），留空则以游客身份连接
+    #[export]
+    cookie: GString,
+    /// 最近一次 start 返回的长连接鉴权包，读取时 key 被打码，完整内容仅供内部连接使用
+    #[var(get = get_ws_auth_body, no_set)]
+    ws_auth_body: GString,
+    /// 最近一次 start 返回的长连接地址列表
+    #[var(no_set)]
+    ws_links: PackedStringArray,
+    /// 收到下播消息时自动停止心跳、断开长连接并关闭当前项目，随后发出 `session_closed`
+    #[export]
+    auto_end_on_stream_end: bool,
+    /// 付费事件（付费礼物、醒目留言、上舰）审计日志，支持 `user://` 路径，为空时不记录
+    ///
+    /// 每行一条 JSON，含事件 data、场次信息（mode、app_id、game_id、room_id）和哈希链字段
+    /// （seq、prev_hash、hash），可用 `verify_audit_log` 校验是否被修改，便于与平台收益对账
+    #[export]
+    audit_log_path: GString,
+    /// 首次互动的持久化记录（JSONL，支持 `user://` 路径）；为空时只判断本场次内的首次，
+    /// 设置后弹幕和礼物事件另带 first_ever
+    #[export]
+    first_interaction_store_path: GString,
+    /// 可靠投递：reliable_event_types 中的事件带上 event_id 和 attempt（第几次发出），
+    /// 需要调用 `ack_event(event_id)` 确认；超过 ack_timeout_secs 未确认时以相同 event_id 重新发出
+    /// `live_event`，最多发出 max_delivery_attempts 次，之后仍可通过 `get_unacked_events` 查询。
+    /// 发放奖励等逻辑应按 event_id 去重
+    #[export]
+    reliable_delivery: bool,
+    #[export]
+    reliable_event_types: PackedStringArray,
+    #[export]
+    ack_timeout_secs: f64,
+    #[export]
+    max_delivery_attempts: i64,
+    /// 折叠短时间内重复的相同弹幕：每轮第一条照常发出，之后的重复不再发出 `live_event`，
+    /// 刷屏结束后发出一次 `danmaku_collapsed`
+    #[export]
+    collapse_repeated_danmaku: bool,
+    /// 相同弹幕间隔不超过该秒数视为同一轮刷屏
+    #[export]
+    collapse_window_secs: f64,
+    /// 检测弹幕是否集中在同一说法上（相似的弹幕归为一类），达到比例时发出 `chat_consensus`
+    #[export]
+    detect_chat_consensus: bool,
+    /// 参与判断的弹幕时间窗口（秒）
+    #[export]
+    consensus_window_secs: f64,
+    /// 最大一类的观众数占窗口内发弹幕观众数的最低比例
+    #[export]
+    consensus_ratio: f64,
+    /// 窗口内至少有多少位观众发弹幕才判断
+    #[export]
+    consensus_min_users: i64,
+    /// 为弹幕事件判断语言，写入 data.lang（zh / ja / ko / ru / th / ar / en / und），
+    /// 并按 `set_language_filter` 和 `add_language_route` 过滤、转发
+    #[export]
+    detect_language: bool,
+    /// 每秒最多发出多少个翻译请求（命中缓存的不计），0 表示不限；设置翻译器时生效
+    #[export]
+    translation_rate_per_sec: f64,
+    /// `initialize` 时先加载礼物目录，加载成功才发出 `blive_ready(true)`
+    #[export]
+    prefetch_gift_catalog: bool,
+    /// 礼物目录的磁盘缓存文件（系统路径，可用 `ProjectSettings.globalize_path("user://...")` 得到），
+    /// 为空时使用系统临时目录
+    #[export]
+    gift_catalog_cache_path: GString,
+    /// 礼物目录缓存的有效期（秒）
+    #[export]
+    gift_catalog_ttl_secs: f64,
+    /// 项目心跳连续失败多少次后发出 `heartbeat_degraded`
+    #[export]
+    heartbeat_failure_threshold: i64,
+    /// 连接质量变差（长连接心跳回复超时、待处理消息积压、项目心跳连续失败）时自动进入降级模式：
+    /// 不再逐条发出 `ws_message_received`，改为定期发出 `message_digest`，并限制每帧处理的消息数；
+    /// 各项指标恢复后还原。阈值通过 `set_degradation_options` 调整
+    #[export]
+    auto_degrade: bool,
+    /// 在多少个线程上解析长连接消息，0 表示在接收任务中直接解析；弹幕量很大的直播间可设为 2~4，
+    /// 同一观众的消息仍按到达顺序发出，不同观众之间的顺序不再保证；下次连接时生效
+    #[export]
+    parse_workers: i64,
+    /// 每隔多少秒发出一次 `bandwidth_report`，0 表示不发出
+    #[export]
+    bandwidth_report_interval: f64,
+    /// HTTP 建立连接（含 TLS 握手）的超时（秒），0 表示不限
+    #[export]
+    http_connect_timeout: f64,
+    /// 单个 HTTP 请求从发出到读完响应的超时（秒），0 表示不限；
+    /// 开放平台请求超时时发出 `error_occurred("timeout", 0, ...)`
+    #[export]
+    http_timeout: f64,
+    /// 额外信任的根证书（PEM，可含多张，支持 `user://` 路径），同时用于 HTTP 请求和长连接，
+    /// 如测试环境自签名证书的网关；为空时使用 `set_tls_ca_certificate` 设置的证书
+    #[export]
+    tls_ca_cert_path: GString,
+    /// 长连接不校验服务器证书（自签名证书的测试网关可用），HTTP 请求不受影响；下次连接时生效。
+    /// 会使连接失去防中间人保护，不要在正式环境开启
+    #[export]
+    ws_insecure_skip_verify: bool,
+    /// 开放平台请求（含心跳）每秒最多发送的次数，0 表示不限
+    #[export]
+    api_qps: f64,
+    /// 超出 api_qps 的请求排队等待（最多 10 秒）还是直接失败；两种情况都会发出 `rate_limited`
+    #[export]
+    api_rate_limit_queue: bool,
+    /// 头像、礼物图片等资源的磁盘缓存目录（支持 `user://` 路径），为空时只缓存在内存中
+    #[export]
+    asset_cache_dir: GString,
+    /// 内存中已解码纹理的总大小上限（MB，按 RGBA8 估算），超过后淘汰最久未使用的纹理
+    #[export]
+    asset_memory_cache_mb: f64,
+    /// 磁盘缓存的总大小上限（MB），超过后删除最久未使用的文件
+    #[export]
+    asset_disk_cache_mb: f64,
+    /// 录制分段的大小上限（MB），超过后切换到新分段，0 表示不限
+    #[export]
+    recording_max_segment_mb: f64,
+    /// 录制分段的时长上限（分钟），0 表示不限
+    #[export]
+    recording_max_segment_minutes: f64,
+    /// 录制目录下最多保留的会话数（含本次），更早的会话在开始录制时删除；0 表示全部保留
+    #[export]
+    recording_keep_sessions: i64,
+    /// 是否把结束的分段压缩为 .jsonl.gz
+    #[export]
+    recording_compress: bool,
+    /// start 因身份码错误失败后，最多可通过 `set_code` 自动重试的次数，0 表示不重试
+    #[export]
+    invalid_code_retries: i64,
+    /// start 结果的进程内缓存有效期（秒），从 start 成功或最近一次心跳成功算起；
+    /// 平台约 60 秒无心跳关闭场次，不宜设得更长
+    #[export]
+    session_cache_ttl_secs: f64,
+    /// 长连接超过该秒数未收到心跳回复时视为连接已失效：发出 `ws_timeout`，断开后按原地址重新连接；
+    /// 0 表示不检查
+    #[export]
+    ws_reply_timeout_secs: f64,
+
+    runtime: Arc<RuntimeManager>,
+
+    ws_message_tx: Option<mpsc::UnboundedSender<ThreadMessage>>,
+    ws_message_rx: Arc<Mutex<mpsc::UnboundedReceiver<ThreadMessage>>>,
+
+    heartbeats: Arc<Mutex<HeartbeatSchedule>>,
+    /// 心跳场次变化时唤醒调度任务，开始 / 停止立即生效
+    heartbeat_notify: Arc<Notify>,
+    ws_running: Arc<AtomicBool>,
+    ws_guest: Arc<AtomicBool>,
+    /// 直连模式解析出的真实房间号，未连接时为 0
+    direct_room_id: Arc<AtomicI64>,
+    last_danmaku_sent: Option<Instant>,
+    room_info_cache: HashMap<i64, serde_json::Value>,
+    /// 未知时为 None，从未知变为已知也会发出对应信号
+    live_state: Option<bool>,
+    live_poll_running: Arc<AtomicBool>,
+    qr_login_running: Arc<AtomicBool>,
+    ws_outbound_tx: Option<mpsc::UnboundedSender<Vec<u8>>>,
+    /// 当前长连接的停止端，停止或丢弃时连接发送关闭帧并立即退出
+    ws_stopper: Option<Stopper>,
+    /// 最近一次连接的目标，心跳回复超时后用于重新连接
+    ws_target: Option<WsTarget>,
+    /// 已收到 ws_timeout，收到对应的 ws_disconnected 后重新连接
+    ws_reconnect_pending: bool,
+    /// `start_websocket_for` 启动的连接，按连接 ID 索引
+    keyed_connections: HashMap<String, KeyedConnection>,
+    protocol: Protocol,
+    /// 主线程已收到 ws_connected 且尚未收到 ws_disconnected
+    ws_connected: bool,
+    /// 当前长连接使用的解析线程数
+    ws_parse_workers: usize,
+    last_ws_heartbeat_reply: Arc<Mutex<Option<Instant>>>,
+    last_api_heartbeat_ok: Arc<Mutex<Option<Instant>>>,
+    heartbeat_health: HeartbeatHealth,
+    ready_state: ReadyState,
+    gift_catalog: GiftCatalog,
+    language_filter: Vec<String>,
+    language_routes: Vec<LanguageRoute>,
+    translator: Option<Translator>,
+    webhook: Option<Webhook>,
+    /// 每个开放平台接口路径最近一次响应的状态码和关键响应头
+    http_responses: HashMap<String, HttpMeta>,
+    recorder: Option<SessionRecorder>,
+    load_test: Option<LoadTest>,
+    /// 正在切换的 (旧场次, 新场次)，旧场次的 end 完成后清空
+    session_switch: Option<(String, String)>,
+    /// 被 `switch_session` 替换、尚未退出的长连接数，它们的 ws_disconnected 不再发出
+    superseded_connections: usize,
+    /// 发出 `invalid_code` 后等待 `set_code` 时为剩余的重试次数
+    awaiting_code: Option<i64>,
+    /// 最近分配的请求 ID，从 1 开始递增
+    last_request_id: i64,
+    /// `pause_heartbeats` 时的 elapsed，以及是否已发出 `heartbeat_pause_too_long`
+    heartbeat_paused_at: Option<(f64, bool)>,
+    /// `*_with_callback` 发起的请求，完成时以响应调用
+    result_callbacks: HashMap<i64, Callable>,
+    next_callback: Option<Callable>,
+    /// 在后台 runtime 上发送、尚未完成的请求，可用 `cancel_request` 取消
+    in_flight: HashMap<i64, tokio::task::AbortHandle>,
+    load_generator: Arc<GeneratorCounters>,
+    translation: TranslationPipeline,
+    spam: SpamCollapser,
+    consensus: ConsensusDetector,
+    acks: AckTracker,
+    scheduler: EventScheduler,
+    connection_history: Option<ConnectionHistory>,
+    /// 向只读附加节点广播已解析的信号
+    shared: SharedSession,
+    /// 以只读方式附加到的主节点会话
+    attachment: Option<SharedAttachment>,
+    audit_log: Option<AuditLog>,
+    /// 已报告过审计日志错误，成功写入后重置
+    audit_log_failed: bool,
+    first_interactions: FirstInteractions,
+    /// 已按其打开持久化记录的 first_interaction_store_path，变化时重新打开
+    first_interaction_store_opened: GString,
+    degradation: DegradationPolicy,
+    message_digest: MessageDigest,
+
+    interaction_gate: Arc<Mutex<InteractionGate>>,
+    group_forwards: Vec<GroupForward>,
+    /// 最近一次 start 成功返回的场次 ID，end 后清空
+    game_id: String,
+    session: SessionLifecycle,
+    combos: Option<ComboTracker>,
+    super_chats: Option<SuperChatTimers>,
+    subsystems: Subsystems,
+    /// `get_asset` 已解码的纹理
+    asset_textures: Lru<Gd<ImageTexture>>,
+    /// 与后台加载共用的 (打开时的目录, 磁盘缓存)
+    asset_disk: Arc<Mutex<(Option<PathBuf>, Option<DiskCache>)>>,
+    /// 正在后台加载的 url，避免重复下载
+    asset_loading: HashSet<String>,
+    deprecations: DeprecationLog,
+    clock: ClockOffset,
+    /// 所有 HTTP 请求共用的客户端，复用连接池和 TLS 会话；克隆只增加引用计数
+    http: reqwest::blocking::Client,
+    /// 创建 `http` 时使用的 (连接超时, 请求超时, TLS 设置)
+    http_config: (f64, f64, TlsOptions),
+    /// `set_tls_ca_certificate` 设置的根证书
+    tls_ca_pem: Vec<u8>,
+    /// 从 `tls_ca_cert_path` 读入的 (路径, 证书)，路径变化后重新读取
+    tls_ca_file: (GString, Vec<u8>),
+    /// 主线程和后台任务共用的开放平台请求令牌桶
+    api_limiter: Arc<SharedLimiter>,
+    traffic: Arc<TrafficStats>,
+    /// 上次发出 bandwidth_report 的时间（elapsed）和当时的计数
+    last_bandwidth_report: (f64, TrafficSnapshot),
+    /// 最近几秒的业务消息计数样本，用于计算 `get_ws_stats` 的 ws_message_rate
+    ws_message_window: RateWindow,
+    /// 节点运行的累计秒数，用作连击等计时的时钟
+    elapsed: f64,
+}
+
+impl Drop for Blive {
+    /// 节点释放时先让所有后台循环退出并关闭通道，之后到达的消息直接丢弃，
+    /// 最后随 RuntimeManager 一起取消仍在运行的任务
+    fn drop(&mut self) {
+        for running in [
+            &self.ws_running,
+            &self.live_poll_running,
+            &self.qr_login_running,
+        ] {
+            running.store(false, Ordering::SeqCst);
+        }
+        if let Ok(mut schedule) = self.heartbeats.lock() {
+            schedule.single = None;
+            schedule.batch = None;
+        }
+        self.heartbeat_notify.notify_one();
+        self.ws_outbound_tx = None;
+        self.ws_stopper = None;
+        self.ws_message_tx = None;
+        if let Ok(mut rx) = self.ws_message_rx.lock() {
+            rx.close();
+            while rx.try_recv().is_ok() {}
+        }
+    }
+}
+
+#[godot_api]
+impl INode for Blive {
+    fn init(base: Base<Node>) -> Self {
+        godot_print!("Blive: Initializing");
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            base,
+            app_id: 0,
+            access_key_id: GString::new(),
+            access_key_secret: GString::new(),
+            api_base_url: GString::from("https://live-open.biliapi.com"),
+            channel_name: GString::new(),
+            cookie: GString::new(),
+            ws_auth_body: GString::new(),
+            ws_links: PackedStringArray::new(),
+            auto_end_on_stream_end: false,
+            audit_log_path: GString::from("user://gdblive_paid_audit.jsonl"),
+            first_interaction_store_path: GString::new(),
+            reliable_delivery: false,
+            reliable_event_types: [
+                events::EVENT_GIFT,
+                events::EVENT_SUPER_CHAT,
+                events::EVENT_GUARD,
+            ]
+            .into_iter()
+            .map(GString::from)
+            .collect(),
+            ack_timeout_secs: 5.0,
+            max_delivery_attempts: 5,
+            collapse_repeated_danmaku: false,
+            collapse_window_secs: 3.0,
+            detect_chat_consensus: false,
+            consensus_window_secs: 10.0,
+            consensus_ratio: 0.5,
+            consensus_min_users: 5,
+            detect_language: false,
+            translation_rate_per_sec: 5.0,
+            prefetch_gift_catalog: false,
+            gift_catalog_cache_path: GString::new(),
+            gift_catalog_ttl_secs: 86400.0,
+            heartbeat_failure_threshold: 2,
+            auto_degrade: false,
+            parse_workers: 0,
+            bandwidth_report_interval: 10.0,
+            runtime: Arc::new(RuntimeManager::new()),
+            ws_message_tx: Some(tx),
+            ws_message_rx: Arc::new(Mutex::new(rx)),
+            heartbeats: Arc::new(Mutex::new(HeartbeatSchedule::default())),
+            heartbeat_notify: Arc::new(Notify::new()),
+            ws_running: Arc::new(AtomicBool::new(false)),
+            ws_guest: Arc::new(AtomicBool::new(false)),
+            direct_room_id: Arc::new(AtomicI64::new(0)),
+            last_danmaku_sent: None,
+            room_info_cache: HashMap::new(),
+            live_state: None,
+            live_poll_running: Arc::new(AtomicBool::new(false)),
+            qr_login_running: Arc::new(AtomicBool::new(false)),
+            ws_outbound_tx: None,
+            ws_stopper: None,
+            ws_target: None,
+            ws_reconnect_pending: false,
+            keyed_connections: HashMap::new(),
+            protocol: Protocol::default(),
+            ws_connected: false,
+            ws_parse_workers: 0,
+            last_ws_heartbeat_reply: Arc::new(Mutex::new(None)),
+            last_api_heartbeat_ok: Arc::new(Mutex::new(None)),
+            heartbeat_health: HeartbeatHealth::default(),
+            ready_state: ReadyState::Uninitialized,
+            gift_catalog: GiftCatalog::default(),
+            language_filter: Vec::new(),
+            language_routes: Vec::new(),
+            translator: None,
+            webhook: None,
+            http_responses: HashMap::new(),
+            recorder: None,
+            load_test: None,
+            session_switch: None,
+            superseded_connections: 0,
+            awaiting_code: None,
+            last_request_id: 0,
+            heartbeat_paused_at: None,
+            result_callbacks: HashMap::new(),
+            next_callback: None,
+            in_flight: HashMap::new(),
+            load_generator: Arc::new(GeneratorCounters::default()),
+            translation: TranslationPipeline::new("", 5.0),
+            spam: SpamCollapser::default(),
+            consensus: ConsensusDetector::default(),
+            acks: AckTracker::default(),
+            scheduler: EventScheduler::default(),
+            connection_history: None,
+            shared: SharedSession::default(),
+            attachment: None,
+            audit_log: None,
+            audit_log_failed: false,
+            first_interactions: FirstInteractions::default(),
+            first_interaction_store_opened: GString::new(),
+            degradation: DegradationPolicy::default(),
+            message_digest: MessageDigest::default(),
+            interaction_gate: Arc::new(Mutex::new(InteractionGate::default())),
+            group_forwards: Vec::new(),
+            game_id: String::new(),
+            session: SessionLifecycle::default(),
+            combos: None,
+            super_chats: None,
+            subsystems: Subsystems::default(),
+            asset_textures: Lru::default(),
+            asset_disk: Arc::new(Mutex::new((None, None))),
+            asset_loading: HashSet::new(),
+            deprecations: DeprecationLog::default(),
+            clock: ClockOffset::default(),
+            http_connect_timeout: DEFAULT_HTTP_CONNECT_TIMEOUT,
+            http_timeout: DEFAULT_HTTP_TIMEOUT,
+            tls_ca_cert_path: GString::new(),
+            ws_insecure_skip_verify: false,
+            api_qps: 10.0,
+            api_rate_limit_queue: true,
+            asset_cache_dir: GString::from("user://gdblive_assets"),
+            asset_memory_cache_mb: 64.0,
+            asset_disk_cache_mb: 256.0,
+            api_limiter: Arc::new(SharedLimiter::default()),
+            recording_max_segment_mb: 64.0,
+            recording_max_segment_minutes: 60.0,
+            recording_keep_sessions: 10,
+            recording_compress: true,
+            invalid_code_retries: 3,
+            session_cache_ttl_secs: session::EXPIRING_AFTER_SECS,
+            ws_reply_timeout_secs: 60.0,
+            http: build_http_client(
+                DEFAULT_HTTP_CONNECT_TIMEOUT,
+                DEFAULT_HTTP_TIMEOUT,
+                &TlsOptions::default(),
+            ),
+            http_config: (
+                DEFAULT_HTTP_CONNECT_TIMEOUT,
+                DEFAULT_HTTP_TIMEOUT,
+                TlsOptions::default(),
+            ),
+            tls_ca_pem: Vec::new(),
+            tls_ca_file: (GString::new(), Vec::new()),
+            traffic: Arc::new(TrafficStats::default()),
+            last_bandwidth_report: (0.0, TrafficSnapshot::default()),
+            ws_message_window: RateWindow::default(),
+            elapsed: 0.0,
+        }
+    }
+
+    fn ready(&mut self) {
+        self.register_signal_aliases();
+    }
+
+    fn process(&mut self, delta: f64) {
+        self.elapsed += delta;
+        self.drain_attachment();
+        if let Some(transition) = self.session.tick(delta) {
+            self.emit_transition(transition);
+        }
+        let expired = match self.super_chats.as_mut() {
+            Some(super_chats) => super_chats.expire(self.elapsed),
+            None => Vec::new(),
+        };
+        for message_id in expired {
+            self.emit("super_chat_expired", &[message_id.to_variant()]);
+        }
+        self.report_bandwidth();
+        self.ws_message_window
+            .sample(self.elapsed, self.traffic.snapshot().ws_messages);
+        for connection in self.keyed_connections.values_mut() {
+            connection
+                .message_window
+                .sample(self.elapsed, connection.traffic.snapshot().ws_messages);
+        }
+        self.check_heartbeat_pause();
+        self.tick_load_test(delta);
+        self.update_degradation(delta);
+        self.redeliver_events();
+        self.fire_scheduled_events();
+        for summary in self.spam.flush(self.elapsed, self.collapse_window_secs) {
+            self.emit("danmaku_collapsed", &[json_to_variant(&summary)]);
+        }
+        self.check_chat_consensus();
+        for request_id in self.translation.expired(self.elapsed) {
+            self.finish_translation(request_id, Err("翻译超时".to_string()));
+        }
+
+        // 先把消息取出来再发信号，避免持锁期间回调到脚本；超出每帧预算的消息留到下一帧
+        let budget = self.degradation.frame_budget();
+        let messages: Vec<ThreadMessage> = {
+            let mut rx = self.ws_message_rx.lock().unwrap();
+            let mut messages = Vec::new();
+            while budget == 0 || messages.len() < budget {
+                let Ok(message) = rx.try_recv() else {
+                    break;
+                };
+                messages.push(message);
+            }
+            messages
+        };
+        let engine_now = (Instant::now(), Time::singleton().get_ticks_msec());
+
+        for message in messages {
+            match message {
+                ThreadMessage::Signal { name, args } => {
+                    if name == "ws_disconnected" && self.superseded_connections > 0 {
+                        self.superseded_connections -= 1;
+                        continue;
+                    }
+                    match name.as_str() {
+                        "ws_connected" => self.ws_connected = true,
+                        "ws_disconnected" => self.ws_connected = false,
+                        _ => {}
+                    }
+                    self.shared.publish(&name, || {
+                        args.iter()
+                            .map(|arg| serde_json::Value::String(arg.clone()))
+                            .collect()
+                    });
+                    if name == "ws_message_received" && self.degradation.is_degraded() {
+                        self.message_digest.add(&args[0]);
+                    } else {
+                        let variants: Vec<Variant> =
+                            args.iter().map(|arg| arg.to_variant()).collect();
+                        self.emit(name.as_str(), &variants);
+                    }
+                    if name == "ws_message_received" && self.recorder.is_some() {
+                        self.record_message(&args[0], &args[1]);
+                    }
+                    if name == "ws_message_received" && !self.group_forwards.is_empty() {
+                        self.forward_to_groups(&args[0], &args[1]);
+                    }
+                    if name == "ws_disconnected" && std::mem::take(&mut self.ws_reconnect_pending) {
+                        self.reconnect_after_timeout();
+                    }
+                }
+                ThreadMessage::JsonSignal { name, args } => {
+                    if name == "ws_timeout" {
+                        self.ws_reconnect_pending = true;
+                    }
+                    self.shared.publish(&name, || args.clone());
+                    let variants: Vec<Variant> = args.iter().map(json_to_variant).collect();
+                    self.emit(name.as_str(), &variants);
+                }
+                ThreadMessage::LoginSucceeded { cookie } => {
+                    let summary = DirectCredentials::from_cookie(&cookie).redacted();
+                    self.cookie = GString::from(cookie.as_str());
+                    self.emit("login_succeeded", &[summary.to_variant()]);
+                }
+                ThreadMessage::LiveStatus { live } => self.update_live_state(live),
+                ThreadMessage::HeartbeatOk { game_ids } => {
+                    for game_id in game_ids {
+                        session::SESSION_CACHE.touch(&game_id, Instant::now());
+                        if let Some(transition) = self.session.heartbeat_ok(&game_id) {
+                            self.emit_transition(transition);
+                        }
+                    }
+                }
+                ThreadMessage::GiftCatalog { result } => match result {
+                    Ok(catalog) => {
+                        let count = catalog.len() as i64;
+                        self.gift_catalog = catalog;
+                        self.emit("gift_catalog_updated", &[count.to_variant()]);
+                    }
+                    Err(e) => {
+                        self.emit("gift_catalog_failed", &[e.to_variant()]);
+                    }
+                },
+                ThreadMessage::Translation { request_id, result } => {
+                    self.finish_translation(request_id, result)
+                }
+                ThreadMessage::ConnectionAttempt(attempt) => {
+                    let enabled = self.subsystems.is_enabled(subsystems::STATS);
+                    if let Some(history) = subsystems::lazy(&mut self.connection_history, enabled) {
+                        history.push(attempt);
+                    }
+                }
+                ThreadMessage::Error(error) => self.report_error(&error),
+                ThreadMessage::StartCompleted {
+                    request_id,
+                    code,
+                    response,
+                    error,
+                } => {
+                    if self.in_flight.remove(&request_id).is_none() {
+                        self.discard_cancelled_start(&response);
+                        continue;
+                    }
+                    let result = self.finish_start(request_id, response, error);
+                    self.handle_start_result(code, self.invalid_code_retries.max(0), result);
+                }
+                ThreadMessage::EndCompleted {
+                    request_id,
+                    game_id,
+                    response,
+                    error,
+                } => {
+                    if self.in_flight.remove(&request_id).is_none() {
+                        continue;
+                    }
+                    let switch = self
+                        .session_switch
+                        .take_if(|(previous, _)| *previous == game_id);
+                    self.finish_end(&game_id, "ended", request_id, response, error);
+                    if let Some((previous, next)) = switch {
+                        self.emit_switch_progress("completed", &previous, &next);
+                    }
+                }
+                ThreadMessage::RateLimited { path, wait_secs } => {
+                    self.emit_rate_limited(&path, wait_secs)
+                }
+                ThreadMessage::HttpResponse { path, meta } => {
+                    self.record_http_response(&path, meta)
+                }
+                ThreadMessage::RequestCompleted {
+                    request_id,
+                    path,
+                    response,
+                    error,
+                } => {
+                    if self.in_flight.remove(&request_id).is_none() {
+                        continue;
+                    }
+                    if let Some(error) = &error {
+                        self.report_error(error);
+                    }
+                    let response = error::with_request_id(&response, request_id);
+                    self.emit(
+                        "request_completed",
+                        &[path.to_variant(), response.to_variant()],
+                    );
+                    self.deliver_result(request_id, &response);
+                }
+                ThreadMessage::Ready { result } => {
+                    let (success, error) = match result {
+                        Ok(()) => (true, String::new()),
+                        Err(e) => (false, e),
+                    };
+                    self.finish_initialize(success, error);
+                }
+                ThreadMessage::AssetFetched { url, result } => self.finish_asset(url, result),
+                ThreadMessage::Connection { conn_id, message } => {
+                    self.handle_connection_message(conn_id, *message, engine_now)
+                }
+                ThreadMessage::RawPacket { operation, body } => self.emit(
+                    "ws_raw_packet",
+                    &[
+                        (operation as i64).to_variant(),
+                        PackedByteArray::from(body).to_variant(),
+                    ],
+                ),
+                ThreadMessage::HeartbeatResult { ok, rtt_ms, error } => {
+                    let threshold = self.heartbeat_failure_threshold.max(1) as u32;
+                    if self.heartbeat_health.record(ok, rtt_ms, &error, threshold) {
+                        let failures = self.heartbeat_health.consecutive_failures() as i64;
+                        let error = self.heartbeat_health.last_error().to_string();
+                        self.emit(
+                            "heartbeat_degraded",
+                            &[failures.to_variant(), error.to_variant()],
+                        );
+                    }
+                }
+                ThreadMessage::SuperChatDeleted { message_ids } => {
+                    let removed = match self.super_chats.as_mut() {
+                        Some(super_chats) => super_chats.remove(&message_ids),
+                        None => Vec::new(),
+                    };
+                    for message_id in removed {
+                        self.emit("super_chat_deleted", &[message_id.to_variant()]);
+                    }
+                }
+                ThreadMessage::StreamEnded => {
+                    if self.auto_end_on_stream_end {
+                        self.close_session("stream_ended");
+                    }
+                }
+                ThreadMessage::RoomInfo { room_id, result } => match result {
+                    Ok(info) => {
+                        let dictionary = json_to_variant(&info);
+                        self.room_info_cache.insert(room_id, info);
+                        self.emit("room_info_received", &[room_id.to_variant(), dictionary]);
+                    }
+                    Err(e) => {
+                        self.emit("room_info_failed", &[room_id.to_variant(), e.to_variant()]);
+                    }
+                },
+                ThreadMessage::LiveEvent {
+                    event_type,
+                    mut data,
+                    received_at,
+                } => {
+                    data["received_ticks_msec"] = engine_ticks_at(received_at, engine_now).into();
+                    let synthetic = self.load_test.is_some() && load_test::is_synthetic(&data);
+                    if synthetic {
+                        if let Some(test) = self.load_test.as_mut() {
+                            test.observe_dispatched();
+                        }
+                    }
+                    if event_type == events::EVENT_GIFT {
+                        self.gift_catalog.enrich(&mut data);
+                    }
+                    if audit::is_paid_event(&event_type, &data) {
+                        self.audit_paid_event(&event_type, &data);
+                    }
+                    if event_type == events::EVENT_DANMAKU && self.detect_chat_consensus {
+                        self.consensus.observe(
+                            data["user_id"].as_str().unwrap_or_default(),
+                            data["message"].as_str().unwrap_or_default(),
+                            self.elapsed,
+                        );
+                    }
+                    if event_type == events::EVENT_DANMAKU
+                        && self.collapse_repeated_danmaku
+                        && self
+                            .spam
+                            .observe(&data, self.elapsed, self.collapse_window_secs)
+                    {
+                        continue;
+                    }
+                    if event_type == events::EVENT_DANMAKU && self.detect_language {
+                        let lang = language::detect(data["message"].as_str().unwrap_or_default());
+                        data["lang"] = lang.into();
+                        let filtered = !self.language_filter.is_empty()
+                            && !self.language_filter.iter().any(|allowed| allowed == lang);
+                        if filtered {
+                            continue;
+                        }
+                        self.route_language(lang, &data);
+                    }
+                    if event_type == events::EVENT_DANMAKU && self.translator.is_some() {
+                        self.request_translation(&data);
+                    }
+                    if data["timestamp"].as_i64().unwrap_or(0) > 0 {
+                        self.clock
+                            .add_sample(data["latency_ms"].as_i64().unwrap_or(0));
+                    }
+                    if !synthetic {
+                        self.mark_first_interaction(&event_type, &mut data);
+                    }
+                    self.track_timed_events(&event_type, &data);
+                    if self.reliable_delivery
+                        && self
+                            .reliable_event_types
+                            .as_slice()
+                            .iter()
+                            .any(|reliable| reliable.to_string() == event_type)
+                    {
+                        data = self.acks.track(&event_type, data, self.elapsed);
+                    }
+                    if !synthetic {
+                        self.send_webhook(&event_type, &data);
+                    }
+                    self.shared.publish("live_event", || {
+                        vec![event_type.clone().into(), data.clone()]
+                    });
+                    self.emit(
+                        "live_event",
+                        &[event_type.to_variant(), json_to_variant(&data)],
+                    );
+                    if data["blind_box"].as_bool() == Some(true) {
+                        self.emit("blind_box_opened", &[json_to_variant(&data)]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[godot_api]
+impl Blive {
+    /// 以下 `*_completed` 的响应 JSON 中带有发起调用时返回的 `request_id`
+    #[signal]
+    fn start_completed(response_json: GString);
+    /// `cancel_request` 取消了该请求，之后不会再发出它的完成信号
+    #[signal]
+    fn request_cancelled(request_id: i64);
+    /// start 成功时在 `start_completed` 之后发出，可直接 `start_websocket_links(wss_links, auth_body)`
+    #[signal]
+    fn start_succeeded(
+        game_id: GString,
+        wss_links: PackedStringArray,
+        auth_body: GString,
+        anchor_info: Dictionary,
+    );
+    #[signal]
+    fn end_completed(response_json: GString);
+    /// `request` 的响应，失败时 response_json 为 `{"code":-1,...}` 形式的错误 JSON
+    #[signal]
+    fn request_completed(path: GString, response_json: GString);
+    /// 每次开放平台请求（start / end / 心跳 / request）收到响应或失败时，先于对应的完成信号发出；
+    /// status 为 HTTP 状态码（未收到响应时为 0），headers 只含 content-type、date、retry-after、
+    /// x-ratelimit-* 等关键响应头，名称为小写
+    #[signal]
+    fn http_response_received(path: GString, status: i64, headers: Dictionary);
+    /// 开放平台请求超出 `api_qps`：queued 为 true 时已排队，wait_secs 秒后发送；
+    /// 为 false 时请求未发送，对应的完成信号带 HTTP 429 错误
+    #[signal]
+    fn rate_limited(path: GString, queued: bool, wait_secs: f64);
+    #[signal]
+    fn heartbeat_completed(response_json: GString);
+    /// `run_diagnostics` 的报告：passed，以及 checks 数组（每项含 name、status、detail、duration_ms），
+    /// status 为 ok / failed / warning / skipped
+    #[signal]
+    fn diagnostics_completed(report: Dictionary);
+    #[signal]
+    fn batch_heartbeat_completed(response_json: GString);
+    #[signal]
+    fn ws_connected();
+    #[signal]
+    fn ws_disconnected();
+    /// 长连接超过 `ws_reply_timeout_secs` 秒未收到心跳回复，seconds 为距上次回复（或连接建立）的秒数；
+    /// 随后发出 `ws_disconnected` 并按原地址重新连接
+    #[signal]
+    fn ws_timeout(seconds: f64);
+    /// `start_websocket_for` 启动的连接的信号：name 和 args 与主连接的同名信号相同
+    /// （ws_connected、ws_disconnected、ws_message_received、live_event、ws_error、ws_timeout、
+    /// error_occurred 等），conn_id 为启动时传入的连接 ID；每次连接尝试另以 connection_attempt 发出，
+    /// 参数为与 `get_connection_history` 元素相同的 Dictionary
+    #[signal]
+    fn ws_connection_event(conn_id: GString, name: GString, args: Array<Variant>);
+    /// 长连接收到本插件不处理的操作码时发出，body 为（已解压的）包体原始数据，
+    /// 可配合 `send_raw_packet` 试验新的操作码
+    #[signal]
+    fn ws_raw_packet(operation: i64, body: PackedByteArray);
+    #[signal]
+    fn ws_message_received(cmd: GString, data_json: GString);
+    #[signal]
+    fn ws_error(error_msg: GString);
+    #[signal]
+    fn ws_debug(debug_msg: GString);
+    /// 分类后的错误：domain 为 http / signature / websocket / protocol / api / timeout（见 `BliveNames.ERROR_*`），
+    /// code 为 HTTP 状态码或平台错误码（没有时为 0）。原有的 `*_completed` 信号和 ws_error 照常发出
+    #[signal]
+    fn error_occurred(domain: GString, code: i64, message: GString);
+    /// 超出 `max_message_size` / `max_decompressed_size` 的消息被整体丢弃，连接保持；
+    /// kind 为 message_too_large 或 decompressed_too_large
+    #[signal]
+    fn protocol_error(kind: GString, detail: GString);
+    #[signal]
+    fn heartbeat_debug(debug_msg: GString);
+    #[signal]
+    fn interaction_rejected(open_id: GString, reason: GString);
+    /// 弹幕、礼物、SC、上舰的统一事件，开放平台和直连模式字段一致
+    ///
+    /// event_type 为 danmaku / gift / super_chat / guard；data 总是包含 user_id、uname、avatar、
+    /// medal_level、guard_level、timestamp、timestamp_ms、time（UTC 日期时间 Dictionary）和
+    /// latency_ms（本地收到时间减平台时间）、seq（本次连接内的到达序号）、received_ticks_msec
+    /// （工作线程收到消息时对应的 `Time.get_ticks_msec()`，可直接与引擎时间比较），另有 message、gift_name、
+    /// gift_num、price（千分之一元）等；礼物目录已加载时 gift 事件另有 gift_icon，并补全缺失的礼物名称
+    ///
+    /// 同一观众的事件总是按到达顺序发出；启用 `parse_workers` 后不同观众之间的事件可能交错，
+    /// 此时 `is_global_order_relaxed()` 返回 true，需要全局顺序的逻辑可按 seq 排序
+    ///
+    /// `schedule_event` 注册的定时合成事件也经该信号发出，data 中 synthetic 为 true
+    #[signal]
+    fn live_event(event_type: GString, data: Dictionary);
+    /// 直连模式：累计看过人数（WATCHED_CHANGE）
+    #[signal]
+    fn watched_count_updated(count: i64);
+    /// 直连模式：高能用户人数（ONLINE_RANK_COUNT）
+    #[signal]
+    fn online_count_updated(count: i64);
+    /// 开始连接某个长连接地址时发出，index 为它在地址列表中的位置（换地址重试时递增）
+    #[signal]
+    fn ws_host_selected(url: GString, index: i64);
+    /// 每次收到长连接心跳回复时发出，value 为回复中的人气值
+    #[signal]
+    fn popularity_updated(value: i64);
+    /// 长连接业务消息的包头序列号不连续（跳号、重复或乱序）时发出，expected 为期望的序列号
+    #[signal]
+    fn ws_sequence_gap(expected: i64, got: i64);
+    /// 直连模式：高能榜（ONLINE_RANK_V2），元素为 Dictionary
+    #[signal]
+    fn online_rank_updated(list: Array<Variant>);
+    /// 醒目留言的付费展示时间结束
+    #[signal]
+    fn super_chat_expired(message_id: i64);
+    /// 盲盒礼物开出，data 与对应的 gift 事件相同（blind_box 为 true），比普通 gift 事件多
+    /// original_gift_id、original_gift_name、original_price、revealed_price
+    #[signal]
+    fn blind_box_opened(data: Dictionary);
+    /// 观众在本场次内第一次发弹幕（kind 为 danmaku）或送礼物（gift），在对应的 `live_event` 之前发出；
+    /// 事件 data 中另带 first_in_session，配置了 first_interaction_store_path 时还带 first_ever
+    #[signal]
+    fn first_interaction(open_id: GString, kind: GString);
+    /// 醒目留言在到期前被服务端删除，之后不会再发出 `super_chat_expired`
+    #[signal]
+    fn super_chat_deleted(message_id: i64);
+    /// 直连模式：观众关注主播（含特别关注、互相关注），open_id 为观众 uid
+    #[signal]
+    fn viewer_followed(open_id: GString, uname: GString);
+    /// 直连模式：观众分享直播间
+    #[signal]
+    fn viewer_shared(open_id: GString, uname: GString);
+    #[signal]
+    fn stream_went_live();
+    #[signal]
+    fn stream_went_offline();
+    #[signal]
+    fn qr_login_ready(qr_url: GString);
+    /// waiting（未扫码）/ scanned（已扫码待确认）/ expired（二维码过期）
+    #[signal]
+    fn qr_login_status(status: GString);
+    #[signal]
+    fn login_succeeded(cookie_summary: GString);
+    #[signal]
+    fn login_failed(reason: GString);
+    /// info 包含 title、area、parent_area、cover、live_status、is_live、online 等字段
+    #[signal]
+    fn room_info_received(room_id: i64, info: Dictionary);
+    #[signal]
+    fn room_info_failed(room_id: i64, error_msg: GString);
+    #[signal]
+    fn danmaku_sent(text: GString);
+    #[signal]
+    fn danmaku_send_failed(text: GString, reason: GString);
+    /// 会话被自动关闭，reason 目前只有 stream_ended
+    #[signal]
+    fn session_closed(reason: GString);
+    /// `switch_session` 的进度，stage 依次为 starting、connecting（仅在迁移长连接时）、
+    /// ending_previous（仅在有旧场次时）和 completed；starting 阶段 new_game_id 为空
+    #[signal]
+    fn session_switch_progress(stage: GString, previous_game_id: GString, new_game_id: GString);
+    /// 新场次开启失败，当前场次、心跳和长连接保持不变
+    #[signal]
+    fn session_switch_failed(previous_game_id: GString, error: GString);
+    /// start 因身份码错误或过期失败（另有 `start_completed` 和 `error_occurred`）。
+    /// retries_left 大于 0 时可提示主播重新输入，并用 `set_code` 提交以自动重试
+    #[signal]
+    fn invalid_code(code: GString, error_code: i64, message: GString, retries_left: i64);
+    /// 每 `bandwidth_report_interval` 秒发出，内容同 `get_ws_stats`，另有 interval（秒）和
+    /// ws_receive_rate、ws_send_rate、http_receive_rate、http_send_rate（字节 / 秒）
+    #[signal]
+    fn bandwidth_report(report: Dictionary);
+    /// 压测结束：messages_per_minute、duration、generated（生成的消息数）、dispatched（到达主线程的事件数）、
+    /// dropped（未到达的消息数，含被互动门槛拒绝的）、decode_errors、baseline / loaded（空载与加载期间的
+    /// frames、avg_ms、p95_ms、max_ms）和 frame_time_increase_ms（平均帧时间的增量）
+    #[signal]
+    fn load_test_completed(report: Dictionary);
+    #[signal]
+    fn session_started(game_id: GString);
+    #[signal]
+    fn session_heartbeat_ok(game_id: GString);
+    /// 超过 45 秒没有成功的项目心跳，场次即将被平台关闭
+    #[signal]
+    fn session_expiring(game_id: GString);
+    /// `initialize` 完成，失败时 error 为原因
+    #[signal]
+    fn blive_ready(success: bool, error: GString);
+    /// 脚本第一次使用已弃用的信号名时发出，since_version 为开始弃用的 API 版本（同时输出警告）
+    #[signal]
+    fn deprecation_warning(old_name: GString, new_name: GString, since_version: i64);
+    /// 礼物目录已加载，count 为礼物数量
+    #[signal]
+    fn gift_catalog_updated(count: i64);
+    #[signal]
+    fn gift_catalog_failed(error: GString);
+    /// 弹幕译文：data 与 `live_event` 相同，另有 translated_message 和 translated_lang
+    #[signal]
+    fn danmaku_translated(data: Dictionary);
+    /// 一轮刷屏的汇总：data 为这一轮第一条弹幕的事件 data，另有 repeat_count（含第一条的总条数）、
+    /// unique_users（参与的观众数）和 duration（秒）
+    #[signal]
+    fn danmaku_collapsed(data: Dictionary);
+    /// 开启 `detect_chat_consensus` 后，窗口内弹幕集中在同一说法上时发出：text 为该类中最常见的原文，
+    /// ratio 为该类观众数占窗口内发弹幕观众数的比例（每位观众只按最近一条计）；同一说法持续达标时只发出一次
+    #[signal]
+    fn chat_consensus(text: GString, ratio: f64);
+    /// 审计日志无法打开（含已有记录校验失败）或写入，恢复写入前不再重复发出
+    #[signal]
+    fn audit_log_failed(error: GString);
+    #[signal]
+    fn translation_failed(data: Dictionary, error: GString);
+    /// webhook 投递在重试 max_attempts 次后仍失败，或接收方返回不可重试的 4xx
+    #[signal]
+    fn webhook_failed(delivery_id: GString, event_type: GString, error: GString);
+    /// 一个录制分段已结束（压缩开启时为压缩完成），path 为系统路径
+    #[signal]
+    fn recording_segment_closed(path: GString);
+    /// 录制文件无法创建、写入或压缩，录制随之停止
+    #[signal]
+    fn recording_failed(error: GString);
+    /// `get_asset` 开始的加载已完成，texture 同时放入内存缓存
+    #[signal]
+    fn asset_loaded(url: GString, texture: Gd<ImageTexture>);
+    #[signal]
+    fn asset_failed(url: GString, error: GString);
+    /// 项目心跳连续失败达到 `heartbeat_failure_threshold` 次，场次可能即将失效；恢复成功前不再重复发出
+    #[signal]
+    fn heartbeat_degraded(consecutive_failures: i64, last_error: GString);
+    /// 心跳暂停已超过 45 秒，平台约 60 秒没有心跳会关闭场次，每次暂停只发出一次
+    #[signal]
+    fn heartbeat_pause_too_long(paused_secs: f64);
+    /// 进入或退出降级模式；reason 为 ws_reply_timeout / queue_backlog / heartbeat_failing / recovered
+    #[signal]
+    fn degradation_changed(degraded: bool, reason: GString);
+    /// 降级期间代替 `ws_message_received`：counts 为这段时间内各 cmd 的消息数量
+    #[signal]
+    fn message_digest(counts: Dictionary);
+    /// reason：ended（调用 end）、stream_ended（自动关闭）、replaced（被新的 start 替换）
+    #[signal]
+    fn session_ended(game_id: GString, reason: GString);
+
+    /// 检查运行时和凭据配置，完成后台准备后发出 `blive_ready`；重复调用时在上次结果的基础上重新检查
+    ///
+    /// 调用过 initialize 后，`start` 会等到初始化成功才放行，未完成或失败时直接以错误响应
+    /// 发出 `start_completed`；从未调用 initialize 时 start 不受限制
+    #[func]
+    fn initialize(&mut self) {
+        godot_print!("initialize 函数被调用");
+        if self.ready_state == ReadyState::Initializing {
+            return;
+        }
+        if let Err(e) = self.validate_config() {
+            self.finish_initialize(false, e);
+            return;
+        }
+        self.ready_state = ReadyState::Initializing;
+        let Some(sender) = self.ws_message_tx.clone() else {
+            return;
+        };
+        if !self.prefetch_gift_catalog {
+            // 经由后台任务回到主线程，确认工作线程可用
+            self.runtime.handle().spawn(async move {
+                let _ = sender.send(ThreadMessage::Ready { result: Ok(()) });
+            });
+            return;
+        }
+        let path = self.gift_catalog_path();
+        let ttl_secs = self.gift_catalog_ttl_secs;
+        let http = self.http_client();
+        self.runtime.handle().spawn_blocking(move || {
+            let result = GiftCatalog::load_or_fetch(&http, &path, ttl_secs, events::now_ms());
+            let ready = match &result {
+                Ok(_) => Ok(()),
+                Err(e) => Err(format!("礼物目录加载失败: {}", e)),
+            };
+            let _ = sender.send(ThreadMessage::GiftCatalog { result });
+            let _ = sender.send(ThreadMessage::Ready { result: ready });
+        });
+    }
+
+    /// 加载礼物目录：缓存未过期时直接读取，否则从平台获取并写入缓存；
+    /// 完成后发出 `gift_catalog_updated` 或 `gift_catalog_failed`。force 为 true 时忽略缓存
+    #[func]
+    fn fetch_gift_catalog(&mut self, force: bool) {
+        let Some(sender) = self.ws_message_tx.clone() else {
+            return;
+        };
+        let path = self.gift_catalog_path();
+        let ttl_secs = if force {
+            -1.0
+        } else {
+            self.gift_catalog_ttl_secs
+        };
+        let http = self.http_client();
+        self.runtime.handle().spawn_blocking(move || {
+            let result = GiftCatalog::load_or_fetch(&http, &path, ttl_secs, events::now_ms());
+            let _ = sender.send(ThreadMessage::GiftCatalog { result });
+        });
+    }
+
+    /// 礼物目录中的 id、name、price（单价，千分之一元）、icon 和 coin_type，目录中没有时返回空字典
+    #[func]
+    fn get_gift_info(&self, gift_id: i64) -> Dictionary {
+        self.gift_catalog
+            .get(gift_id)
+            .and_then(|gift| gift.to_json().as_object().map(json_to_dictionary))
+            .unwrap_or_default()
+    }
+
+    /// initialize 已成功完成
+    #[func]
+    fn is_ready(&self) -> bool {
+        self.ready_state == ReadyState::Ready
+    }
+
+    /// 开启项目，通过 `start_completed` 返回完整响应，成功时另发出已解析字段的 `start_succeeded`
+    ///
+    /// 返回本次请求的 ID，与 `start_completed` 响应 JSON 中的 `request_id` 相同
+    #[func]
+    fn start(&mut self, code: GString) -> i64 {
+        godot_print!("start 函数被调用");
+        let request_id = self.next_request_id();
+        if self.reject_start(request_id) {
+            return request_id;
+        }
+        self.awaiting_code = None;
+        self.start_with_retries(
+            code.to_string(),
+            self.invalid_code_retries.max(0),
+            request_id,
+        );
+        request_id
+    }
+
+    /// 在 `invalid_code` 之后提交新的身份码并自动重新 start，返回重试的请求 ID；
+    /// 未在等待身份码时返回 0
+    #[func]
+    fn set_code(&mut self, code: GString) -> i64 {
+        let Some(retries_left) = self.awaiting_code.take() else {
+            godot_warn!("set_code: 当前没有等待重新输入的身份码");
+            return 0;
+        };
+        let request_id = self.next_request_id();
+        self.start_with_retries(code.to_string(), retries_left - 1, request_id);
+        request_id
+    }
+
+    /// 是否已发出 `invalid_code` 且正在等待 `set_code`
+    #[func]
+    fn is_awaiting_code(&self) -> bool {
+        self.awaiting_code.is_some()
+    }
+
+    /// 放弃等待新的身份码，之后的 `set_code` 返回 0
+    #[func]
+    fn cancel_code_retry(&mut self) {
+        self.awaiting_code = None;
+    }
+
+    /// 不停播切换到另一位主播：先用 new_code 开启新场次（失败时保持当前场次不变并发出
+    /// `session_switch_failed`），把进行中的心跳换成新场次，已连接开放平台长连接时立即连接新场次的
+    /// 长连接，最后在后台关闭旧场次（旧场次在新场次开启时即以 replaced 发出 `session_ended`）。
+    /// 各阶段通过 `session_switch_progress` 发出；已在切换中时返回 false
+    #[func]
+    fn switch_session(&mut self, new_code: GString) -> bool {
+        if self.reject_read_only("switch_session") {
+            return false;
+        }
+        if self.session_switch.is_some() {
+            godot_warn!("上一次场次切换尚未完成");
+            return false;
+        }
+        let previous = self.game_id.clone();
+        self.emit_switch_progress("starting", &previous, "");
+        let request_id = self.next_request_id();
+        let info = match self.start_game(&new_code.to_string(), request_id) {
+            Ok(info) => info,
+            Err(e) => {
+                self.emit(
+                    "session_switch_failed",
+                    &[previous.to_variant(), e.to_string().to_variant()],
+                );
+                return false;
+            }
+        };
+        let next = info.game_id.clone();
+
+        let mut migrated = false;
+        if !previous.is_empty() {
+            let mut guard = self.heartbeats.lock().unwrap();
+            let schedule = &mut *guard;
+            for game_id in schedule
+                .single
+                .iter_mut()
+                .chain(schedule.batch.iter_mut().flatten())
+            {
+                if *game_id == previous {
+                    *game_id = next.clone();
+                    migrated = true;
+                }
+            }
+        }
+        if migrated {
+            self.wake_heartbeat_scheduler();
+        }
+
+        let open_platform_connection = self.ws_running.load(Ordering::SeqCst)
+            && self.direct_room_id.load(Ordering::SeqCst) == 0;
+        if open_platform_connection && !info.wss_links.is_empty() {
+            self.emit_switch_progress("connecting", &previous, &next);
+            // 旧连接关闭后持有原来的运行标志退出，新连接使用新的标志
+            self.close_websocket();
+            self.ws_running = Arc::new(AtomicBool::new(false));
+            self.superseded_connections += 1;
+            self.spawn_websocket(WsTarget::OpenPlatform {
+                ws_urls: info.wss_links.clone(),
+                auth_body: info.auth_body.clone(),
+            });
+        }
+
+        if previous.is_empty() {
+            self.emit_switch_progress("completed", &previous, &next);
+        } else {
+            self.emit_switch_progress("ending_previous", &previous, &next);
+            self.session_switch = Some((previous.clone(), next));
+            self.end_async(GString::from(previous.as_str()));
+        }
+        true
+    }
+
+    /// 与 `start` 相同，但请求在后台 runtime 上发送，不阻塞当前帧；可用 `cancel_request` 取消。
+    /// 取消时已发出的请求仍可能到达平台：若随后收到成功响应，会自动关闭该场次
+    #[func]
+    fn start_async(&mut self, code: GString) -> i64 {
+        godot_print!("start_async 函数被调用");
+        let request_id = self.next_request_id();
+        if self.reject_start(request_id) {
+            return request_id;
+        }
+        self.awaiting_code = None;
+        let code = code.to_string();
+        let Some(sender) = self.ws_message_tx.clone() else {
+            self.start_with_retries(code, self.invalid_code_retries.max(0), request_id);
+            return request_id;
+        };
+        let Ok(body) = self.start_request_body(&code, request_id) else {
+            return request_id;
+        };
+        let credentials = self.api_credentials();
+        let task = self.runtime.handle().spawn(async move {
+            let (response, error) = credentials.post("/v2/app/start", body).await;
+            let _ = sender.send(ThreadMessage::StartCompleted {
+                request_id,
+                code,
+                response,
+                error,
+            });
+        });
+        self.in_flight.insert(request_id, task.abort_handle());
+        request_id
+    }
+
+    /// 取消 `start_async` / `end_async` / `request` 等后台请求：不再发出其完成信号，改为发出
+    /// `request_cancelled`（带回调的请求以 `{"code": -1, ...}` 调用回调）。请求已完成或不存在时返回 false
+    #[func]
+    fn cancel_request(&mut self, request_id: i64) -> bool {
+        let Some(handle) = self.in_flight.get(&request_id) else {
+            return false;
+        };
+        if handle.is_finished() {
+            return false;
+        }
+        handle.abort();
+        self.in_flight.remove(&request_id);
+        let response = serde_json::json!({
+            "request_id": request_id,
+            "code": -1,
+            "message": "请求已取消",
+        })
+        .to_string();
+        self.emit("request_cancelled", &[request_id.to_variant()]);
+        self.deliver_result(request_id, &response);
+        true
+    }
+
+    /// 取消所有未完成的后台请求，返回取消的数量
+    #[func]
+    fn cancel_all_requests(&mut self) -> i64 {
+        let mut request_ids: Vec<i64> = self.in_flight.keys().copied().collect();
+        request_ids.sort_unstable();
+        request_ids
+            .into_iter()
+            .filter(|request_id| self.cancel_request(*request_id))
+            .count() as i64
+    }
+
+    /// 未初始化完成或只读时发出失败的 `start_completed` 并返回 true
+    fn reject_start(&mut self, request_id: i64) -> bool {
+        let not_ready = match &self.ready_state {
+            _ if self.attachment.is_some() => Some("已附加到其他节点的会话，只读".to_string()),
+            ReadyState::Uninitialized | ReadyState::Ready => None,
+            ReadyState::Initializing => Some("初始化尚未完成".to_string()),
+            ReadyState::Failed(e) => Some(format!("初始化失败: {}", e)),
+        };
+        let Some(message) = not_ready else {
+            return false;
+        };
+        godot_error!("错误：{}", message);
+        let response = serde_json::json!({
+            "request_id": request_id,
+            "code": -1,
+            "message": message,
+        })
+        .to_string();
+        self.emit("start_completed", &[response.to_variant()]);
+        self.deliver_result(request_id, &response);
+        true
+    }
+
+    /// 被取消的 start 仍成功开启了场次时关闭它，避免留下没有心跳的场次
+    fn discard_cancelled_start(&mut self, response: &str) {
+        let json: serde_json::Value = serde_json::from_str(response).unwrap_or_default();
+        if let Some(info) = StartInfo::parse(&json) {
+            godot_print!("已取消的 start 开启了场次 {}，自动关闭", info.game_id);
+            self.end_async(GString::from(info.game_id.as_str()));
+        }
+    }
+
+    /// 身份码错误时发出 `invalid_code`，还有重试次数时等待 `set_code`
+    fn start_with_retries(&mut self, code: String, retries_left: i64, request_id: i64) {
+        let result = self.start_game(&code, request_id);
+        self.handle_start_result(code, retries_left, result);
+    }
+
+    /// `start_with_retries` 与 `start_async` 共用的结果处理
+    fn handle_start_result(
+        &mut self,
+        code: String,
+        retries_left: i64,
+        result: Result<StartInfo, BliveError>,
+    ) {
+        let Err(error) = result else {
+            return;
+        };
+        if !error.is_invalid_code() {
+            return;
+        }
+        self.awaiting_code = (retries_left > 0).then_some(retries_left);
+        let args = [
+            code.to_variant(),
+            error.code().to_variant(),
+            error.message().to_variant(),
+            retries_left.max(0).to_variant(),
+        ];
+        self.emit("invalid_code", &args);
+    }
+
+    /// 发送 start 请求并发出 `start_completed`，成功时另发出 `start_succeeded` 并返回场次信息
+    fn start_game(&mut self, code: &str, request_id: i64) -> Result<StartInfo, BliveError> {
+        let body = self.start_request_body(code, request_id)?;
+        let (response, error) = self.post("/v2/app/start", &body);
+        self.finish_start(request_id, response, error)
+    }
+
+    /// 身份码为空时不发送请求，按身份码错误发出 `start_completed`
+    fn start_request_body(&mut self, code: &str, request_id: i64) -> Result<String, BliveError> {
+        signing::start_body(code, self.app_id).map_err(|message| {
+            godot_error!("start 请求未发送: {}", message);
+            let error = BliveError::Api {
+                code: error::INVALID_CODE,
+                message,
+            };
+            self.report_error(&error);
+            let response = error::with_request_id(&error.to_response(), request_id);
+            self.emit("start_completed", &[response.to_variant()]);
+            self.deliver_result(request_id, &response);
+            error
+        })
+    }
+
+    fn finish_start(
+        &mut self,
+        request_id: i64,
+        response: String,
+        error: Option<BliveError>,
+    ) -> Result<StartInfo, BliveError> {
+        if let Some(error) = &error {
+            self.report_error(error);
+        }
+        let json: serde_json::Value = serde_json::from_str(&response).unwrap_or_default();
+        let info = StartInfo::parse(&json);
+        if let Some(info) = &info {
+            session::SESSION_CACHE.store(self.app_id, info, Instant::now());
+            self.adopt_session(info);
+        }
+        let response = error::with_request_id(&response, request_id);
+        self.emit("start_completed", &[response.to_variant()]);
+        self.deliver_result(request_id, &response);
+        match info {
+            Some(info) => {
+                self.emit_start_succeeded(info.clone());
+                Ok(info)
+            }
+            None => Err(error.unwrap_or_else(|| BliveError::Api {
+                code: -1,
+                message: "响应缺少场次信息".to_string(),
+            })),
+        }
+    }
+
+    fn adopt_session(&mut self, info: &StartInfo) {
+        self.ws_auth_body = GString::from(info.auth_body.as_str());
+        self.ws_links = info
+            .wss_links
+            .iter()
+            .map(|link| GString::from(link.as_str()))
+            .collect();
+        self.game_id = info.game_id.clone();
+        for transition in self.session.start(&info.game_id) {
+            self.emit_transition(transition);
+        }
+    }
+
+    /// 最近一次 start 成功的场次（进程内缓存，节点释放重建后仍可取回）：
+    /// `{"game_id", "wss_links", "auth_body", "anchor_info", "age_secs"}`，auth_body 已脱敏；
+    /// 没有缓存、不是当前 app_id 或已超过 `session_cache_ttl_secs` 时返回空 Dictionary
+    #[func]
+    fn get_cached_session(&self) -> Dictionary {
+        let Some((info, age)) =
+            session::SESSION_CACHE.get(self.app_id, self.session_cache_ttl_secs, Instant::now())
+        else {
+            return Dictionary::new();
+        };
+        serde_json::json!({
+            "game_id": info.game_id,
+            "wss_links": info.wss_links,
+            "auth_body": direct::redact_auth_body(&info.auth_body),
+            "anchor_info": info.anchor_info,
+            "age_secs": age,
+        })
+        .as_object()
+        .map(json_to_dictionary)
+        .unwrap_or_default()
+    }
+
+    /// 不调用 start，用缓存的场次恢复 game_id 等状态，连接第一个长连接地址并重新开始心跳；
+    /// 没有有效缓存时返回 false
+    #[func]
+    fn reconnect_websocket_from_cache(&mut self) -> bool {
+        godot_print!("reconnect_websocket_from_cache 函数被调用");
+        if self.reject_read_only("reconnect_websocket_from_cache") {
+            return false;
+        }
+        let cached =
+            session::SESSION_CACHE.get(self.app_id, self.session_cache_ttl_secs, Instant::now());
+        let Some((info, _)) = cached.filter(|(info, _)| !info.wss_links.is_empty()) else {
+            godot_warn!("没有可用的场次缓存，请重新调用 start");
+            return false;
+        };
+        self.adopt_session(&info);
+        let links: PackedStringArray = info.wss_links.iter().map(GString::from).collect();
+        self.start_websocket_links(links, GString::from(info.auth_body.as_str()));
+        self.start_heartbeat(GString::from(info.game_id.as_str()));
+        true
+    }
+
+    /// 关闭项目，成功后自动停止该场次的心跳（单场次心跳停止，批量心跳中移除该场次）
+    ///
+    /// game_id 传空字符串时使用最近一次 start 返回的场次。返回本次请求的 ID，
+    /// 与 `end_completed` 响应 JSON 中的 `request_id` 相同；未发送请求时返回 0
+    #[func]
+    fn end(&mut self, game_id: GString) -> i64 {
+        godot_print!("end 函数被调用");
+        if self.reject_read_only("end") {
+            return 0;
+        }
+        self.end_game(game_id, "ended")
+    }
+
+    /// 与 `end` 相同，但请求在后台 runtime 上发送，不阻塞当前帧；完成后同样发出 `end_completed`
+    #[func]
+    fn end_async(&mut self, game_id: GString) -> i64 {
+        godot_print!("end_async 函数被调用");
+        if self.reject_read_only("end_async") {
+            return 0;
+        }
+        let Some(game_id) = self.resolve_game_id(game_id) else {
+            return 0;
+        };
+        let Some(sender) = self.ws_message_tx.clone() else {
+            return self.end_game(game_id, "ended");
+        };
+        let request_id = self.next_request_id();
+        let game_id = game_id.to_string();
+        let body = signing::end_body(self.app_id, &game_id);
+        let credentials = self.api_credentials();
+        let task = self.runtime.handle().spawn(async move {
+            let (response, error) = credentials.post("/v2/app/end", body).await;
+            let _ = sender.send(ThreadMessage::EndCompleted {
+                request_id,
+                game_id,
+                response,
+                error,
+            });
+        });
+        self.in_flight.insert(request_id, task.abort_handle());
+        request_id
+    }
+
+    /// 向任意开放平台接口发送签名后的 POST 请求，用于本插件尚未封装的接口
+    ///
+    /// path 为接口路径（如 `/v2/app/batchHeartbeat`），body_json 为空时发送 `{}`。
+    /// 请求在后台 runtime 上发送，完成后发出 `request_completed(path, response_json)`；
+    /// 返回本次请求的 ID（即 response_json 中的 `request_id`），参数无效时返回 0
+    #[func]
+    fn request(&mut self, path: GString, body_json: GString) -> i64 {
+        if self.reject_read_only("request") {
+            return 0;
+        }
+        let path = path.to_string();
+        if !path.starts_with('/') {
+            godot_error!("错误：接口路径必须以 / 开头: {}", path);
+            return 0;
+        }
+        let body = match body_json.to_string().trim() {
+            "" => "{}".to_string(),
+            body => body.to_string(),
+        };
+        if let Err(e) = serde_json::from_str::<serde_json::Value>(&body) {
+            godot_error!("错误：请求体不是合法的 JSON: {}", e);
+            return 0;
+        }
+        self.spawn_request(path, ApiCall::Post(body))
+    }
+
+    /// 向开放平台接口发送签名后的 GET 请求，query 的键值转为字符串后按键排序编码到查询串中；
+    /// 完成后同样发出 `request_completed`，返回请求 ID，路径无效时返回 0
+    #[func]
+    fn request_get(&mut self, path: GString, query: Dictionary) -> i64 {
+        if self.reject_read_only("request_get") {
+            return 0;
+        }
+        let path = path.to_string();
+        if !path.starts_with('/') {
+            godot_error!("错误：接口路径必须以 / 开头: {}", path);
+            return 0;
+        }
+        let query = query
+            .iter_shared()
+            .map(|(key, value)| (key.stringify().to_string(), value.stringify().to_string()))
+            .collect();
+        self.spawn_request(path, ApiCall::Get(query))
+    }
+
+    /// 与 `request_get` 相同，完成后以解析后的响应延迟调用 callback
+    #[func]
+    fn request_get_with_callback(
+        &mut self,
+        path: GString,
+        query: Dictionary,
+        callback: Callable,
+    ) -> i64 {
+        self.with_callback(callback, |this| this.request_get(path, query))
+    }
+
+    /// 与 `start` 相同，另在 `start_completed` 之后以解析后的响应（Dictionary，含 request_id）
+    /// 在主线程上延迟调用 callback；返回请求 ID
+    #[func]
+    fn start_with_callback(&mut self, code: GString, callback: Callable) -> i64 {
+        self.with_callback(callback, |this| this.start(code))
+    }
+
+    /// 与 `end_async` 相同，完成后以解析后的响应延迟调用 callback；
+    /// 未发送请求（如没有可关闭的场次）时以 `{"code": -1, ...}` 调用
+    #[func]
+    fn end_with_callback(&mut self, game_id: GString, callback: Callable) -> i64 {
+        self.with_callback(callback, |this| this.end_async(game_id))
+    }
+
+    /// 与 `request` 相同，完成后以解析后的响应延迟调用 callback
+    #[func]
+    fn request_with_callback(
+        &mut self,
+        path: GString,
+        body_json: GString,
+        callback: Callable,
+    ) -> i64 {
+        self.with_callback(callback, |this| this.request(path, body_json))
+    }
+
+    /// path（如 `/v2/app/start`）最近一次响应的 `{"status", "headers"}`，尚未请求过时为空
+    #[func]
+    fn get_last_http_response(&self, path: GString) -> Dictionary {
+        self.http_responses
+            .get(&path.to_string())
+            .and_then(|meta| meta.to_json().as_object().map(json_to_dictionary))
+            .unwrap_or_default()
+    }
+
+    /// 自检：配置校验、API 与长连接主机的 DNS 解析、TLS 握手、时钟偏差估算和一次签名的空心跳请求，
+    /// 结果通过 `diagnostics_completed` 发出，可附在问题反馈中
+    #[func]
+    fn run_diagnostics(&mut self) {
+        let Some(sender) = self.ws_message_tx.clone() else {
+            return;
+        };
+        let config = self.validate_config();
+        let signed = config.is_ok() && self.app_id != 0;
+        let ws_host = match self.ws_links.as_slice().first() {
+            Some(link) => conn_history::host_of(&link.to_string()),
+            None => diagnostics::DEFAULT_WS_HOST.to_string(),
+        };
+        let credentials = self.api_credentials();
+        self.runtime
+            .handle()
+            .spawn(Self::diagnose(config, signed, credentials, ws_host, sender));
+    }
+
+    /// 启动项目心跳，立即发送一次，之后每 20 秒发送一次；game_id 传空字符串时使用最近一次 start 返回的场次
+    ///
+    /// 返回本次启动的请求 ID，此后每次 `heartbeat_completed` 的响应 JSON 都带有该 `request_id`；
+    /// 心跳已在运行或未启动时返回 0
+    #[func]
+    fn start_heartbeat(&mut self, game_id: GString) -> i64 {
+        godot_print!("start_heartbeat 函数被调用");
+        if self.reject_read_only("start_heartbeat") {
+            return 0;
+        }
+        let Some(game_id) = self.resolve_game_id(game_id) else {
+            return 0;
+        };
+        let heartbeats = self.heartbeats.clone();
+        let mut schedule = heartbeats.lock().unwrap();
+        if schedule.single.is_some() {
+            godot_print!("心跳已经在运行中");
+            return 0;
+        }
+        let request_id = self.next_request_id();
+        schedule.single = Some(game_id.to_string());
+        schedule.single_request_id = request_id;
+        drop(schedule);
+        self.wake_heartbeat_scheduler();
+        request_id
+    }
+
+    /// 公开接口的版本，随函数、信号或导出属性的新增和改名递增
+    #[func]
+    fn get_api_version(&self) -> i64 {
+        api::API_VERSION
+    }
+
+    // 旧信号名的转发入口，由 `register_signal_aliases` 按参数个数连接，脚本不应直接调用
+    #[func]
+    fn _forward_signal_alias_0(&mut self, old: GString) {
+        self.forward_signal_alias(old, &[]);
+    }
+
+    #[func]
+    fn _forward_signal_alias_1(&mut self, a: Variant, old: GString) {
+        self.forward_signal_alias(old, &[a]);
+    }
+
+    #[func]
+    fn _forward_signal_alias_2(&mut self, a: Variant, b: Variant, old: GString) {
+        self.forward_signal_alias(old, &[a, b]);
+    }
+
+    #[func]
+    fn _forward_signal_alias_3(&mut self, a: Variant, b: Variant, c: Variant, old: GString) {
+        self.forward_signal_alias(old, &[a, b, c]);
+    }
+
+    #[func]
+    fn _forward_signal_alias_4(
+        &mut self,
+        a: Variant,
+        b: Variant,
+        c: Variant,
+        d: Variant,
+        old: GString,
+    ) {
+        self.forward_signal_alias(old, &[a, b, c, d]);
+    }
+
+    #[func]
+    fn get_ws_auth_body(&self) -> GString {
+        GString::from(direct::redact_auth_body(&self.ws_auth_body.to_string()).as_str())
+    }
+
+    /// 最近一次 start 成功返回的场次 ID，没有进行中的场次时为空
+    #[func]
+    fn get_game_id(&self) -> GString {
+        GString::from(self.game_id.as_str())
+    }
+
+    /// 导出当前场次的会话凭据（game_id、长连接地址和鉴权包），有效期 ttl_secs 秒（1~86400），
+    /// 以 access_key_secret 签名；同一应用的其他进程可用 `import_session_handle` 接入同一场次。
+    /// 没有进行中的场次时返回空字符串
+    #[func]
+    fn export_session_handle(&self, ttl_secs: i64) -> GString {
+        if self.game_id.is_empty() || self.ws_auth_body.is_empty() {
+            godot_error!("错误：没有可导出的场次，请先调用 start");
+            return GString::new();
+        }
+        let handle = SessionHandle {
+            game_id: self.game_id.clone(),
+            ws_links: self
+                .ws_links
+                .as_slice()
+                .iter()
+                .map(|link| link.to_string())
+                .collect(),
+            auth_body: self.ws_auth_body.to_string(),
+            expires_at_ms: events::now_ms() + ttl_secs.clamp(1, 86400) * 1000,
+        };
+        GString::from(handle.encode(&self.access_key_secret.to_string()).as_str())
+    }
+
+    /// 校验 `export_session_handle` 导出的凭据并连接其中的长连接，成功返回 true
+    ///
+    /// 需要与导出方相同的 access_key_secret。接入方只接收消息，心跳和 end 仍由导出方负责
+    #[func]
+    fn import_session_handle(&mut self, handle: GString) -> bool {
+        if self.reject_read_only("import_session_handle") {
+            return false;
+        }
+        let handle = match SessionHandle::decode(
+            &handle.to_string(),
+            &self.access_key_secret.to_string(),
+            events::now_ms(),
+        ) {
+            Ok(handle) => handle,
+            Err(e) => {
+                godot_error!("{}", e);
+                return false;
+            }
+        };
+        godot_print!("已导入场次 {}", handle.game_id);
+        self.game_id = handle.game_id;
+        self.ws_auth_body = GString::from(handle.auth_body.as_str());
+        self.ws_links = handle
+            .ws_links
+            .iter()
+            .map(|link| GString::from(link.as_str()))
+            .collect();
+        self.spawn_websocket(WsTarget::OpenPlatform {
+            ws_urls: handle.ws_links,
+            auth_body: handle.auth_body,
+        });
+        true
+    }
+
+    #[func]
+    fn stop_heartbeat(&mut self) {
+        godot_print!("stop_heartbeat 函数被调用");
+        if self.heartbeats.lock().unwrap().single.take().is_some() {
+            self.heartbeat_notify.notify_one();
+        }
+    }
+
+    /// 暂停单场次和批量心跳（如已知的维护窗口或切换场次期间），心跳场次和调度任务保留；
+    /// 平台约 60 秒没有心跳会关闭场次，暂停超过 45 秒时发出 `heartbeat_pause_too_long`
+    #[func]
+    fn pause_heartbeats(&mut self) {
+        if self.heartbeat_paused_at.is_some() {
+            return;
+        }
+        self.heartbeats.lock().unwrap().paused = true;
+        self.heartbeat_paused_at = Some((self.elapsed, false));
+        godot_print!("心跳已暂停");
+    }
+
+    /// 恢复心跳并立即发送一轮
+    #[func]
+    fn resume_heartbeats(&mut self) {
+        let Some((paused_at, _)) = self.heartbeat_paused_at.take() else {
+            return;
+        };
+        self.heartbeats.lock().unwrap().paused = false;
+        godot_print!("心跳已恢复，暂停了 {:.1} 秒", self.elapsed - paused_at);
+        self.wake_heartbeat_scheduler();
+    }
+
+    #[func]
+    fn is_heartbeats_paused(&self) -> bool {
+        self.heartbeat_paused_at.is_some()
+    }
+
+    /// 批量心跳，单次最多 200 个场次，与单场次心跳共用同一个定时任务；
+    /// 返回值与 `start_heartbeat` 相同，对应 `batch_heartbeat_completed` 中的 `request_id`
+    #[func]
+    fn start_batch_heartbeat(&mut self, game_ids: Array<GString>) -> i64 {
+        godot_print!("start_batch_heartbeat 函数被调用，数量: {}", game_ids.len());
+        if self.reject_read_only("start_batch_heartbeat") {
+            return 0;
+        }
+        if game_ids.is_empty() {
+            godot_error!("错误：game_ids 为空");
+            return 0;
+        }
+        if game_ids.len() > 200 {
+            godot_warn!("警告：game_ids 数量超过 200，可能会失败");
+        }
+        let heartbeats = self.heartbeats.clone();
+        let mut schedule = heartbeats.lock().unwrap();
+        if schedule.batch.is_some() {
+            godot_print!("批量心跳已经在运行中");
+            return 0;
+        }
+        let request_id = self.next_request_id();
+        schedule.batch = Some(game_ids.iter_shared().map(|id| id.to_string()).collect());
+        schedule.batch_request_id = request_id;
+        drop(schedule);
+        self.wake_heartbeat_scheduler();
+        request_id
+    }
+
+    #[func]
+    fn stop_batch_heartbeat(&mut self) {
+        godot_print!("stop_batch_heartbeat 函数被调用");
+        if self.heartbeats.lock().unwrap().batch.take().is_some() {
+            self.heartbeat_notify.notify_one();
+        }
+    }
+
+    /// 更新批量心跳的场次列表，下一轮心跳生效
+    #[func]
+    fn update_batch_heartbeat_game_ids(&mut self, game_ids: Array<GString>) {
+        godot_print!(
+            "update_batch_heartbeat_game_ids 函数被调用，数量: {}",
+            game_ids.len()
+        );
+        if let Some(batch) = self.heartbeats.lock().unwrap().batch.as_mut() {
+            *batch = game_ids.iter_shared().map(|id| id.to_string()).collect();
+        }
+        godot_print!("批量心跳 game_ids 已更新");
+    }
+
+    /// 连接长连接服务器，鉴权后开始接收消息
+    #[func]
+    fn start_websocket(&mut self, ws_url: GString, auth_body: GString) {
+        godot_print!("start_websocket 函数被调用");
+        godot_print!("Auth body 长度: {}", auth_body.len());
+        if self.reject_read_only("start_websocket") {
+            return;
+        }
+        self.spawn_websocket(WsTarget::OpenPlatform {
+            ws_urls: vec![ws_url.to_string()],
+            auth_body: auth_body.to_string(),
+        });
+    }
+
+    /// 与 `start_websocket` 相同，但接受 start 返回的全部 wss_link（如 `get_ws_links()`）：
+    /// 按顺序连接，连接失败时换下一个地址，每次选定地址时发出 `ws_host_selected`；全部失败后发出 `ws_disconnected`
+    #[func]
+    fn start_websocket_links(&mut self, links: PackedStringArray, auth_body: GString) {
+        godot_print!(
+            "start_websocket_links 函数被调用，共 {} 个地址",
+            links.len()
+        );
+        if self.reject_read_only("start_websocket_links") {
+            return;
+        }
+        let ws_urls: Vec<String> = links
+            .as_slice()
+            .iter()
+            .map(|link| link.to_string())
+            .filter(|link| !link.is_empty())
+            .collect();
+        if ws_urls.is_empty() {
+            godot_error!("错误：没有可用的长连接地址");
+            return;
+        }
+        self.spawn_websocket(WsTarget::OpenPlatform {
+            ws_urls,
+            auth_body: auth_body.to_string(),
+        });
+    }
+
+    /// 以 PEM 内容设置额外信任的根证书（`tls_ca_cert_path` 为空时生效），传空数组清除；
+    /// 证书无效时返回 false 且不修改当前设置。之后新建的 HTTP 请求和长连接使用新证书
+    #[func]
+    fn set_tls_ca_certificate(&mut self, pem: PackedByteArray) -> bool {
+        let pem = pem.to_vec();
+        if !pem.is_empty() {
+            if let Err(e) = tls::pem_certificates(&pem) {
+                godot_error!("根证书无效: {}", e);
+                return false;
+            }
+        }
+        self.tls_ca_pem = pem;
+        true
+    }
+
+    /// 直连模式：不使用开放平台凭据，直接连接公开弹幕服务器读取直播间消息（支持短号）
+    ///
+    /// 消息的 cmd 为网页端原始命令（如 `DANMU_MSG`），同样通过 `ws_message_received` 发出
+    #[func]
+    fn start_room_websocket(&mut self, room_id: i64) {
+        godot_print!("start_room_websocket 函数被调用: room_id={}", room_id);
+        if self.reject_read_only("start_room_websocket") {
+            return;
+        }
+        let credentials = DirectCredentials::from_cookie(&self.cookie.to_string());
+        if !credentials.is_guest() {
+            godot_print!("使用直连凭据: {}", credentials.redacted());
+        }
+        self.spawn_websocket(WsTarget::Room {
+            room_id,
+            credentials,
+        });
+    }
+
+    /// 按字段设置直连模式凭据，等价于把对应的 Cookie 写入 `cookie`；bili_jct 仅发送弹幕时需要，可留空
+    #[func]
+    fn set_direct_credentials(
+        &mut self,
+        sessdata: GString,
+        buvid: GString,
+        uid: i64,
+        bili_jct: GString,
+    ) {
+        let credentials = DirectCredentials {
+            uid,
+            sessdata: sessdata.to_string(),
+            buvid: buvid.to_string(),
+            csrf: bili_jct.to_string(),
+        };
+        godot_print!("直连凭据已更新: {}", credentials.redacted());
+        self.cookie = GString::from(credentials.cookie_header().as_str());
+    }
+
+    /// 当前连接是否为未登录的直连模式，此时消息 JSON 顶层带有 `"guest": true`，用户名可能被打码
+    #[func]
+    fn is_guest_session(&self) -> bool {
+        self.ws_guest.load(Ordering::SeqCst)
+    }
+
+    /// 扫码登录：先发出 `qr_login_ready`（可用 `make_qr_image` 生成图片），
+    /// 用户在手机端确认后自动写入 `cookie` 并发出 `login_succeeded`
+    #[func]
+    fn start_qr_login(&mut self) {
+        godot_print!("start_qr_login 函数被调用");
+        if self.qr_login_running.swap(true, Ordering::SeqCst) {
+            godot_print!("扫码登录已经在进行中");
+            return;
+        }
+        let Some(sender) = self.ws_message_tx.clone() else {
+            return;
+        };
+        let running = self.qr_login_running.clone();
+        let http = self.http_client();
+
+        self.runtime.handle().spawn(async move {
+            let client = http.clone();
+            let qrcode_key =
+                match tokio::task::spawn_blocking(move || login::generate(&client)).await {
+                    Ok(Ok((url, key))) => {
+                        send_signal_to_main(&sender, "qr_login_ready", vec![url]);
+                        key
+                    }
+                    Ok(Err(e)) => {
+                        send_signal_to_main(&sender, "login_failed", vec![e]);
+                        running.store(false, Ordering::SeqCst);
+                        return;
+                    }
+                    Err(e) => {
+                        send_signal_to_main(&sender, "login_failed", vec![e.to_string()]);
+                        running.store(false, Ordering::SeqCst);
+                        return;
+                    }
+                };
+
+            let mut last_status = None;
+            while running.load(Ordering::SeqCst) {
+                tokio::time::sleep(Duration::from_secs(QR_LOGIN_POLL_SECS)).await;
+                let key = qrcode_key.clone();
+                let client = http.clone();
+                let status =
+                    match tokio::task::spawn_blocking(move || login::poll(&client, &key)).await {
+                        Ok(Ok(status)) => status,
+                        Ok(Err(e)) => {
+                            send_signal_to_main(&sender, "login_failed", vec![e]);
+                            break;
+                        }
+                        Err(e) => {
+                            send_signal_to_main(&sender, "login_failed", vec![e.to_string()]);
+                            break;
+                        }
+                    };
+                let name = match &status {
+                    PollStatus::Succeeded(cookie) => {
+                        let _ = sender.send(ThreadMessage::LoginSucceeded {
+                            cookie: cookie.clone(),
+                        });
+                        break;
+                    }
+                    PollStatus::Waiting => "waiting",
+                    PollStatus::Scanned => "scanned",
+                    PollStatus::Expired => "expired",
+                };
+                if last_status != Some(name) {
+                    send_signal_to_main(&sender, "qr_login_status", vec![name.to_string()]);
+                    last_status = Some(name);
+                }
+                if status == PollStatus::Expired {
+                    break;
+                }
+            }
+            running.store(false, Ordering::SeqCst);
+        });
+    }
+
+    /// 以 `cookie` 中的账号向直连模式所在直播间发送弹幕，结果通过 `danmaku_sent` / `danmaku_send_failed` 返回
+    ///
+    /// 发送过快时直接失败（reason 为 rate_limited），返回值表示请求是否已提交
+    #[func]
+    fn send_danmaku(&mut self, text: GString) -> bool {
+        let room_id = self.direct_room_id.load(Ordering::SeqCst);
+        let reason = if self.attachment.is_some() {
+            Some("read_only")
+        } else if room_id == 0 {
+            Some("not_connected")
+        } else if text.is_empty() {
+            Some("empty_text")
+        } else if self
+            .last_danmaku_sent
+            .is_some_and(|last| last.elapsed() < DANMAKU_SEND_INTERVAL)
+        {
+            Some("rate_limited")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            self.emit(
+                "danmaku_send_failed",
+                &[text.to_variant(), reason.to_variant()],
+            );
+            return false;
+        }
+        let Some(sender) = self.ws_message_tx.clone() else {
+            return false;
+        };
+
+        self.last_danmaku_sent = Some(Instant::now());
+        let credentials = DirectCredentials::from_cookie(&self.cookie.to_string());
+        let text = text.to_string();
+        let http = self.http_client();
+        self.runtime.handle().spawn_blocking(move || {
+            match direct::send_danmaku(&http, room_id, &text, &credentials) {
+                Ok(()) => send_signal_to_main(&sender, "danmaku_sent", vec![text]),
+                Err(e) => send_signal_to_main(&sender, "danmaku_send_failed", vec![text, e]),
+            }
+        });
+        true
+    }
+
+    /// 查询直播间标题、分区、封面和开播状态，结果通过 `room_info_received` 返回并缓存
+    #[func]
+    fn fetch_room_info(&mut self, room_id: i64) {
+        let Some(sender) = self.ws_message_tx.clone() else {
+            return;
+        };
+        let http = self.http_client();
+        self.runtime.handle().spawn_blocking(move || {
+            let result = direct::fetch_room_info(&http, room_id);
+            let _ = sender.send(ThreadMessage::RoomInfo { room_id, result });
+        });
+    }
+
+    /// 直播状态：长连接收到开播 / 下播消息时立即更新，另外每 interval_secs 秒查询一次房间状态，
+    /// 重连期间错过的状态切换也能补上
+    #[func]
+    fn start_live_status_polling(&mut self, room_id: i64, interval_secs: f64) {
+        let Some(sender) = self.ws_message_tx.clone() else {
+            return;
+        };
+        if self.live_poll_running.swap(true, Ordering::SeqCst) {
+            godot_print!("直播状态轮询已经在运行中");
+            return;
+        }
+        let running = self.live_poll_running.clone();
+        let http = self.http_client();
+        let interval = Duration::from_secs_f64(interval_secs.max(5.0));
+        self.runtime.handle().spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            while running.load(Ordering::SeqCst) {
+                ticker.tick().await;
+                if !running.load(Ordering::SeqCst) {
+                    break;
+                }
+                let client = http.clone();
+                let info =
+                    tokio::task::spawn_blocking(move || direct::fetch_room_info(&client, room_id));
+                match info.await {
+                    Ok(Ok(info)) => {
+                        let live = info["is_live"].as_bool().unwrap_or(false);
+                        let _ = sender.send(ThreadMessage::LiveStatus { live });
+                    }
+                    Ok(Err(e)) => send_signal_to_main(
+                        &sender,
+                        "ws_debug",
+                        vec![format!("查询直播状态失败: {}", e)],
+                    ),
+                    Err(e) => send_signal_to_main(
+                        &sender,
+                        "ws_debug",
+                        vec![format!("查询直播状态任务失败: {}", e)],
+                    ),
+                }
+            }
+        });
+    }
+
+    #[func]
+    fn stop_live_status_polling(&mut self) {
+        self.live_poll_running.store(false, Ordering::SeqCst);
+    }
+
+    /// 当前是否在直播，状态未知时返回 false
+    #[func]
+    fn is_live(&self) -> bool {
+        self.live_state.unwrap_or(false)
+    }
+
+    /// 返回最近一次 `fetch_room_info` 的结果，没有缓存时返回空 Dictionary
+    #[func]
+    fn get_cached_room_info(&self, room_id: i64) -> Dictionary {
+        self.room_info_cache
+            .get(&room_id)
+            .and_then(|info| info.as_object())
+            .map(json_to_dictionary)
+            .unwrap_or_default()
+    }
+
+    /// 本地时钟减平台时钟的毫秒数（正数表示本地偏快），由收到的事件时间估算，尚无事件时为 0
+    #[func]
+    fn get_clock_offset_ms(&self) -> i64 {
+        self.clock.offset_ms().unwrap_or(0)
+    }
+
+    /// 按时钟偏移换算的平台当前时间（Unix 毫秒），用于和平台时间戳对齐的倒计时等
+    #[func]
+    fn get_server_time_ms(&self) -> i64 {
+        self.clock.server_time_ms(events::now_ms())
+    }
+
+    /// 最近 20 次项目心跳的统计：samples、success_rate（0~1）、avg_rtt_ms、last_rtt_ms、
+    /// consecutive_failures、degraded 和 last_error
+    #[func]
+    fn get_heartbeat_health(&self) -> Dictionary {
+        self.heartbeat_health
+            .to_json()
+            .as_object()
+            .map(json_to_dictionary)
+            .unwrap_or_default()
+    }
+
+    /// 确认已处理可靠投递的事件，不再重新发出；事件不存在或已确认时返回 false
+    #[func]
+    fn ack_event(&mut self, event_id: i64) -> bool {
+        self.acks.ack(event_id)
+    }
+
+    /// 尚未确认的可靠投递事件，每项为 `live_event` 的 data 另加 event_type
+    #[func]
+    fn get_unacked_events(&self) -> Array<Dictionary> {
+        self.acks
+            .unacked()
+            .iter()
+            .filter_map(|event| event.as_object())
+            .map(json_to_dictionary)
+            .collect()
+    }
+
+    /// 放弃所有未确认的事件
+    #[func]
+    fn clear_unacked_events(&mut self) {
+        self.acks.clear();
+    }
+
+    /// 注册一个定时合成事件，到期后与平台事件一样经 `live_event` 发出，data 另有 synthetic = true、
+    /// schedule_id 和 fire_count；interval_secs > 0 时周期重复，repeat 为总次数（0 表示不限）。返回计划 ID
+    #[func]
+    fn schedule_event(
+        &mut self,
+        event_type: GString,
+        data: Dictionary,
+        delay_secs: f64,
+        interval_secs: f64,
+        repeat: i64,
+    ) -> i64 {
+        self.scheduler.schedule(
+            &event_type.to_string(),
+            dictionary_to_json(&data),
+            self.elapsed,
+            delay_secs,
+            interval_secs,
+            repeat,
+        )
+    }
+
+    /// delay_secs 秒后发出一条 announcement 事件，data 为 {message}
+    #[func]
+    fn schedule_announcement(&mut self, message: GString, delay_secs: f64) -> i64 {
+        self.scheduler.schedule(
+            events::EVENT_ANNOUNCEMENT,
+            serde_json::json!({ "message": message.to_string() }),
+            self.elapsed,
+            delay_secs,
+            0.0,
+            1,
+        )
+    }
+
+    #[func]
+    fn cancel_scheduled_event(&mut self, schedule_id: i64) -> bool {
+        self.scheduler.cancel(schedule_id)
+    }
+
+    #[func]
+    fn clear_scheduled_events(&mut self) {
+        self.scheduler.clear();
+    }
+
+    /// 以只读方式附加到另一个 Blive 节点的会话：不建立连接，直接收到对方已解析的
+    /// live_event、ws_message_received、连接状态等信号。附加期间 start / end / 心跳 / 连接 /
+    /// 发送类方法会被忽略；对方释放后自动解除并发出 ws_disconnected
+    #[func]
+    fn attach_to_session(&mut self, primary: Gd<Blive>) -> bool {
+        if primary.instance_id() == self.to_gd().instance_id() {
+            godot_error!("不能附加到自身的会话");
+            return false;
+        }
+        let (attachment, connected) = {
+            let primary = primary.bind();
+            (primary.shared.subscribe(), primary.ws_connected)
+        };
+        self.attachment = Some(attachment);
+        if connected && !std::mem::replace(&mut self.ws_connected, true) {
+            self.emit("ws_connected", &[]);
+        }
+        true
+    }
+
+    #[func]
+    fn detach_session(&mut self) {
+        if self.attachment.take().is_some() && std::mem::take(&mut self.ws_connected) {
+            self.emit("ws_disconnected", &[]);
+        }
+    }
+
+    #[func]
+    fn is_attached(&self) -> bool {
+        self.attachment.is_some()
+    }
+
+    /// 最近的长连接尝试（最多 50 条，按时间先后），每项包含 host、started_ms、
+    /// outcome（failed / disconnected / stopped）、error_category（dns / tls / timeout / refused /
+    /// http / closed / network / resolve / other，正常停止时为空）、error 和 duration_ms
+    #[func]
+    fn get_connection_history(&self) -> Array<Dictionary> {
+        self.connection_history
+            .as_ref()
+            .map(ConnectionHistory::to_json)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|attempt| attempt.as_object())
+            .map(json_to_dictionary)
+            .collect()
+    }
+
+    #[func]
+    fn clear_connection_history(&mut self) {
+        if let Some(history) = self.connection_history.as_mut() {
+            history.clear();
+        }
+    }
+
+    /// 开启或关闭可选子系统（stats / recorder / webhooks / combos / super_chat_timers），
+    /// 默认全部开启；关闭时释放其状态（录制中则结束录制，webhook 设置被清除），
+    /// 之后不再为其处理事件。名称未知时返回 false
+    #[func]
+    fn set_subsystem_enabled(&mut self, name: GString, enabled: bool) -> bool {
+        let name = match subsystems::lookup(&name.to_string()) {
+            Ok(name) => name,
+            Err(e) => {
+                godot_error!("{}", e);
+                return false;
+            }
+        };
+        if !self.subsystems.set(name, enabled) || enabled {
+            return true;
+        }
+        match name {
+            subsystems::STATS => self.connection_history = None,
+            subsystems::RECORDER => self.stop_recording(),
+            subsystems::WEBHOOKS => self.webhook = None,
+            subsystems::COMBOS => self.combos = None,
+            subsystems::SUPER_CHAT_TIMERS => self.super_chats = None,
+            _ => {}
+        }
+        true
+    }
+
+    #[func]
+    fn is_subsystem_enabled(&self, name: GString) -> bool {
+        self.subsystems.is_enabled(&name.to_string())
+    }
+
+    /// 当前开启的子系统名称
+    #[func]
+    fn get_enabled_subsystems(&self) -> PackedStringArray {
+        self.subsystems
+            .enabled()
+            .into_iter()
+            .map(GString::from)
+            .collect()
+    }
+
+    /// 取得 url 对应的纹理（头像、礼物图片等）：内存缓存命中时直接返回，否则返回 null 并在后台
+    /// 依次读取磁盘缓存、下载，完成后发出 `asset_loaded` 或 `asset_failed`
+    #[func]
+    fn get_asset(&mut self, url: GString) -> Option<Gd<ImageTexture>> {
+        let url = url.to_string();
+        if url.is_empty() {
+            return None;
+        }
+        if let Some(texture) = self.asset_textures.get(&url) {
+            return Some(texture);
+        }
+        let sender = self.ws_message_tx.clone()?;
+        if !self.asset_loading.insert(url.clone()) {
+            return None;
+        }
+        let disk = self.asset_disk.clone();
+        let dir = (!self.asset_cache_dir.is_empty()).then(|| globalize_path(&self.asset_cache_dir));
+        let max_disk_bytes = (self.asset_disk_cache_mb.max(0.0) * 1024.0 * 1024.0) as u64;
+        let http = self.http_client();
+        self.runtime.handle().spawn_blocking(move || {
+            let result = fetch_asset(&http, &disk, dir, &url, max_disk_bytes);
+            let _ = sender.send(ThreadMessage::AssetFetched { url, result });
+        });
+        None
+    }
+
+    /// 清空内存和磁盘中的资源缓存
+    #[func]
+    fn clear_asset_cache(&mut self) {
+        self.asset_textures.clear();
+        if let Some(disk) = self.asset_disk.lock().unwrap().1.as_mut() {
+            disk.clear();
+        }
+    }
+
+    /// 资源缓存的 memory_entries、memory_bytes、disk_entries、disk_bytes（磁盘缓存尚未打开时为 0）
+    #[func]
+    fn get_asset_cache_stats(&self) -> Dictionary {
+        let (disk_entries, disk_bytes) = match self.asset_disk.lock().unwrap().1.as_ref() {
+            Some(disk) => (disk.len() as i64, disk.total_size() as i64),
+            None => (0, 0),
+        };
+        let mut stats = Dictionary::new();
+        stats.set("memory_entries", self.asset_textures.len() as i64);
+        stats.set("memory_bytes", self.asset_textures.total_size() as i64);
+        stats.set("disk_entries", disk_entries);
+        stats.set("disk_bytes", disk_bytes);
+        stats
+    }
+
+    /// 尚未结束的定时事件，每项包含 id、event_type、data、remaining（距下次触发的秒数）、interval 和 fired
+    #[func]
+    fn get_scheduled_events(&self) -> Array<Dictionary> {
+        self.scheduler
+            .pending(self.elapsed)
+            .iter()
+            .filter_map(|event| event.as_object())
+            .map(json_to_dictionary)
+            .collect()
+    }
+
+    /// 清空本场次的首次互动记录，之后每位观众的下一条弹幕 / 礼物重新算作本场次首次；
+    /// 场次变化时会自动清空，持久化记录不受影响
+    #[func]
+    fn reset_first_interactions(&mut self) {
+        self.first_interactions.reset_session();
+    }
+
+    /// 校验审计日志的哈希链，path 为空时校验 `audit_log_path`；返回 ok、entries（记录数）和 error
+    #[func]
+    fn verify_audit_log(&self, path: GString) -> Dictionary {
+        let path = if path.is_empty() {
+            &self.audit_log_path
+        } else {
+            &path
+        };
+        let result = match audit::verify(&globalize_path(path)) {
+            Ok((entries, _)) => serde_json::json!({ "ok": true, "entries": entries, "error": "" }),
+            Err(e) => serde_json::json!({ "ok": false, "entries": 0, "error": e }),
+        };
+        result
+            .as_object()
+            .map(json_to_dictionary)
+            .unwrap_or_default()
+    }
+
+    /// 把之后收到的每条 `ws_message_received` 录制到 dir 下新建的 `session-<Unix 毫秒>` 目录，
+    /// 分段按 `recording_max_segment_mb` / `recording_max_segment_minutes` 切换，
+    /// 并按 `recording_keep_sessions` 删除更早的会话；已在录制时先结束上一次录制
+    #[func]
+    fn start_recording(&mut self, dir: GString) -> bool {
+        self.stop_recording();
+        if !self.subsystems.is_enabled(subsystems::RECORDER) {
+            godot_error!("recorder 子系统已关闭");
+            return false;
+        }
+        let options = RecorderOptions {
+            max_segment_bytes: (self.recording_max_segment_mb.max(0.0) * 1024.0 * 1024.0) as u64,
+            max_segment_ms: (self.recording_max_segment_minutes.max(0.0) * 60_000.0) as i64,
+            keep_sessions: self.recording_keep_sessions.max(0) as usize,
+        };
+        match SessionRecorder::start(&globalize_path(&dir), options, events::now_ms()) {
+            Ok(recorder) => {
+                self.recorder = Some(recorder);
+                true
+            }
+            Err(e) => {
+                godot_error!("{}", e);
+                self.emit("recording_failed", &[e.to_variant()]);
+                false
+            }
+        }
+    }
+
+    /// 结束录制，最后一个分段同样发出 `recording_segment_closed`
+    #[func]
+    fn stop_recording(&mut self) {
+        let Some(recorder) = self.recorder.take() else {
+            return;
+        };
+        match recorder.finish() {
+            Ok(path) => self.close_segment(path),
+            Err(e) => self.report_recording_failure(e),
+        }
+    }
+
+    #[func]
+    fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// 本次录制的会话目录（系统路径），未在录制时为空
+    #[func]
+    fn get_recording_dir(&self) -> GString {
+        self.recorder
+            .as_ref()
+            .map(|recorder| GString::from(recorder.session_dir().to_string_lossy().as_ref()))
+            .unwrap_or_default()
+    }
+
+    /// received（收到的弹幕总数，含被折叠的）和 collapsed（被折叠未单独发出的条数）
+    #[func]
+    fn get_spam_stats(&self) -> Dictionary {
+        self.spam
+            .stats()
+            .as_object()
+            .map(json_to_dictionary)
+            .unwrap_or_default()
+    }
+
+    /// 当前长连接在多个线程上解析消息时返回 true：同一观众的事件仍按到达顺序发出，
+    /// 不同观众之间的先后不再保证，可按 live_event 的 data.seq 还原
+    #[func]
+    fn is_global_order_relaxed(&self) -> bool {
+        self.ws_running.load(Ordering::SeqCst) && self.ws_parse_workers > 0
+    }
+
+    /// 压测：先测量 2 秒空载帧时间，再以 messages_per_minute 的速率持续 duration 秒生成合成的弹幕、
+    /// 免费礼物和点赞，封成与平台相同的 zlib 压缩包，经完整的解包 → 解析 → 主线程分发流程发出
+    /// `live_event` 等信号，结束后发出 `load_test_completed`。合成事件的 user_id 以 `load-test-` 开头，
+    /// 不投递到 webhook；已在压测时返回 false
+    #[func]
+    fn start_load_test(&mut self, messages_per_minute: i64, duration: f64) -> bool {
+        if self.load_test.is_some() || self.ws_message_tx.is_none() {
+            return false;
+        }
+        self.load_test = Some(LoadTest::new(
+            messages_per_minute.clamp(1, 1_000_000) as u64,
+            duration.clamp(0.1, 3600.0),
+        ));
+        // 生成任务启动前提前结束时不需要等待排空
+        self.load_generator = Arc::new(GeneratorCounters::default());
+        self.load_generator.finished.store(true, Ordering::SeqCst);
+        true
+    }
+
+    /// 提前结束生成，排空队列后照常发出 `load_test_completed`
+    #[func]
+    fn stop_load_test(&mut self) {
+        if let Some(Step::StopGenerator) = self.load_test.as_mut().and_then(|test| test.stop()) {
+            self.load_generator.stop.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[func]
+    fn is_load_test_running(&self) -> bool {
+        self.load_test.is_some()
+    }
+
+    /// 累计流量：ws_bytes_received、ws_bytes_sent、ws_frames_received、ws_frames_sent、
+    /// ws_packets_decoded（解出的包数）、ws_messages（业务消息数）、ws_connections、ws_reconnects，
+    /// 以及开放平台 HTTP 请求的 http_requests、http_bytes_sent、http_bytes_received（请求体 / 响应体）；
+    /// ws_message_rate 为最近约 5 秒内平均每秒的业务消息数
+    #[func]
+    fn get_ws_stats(&self) -> Dictionary {
+        Self::ws_stats(&self.traffic, &self.ws_message_window, self.elapsed)
+    }
+
+    /// 与 `get_ws_stats` 相同，但只统计 `start_websocket_for` 启动的该连接；
+    /// 主连接的 `get_ws_stats` 和 `bandwidth_report` 不包含附加连接。没有该连接时返回空 Dictionary
+    #[func]
+    fn get_ws_stats_for(&self, conn_id: GString) -> Dictionary {
+        self.keyed_connections
+            .get(&conn_id.to_string())
+            .map(|connection| {
+                Self::ws_stats(
+                    &connection.traffic,
+                    &connection.message_window,
+                    self.elapsed,
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    /// 进行中的礼物连击，每项包含 user_id、uname、gift_id、gift_name、count（累计数量）、
+    /// price（累计总价）、elapsed（已持续秒数）和 remaining（无新礼物时距结束的秒数）
+    #[func]
+    fn get_active_combos(&mut self) -> Array<Dictionary> {
+        let Some(combos) = self.combos.as_mut() else {
+            return Array::new();
+        };
+        combos.prune(self.elapsed);
+        combos
+            .active(self.elapsed)
+            .iter()
+            .filter_map(|combo| combo.as_object())
+            .map(json_to_dictionary)
+            .collect()
+    }
+
+    #[func]
+    fn cancel_qr_login(&mut self) {
+        self.qr_login_running.store(false, Ordering::SeqCst);
+    }
+
+    /// 把文本（通常是 `qr_login_ready` 给出的地址）生成灰度二维码图片，scale 为每个模块的像素数
+    #[func]
+    fn make_qr_image(&self, text: GString, scale: i64) -> Option<Gd<Image>> {
+        match login::qr_pixels(&text.to_string(), scale.max(1) as usize) {
+            Ok((size, pixels)) => Image::create_from_data(
+                size as i32,
+                size as i32,
+                false,
+                Format::L8,
+                &PackedByteArray::from(pixels),
+            ),
+            Err(e) => {
+                godot_error!("{}", e);
+                None
+            }
+        }
+    }
+
+    /// 运行状态汇总，供看门狗脚本和排查问题使用
+    ///
+    /// 包含 runtime_alive、ws_state（disconnected / connecting / connected）、
+    /// seconds_since_ws_heartbeat_reply、seconds_since_api_heartbeat_ok（从未成功时为 -1）、
+    /// queue_depth（尚未转换为信号的后台消息数）、active_tasks（运行中的后台任务名）
+    /// traffic（同 `get_ws_stats`）、global_order_relaxed、attached（是否以只读方式附加到其他节点的会话）
+    /// 和 shared_attachments（附加到本节点会话的节点数）
+    #[func]
+    fn health_check(&self) -> Dictionary {
+        let seconds_since = |at: &Mutex<Option<Instant>>| {
+            at.lock()
+                .unwrap()
+                .map(|at| at.elapsed().as_secs_f64())
+                .unwrap_or(-1.0)
+        };
+        let ws_state = if self.ws_connected {
+            "connected"
+        } else if self.ws_running.load(Ordering::SeqCst) {
+            "connecting"
+        } else {
+            "disconnected"
+        };
+        let active_tasks: Vec<&str> = [
+            ("websocket", &self.ws_running),
+            ("live_status_polling", &self.live_poll_running),
+            ("qr_login", &self.qr_login_running),
+        ]
+        .into_iter()
+        .filter(|(_, running)| running.load(Ordering::SeqCst))
+        .map(|(name, _)| name)
+        .collect();
+        let schedule = self.heartbeats.lock().unwrap();
+        let heartbeat_tasks = [
+            ("heartbeat", schedule.single.is_some()),
+            ("batch_heartbeat", schedule.batch.is_some()),
+        ];
+        drop(schedule);
+        let active_tasks: Vec<&str> = heartbeat_tasks
+            .into_iter()
+            .filter(|(_, active)| *active)
+            .map(|(name, _)| name)
+            .chain(active_tasks)
+            .collect();
+
+        let mut health = serde_json::Map::new();
+        health.insert("runtime_alive".into(), self.runtime.is_alive().into());
+        health.insert("ws_state".into(), ws_state.into());
+        health.insert(
+            "seconds_since_ws_heartbeat_reply".into(),
+            seconds_since(&self.last_ws_heartbeat_reply).into(),
+        );
+        health.insert(
+            "seconds_since_api_heartbeat_ok".into(),
+            seconds_since(&self.last_api_heartbeat_ok).into(),
+        );
+        health.insert(
+            "queue_depth".into(),
+            self.ws_message_rx.lock().unwrap().len().into(),
+        );
+        health.insert("active_tasks".into(), active_tasks.into());
+        health.insert("traffic".into(), self.traffic.snapshot().to_json());
+        health.insert(
+            "global_order_relaxed".into(),
+            self.is_global_order_relaxed().into(),
+        );
+        health.insert("attached".into(), self.attachment.is_some().into());
+        health.insert(
+            "shared_attachments".into(),
+            self.shared.attachments().into(),
+        );
+        json_to_dictionary(&health)
+    }
+
+    #[func]
+    fn stop_websocket(&mut self) {
+        godot_print!("stop_websocket 函数被调用");
+        self.close_websocket();
+        self.ws_guest.store(false, Ordering::SeqCst);
+        self.direct_room_id.store(0, Ordering::SeqCst);
+    }
+
+    /// 在主连接之外再建立一条开放平台长连接，用于同时接入多个直播间；该连接的所有信号
+    /// 都通过 `ws_connection_event(conn_id, name, args)` 发出，不影响主连接和场次状态，
+    /// 流量单独统计（见 `get_ws_stats_for`）。conn_id 已在使用或参数为空时返回 false
+    #[func]
+    fn start_websocket_for(&mut self, conn_id: GString, url: GString, auth_body: GString) -> bool {
+        godot_print!("start_websocket_for 函数被调用: {}", conn_id);
+        if self.reject_read_only("start_websocket_for") {
+            return false;
+        }
+        let conn_id = conn_id.to_string();
+        if conn_id.is_empty() || url.is_empty() {
+            godot_error!("错误：连接 ID 和长连接地址不能为空");
+            return false;
+        }
+        let in_use = self
+            .keyed_connections
+            .get(&conn_id)
+            .is_some_and(|connection| connection.running.load(Ordering::SeqCst));
+        if in_use {
+            godot_warn!("连接 {} 已经在运行中", conn_id);
+            return false;
+        }
+        let traffic = Arc::new(TrafficStats::default());
+        let Some(connection) =
+            self.spawn_keyed_connection(&conn_id, url.to_string(), auth_body.to_string(), traffic)
+        else {
+            return false;
+        };
+        self.keyed_connections.insert(conn_id, connection);
+        true
+    }
+
+    /// 停止 `start_websocket_for` 启动的连接，随后发出该连接的 ws_disconnected；
+    /// 没有该连接时返回 false
+    #[func]
+    fn stop_websocket_for(&mut self, conn_id: GString) -> bool {
+        let Some(connection) = self.keyed_connections.remove(&conn_id.to_string()) else {
+            return false;
+        };
+        connection.running.store(false, Ordering::SeqCst);
+        connection.stopper.stop();
+        true
+    }
+
+    /// `start_websocket_for` 启动的连接 ID，按名称排序；连接断开后直到该连接的 ws_disconnected 发出为止
+    /// 仍在列表中（超时重连时一直保留），`stop_websocket_for` 会立即移除
+    #[func]
+    fn get_websocket_connections(&self) -> PackedStringArray {
+        let mut ids: Vec<&String> = self.keyed_connections.keys().collect();
+        ids.sort();
+        ids.into_iter().map(GString::from).collect()
+    }
+
+    /// 与 `send_raw_packet` 相同，但通过 `start_websocket_for` 启动的连接发送
+    #[func]
+    fn send_raw_packet_for(
+        &mut self,
+        conn_id: GString,
+        operation: i64,
+        body: PackedByteArray,
+    ) -> bool {
+        let Some(connection) = self.keyed_connections.get(&conn_id.to_string()) else {
+            godot_warn!("连接 {} 不存在", conn_id);
+            return false;
+        };
+        let packet = self
+            .protocol
+            .encode_packet(body.as_slice(), operation as u32);
+        connection.outbound_tx.send(packet).is_ok()
+    }
+
+    /// 通过已鉴权的长连接发送任意操作码的包，包头由 `encode_packet` 生成，经写入任务发出；
+    /// 未连接时返回 false。`send_ws_packet` 是它的别名
+    #[func]
+    fn send_raw_packet(&mut self, operation: i64, body: PackedByteArray) -> bool {
+        if self.reject_read_only("send_raw_packet") {
+            return false;
+        }
+        if !self.ws_running.load(Ordering::SeqCst) {
+            godot_warn!("WebSocket 未连接，无法发送");
+            return false;
+        }
+        let Some(outbound) = self.ws_outbound_tx.as_ref() else {
+            return false;
+        };
+        let packet = self
+            .protocol
+            .encode_packet(body.as_slice(), operation as u32);
+        outbound.send(packet).is_ok()
+    }
+
+    /// 与 `send_raw_packet` 相同
+    #[func]
+    fn send_ws_packet(&mut self, operation: i64, body: PackedByteArray) -> bool {
+        self.send_raw_packet(operation, body)
+    }
+
+    /// 覆盖长连接协议参数，下次 `start_websocket` 时生效
+    ///
+    /// 可用的键：header_length、version、zlib_version、brotli_version、op_heartbeat、op_heartbeat_reply、
+    /// op_message、op_auth、op_auth_reply、max_message_size（单条消息字节数，默认 1 MiB）、
+    /// max_decompressed_size（一条消息解压后的总字节数，默认 8 MiB）；auth_protover 改写鉴权包请求的压缩方式
+    /// （0 不压缩、2 zlib、3 brotli，负数恢复原样，仅在鉴权包为 JSON 时生效）；
+    /// 未知键或越界值会被忽略并打印警告
+    #[func]
+    fn set_protocol_options(&mut self, options: Dictionary) {
+        for (key, value) in options.iter_shared() {
+            let key = key.stringify().to_string();
+            let applied = match value.try_to::<i64>() {
+                Ok(value) => self.protocol.set(&key, value),
+                Err(_) => false,
+            };
+            if !applied {
+                godot_warn!("忽略无效的协议参数: {} = {}", key, value);
+            }
+        }
+    }
+
+    #[func]
+    fn reset_protocol_options(&mut self) {
+        self.protocol = Protocol::default();
+    }
+
+    /// 调整降级策略，立即生效
+    ///
+    /// 可用的键：reply_age_secs（长连接心跳回复超时秒数，默认 70）、queue_depth（积压消息数，默认 2000）、
+    /// recover_secs（恢复后保持多少秒才退出降级，默认 10）、frame_budget / degraded_frame_budget
+    /// （正常 / 降级时每帧最多处理的消息数，0 不限，默认 0 / 200）、digest_interval（`message_digest`
+    /// 间隔秒数，默认 1）；未知键或负数会被忽略并打印警告
+    #[func]
+    fn set_degradation_options(&mut self, options: Dictionary) {
+        for (key, value) in options.iter_shared() {
+            let key = key.stringify().to_string();
+            let applied = match value.try_to::<f64>() {
+                Ok(value) => self.degradation.set(&key, value),
+                Err(_) => false,
+            };
+            if !applied {
+                godot_warn!("忽略无效的降级参数: {} = {}", key, value);
+            }
+        }
+    }
+
+    /// 是否处于降级模式；头像、礼物图标等非必要的加载可据此暂停
+    #[func]
+    fn is_degraded(&self) -> bool {
+        self.degradation.is_degraded()
+    }
+
+    /// 为弹幕指令（弹幕第一个词，如 "!join"）设置粉丝勋章门槛，不满足时丢弃该弹幕并发出 `interaction_rejected`
+    #[func]
+    fn set_command_medal_requirement(
+        &mut self,
+        command: GString,
+        min_medal_level: i64,
+        require_room_medal: bool,
+    ) {
+        let mut gate = self.interaction_gate.lock().unwrap();
+        let requirement = gate.command_mut(&command.to_string());
+        requirement.min_medal_level = min_medal_level;
+        requirement.require_room_medal = require_room_medal;
+    }
+
+    /// 为游戏侧子系统（排队、投票等）设置粉丝勋章门槛，配合 `check_subsystem_access` 使用
+    #[func]
+    fn set_subsystem_medal_requirement(
+        &mut self,
+        subsystem: GString,
+        min_medal_level: i64,
+        require_room_medal: bool,
+    ) {
+        let mut gate = self.interaction_gate.lock().unwrap();
+        let requirement = gate.subsystem_mut(&subsystem.to_string());
+        requirement.min_medal_level = min_medal_level;
+        requirement.require_room_medal = require_room_medal;
+    }
+
+    /// 限定弹幕指令仅舰长及以上可用：`guard_level` 为 3 表示任意舰队成员，2 提督及以上，1 仅总督，0 取消限制
+    #[func]
+    fn set_command_guard_requirement(&mut self, command: GString, guard_level: i64) {
+        self.interaction_gate
+            .lock()
+            .unwrap()
+            .command_mut(&command.to_string())
+            .guard_level = guard_level;
+    }
+
+    /// 限定子系统（排队、投票等）仅舰队成员可用，`guard_level` 含义同 `set_command_guard_requirement`
+    #[func]
+    fn set_subsystem_guard_requirement(&mut self, subsystem: GString, guard_level: i64) {
+        self.interaction_gate
+            .lock()
+            .unwrap()
+            .subsystem_mut(&subsystem.to_string())
+            .guard_level = guard_level;
+    }
+
+    /// 把 cmds 中的消息（为空时全部）转发给 group 内所有节点的 method(cmd, data)，data 为 Dictionary
+    #[func]
+    fn add_group_forward(&mut self, group: GString, method: GString, cmds: PackedStringArray) {
+        self.group_forwards.push(GroupForward {
+            group: group.to_string(),
+            method: method.to_string(),
+            cmds: cmds.as_slice().iter().map(|cmd| cmd.to_string()).collect(),
+        });
+    }
+
+    #[func]
+    fn clear_group_forwards(&mut self) {
+        self.group_forwards.clear();
+    }
+
+    /// 只发出这些语言（如 "zh"、"ja"、"und"）的弹幕事件，为空时不过滤；需要开启 `detect_language`
+    #[func]
+    fn set_language_filter(&mut self, langs: PackedStringArray) {
+        self.language_filter = langs
+            .as_slice()
+            .iter()
+            .map(|lang| lang.to_string())
+            .collect();
+    }
+
+    /// 把 lang 语言（"*" 表示全部）的弹幕转发给 group 内所有节点的 method(lang, data)，
+    /// data 与 `live_event` 相同；需要开启 `detect_language`
+    #[func]
+    fn add_language_route(&mut self, lang: GString, group: GString, method: GString) {
+        self.language_routes.push(LanguageRoute {
+            lang: lang.to_string(),
+            group: group.to_string(),
+            method: method.to_string(),
+        });
+    }
+
+    #[func]
+    fn clear_language_routes(&mut self) {
+        self.language_routes.clear();
+    }
+
+    /// 把 event_types 中的 `live_event`（为空时全部事件）以 JSON POST 到 url，
+    /// 请求体为 `{"delivery_id", "event_type", "data", "sent_ms"}`，
+    /// `X-GDBlive-Signature` 为 `sha256=` + HMAC-SHA256(secret, "{X-GDBlive-Timestamp}.{请求体}")；
+    /// 网络错误、429 和 5xx 按 1、2、4 … 秒退避重试，共尝试 max_attempts 次。url 为空时关闭
+    #[func]
+    fn set_webhook(
+        &mut self,
+        url: GString,
+        secret: GString,
+        event_types: PackedStringArray,
+        max_attempts: i64,
+    ) {
+        if url.is_empty() {
+            self.webhook = None;
+            return;
+        }
+        if !self.subsystems.is_enabled(subsystems::WEBHOOKS) {
+            godot_error!("webhooks 子系统已关闭");
+            return;
+        }
+        let event_types = event_types
+            .as_slice()
+            .iter()
+            .map(|event_type| event_type.to_string())
+            .collect();
+        self.webhook = Some(Webhook::new(
+            &url.to_string(),
+            &secret.to_string(),
+            event_types,
+            max_attempts.clamp(1, 10) as u32,
+        ));
+    }
+
+    #[func]
+    fn clear_webhook(&mut self) {
+        self.webhook = None;
+    }
+
+    /// 把与 target_lang 语言不同的弹幕 POST 到 url 翻译，请求体为
+    /// `{"text": 原文, "source": 原文语言, "target": 目标语言}`，响应为 `{"text": 译文}`；
+    /// 译文通过 `danmaku_translated` 发出，`live_event` 仍立即以原文发出。url 为空时关闭翻译
+    #[func]
+    fn set_translation_endpoint(&mut self, url: GString, target_lang: GString) {
+        self.translator = (!url.is_empty()).then(|| Translator::Endpoint(url.to_string()));
+        self.translation =
+            TranslationPipeline::new(&target_lang.to_string(), self.translation_rate_per_sec);
+    }
+
+    /// 用 Callable 翻译弹幕：`callable(request_id, text, source_lang, target_lang)` 直接返回译文，
+    /// 或返回空字符串并在稍后调用 `submit_translation(request_id, text)`；10 秒内没有结果视为失败
+    #[func]
+    fn set_translation_callable(&mut self, callable: Callable, target_lang: GString) {
+        self.translator = Some(Translator::Callable(callable));
+        self.translation =
+            TranslationPipeline::new(&target_lang.to_string(), self.translation_rate_per_sec);
+    }
+
+    /// 提交异步翻译的结果，text 为空表示翻译失败
+    #[func]
+    fn submit_translation(&mut self, request_id: i64, text: GString) {
+        let result = if text.is_empty() {
+            Err("翻译失败".to_string())
+        } else {
+            Ok(text.to_string())
+        };
+        self.finish_translation(request_id, result);
+    }
+
+    #[func]
+    fn clear_translation_cache(&mut self) {
+        self.translation.clear_cache();
+    }
+
+    /// cache_size（缓存的译文数）、pending（等待中的请求）和 rate_limited（因限流未翻译的弹幕数）
+    #[func]
+    fn get_translation_stats(&self) -> Dictionary {
+        self.translation
+            .stats()
+            .as_object()
+            .map(json_to_dictionary)
+            .unwrap_or_default()
+    }
+
+    #[func]
+    fn clear_interaction_requirements(&mut self) {
+        self.interaction_gate.lock().unwrap().clear();
+    }
+
+    /// 检查消息 data（`ws_message_received` 中的 data 字段）对应的观众能否使用子系统，不能时发出 `interaction_rejected`
+    #[func]
+    fn check_subsystem_access(&mut self, subsystem: GString, data_json: GString) -> bool {
+        let data: serde_json::Value =
+            serde_json::from_str(&data_json.to_string()).unwrap_or_default();
+        let viewer = Viewer::from_data(&data);
+        let result = self
+            .interaction_gate
+            .lock()
+            .unwrap()
+            .check_subsystem(&subsystem.to_string(), &viewer);
+        match result {
+            Ok(()) => true,
+            Err(reason) => {
+                self.emit(
+                    "interaction_rejected",
+                    &[viewer.open_id.to_variant(), reason.to_variant()],
+                );
+                false
+            }
+        }
+    }
+}
+
+impl Blive {
+    /// 启动一条附加连接的任务，消息带上 conn_id 转发到主线程；traffic 在重连时沿用
+    fn spawn_keyed_connection(
+        &mut self,
+        conn_id: &str,
+        url: String,
+        auth_body: String,
+        traffic: Arc<TrafficStats>,
+    ) -> Option<KeyedConnection> {
+        let main_sender = self.ws_message_tx.clone()?;
+        let running = Arc::new(AtomicBool::new(true));
+        let (outbound_tx, outbound_rx) = mpsc::unbounded_channel();
+        let (stopper, stop) = ws_stop::channel();
+        let session = WsSession {
+            ws_urls: vec![url.clone()],
+            auth_body: auth_body.clone(),
+            protocol: self.protocol.clone(),
+            guest: false,
+            parse_workers: self.parse_workers.clamp(0, 16) as usize,
+            tls: self.tls_options(),
+            reply_timeout: (self.ws_reply_timeout_secs > 0.0)
+                .then(|| Duration::from_secs_f64(self.ws_reply_timeout_secs)),
+            stop,
+        };
+        // 连接任务的消息先进入单独的通道，再带上连接 ID 转发到主线程
+        let (sender, mut rx) = mpsc::unbounded_channel();
+        let forward_id = conn_id.to_string();
+        self.runtime.handle().spawn(async move {
+            while let Some(message) = rx.recv().await {
+                let message = ThreadMessage::Connection {
+                    conn_id: forward_id.clone(),
+                    message: Box::new(message),
+                };
+                if main_sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+        self.runtime.handle().spawn(Self::run_websocket(
+            session,
+            running.clone(),
+            self.interaction_gate.clone(),
+            sender,
+            outbound_rx,
+            Arc::new(Mutex::new(None)),
+            traffic.clone(),
+        ));
+        Some(KeyedConnection {
+            url,
+            auth_body,
+            running,
+            stopper,
+            outbound_tx,
+            traffic,
+            message_window: RateWindow::default(),
+            reconnect_pending: false,
+        })
+    }
+
+    /// 附加连接发出 ws_disconnected 后：收到过 ws_timeout 时按原地址重连，否则移除该连接。
+    /// 同一 ID 已被新的 `start_websocket_for` 占用时不做处理
+    fn keyed_connection_ended(&mut self, conn_id: &str) {
+        let Some(connection) = self.keyed_connections.get_mut(conn_id) else {
+            return;
+        };
+        if connection.running.load(Ordering::SeqCst) {
+            return;
+        }
+        let reconnect = std::mem::take(&mut connection.reconnect_pending);
+        let Some(previous) = self.keyed_connections.remove(conn_id) else {
+            return;
+        };
+        if !reconnect {
+            return;
+        }
+        godot_print!("连接 {} 心跳回复超时，重新连接", conn_id);
+        let restarted = self.spawn_keyed_connection(
+            conn_id,
+            previous.url,
+            previous.auth_body,
+            previous.traffic,
+        );
+        if let Some(mut connection) = restarted {
+            connection.message_window = previous.message_window;
+            self.keyed_connections
+                .insert(conn_id.to_string(), connection);
+        }
+    }
+
+    /// 主连接和附加连接共用的 `get_ws_stats` 结果
+    fn ws_stats(traffic: &TrafficStats, message_window: &RateWindow, now: f64) -> Dictionary {
+        let snapshot = traffic.snapshot();
+        let mut stats = snapshot.to_json();
+        stats["ws_message_rate"] = message_window.rate(now, snapshot.ws_messages).into();
+        stats
+            .as_object()
+            .map(json_to_dictionary)
+            .unwrap_or_default()
+    }
+
+    /// 附加连接的消息不经过主连接的状态处理（场次、统计、降级等），转换为 `ws_connection_event`；
+    /// ws_timeout 之后的 ws_disconnected 会按原地址重连该连接
+    fn handle_connection_message(
+        &mut self,
+        conn_id: String,
+        message: ThreadMessage,
+        engine_now: (Instant, u64),
+    ) {
+        let events: Vec<(String, Vec<Variant>)> = match message {
+            ThreadMessage::Signal { name, args } => {
+                vec![(name, args.iter().map(|arg| arg.to_variant()).collect())]
+            }
+            ThreadMessage::JsonSignal { name, args } => {
+                if name == "ws_timeout" {
+                    if let Some(connection) = self.keyed_connections.get_mut(&conn_id) {
+                        connection.reconnect_pending = true;
+                    }
+                }
+                vec![(name, args.iter().map(json_to_variant).collect())]
+            }
+            ThreadMessage::LiveEvent {
+                event_type,
+                mut data,
+                received_at,
+            } => {
+                data["received_ticks_msec"] = engine_ticks_at(received_at, engine_now).into();
+                vec![(
+                    "live_event".to_string(),
+                    vec![event_type.to_variant(), json_to_variant(&data)],
+                )]
+            }
+            ThreadMessage::LiveStatus { live } => {
+                let name = if live {
+                    "stream_went_live"
+                } else {
+                    "stream_went_offline"
+                };
+                vec![(name.to_string(), Vec::new())]
+            }
+            ThreadMessage::SuperChatDeleted { message_ids } => message_ids
+                .into_iter()
+                .map(|id| ("super_chat_deleted".to_string(), vec![id.to_variant()]))
+                .collect(),
+            ThreadMessage::RawPacket { operation, body } => vec![(
+                "ws_raw_packet".to_string(),
+                vec![
+                    (operation as i64).to_variant(),
+                    PackedByteArray::from(body).to_variant(),
+                ],
+            )],
+            ThreadMessage::Error(error) => vec![(
+                "error_occurred".to_string(),
+                vec![
+                    error.domain().to_variant(),
+                    error.code().to_variant(),
+                    error.message().to_variant(),
+                ],
+            )],
+            ThreadMessage::ConnectionAttempt(attempt) => {
+                let attempt = attempt
+                    .to_json()
+                    .as_object()
+                    .map(json_to_dictionary)
+                    .unwrap_or_default();
+                vec![("connection_attempt".to_string(), vec![attempt.to_variant()])]
+            }
+            // 下播已随 LiveStatus 发出 stream_went_offline，自动关闭场次只针对主连接
+            ThreadMessage::StreamEnded => Vec::new(),
+            // 长连接任务不会发出其余消息
+            _ => Vec::new(),
+        };
+        for (name, args) in events {
+            let args: Array<Variant> = args.into_iter().collect();
+            self.emit(
+                "ws_connection_event",
+                &[conn_id.to_variant(), name.to_variant(), args.to_variant()],
+            );
+            if name == "ws_disconnected" {
+                self.keyed_connection_ended(&conn_id);
+            }
+        }
+    }
+
+    fn update_live_state(&mut self, live: bool) {
+        if self.live_state.replace(live) == Some(live) {
+            return;
+        }
+        let signal = if live {
+            "stream_went_live"
+        } else {
+            "stream_went_offline"
+        };
+        self.emit(signal, &[]);
+    }
+
+    /// 同步关闭项目，返回请求 ID，未指定场次时返回 0
+    fn end_game(&mut self, game_id: GString, reason: &str) -> i64 {
+        let Some(game_id) = self.resolve_game_id(game_id) else {
+            return 0;
+        };
+        let request_id = self.next_request_id();
+        let body = signing::end_body(self.app_id, &game_id.to_string());
+        let (response, error) = self.post("/v2/app/end", &body);
+        self.finish_end(&game_id.to_string(), reason, request_id, response, error);
+        request_id
+    }
+
+    /// 分配请求 ID；`with_callback` 期间分配的第一个 ID 与其 Callable 关联
+    fn next_request_id(&mut self) -> i64 {
+        self.last_request_id += 1;
+        if let Some(callback) = self.next_callback.take() {
+            self.result_callbacks.insert(self.last_request_id, callback);
+        }
+        self.last_request_id
+    }
+
+    /// 执行 call 并把其中发起的请求与 callback 关联；没有发起请求（返回 0）时以错误响应调用 callback
+    fn with_callback(&mut self, callback: Callable, call: impl FnOnce(&mut Self) -> i64) -> i64 {
+        self.next_callback = Some(callback);
+        let request_id = call(self);
+        if let Some(callback) = self.next_callback.take() {
+            let response = serde_json::json!({ "code": -1, "message": "请求未发送" });
+            callback.call_deferred(&[json_to_variant(&response)]);
+        }
+        request_id
+    }
+
+    /// 以解析后的响应（Dictionary）延迟调用 request_id 关联的 Callable
+    fn deliver_result(&mut self, request_id: i64, response: &str) {
+        let Some(callback) = self.result_callbacks.remove(&request_id) else {
+            return;
+        };
+        let response = serde_json::from_str(response)
+            .unwrap_or_else(|_| serde_json::json!({ "code": -1, "message": response }));
+        callback.call_deferred(&[json_to_variant(&response)]);
+    }
+
+    /// 关闭项目成功后停止该场次的心跳并发出 `end_completed`
+    fn finish_end(
+        &mut self,
+        game_id: &str,
+        reason: &str,
+        request_id: i64,
+        response: String,
+        error: Option<BliveError>,
+    ) {
+        if let Some(error) = &error {
+            self.report_error(error);
+        }
+        let json: serde_json::Value = serde_json::from_str(&response).unwrap_or_default();
+        if json["code"].as_i64() == Some(0) {
+            let game_id = game_id.to_string();
+            let mut schedule = self.heartbeats.lock().unwrap();
+            let heartbeating = schedule.single.as_ref() == Some(&game_id);
+            if let Some(batch) = schedule.batch.as_mut() {
+                batch.retain(|id| *id != game_id);
+            }
+            drop(schedule);
+            if heartbeating {
+                self.stop_heartbeat();
+            }
+            if game_id == self.game_id {
+                self.game_id.clear();
+            }
+            session::SESSION_CACHE.clear(&game_id);
+            if let Some(transition) = self.session.end(&game_id, reason) {
+                self.emit_transition(transition);
+            }
+        }
+        let response = error::with_request_id(&response, request_id);
+        self.emit("end_completed", &[response.to_variant()]);
+        self.deliver_result(request_id, &response);
+    }
+
+    fn report_error(&mut self, error: &BliveError) {
+        let args = [
+            error.domain().to_variant(),
+            error.code().to_variant(),
+            error.message().to_variant(),
+        ];
+        self.emit("error_occurred", &args);
+    }
+
+    fn emit_switch_progress(&mut self, stage: &str, previous: &str, next: &str) {
+        self.emit(
+            "session_switch_progress",
+            &[stage.to_variant(), previous.to_variant(), next.to_variant()],
+        );
+    }
+
+    fn emit_start_succeeded(&mut self, info: StartInfo) {
+        let args = [
+            info.game_id.to_variant(),
+            self.ws_links.to_variant(),
+            info.auth_body.to_variant(),
+            json_to_variant(&info.anchor_info),
+        ];
+        self.emit("start_succeeded", &args);
+    }
+
+    /// 共用的 HTTP 客户端，超时或 TLS 设置变化后重新创建
+    fn http_client(&mut self) -> reqwest::blocking::Client {
+        let config = (
+            self.http_connect_timeout,
+            self.http_timeout,
+            self.tls_options(),
+        );
+        if config != self.http_config {
+            self.http = build_http_client(config.0, config.1, &config.2);
+            self.http_config = config;
+        }
+        self.http.clone()
+    }
+
+    /// HTTP 请求和长连接共用的 TLS 设置：`tls_ca_cert_path` 优先于 `set_tls_ca_certificate`
+    fn tls_options(&mut self) -> TlsOptions {
+        let insecure_skip_verify = self.ws_insecure_skip_verify;
+        if self.tls_ca_cert_path.is_empty() {
+            return TlsOptions {
+                ca_pem: self.tls_ca_pem.clone(),
+                insecure_skip_verify,
+            };
+        }
+        if self.tls_ca_file.0 != self.tls_ca_cert_path {
+            let path = globalize_path(&self.tls_ca_cert_path);
+            let pem = std::fs::read(&path).unwrap_or_else(|e| {
+                godot_error!("读取根证书 {} 失败: {}", path.display(), e);
+                Vec::new()
+            });
+            self.tls_ca_file = (self.tls_ca_cert_path.clone(), pem);
+        }
+        TlsOptions {
+            ca_pem: self.tls_ca_file.1.clone(),
+            insecure_skip_verify,
+        }
+    }
+
+    fn api_limiter(&self) -> Arc<SharedLimiter> {
+        self.api_limiter.configure(self.api_qps.max(0.0));
+        self.api_limiter.clone()
+    }
+
+    fn api_credentials(&mut self) -> ApiCredentials {
+        ApiCredentials {
+            base_url: self.api_base_url.to_string(),
+            access_key_id: self.access_key_id.to_string(),
+            access_key_secret: self.access_key_secret.to_string(),
+            http: self.http_client(),
+            traffic: self.traffic.clone(),
+            limiter: self.api_limiter(),
+            queue_excess: self.api_rate_limit_queue,
+            sender: self.ws_message_tx.clone(),
+        }
+    }
+
+    /// 心跳场次变化后调用：调度任务未运行时在共享 runtime 上启动，否则唤醒它立即发送一轮
+    fn wake_heartbeat_scheduler(&mut self) {
+        let Some(sender) = self.ws_message_tx.clone() else {
+            return;
+        };
+        let mut schedule = self.heartbeats.lock().unwrap();
+        if schedule.task_running {
+            drop(schedule);
+            self.heartbeat_notify.notify_one();
+            return;
+        }
+        schedule.task_running = true;
+        drop(schedule);
+
+        let credentials = self.api_credentials();
+        self.runtime.handle().spawn(Self::run_heartbeats(
+            self.heartbeats.clone(),
+            self.heartbeat_notify.clone(),
+            self.last_api_heartbeat_ok.clone(),
+            credentials,
+            sender,
+        ));
+    }
+
+    async fn diagnose(
+        config: Result<(), String>,
+        signed: bool,
+        credentials: ApiCredentials,
+        ws_host: String,
+        sender: mpsc::UnboundedSender<ThreadMessage>,
+    ) {
+        let elapsed_ms = |started: Instant| started.elapsed().as_millis() as i64;
+        let mut report = DiagnosticReport::default();
+        report.record("config", config.map(|_| "配置有效".to_string()), 0);
+
+        let api_host = conn_history::host_of(&credentials.base_url);
+        for (name, host) in [("dns_api", api_host), ("dns_ws", ws_host)] {
+            let started = Instant::now();
+            let result = tokio::net::lookup_host(diagnostics::with_default_port(&host, 443))
+                .await
+                .map(|addrs| format!("{} 解析到 {} 个地址", host, addrs.count()))
+                .map_err(|e| format!("{} 解析失败: {}", host, e));
+            report.record(name, result, elapsed_ms(started));
+        }
+
+        // 任意 HTTP 响应都说明 TLS 握手成功，响应的 Date 头用于估算时钟偏差
+        let base_url = credentials.base_url.clone();
+        let http = credentials.http.clone();
+        let started = Instant::now();
+        let probe = tokio::task::spawn_blocking(move || {
+            let response = http.get(&base_url).send().map_err(|e| e.to_string())?;
+            let date = response
+                .headers()
+                .get("date")
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            Ok::<_, String>((response.status().as_u16(), date))
+        })
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+        let local_ms = events::now_ms();
+        match probe {
+            Ok((status, date)) => {
+                report.record("tls", Ok(format!("HTTP {}", status)), elapsed_ms(started));
+                match date.as_deref().and_then(diagnostics::parse_http_date) {
+                    Some(server_ms) => report.record_clock_skew(local_ms - server_ms),
+                    None => report.skip("clock_skew", "响应没有 Date 头"),
+                }
+            }
+            Err(e) => {
+                report.record("tls", Err(e), elapsed_ms(started));
+                report.skip("clock_skew", "TLS 握手失败");
+            }
+        }
+
+        // 空场次的心跳不改变任何状态，只要平台没有拒绝签名即视为通过
+        if signed {
+            let started = Instant::now();
+            let (_, error) = credentials
+                .post("/v2/app/heartbeat", signing::heartbeat_body(""))
+                .await;
+            let result = match error {
+                None => Ok("签名通过".to_string()),
+                Some(e @ (BliveError::Signature { .. } | BliveError::Http { .. })) => {
+                    Err(e.to_string())
+                }
+                Some(e) => Ok(format!("签名通过，接口返回 {}", e)),
+            };
+            report.record("signed_request", result, elapsed_ms(started));
+        } else {
+            report.skip("signed_request", "未配置开放平台凭据或配置无效");
+        }
+        send_json_signal_to_main(&sender, "diagnostics_completed", vec![report.to_json()]);
+    }
+
+    /// 心跳调度任务：每 20 秒为单场次和批量心跳各发送一次，场次变化时立即发送并重新计时
+    async fn run_heartbeats(
+        schedule: Arc<Mutex<HeartbeatSchedule>>,
+        notify: Arc<Notify>,
+        last_ok: Arc<Mutex<Option<Instant>>>,
+        credentials: ApiCredentials,
+        sender: mpsc::UnboundedSender<ThreadMessage>,
+    ) {
+        let debug =
+            |msg: &str| send_signal_to_main(&sender, "heartbeat_debug", vec![msg.to_string()]);
+        debug("心跳任务已启动");
+        let mut interval = tokio::time::interval(Duration::from_secs(HEARTBEAT_INTERVAL_SECS));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = notify.notified() => interval.reset(),
+            }
+            let (single, batch, single_request_id, batch_request_id) = {
+                let mut schedule = schedule.lock().unwrap();
+                if schedule.single.is_none() && schedule.batch.is_none() {
+                    schedule.task_running = false;
+                    break;
+                }
+                if schedule.paused {
+                    drop(schedule);
+                    debug("心跳已暂停，跳过本轮");
+                    continue;
+                }
+                (
+                    schedule.single.clone(),
+                    schedule.batch.clone(),
+                    schedule.single_request_id,
+                    schedule.batch_request_id,
+                )
+            };
+
+            if let Some(game_id) = single {
+                debug(&format!("准备发送心跳: game_id={}", game_id));
+                let body = signing::heartbeat_body(&game_id);
+                let started = Instant::now();
+                let (response, error) = credentials.post("/v2/app/heartbeat", body).await;
+                if let Some(error) = error {
+                    let _ = sender.send(ThreadMessage::Error(error));
+                }
+                Self::send_heartbeat_result(&sender, &response, started);
+                if Self::record_heartbeat_ok(&response, &last_ok) {
+                    let _ = sender.send(ThreadMessage::HeartbeatOk {
+                        game_ids: vec![game_id],
+                    });
+                }
+                let response = error::with_request_id(&response, single_request_id);
+                send_signal_to_main(&sender, "heartbeat_completed", vec![response]);
+            }
+
+            match batch {
+                Some(ids) if ids.is_empty() => debug("game_ids 为空，跳过本次心跳"),
+                Some(ids) => {
+                    debug(&format!("准备发送批量心跳: {} 个场次", ids.len()));
+                    let body = signing::batch_heartbeat_body(&ids);
+                    let started = Instant::now();
+                    let (response, error) = credentials.post("/v2/app/batchHeartbeat", body).await;
+                    if let Some(error) = error {
+                        let _ = sender.send(ThreadMessage::Error(error));
+                    }
+                    Self::send_heartbeat_result(&sender, &response, started);
+                    if Self::record_heartbeat_ok(&response, &last_ok) {
+                        // 响应中列出的失败场次不算成功
+                        let json: serde_json::Value =
+                            serde_json::from_str(&response).unwrap_or_default();
+                        let failed = &json["data"]["failed_game_ids"];
+                        let game_ids = ids
+                            .into_iter()
+                            .filter(|id| {
+                                !failed
+                                    .as_array()
+                                    .is_some_and(|failed| failed.iter().any(|f| f == id))
+                            })
+                            .collect();
+                        let _ = sender.send(ThreadMessage::HeartbeatOk { game_ids });
+                    }
+                    let response = error::with_request_id(&response, batch_request_id);
+                    send_signal_to_main(&sender, "batch_heartbeat_completed", vec![response]);
+                }
+                None => {}
+            }
+        }
+        debug("心跳任务结束");
+    }
+
+    /// 空 game_id 回退到当前场次，两者都为空时报错并返回 None
+    fn resolve_game_id(&self, game_id: GString) -> Option<GString> {
+        if !game_id.is_empty() {
+            return Some(game_id);
+        }
+        if self.game_id.is_empty() {
+            godot_error!("错误：未指定 game_id，且没有通过 start 开启的场次");
+            return None;
+        }
+        Some(GString::from(self.game_id.as_str()))
+    }
+
+    fn emit_transition(&mut self, transition: Transition) {
+        let (signal, args) = match transition {
+            Transition::Started(game_id) => ("session_started", vec![game_id.to_variant()]),
+            Transition::HeartbeatOk(game_id) => {
+                ("session_heartbeat_ok", vec![game_id.to_variant()])
+            }
+            Transition::Expiring(game_id) => ("session_expiring", vec![game_id.to_variant()]),
+            Transition::Ended(game_id, reason) => (
+                "session_ended",
+                vec![game_id.to_variant(), reason.to_variant()],
+            ),
+        };
+        self.emit(signal, &args);
+    }
+
+    /// 开放平台凭据要么全部填写，要么全部留空（只使用直连模式）
+    fn validate_config(&self) -> Result<(), String> {
+        if !self.runtime.is_alive() {
+            return Err("后台运行时未启动".to_string());
+        }
+        let open_platform = self.app_id != 0
+            || !self.access_key_id.is_empty()
+            || !self.access_key_secret.is_empty();
+        if !open_platform {
+            return Ok(());
+        }
+        if self.app_id <= 0 {
+            return Err("app_id 无效".to_string());
+        }
+        if self.access_key_id.is_empty() || self.access_key_secret.is_empty() {
+            return Err("access_key_id 和 access_key_secret 必须同时填写".to_string());
+        }
+        let base_url = self.api_base_url.to_string();
+        if !base_url.starts_with("https://") && !base_url.starts_with("http://") {
+            return Err(format!("api_base_url 无效: {}", base_url));
+        }
+        Ok(())
+    }
+
+    fn gift_catalog_path(&self) -> PathBuf {
+        if self.gift_catalog_cache_path.is_empty() {
+            std::env::temp_dir().join("gdblive_gift_catalog.json")
+        } else {
+            PathBuf::from(self.gift_catalog_cache_path.to_string())
+        }
+    }
+
+    fn finish_initialize(&mut self, success: bool, error: String) {
+        self.ready_state = if success {
+            ReadyState::Ready
+        } else {
+            godot_error!("初始化失败: {}", error);
+            ReadyState::Failed(error.clone())
+        };
+        self.emit("blive_ready", &[success.to_variant(), error.to_variant()]);
+    }
+
+    /// 评估连接质量并切换降级状态；降级期间按 digest_interval 发出 `message_digest`
+    fn update_degradation(&mut self, delta: f64) {
+        let change = if self.auto_degrade {
+            let reply_age_secs = match *self.last_ws_heartbeat_reply.lock().unwrap() {
+                Some(at) if self.ws_connected => at.elapsed().as_secs_f64(),
+                _ => -1.0,
+            };
+            let quality = Quality {
+                reply_age_secs,
+                queue_depth: self.ws_message_rx.lock().unwrap().len(),
+                heartbeat_failing: self.heartbeat_health.is_degraded(),
+            };
+            self.degradation.update(quality, delta)
+        } else if self.degradation.reset() {
+            Some(Change::Recovered)
+        } else {
+            None
+        };
+        let interval = self.degradation.digest_interval;
+        let flush = matches!(change, Some(Change::Recovered));
+        if self.degradation.is_degraded() || flush {
+            let interval = if flush { 0.0 } else { interval };
+            if let Some(counts) = self.message_digest.take_due(self.elapsed, interval) {
+                self.emit("message_digest", &[json_to_variant(&counts)]);
+            }
+        }
+        let (degraded, reason) = match change {
+            Some(Change::Degraded(reason)) => (true, reason),
+            Some(Change::Recovered) => (false, "recovered"),
+            None => return,
+        };
+        godot_print!("降级状态变化: {} ({})", degraded, reason);
+        self.emit(
+            "degradation_changed",
+            &[degraded.to_variant(), reason.to_variant()],
+        );
+    }
+
+    fn tick_load_test(&mut self, delta: f64) {
+        let Some(test) = self.load_test.as_mut() else {
+            return;
+        };
+        let counters = self.load_generator.clone();
+        let generated = counters.generated.load(Ordering::SeqCst);
+        let drained = counters.finished.load(Ordering::SeqCst) && test.dispatched() >= generated;
+        let (step, done) = test.tick(delta, drained);
+        match step {
+            Some(Step::StartGenerator) => self.spawn_load_generator(),
+            Some(Step::StopGenerator) => counters.stop.store(true, Ordering::SeqCst),
+            None => {}
+        }
+        if done {
+            if let Some(test) = self.load_test.take() {
+                let report = test.report(generated, counters.decode_errors.load(Ordering::SeqCst));
+                self.emit("load_test_completed", &[json_to_variant(&report)]);
+            }
+        }
+    }
+
+    /// 在 runtime 上按速率生成合成消息，与长连接接收任务走相同的解包和解析流程
+    fn spawn_load_generator(&mut self) {
+        let (Some(sender), Some(test)) = (self.ws_message_tx.clone(), self.load_test.as_ref())
+        else {
+            return;
+        };
+        let counters = Arc::new(GeneratorCounters::default());
+        self.load_generator = counters.clone();
+        let (rate, duration) = (test.messages_per_minute, test.duration);
+        let protocol = self.protocol.clone();
+        let parse_workers = self.parse_workers.clamp(0, 16) as usize;
+        let context = MessageContext {
+            gate: self.interaction_gate.clone(),
+            sender,
+            guest: false,
+        };
+        self.runtime.handle().spawn(async move {
+            let pool = (parse_workers > 0).then(|| {
+                let context = context.clone();
+                ParsePool::spawn(parse_workers, move |seq, received_at, body| {
+                    Self::handle_message(seq, received_at, body, &context)
+                })
+            });
+            let started = Instant::now();
+            let mut generated = 0;
+            let mut seq = 0u64;
+            while !counters.stop.load(Ordering::SeqCst) {
+                let elapsed = started.elapsed().as_secs_f64().min(duration);
+                let due = load_test::due_count(rate, elapsed);
+                if due > generated {
+                    let packet = load_test::encode_batch(&protocol, generated, due - generated);
+                    match protocol.decode_packet(&packet) {
+                        Ok(packets) => {
+                            for (operation, body) in packets {
+                                if operation != protocol.op_message {
+                                    continue;
+                                }
+                                seq += 1;
+                                let received_at = Instant::now();
+                                match &pool {
+                                    Some(pool) => pool.dispatch(seq, received_at, body),
+                                    None => Self::handle_message(seq, received_at, body, &context),
+                                }
+                            }
+                        }
+                        Err(_) => {
+                            counters.decode_errors.fetch_add(1, Ordering::SeqCst);
+                        }
+                    }
+                    generated = due;
+                    counters.generated.store(generated, Ordering::SeqCst);
+                }
+                if elapsed >= duration {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(load_test::BATCH_INTERVAL_MS)).await;
+            }
+            counters.finished.store(true, Ordering::SeqCst);
+        });
+    }
+
+    /// 发出信号，设置了频道名时字典参数带上 channel 键
+    fn emit(&mut self, signal: &str, args: &[Variant]) {
+        let channel = channel::resolve(&self.base(), &self.channel_name);
+        let args = channel::tag(&channel, args);
+        self.base_mut().emit_signal(signal, &args);
+    }
+
+    /// 为 `api::SIGNAL_ALIASES` 中的旧信号名注册同名信号，新信号发出时以相同参数转发过去
+    fn register_signal_aliases(&mut self) {
+        let this = self.to_gd();
+        for alias in api::SIGNAL_ALIASES {
+            if alias.args > api::MAX_ALIAS_ARGS {
+                godot_error!("信号 {} 的参数过多，无法转发", alias.old);
+                continue;
+            }
+            if !self.base().has_signal(alias.old) {
+                self.base_mut().add_user_signal(alias.old);
+            }
+            let forwarder = format!("_forward_signal_alias_{}", alias.args);
+            let callable = Callable::from_object_method(&this, forwarder.as_str())
+                .bind(&[alias.old.to_variant()]);
+            self.base_mut().connect(alias.new, &callable);
+        }
+    }
+
+    /// 只在有脚本连接旧信号时转发；第一次转发时提示改用新名并发出 deprecation_warning
+    fn forward_signal_alias(&mut self, old: GString, args: &[Variant]) {
+        let Some(alias) = api::alias(&old.to_string()) else {
+            return;
+        };
+        if self.base().get_signal_connection_list(alias.old).is_empty() {
+            return;
+        }
+        if let Some(warning) = self.deprecations.first_use(alias) {
+            godot_warn!("{}", warning);
+            self.emit(
+                "deprecation_warning",
+                &[
+                    alias.old.to_variant(),
+                    alias.new.to_variant(),
+                    alias.since.to_variant(),
+                ],
+            );
+        }
+        self.emit(alias.old, args);
+    }
+
+    /// 礼物连击和醒目留言计时，对应子系统关闭时跳过
+    fn track_timed_events(&mut self, event_type: &str, data: &serde_json::Value) {
+        match event_type {
+            events::EVENT_GIFT => {
+                let enabled = self.subsystems.is_enabled(subsystems::COMBOS);
+                if let Some(combos) = subsystems::lazy(&mut self.combos, enabled) {
+                    combos.record(data, self.elapsed);
+                }
+            }
+            events::EVENT_SUPER_CHAT => {
+                let enabled = self.subsystems.is_enabled(subsystems::SUPER_CHAT_TIMERS);
+                if let Some(super_chats) = subsystems::lazy(&mut self.super_chats, enabled) {
+                    super_chats.add(
+                        data["message_id"].as_i64().unwrap_or(0),
+                        data["duration"].as_i64().unwrap_or(0),
+                        self.elapsed,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn report_bandwidth(&mut self) {
+        let (last_at, last_snapshot) = self.last_bandwidth_report;
+        let interval = self.elapsed - last_at;
+        if !self.subsystems.is_enabled(subsystems::STATS)
+            || self.bandwidth_report_interval <= 0.0
+            || interval < self.bandwidth_report_interval
+        {
+            return;
+        }
+        let snapshot = self.traffic.snapshot();
+        self.last_bandwidth_report = (self.elapsed, snapshot);
+        let report = snapshot.report(last_snapshot, interval);
+        self.emit("bandwidth_report", &[json_to_variant(&report)]);
+    }
+
+    fn check_heartbeat_pause(&mut self) {
+        let Some((paused_at, warned)) = self.heartbeat_paused_at.as_mut() else {
+            return;
+        };
+        let paused_secs = self.elapsed - *paused_at;
+        if *warned || paused_secs < session::EXPIRING_AFTER_SECS {
+            return;
+        }
+        *warned = true;
+        godot_warn!(
+            "心跳已暂停 {:.0} 秒，超过约 60 秒后平台会关闭场次",
+            paused_secs
+        );
+        self.emit("heartbeat_pause_too_long", &[paused_secs.to_variant()]);
+    }
+
+    /// 停止心跳、断开长连接并关闭当前项目
+    fn close_session(&mut self, reason: &str) {
+        godot_print!("关闭会话: {}", reason);
+        self.stop_heartbeat();
+        let game_id = std::mem::take(&mut self.game_id);
+        if let Some(batch) = self.heartbeats.lock().unwrap().batch.as_mut() {
+            batch.retain(|id| *id != game_id);
+        }
+        self.stop_websocket();
+        if !game_id.is_empty() {
+            self.end_game(GString::from(game_id.as_str()), reason);
+            // end 请求失败时心跳也已停止，场次会被平台超时关闭
+            session::SESSION_CACHE.clear(&game_id);
+            if let Some(transition) = self.session.end(&game_id, reason) {
+                self.emit_transition(transition);
+            }
+        }
+        self.emit("session_closed", &[reason.to_variant()]);
+    }
+
+    /// 通知当前长连接发送关闭帧并退出，心跳和发送任务随之中止，连接任务随后发出 `ws_disconnected`
+    fn close_websocket(&mut self) {
+        self.ws_reconnect_pending = false;
+        self.ws_running.store(false, Ordering::SeqCst);
+        self.ws_outbound_tx = None;
+        if let Some(stopper) = self.ws_stopper.take() {
+            stopper.stop();
+        }
+    }
+
+    /// 心跳回复超时断开后，以上次的地址和鉴权包（直连模式重新解析直播间）重新连接
+    fn reconnect_after_timeout(&mut self) {
+        let Some(target) = self.ws_target.clone() else {
+            return;
+        };
+        godot_print!("长连接心跳回复超时，重新连接");
+        self.spawn_websocket(target);
+    }
+
+    fn spawn_websocket(&mut self, target: WsTarget) {
+        if self.ws_running.load(Ordering::SeqCst) {
+            godot_print!("WebSocket 已经在运行中");
+            return;
+        }
+        let Some(sender) = self.ws_message_tx.clone() else {
+            return;
+        };
+
+        self.ws_running.store(true, Ordering::SeqCst);
+        let running = self.ws_running.clone();
+        let gate = self.interaction_gate.clone();
+        let protocol = self.protocol.clone();
+        let guest_flag = self.ws_guest.clone();
+        let direct_room_id = self.direct_room_id.clone();
+        let http = self.http_client();
+        let tls = self.tls_options();
+        let heartbeat_reply = self.last_ws_heartbeat_reply.clone();
+        let traffic = self.traffic.clone();
+        let parse_workers = self.parse_workers.clamp(0, 16) as usize;
+        self.ws_parse_workers = parse_workers;
+        *heartbeat_reply.lock().unwrap() = None;
+        let (outbound_tx, outbound_rx) = mpsc::unbounded_channel();
+        self.ws_outbound_tx = Some(outbound_tx);
+        let (stopper, mut stop) = ws_stop::channel();
+        self.ws_stopper = Some(stopper);
+        self.ws_target = Some(target.clone());
+        let reply_timeout = (self.ws_reply_timeout_secs > 0.0)
+            .then(|| Duration::from_secs_f64(self.ws_reply_timeout_secs));
+
+        godot_print!("准备启动 WebSocket 任务...");
+        self.runtime.handle().spawn(async move {
+            let session = match target {
+                WsTarget::OpenPlatform { ws_urls, auth_body } => WsSession {
+                    ws_urls,
+                    auth_body,
+                    protocol,
+                    guest: false,
+                    parse_workers,
+                    tls,
+                    reply_timeout,
+                    stop,
+                },
+                WsTarget::Room {
+                    room_id,
+                    credentials,
+                } => {
+                    let resolving = tokio::task::spawn_blocking(move || {
+                        direct::resolve_room(&http, room_id, &credentials)
+                    });
+                    let resolved = tokio::select! {
+                        resolved = resolving => resolved.unwrap_or_else(|e| Err(e.to_string())),
+                        _ = stop.stopped() => {
+                            running.store(false, Ordering::SeqCst);
+                            send_signal_to_main(&sender, "ws_disconnected", vec![]);
+                            return;
+                        }
+                    };
+                    match resolved {
+                        Ok(connection) => {
+                            direct_room_id.store(connection.room_id, Ordering::SeqCst);
+                            send_signal_to_main(
+                                &sender,
+                                "ws_debug",
+                                vec![format!("直播间 {} 解析完成", connection.room_id)],
+                            );
+                            if connection.guest {
+                                send_signal_to_main(
+                                    &sender,
+                                    "ws_debug",
+                                    vec!["未登录，以游客身份连接，用户名将被截断".to_string()],
+                                );
+                            }
+                            WsSession {
+                                ws_urls: vec![connection.ws_url],
+                                auth_body: connection.auth_body,
+                                protocol,
+                                guest: connection.guest,
+                                parse_workers,
+                                tls,
+                                reply_timeout,
+                                stop,
+                            }
+                        }
+                        Err(e) => {
+                            let _ =
+                                sender.send(ThreadMessage::ConnectionAttempt(ConnectionAttempt {
+                                    host: String::new(),
+                                    started_ms: events::now_ms(),
+                                    outcome: "failed",
+                                    error_category: "resolve",
+                                    error: e.clone(),
+                                    duration_ms: 0,
+                                }));
+                            send_signal_to_main(
+                                &sender,
+                                "ws_error",
+                                vec![format!("获取直播间信息失败: {}", e)],
+                            );
+                            running.store(false, Ordering::SeqCst);
+                            send_signal_to_main(&sender, "ws_disconnected", vec![]);
+                            return;
+                        }
+                    }
+                }
+            };
+            guest_flag.store(session.guest, Ordering::SeqCst);
+            Self::run_websocket(
+                session,
+                running,
+                gate,
+                sender,
+                outbound_rx,
+                heartbeat_reply,
+                traffic,
+            )
+            .await;
+        });
+    }
+
+    /// 弹幕语言与目标语言不同时交给翻译器，命中缓存时立即发出 `danmaku_translated`
+    fn request_translation(&mut self, data: &serde_json::Value) {
+        let (request_id, text, source, target) = match self.translation.prepare(data, self.elapsed)
+        {
+            Prepared::Request {
+                request_id,
+                text,
+                source,
+                target,
+            } => (request_id, text, source, target),
+            Prepared::Cached(translated) => {
+                let data = self.translation.translated(data.clone(), &translated);
+                self.emit("danmaku_translated", &[json_to_variant(&data)]);
+                return;
+            }
+            Prepared::Skip | Prepared::RateLimited => return,
+        };
+        match self.translator.clone() {
+            Some(Translator::Callable(callable)) => {
+                let result = callable.call(&[
+                    request_id.to_variant(),
+                    text.to_variant(),
+                    source.to_variant(),
+                    target.to_variant(),
+                ]);
+                // 返回空字符串或非字符串时等待 submit_translation
+                if let Ok(translated) = result.try_to::<GString>() {
+                    if !translated.is_empty() {
+                        self.finish_translation(request_id, Ok(translated.to_string()));
+                    }
+                }
+            }
+            Some(Translator::Endpoint(endpoint)) => {
+                let Some(sender) = self.ws_message_tx.clone() else {
+                    return;
+                };
+                let http = self.http_client();
+                self.runtime.handle().spawn_blocking(move || {
+                    let result =
+                        translation::translate_http(&http, &endpoint, &text, &source, &target);
+                    let _ = sender.send(ThreadMessage::Translation { request_id, result });
+                });
+            }
+            None => {}
+        }
+    }
+
+    fn send_webhook(&mut self, event_type: &str, data: &serde_json::Value) {
+        let Some(webhook) = self.webhook.as_mut() else {
+            return;
+        };
+        if !webhook.accepts(event_type) {
+            return;
+        }
+        let delivery = webhook.delivery(event_type, data, events::now_ms());
+        let Some(sender) = self.ws_message_tx.clone() else {
+            return;
+        };
+        let http = self.http_client();
+        self.runtime.handle().spawn(async move {
+            let mut attempt = 0;
+            let error = loop {
+                attempt += 1;
+                let (client, pending) = (http.clone(), delivery.clone());
+                let result = tokio::task::spawn_blocking(move || webhook::post(&client, &pending))
+                    .await
+                    .unwrap_or_else(|e| Err((false, e.to_string())));
+                match result {
+                    Ok(()) => return,
+                    Err((true, _)) if attempt < delivery.max_attempts => {
+                        tokio::time::sleep(webhook::retry_delay(attempt)).await;
+                    }
+                    Err((_, error)) => break error,
+                }
+            };
+            send_signal_to_main(
+                &sender,
+                "webhook_failed",
+                vec![delivery.id, delivery.event_type, error],
+            );
+        });
+    }
+
+    fn finish_translation(&mut self, request_id: i64, result: Result<String, String>) {
+        let Some(data) = self.translation.finish(request_id, &result) else {
+            return;
+        };
+        match result {
+            Ok(translated) => {
+                let data = self.translation.translated(data, &translated);
+                self.emit("danmaku_translated", &[json_to_variant(&data)]);
+            }
+            Err(e) => {
+                self.emit(
+                    "translation_failed",
+                    &[json_to_variant(&data), e.to_variant()],
+                );
+            }
+        }
+    }
+
+    /// 把付费事件追加到审计日志，日志无法打开或写入时发出一次 `audit_log_failed`
+    fn audit_paid_event(&mut self, event_type: &str, data: &serde_json::Value) {
+        if self.audit_log_path.is_empty() {
+            return;
+        }
+        let path = globalize_path(&self.audit_log_path);
+        if self.audit_log.as_ref().is_none_or(|log| log.path() != path) {
+            self.audit_log = match AuditLog::open(&path) {
+                Ok(log) => Some(log),
+                Err(e) => {
+                    self.report_audit_failure(e);
+                    return;
+                }
+            };
+        }
+        let room_id = self.direct_room_id.load(Ordering::SeqCst);
+        let session = serde_json::json!({
+            "mode": if room_id > 0 { "direct" } else { "open_platform" },
+            "app_id": self.app_id,
+            "game_id": self.game_id,
+            "room_id": room_id,
+        });
+        let entry = audit::paid_event_entry(event_type, data, &session);
+        let result = match self.audit_log.as_mut() {
+            Some(log) => log.append(entry, events::now_ms()),
+            None => return,
+        };
+        match result {
+            Ok(()) => self.audit_log_failed = false,
+            Err(e) => self.report_audit_failure(e),
+        }
+    }
+
+    /// 为弹幕和礼物事件标记 first_in_session / first_ever，本场次首次时发出 `first_interaction`
+    fn mark_first_interaction(&mut self, event_type: &str, data: &mut serde_json::Value) {
+        let Some(kind) = first_seen::kind_of(event_type) else {
+            return;
+        };
+        let Some(open_id) = data["user_id"].as_str().map(str::to_string) else {
+            return;
+        };
+        if self.first_interaction_store_opened != self.first_interaction_store_path {
+            self.first_interaction_store_opened = self.first_interaction_store_path.clone();
+            let path = (!self.first_interaction_store_path.is_empty())
+                .then(|| globalize_path(&self.first_interaction_store_path));
+            if let Err(e) = self.first_interactions.open_store(path.as_deref()) {
+                godot_error!("{}", e);
+            }
+        }
+        let firsts = self
+            .first_interactions
+            .observe(&self.game_id, kind, &open_id);
+        data["first_in_session"] = firsts.in_session.into();
+        if let Some(ever) = firsts.ever {
+            data["first_ever"] = ever.into();
+        }
+        if firsts.in_session {
+            self.emit(
+                "first_interaction",
+                &[open_id.to_variant(), kind.to_variant()],
+            );
+        }
+    }
+
+    fn record_message(&mut self, cmd: &str, message_json: &str) {
+        let Some(recorder) = self.recorder.as_mut() else {
+            return;
+        };
+        match recorder.record(cmd, message_json, events::now_ms()) {
+            Ok(Some(finished)) => self.close_segment(finished),
+            Ok(None) => {}
+            Err(e) => {
+                self.recorder = None;
+                self.report_recording_failure(e);
+            }
+        }
+    }
+
+    /// 分段结束：需要压缩时在后台压缩，完成后再发出 `recording_segment_closed`
+    fn close_segment(&mut self, path: PathBuf) {
+        let sender = match self.ws_message_tx.clone() {
+            Some(sender) if self.recording_compress => sender,
+            _ => {
+                let path = path.to_string_lossy().into_owned();
+                self.emit("recording_segment_closed", &[path.to_variant()]);
+                return;
+            }
+        };
+        self.runtime
+            .handle()
+            .spawn_blocking(move || match recorder::compress_segment(&path) {
+                Ok(gz_path) => send_signal_to_main(
+                    &sender,
+                    "recording_segment_closed",
+                    vec![gz_path.to_string_lossy().into_owned()],
+                ),
+                Err(e) => send_signal_to_main(&sender, "recording_failed", vec![e]),
+            });
+    }
+
+    fn report_recording_failure(&mut self, error: String) {
+        godot_error!("{}", error);
+        self.emit("recording_failed", &[error.to_variant()]);
+    }
+
+    fn report_audit_failure(&mut self, error: String) {
+        if std::mem::replace(&mut self.audit_log_failed, true) {
+            return;
+        }
+        godot_error!("{}", error);
+        self.emit("audit_log_failed", &[error.to_variant()]);
+    }
+
+    /// 附加节点：转发主节点广播的信号
+    fn drain_attachment(&mut self) {
+        let Some(attachment) = self.attachment.as_mut() else {
+            return;
+        };
+        let drained = attachment.drain();
+        if drained.lagged > 0 {
+            godot_warn!("附加会话积压过多，丢弃了 {} 个信号", drained.lagged);
+        }
+        for signal in drained.signals {
+            match signal.name.as_str() {
+                "ws_connected" => self.ws_connected = true,
+                "ws_disconnected" => self.ws_connected = false,
+                _ => {}
+            }
+            let variants: Vec<Variant> = signal.args.iter().map(json_to_variant).collect();
+            self.emit(signal.name.as_str(), &variants);
+            self.shared.publish(&signal.name, || signal.args.clone());
+            if signal.name == "live_event" && signal.args[1]["blind_box"].as_bool() == Some(true) {
+                self.emit("blind_box_opened", &[variants[1].clone()]);
+            }
+        }
+        if drained.closed {
+            self.detach_session();
+        }
+    }
+
+    /// 附加到其他节点的会话时拒绝会改变会话状态的调用
+    fn reject_read_only(&self, action: &str) -> bool {
+        if self.attachment.is_none() {
+            return false;
+        }
+        godot_warn!("已以只读方式附加到其他节点的会话，忽略 {}", action);
+        true
+    }
+
+    fn check_chat_consensus(&mut self) {
+        if !self.detect_chat_consensus {
+            return;
+        }
+        let consensus = self.consensus.check(
+            self.elapsed,
+            self.consensus_window_secs,
+            self.consensus_ratio,
+            self.consensus_min_users.max(1) as usize,
+        );
+        if let Some((text, ratio)) = consensus {
+            self.emit("chat_consensus", &[text.to_variant(), ratio.to_variant()]);
+        }
+    }
+
+    fn fire_scheduled_events(&mut self) {
+        let ticks = Time::singleton().get_ticks_msec();
+        for (event_type, mut data) in self.scheduler.due(self.elapsed) {
+            data["received_ticks_msec"] = ticks.into();
+            self.emit(
+                "live_event",
+                &[event_type.to_variant(), json_to_variant(&data)],
+            );
+        }
+    }
+
+    fn redeliver_events(&mut self) {
+        if !self.reliable_delivery {
+            return;
+        }
+        let max_attempts = self.max_delivery_attempts.max(1) as u32;
+        for (event_type, data) in self
+            .acks
+            .due(self.elapsed, self.ack_timeout_secs, max_attempts)
+        {
+            self.emit(
+                "live_event",
+                &[event_type.to_variant(), json_to_variant(&data)],
+            );
+        }
+    }
+
+    fn route_language(&self, lang: &str, data: &serde_json::Value) {
+        let targets: Vec<&LanguageRoute> = self
+            .language_routes
+            .iter()
+            .filter(|route| route.accepts(lang))
+            .collect();
+        if targets.is_empty() {
+            return;
+        }
+        let Some(mut tree) = self.base().get_tree() else {
+            return;
+        };
+        let args = [lang.to_variant(), json_to_variant(data)];
+        for route in targets {
+            tree.call_group(route.group.as_str(), route.method.as_str(), &args);
+        }
+    }
+
+    fn forward_to_groups(&self, cmd: &str, message_json: &str) {
+        let targets: Vec<&GroupForward> = self
+            .group_forwards
+            .iter()
+            .filter(|forward| forward.accepts(cmd))
+            .collect();
+        if targets.is_empty() {
+            return;
+        }
+        let Some(mut tree) = self.base().get_tree() else {
+            return;
+        };
+        let json: serde_json::Value = serde_json::from_str(message_json).unwrap_or_default();
+        let args = [cmd.to_variant(), json_to_variant(&json["data"])];
+        for forward in targets {
+            tree.call_group(forward.group.as_str(), forward.method.as_str(), &args);
+        }
+    }
+
+    async fn run_websocket(
+        session: WsSession,
+        running: Arc<AtomicBool>,
+        gate: Arc<Mutex<InteractionGate>>,
+        sender: mpsc::UnboundedSender<ThreadMessage>,
+        mut outbound_rx: mpsc::UnboundedReceiver<Vec<u8>>,
+        heartbeat_reply: Arc<Mutex<Option<Instant>>>,
+        traffic: Arc<TrafficStats>,
+    ) {
+        let debug = |msg: String| send_signal_to_main(&sender, "ws_debug", vec![msg]);
+        let error = |msg: String| {
+            let _ = sender.send(ThreadMessage::Error(BliveError::WebSocket(msg.clone())));
+            send_signal_to_main(&sender, "ws_error", vec![msg]);
+        };
+        let WsSession {
+            ws_urls,
+            auth_body,
+            protocol,
+            guest,
+            parse_workers,
+            tls,
+            reply_timeout,
+            mut stop,
+        } = session;
+
+        // (地址, 开始时间, 开始的 Unix 毫秒) 对应的一次连接尝试
+        let record_attempt = |attempt: &(String, Instant, i64),
+                              outcome: &'static str,
+                              error_category: &'static str,
+                              error: String| {
+            let (host, started, started_ms) = attempt;
+            let _ = sender.send(ThreadMessage::ConnectionAttempt(ConnectionAttempt {
+                host: host.clone(),
+                started_ms: *started_ms,
+                outcome,
+                error_category,
+                error,
+                duration_ms: started.elapsed().as_millis() as i64,
+            }));
+        };
+
+        let mut connected = None;
+        for (index, ws_url) in ws_urls.iter().enumerate() {
+            let attempt = (
+                conn_history::host_of(ws_url),
+                Instant::now(),
+                events::now_ms(),
+            );
+            send_json_signal_to_main(
+                &sender,
+                "ws_host_selected",
+                vec![ws_url.clone().into(), (index as i64).into()],
+            );
+            debug(format!("开始连接 WebSocket: {}", ws_url));
+            let connect = async {
+                match tls.ws_connector() {
+                    Ok(connector) => {
+                        connect_async_tls_with_config(ws_url.as_str(), None, false, connector)
+                            .await
+                            .map_err(|e| e.to_string())
+                    }
+                    Err(e) => Err(format!("TLS 设置无效: {}", e)),
+                }
+            };
+            let result = tokio::select! {
+                result = connect => result,
+                _ = stop.stopped() => {
+                    record_attempt(&attempt, "stopped", "", String::new());
+                    running.store(false, Ordering::SeqCst);
+                    send_signal_to_main(&sender, "ws_disconnected", vec![]);
+                    return;
+                }
+            };
+            match result {
+                Ok((stream, _)) => {
+                    connected = Some((stream, attempt));
+                    break;
+                }
+                Err(e) => {
+                    record_attempt(&attempt, "failed", conn_history::categorize(&e), e.clone());
+                    error(format!("连接 {} 失败: {}", attempt.0, e));
+                }
+            }
+        }
+        let Some((ws_stream, attempt)) = connected else {
+            running.store(false, Ordering::SeqCst);
+            send_signal_to_main(&sender, "ws_disconnected", vec![]);
+            return;
+        };
+        let record = |outcome: &'static str, error_category: &'static str, error: String| {
+            record_attempt(&attempt, outcome, error_category, error)
+        };
+        debug("WebSocket 连接成功".to_string());
+        traffic.ws_connected();
+        send_signal_to_main(&sender, "ws_connected", vec![]);
+
+        let (write, mut read) = ws_stream.split();
+        let write = Arc::new(tokio::sync::Mutex::new(write));
+
+        debug("准备发送鉴权包".to_string());
+        let auth_body = protocol.auth_body(&auth_body);
+        let auth_packet = protocol.encode_packet(auth_body.as_bytes(), protocol.op_auth);
+        traffic.ws_sent(auth_packet.len());
+        if let Err(e) = write.lock().await.send(Message::Binary(auth_packet)).await {
+            let e = e.to_string();
+            record("failed", conn_history::categorize(&e), e.clone());
+            error(format!("鉴权失败: {}", e));
+            running.store(false, Ordering::SeqCst);
+            send_signal_to_main(&sender, "ws_disconnected", vec![]);
+            return;
+        }
+        debug("鉴权包已发送".to_string());
+
+        debug("启动心跳任务".to_string());
+        let heartbeat_write = write.clone();
+        let heartbeat_running = running.clone();
+        let heartbeat_sender = sender.clone();
+        let heartbeat_packet = protocol.encode_packet(&[], protocol.op_heartbeat);
+        let heartbeat_traffic = traffic.clone();
+        let heartbeat_task = tokio::spawn(async move {
+            send_signal_to_main(
+                &heartbeat_sender,
+                "ws_debug",
+                vec!["心跳任务已启动".to_string()],
+            );
+            while heartbeat_running.load(Ordering::SeqCst) {
+                match heartbeat_write
+                    .lock()
+                    .await
+                    .send(Message::Binary(heartbeat_packet.clone()))
+                    .await
+                {
+                    Ok(_) => {
+                        heartbeat_traffic.ws_sent(heartbeat_packet.len());
+                        send_signal_to_main(
+                            &heartbeat_sender,
+                            "ws_debug",
+                            vec!["心跳包已发送".to_string()],
+                        )
+                    }
+                    Err(e) => {
+                        send_signal_to_main(
+                            &heartbeat_sender,
+                            "ws_error",
+                            vec![format!("心跳失败: {}", e)],
+                        );
+                        break;
+                    }
+                }
+                tokio::time::sleep(Duration::from_secs(HEARTBEAT_INTERVAL_SECS)).await;
+            }
+            send_signal_to_main(
+                &heartbeat_sender,
+                "ws_debug",
+                vec!["心跳任务结束".to_string()],
+            );
+        });
+
+        // 脚本通过 send_raw_packet 提交的包在这里写出
+        let outbound_write = write.clone();
+        let outbound_sender = sender.clone();
+        let outbound_traffic = traffic.clone();
+        let outbound_task = tokio::spawn(async move {
+            while let Some(packet) = outbound_rx.recv().await {
+                outbound_traffic.ws_sent(packet.len());
+                if let Err(e) = outbound_write
+                    .lock()
+                    .await
+                    .send(Message::Binary(packet))
+                    .await
+                {
+                    send_signal_to_main(
+                        &outbound_sender,
+                        "ws_error",
+                        vec![format!("发送失败: {}", e)],
+                    );
+                    break;
+                }
+            }
+        });
+
+        let context = MessageContext {
+            gate,
+            sender: sender.clone(),
+            guest,
+        };
+        // 解析线程池被丢弃时各线程处理完剩余消息后退出
+        let pool = (parse_workers > 0).then(|| {
+            let context = context.clone();
+            ParsePool::spawn(parse_workers, move |seq, received_at, body| {
+                Self::handle_message(seq, received_at, body, &context)
+            })
+        });
+        // 每条业务消息的到达序号，随 live_event 的 data.seq 发出
+        let mut seq = 0u64;
+        // 服务器包头中的序列号，每条连接单独跟踪
+        let mut sequence = SequenceTracker::default();
+        // 服务器直接断开流时按 closed 记录
+        let mut ended = ("disconnected", "closed", "连接已关闭".to_string());
+        // 最近一次收到心跳回复的时间，尚未收到时从连接建立算起
+        let mut last_reply = Instant::now();
+        debug("开始接收消息循环".to_string());
+        loop {
+            let reply_deadline = async {
+                match reply_timeout {
+                    Some(timeout) => tokio::time::sleep_until((last_reply + timeout).into()).await,
+                    None => std::future::pending().await,
+                }
+            };
+            let message = tokio::select! {
+                message = read.next() => message,
+                _ = stop.stopped() => {
+                    ended = ("stopped", "", String::new());
+                    break;
+                }
+                _ = reply_deadline => {
+                    let waited = last_reply.elapsed().as_secs_f64();
+                    send_json_signal_to_main(&sender, "ws_timeout", vec![waited.into()]);
+                    let reason = format!("{:.0} 秒未收到心跳回复", waited);
+                    error(format!("连接超时: {}", reason));
+                    ended = ("disconnected", "timeout", reason);
+                    break;
+                }
+            };
+            let Some(message) = message else {
+                break;
+            };
+            if !running.load(Ordering::SeqCst) {
+                ended = ("stopped", "", String::new());
+                break;
+            }
+            if let Ok(message) = &message {
+                traffic.ws_received(message.len());
+            }
+            match message {
+                Ok(Message::Binary(data)) => match protocol.decode_packet_with_sequence(&data) {
+                    Ok(packets) => {
+                        traffic.ws_decoded(packets.len());
+                        for (operation, packet_sequence, body) in packets {
+                            match operation {
+                                op if op == protocol.op_auth_reply => {
+                                    debug("收到鉴权回复".to_string())
+                                }
+                                op if op == protocol.op_heartbeat_reply => {
+                                    last_reply = Instant::now();
+                                    *heartbeat_reply.lock().unwrap() = Some(last_reply);
+                                    match Protocol::decode_popularity(&body) {
+                                        Ok(popularity) => send_json_signal_to_main(
+                                            &sender,
+                                            "popularity_updated",
+                                            vec![popularity.into()],
+                                        ),
+                                        Err(e) => debug(e.to_string()),
+                                    }
+                                }
+                                op if op == protocol.op_message => {
+                                    if let Some((expected, got)) = sequence.observe(packet_sequence)
+                                    {
+                                        send_json_signal_to_main(
+                                            &sender,
+                                            "ws_sequence_gap",
+                                            vec![expected.into(), got.into()],
+                                        );
+                                    }
+                                    seq += 1;
+                                    traffic.ws_message();
+                                    let received_at = Instant::now();
+                                    match &pool {
+                                        Some(pool) => pool.dispatch(seq, received_at, body),
+                                        None => {
+                                            Self::handle_message(seq, received_at, body, &context)
+                                        }
+                                    }
+                                }
+                                _ => {
+                                    debug(format!("收到未知操作码: {}", operation));
+                                    let _ =
+                                        sender.send(ThreadMessage::RawPacket { operation, body });
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        let _ = sender.send(ThreadMessage::Error(BliveError::Protocol(format!(
+                            "解码包失败: {}",
+                            e
+                        ))));
+                        if let DecodeError::Malformed(_) = e {
+                            send_signal_to_main(
+                                &sender,
+                                "ws_error",
+                                vec![format!("解码包失败: {}", e)],
+                            );
+                        } else {
+                            send_signal_to_main(
+                                &sender,
+                                "protocol_error",
+                                vec![e.kind().to_string(), e.to_string()],
+                            );
+                        }
+                    }
+                },
+                Ok(Message::Close(_)) => {
+                    debug("收到关闭消息".to_string());
+                    ended = ("disconnected", "closed", "服务器关闭连接".to_string());
+                    break;
+                }
+                // tungstenite 收到 Ping 时已排队同样内容的 Pong，随下一次读写发出，这里只做记录
+                Ok(Message::Ping(payload)) => {
+                    traffic.ws_sent(payload.len());
+                    debug("收到 Ping，已自动回复 Pong".to_string());
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    let e = e.to_string();
+                    ended = ("disconnected", conn_history::categorize(&e), e.clone());
+                    error(format!("接收失败: {}", e));
+                    break;
+                }
+            }
+        }
+
+        debug("消息循环结束".to_string());
+        let tasks = [heartbeat_task.abort_handle(), outbound_task.abort_handle()];
+        if ended.0 == "stopped" {
+            if let Err(e) = ws_stop::close(&write, &tasks).await {
+                debug(e);
+            }
+        } else {
+            for task in &tasks {
+                task.abort();
+            }
+        }
+        let (outcome, error_category, error) = ended;
+        record(outcome, error_category, error);
+        running.store(false, Ordering::SeqCst);
+        send_signal_to_main(&sender, "ws_disconnected", vec![]);
+    }
+
+    /// 处理一条业务消息：互动门槛检查、解析统一事件并发往主线程
+    fn handle_message(seq: u64, received_at: Instant, body: Vec<u8>, context: &MessageContext) {
+        let sender = &context.sender;
+        let text = String::from_utf8_lossy(&body).to_string();
+        let mut json = serde_json::from_str::<serde_json::Value>(&text).unwrap_or_default();
+        // 直连模式的 cmd 可能带参数后缀，如 "DANMU_MSG:4:0:2:2:2:0"
+        let cmd = json["cmd"]
+            .as_str()
+            .and_then(|cmd| cmd.split(':').next())
+            .unwrap_or("UNKNOWN")
+            .to_string();
+        send_signal_to_main(sender, "ws_debug", vec![format!("收到消息: {}", cmd)]);
+        if cmd == "LIVE_OPEN_PLATFORM_DM" {
+            let data = &json["data"];
+            let viewer = Viewer::from_data(data);
+            let msg = data["msg"].as_str().unwrap_or_default();
+            let result = context.gate.lock().unwrap().check_danmaku(msg, &viewer);
+            if let Err(reason) = result {
+                send_signal_to_main(sender, "interaction_rejected", vec![viewer.open_id, reason]);
+                return;
+            }
+        }
+        let text = match json.as_object_mut() {
+            Some(object) if context.guest => {
+                object.insert("guest".to_string(), true.into());
+                json.to_string()
+            }
+            _ => text,
+        };
+        let event = events::normalize(&cmd, &json);
+        if let Some(message_ids) = events::super_chat_deleted(&cmd, &json) {
+            let _ = sender.send(ThreadMessage::SuperChatDeleted { message_ids });
+        }
+        match events::interaction(&cmd, &json) {
+            Some(Interaction::Followed(user_id, uname)) => {
+                send_signal_to_main(sender, "viewer_followed", vec![user_id, uname])
+            }
+            Some(Interaction::Shared(user_id, uname)) => {
+                send_signal_to_main(sender, "viewer_shared", vec![user_id, uname])
+            }
+            None => {}
+        }
+        if let Some(live) = events::live_status(&cmd) {
+            let _ = sender.send(ThreadMessage::LiveStatus { live });
+            if !live {
+                let _ = sender.send(ThreadMessage::StreamEnded);
+            }
+        }
+        match events::audience(&cmd, &json) {
+            Some(Audience::Watched(count)) => {
+                send_json_signal_to_main(sender, "watched_count_updated", vec![count.into()])
+            }
+            Some(Audience::OnlineCount(count)) => {
+                send_json_signal_to_main(sender, "online_count_updated", vec![count.into()])
+            }
+            Some(Audience::OnlineRank(list)) => {
+                send_json_signal_to_main(sender, "online_rank_updated", vec![list.into()])
+            }
+            None => {}
+        }
+        send_signal_to_main(sender, "ws_message_received", vec![cmd, text]);
+        if let Some((event_type, mut data)) = event {
+            data["seq"] = seq.into();
+            let _ = sender.send(ThreadMessage::LiveEvent {
+                event_type: event_type.to_string(),
+                data,
+                received_at,
+            });
+        }
+    }
+
+    fn send_heartbeat_result(
+        sender: &mpsc::UnboundedSender<ThreadMessage>,
+        response: &str,
+        started: Instant,
+    ) {
+        let json: serde_json::Value = serde_json::from_str(response).unwrap_or_default();
+        let ok = json["code"].as_i64() == Some(0);
+        let error = if ok {
+            String::new()
+        } else {
+            format!(
+                "code {}: {}",
+                json["code"].as_i64().unwrap_or(-1),
+                json["message"].as_str().unwrap_or_default()
+            )
+        };
+        let _ = sender.send(ThreadMessage::HeartbeatResult {
+            ok,
+            rtt_ms: started.elapsed().as_millis() as i64,
+            error,
+        });
+    }
+
+    /// 项目心跳返回 code 0 时记录成功时间并返回 true
+    fn record_heartbeat_ok(response: &str, last_ok: &Mutex<Option<Instant>>) -> bool {
+        let json: serde_json::Value = serde_json::from_str(response).unwrap_or_default();
+        let ok = json["code"].as_i64() == Some(0);
+        if ok {
+            *last_ok.lock().unwrap() = Some(Instant::now());
+        }
+        ok
+    }
+
+    /// 在后台 runtime 上发送 `request` / `request_get` 的请求，完成后发出 `request_completed`
+    fn spawn_request(&mut self, path: String, call: ApiCall) -> i64 {
+        let Some(sender) = self.ws_message_tx.clone() else {
+            return 0;
+        };
+        let request_id = self.next_request_id();
+        let credentials = self.api_credentials();
+        let task = self.runtime.handle().spawn(async move {
+            let (response, error) = credentials.send(&path, call).await;
+            let _ = sender.send(ThreadMessage::RequestCompleted {
+                request_id,
+                path,
+                response,
+                error,
+            });
+        });
+        self.in_flight.insert(request_id, task.abort_handle());
+        request_id
+    }
+
+    /// 解码后台加载的资源并放入内存缓存
+    fn finish_asset(&mut self, url: String, result: Result<Vec<u8>, String>) {
+        self.asset_loading.remove(&url);
+        let decoded = result.and_then(|bytes| asset_cache::decode_texture(&bytes));
+        match decoded {
+            Ok((texture, size)) => {
+                let capacity = (self.asset_memory_cache_mb.max(0.0) * 1024.0 * 1024.0) as usize;
+                self.asset_textures
+                    .insert(&url, texture.clone(), size, capacity);
+                self.emit("asset_loaded", &[url.to_variant(), texture.to_variant()]);
+            }
+            Err(e) => self.emit("asset_failed", &[url.to_variant(), e.to_variant()]),
+        }
+    }
+
+    /// 同步请求，限流排队时会阻塞当前帧
+    fn post(&mut self, path: &str, body: &str) -> (String, Option<BliveError>) {
+        match admit_request(&self.api_limiter(), self.api_rate_limit_queue, path) {
+            Ok(wait) if wait > 0.0 => {
+                self.emit_rate_limited(path, Some(wait));
+                std::thread::sleep(Duration::from_secs_f64(wait));
+            }
+            Ok(_) => {}
+            Err(error) => {
+                self.emit_rate_limited(path, None);
+                return error::check_response(Err(error));
+            }
+        }
+        let (result, meta) = Self::blocking_send(
+            &self.http_client(),
+            &self.api_base_url.to_string(),
+            path,
+            &ApiCall::Post(body.to_string()),
+            &self.access_key_id.to_string(),
+            &self.access_key_secret.to_string(),
+            &self.traffic,
+        );
+        self.record_http_response(path, meta);
+        error::check_response(result)
+    }
+
+    fn emit_rate_limited(&mut self, path: &str, wait_secs: Option<f64>) {
+        let args = [
+            path.to_variant(),
+            wait_secs.is_some().to_variant(),
+            wait_secs.unwrap_or(0.0).to_variant(),
+        ];
+        self.emit("rate_limited", &args);
+    }
+
+    fn record_http_response(&mut self, path: &str, meta: HttpMeta) {
+        let headers = json_to_variant(&meta.headers_json());
+        self.emit(
+            "http_response_received",
+            &[
+                path.to_variant(),
+                (meta.status as i64).to_variant(),
+                headers,
+            ],
+        );
+        self.http_responses.insert(path.to_string(), meta);
+    }
+
+    /// 发送签名后的请求，返回响应文本
+    fn blocking_send(
+        client: &reqwest::blocking::Client,
+        base_url: &str,
+        path: &str,
+        call: &ApiCall,
+        access_key_id: &str,
+        access_key_secret: &str,
+        traffic: &TrafficStats,
+    ) -> (Result<String, BliveError>, HttpMeta) {
+        let mut url = format!("{}{}", base_url, path);
+        if let ApiCall::Get(query) = call {
+            if !query.is_empty() {
+                url = format!("{}?{}", url, signing::canonical_query(query));
+            }
+        }
+        godot_print!("发送 HTTP 请求到: {}", url);
+        let headers = Self::generate_signed_headers(call, access_key_id, access_key_secret);
+
+        let (mut request, body) = match call {
+            ApiCall::Post(body) => (client.post(&url).body(body.clone()), body.as_str()),
+            ApiCall::Get(_) => (client.get(&url), ""),
+        };
+        for (key, value) in &headers {
+            request = request.header(key.as_str(), value.as_str());
+        }
+
+        let http_error = |status: u16, stage: &str, e: reqwest::Error| {
+            if e.is_timeout() {
+                BliveError::Timeout(e.to_string())
+            } else {
+                BliveError::Http {
+                    status,
+                    message: format!("{}: {}", stage, e),
+                }
+            }
+        };
+        let response = match request.send() {
+            Ok(response) => response,
+            Err(e) => return (Err(http_error(0, "请求发送失败", e)), HttpMeta::default()),
+        };
+        let meta = HttpMeta::from_response(&response);
+        let status = response.status();
+        let text = match response.text() {
+            Ok(text) => text,
+            Err(e) => return (Err(http_error(status.as_u16(), "响应读取失败", e)), meta),
+        };
+        traffic.http_exchange(body.len(), text.len());
+        if !status.is_success() {
+            let error = BliveError::Http {
+                status: status.as_u16(),
+                message: text,
+            };
+            return (Err(error), meta);
+        }
+        (Ok(text), meta)
+    }
+
+    /// 生成开放平台签名头，可在后台线程中使用；POST 的 body 必须与实际发送的字节完全一致
+    fn generate_signed_headers(
+        call: &ApiCall,
+        access_key_id: &str,
+        access_key_secret: &str,
+    ) -> BTreeMap<String, String> {
+        let (nonce, timestamp) = (Self::generate_nonce(), Self::generate_timestamp());
+        match call {
+            ApiCall::Post(body) => {
+                signing::signed_headers(body, access_key_id, access_key_secret, &nonce, &timestamp)
+            }
+            ApiCall::Get(_) => {
+                signing::signed_get_headers(access_key_id, access_key_secret, &nonce, &timestamp)
+            }
+        }
+    }
+
+    fn generate_nonce() -> String {
+        rand::thread_rng().gen::<u64>().to_string()
+    }
+
+    fn generate_timestamp() -> String {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            .to_string()
+    }
+}
@@ -0,0 +1,32 @@
+use godot::prelude::*;
+
+/// 节点元数据中的频道名，导出属性 `channel_name` 为空时使用
+pub const META_KEY: &str = "channel_name";
+/// 信号字典参数中标记来源的键
+pub const DATA_KEY: &str = "channel";
+
+/// 节点的频道名：导出属性优先，为空时读取节点元数据 `channel_name`
+pub fn resolve(node: &Node, exported: &GString) -> GString {
+    if !exported.is_empty() || !node.has_meta(META_KEY) {
+        return exported.clone();
+    }
+    node.get_meta(META_KEY).try_to().unwrap_or_default()
+}
+
+/// 给信号参数中的字典加上 channel 键；字典先复制一份，不改动调用方或其他监听者持有的同一字典。
+/// 已带有 channel 的字典（例如转发自其他节点的事件）保留原来的来源
+pub fn tag(channel: &GString, args: &[Variant]) -> Vec<Variant> {
+    if channel.is_empty() {
+        return args.to_vec();
+    }
+    args.iter()
+        .map(|arg| match arg.try_to::<Dictionary>() {
+            Ok(dictionary) if !dictionary.contains_key(DATA_KEY) => {
+                let mut tagged = dictionary.duplicate_shallow();
+                tagged.set(DATA_KEY, channel.clone());
+                tagged.to_variant()
+            }
+            _ => arg.clone(),
+        })
+        .collect()
+}
@@ -0,0 +1,54 @@
+use std::collections::VecDeque;
+
+/// 参与估算的最近样本数
+const SAMPLE_WINDOW: usize = 32;
+
+/// 本地时钟与平台时钟的差值估算
+///
+/// 每个样本是事件的本地接收时间减平台时间（毫秒），包含网络延迟，
+/// 取最近样本中的最小值作为时钟偏移，即延迟最低的那条消息。
+#[derive(Debug, Default)]
+pub struct ClockOffset {
+    samples: VecDeque<i64>,
+}
+
+impl ClockOffset {
+    pub fn add_sample(&mut self, latency_ms: i64) {
+        if self.samples.len() == SAMPLE_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(latency_ms);
+    }
+
+    /// 本地时间减平台时间（毫秒），正数表示本地时钟偏快，没有样本时为 None
+    pub fn offset_ms(&self) -> Option<i64> {
+        self.samples.iter().min().copied()
+    }
+
+    /// 按偏移换算的平台当前时间，没有样本时等于本地时间
+    pub fn server_time_ms(&self, local_ms: i64) -> i64 {
+        local_ms - self.offset_ms().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_lowest_recent_latency() {
+        let mut clock = ClockOffset::default();
+        assert_eq!(clock.offset_ms(), None);
+        assert_eq!(clock.server_time_ms(1000), 1000);
+
+        clock.add_sample(-300);
+        for _ in 0..SAMPLE_WINDOW - 1 {
+            clock.add_sample(-200);
+        }
+        assert_eq!(clock.offset_ms(), Some(-300));
+        assert_eq!(clock.server_time_ms(1000), 1300);
+
+        clock.add_sample(-150);
+        assert_eq!(clock.offset_ms(), Some(-200));
+    }
+}
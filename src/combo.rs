@@ -0,0 +1,115 @@
+use serde_json::{json, Value};
+
+/// 同一观众的同一礼物在该秒数内再次送出视为连击
+pub const COMBO_TIMEOUT_SECS: f64 = 5.0;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Combo {
+    user_id: String,
+    uname: String,
+    gift_id: i64,
+    gift_name: String,
+    count: i64,
+    price: i64,
+    started: f64,
+    last_gift: f64,
+}
+
+/// 进行中的礼物连击，由统一的 gift 事件驱动
+#[derive(Debug, Default)]
+pub struct ComboTracker {
+    combos: Vec<Combo>,
+}
+
+impl ComboTracker {
+    /// 记录一次 gift 事件（`events::normalize` 的 data），返回该连击累计的礼物数量
+    pub fn record(&mut self, data: &Value, now: f64) -> i64 {
+        self.prune(now);
+        let user_id = data["user_id"].as_str().unwrap_or_default();
+        let gift_id = data["gift_id"].as_i64().unwrap_or(0);
+        let gift_name = data["gift_name"].as_str().unwrap_or_default();
+        let num = data["gift_num"].as_i64().unwrap_or(1).max(1);
+        let price = data["price"].as_i64().unwrap_or(0);
+
+        let existing = self.combos.iter_mut().find(|combo| {
+            combo.user_id == user_id && combo.gift_id == gift_id && combo.gift_name == gift_name
+        });
+        if let Some(combo) = existing {
+            combo.count += num;
+            combo.price += price;
+            combo.last_gift = now;
+            return combo.count;
+        }
+        self.combos.push(Combo {
+            user_id: user_id.to_string(),
+            uname: data["uname"].as_str().unwrap_or_default().to_string(),
+            gift_id,
+            gift_name: gift_name.to_string(),
+            count: num,
+            price,
+            started: now,
+            last_gift: now,
+        });
+        num
+    }
+
+    /// 移除超过 COMBO_TIMEOUT_SECS 没有新礼物的连击
+    pub fn prune(&mut self, now: f64) {
+        self.combos
+            .retain(|combo| now - combo.last_gift < COMBO_TIMEOUT_SECS);
+    }
+
+    /// 进行中的连击，按开始时间排序；elapsed 为连击已持续的秒数，remaining 为距离结束的秒数
+    pub fn active(&self, now: f64) -> Vec<Value> {
+        self.combos
+            .iter()
+            .filter(|combo| now - combo.last_gift < COMBO_TIMEOUT_SECS)
+            .map(|combo| {
+                json!({
+                    "user_id": combo.user_id,
+                    "uname": combo.uname,
+                    "gift_id": combo.gift_id,
+                    "gift_name": combo.gift_name,
+                    "count": combo.count,
+                    "price": combo.price,
+                    "elapsed": now - combo.started,
+                    "remaining": COMBO_TIMEOUT_SECS - (now - combo.last_gift),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gift(user_id: &str, gift_name: &str, num: i64) -> Value {
+        json!({
+            "user_id": user_id,
+            "uname": user_id,
+            "gift_id": 1,
+            "gift_name": gift_name,
+            "gift_num": num,
+            "price": 100 * num,
+        })
+    }
+
+    #[test]
+    fn accumulates_and_expires_combos() {
+        let mut combos = ComboTracker::default();
+        assert_eq!(combos.record(&gift("a", "辣条", 1), 0.0), 1);
+        assert_eq!(combos.record(&gift("a", "辣条", 2), 3.0), 3);
+        assert_eq!(combos.record(&gift("b", "辣条", 1), 3.0), 1);
+
+        let active = combos.active(4.0);
+        assert_eq!(active.len(), 2);
+        assert_eq!(active[0]["count"], 3);
+        assert_eq!(active[0]["price"], 300);
+        assert_eq!(active[0]["elapsed"], 4.0);
+        assert_eq!(active[0]["remaining"], 4.0);
+
+        assert_eq!(combos.active(8.0).len(), 0);
+        assert_eq!(combos.record(&gift("a", "辣条", 1), 9.0), 1);
+    }
+}
@@ -0,0 +1,129 @@
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+
+/// 最多保留的连接记录数，超出后丢弃最早的记录
+pub const MAX_CONNECTION_HISTORY: usize = 50;
+
+/// 一次长连接尝试，从发起连接到失败或断开
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionAttempt {
+    pub host: String,
+    /// 发起连接的 Unix 毫秒时间戳
+    pub started_ms: i64,
+    /// failed（未建立连接）、disconnected（建立后断开）或 stopped（主动停止）
+    pub outcome: &'static str,
+    /// 见 `categorize`，正常结束时为空
+    pub error_category: &'static str,
+    pub error: String,
+    pub duration_ms: i64,
+}
+
+impl ConnectionAttempt {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "host": self.host,
+            "started_ms": self.started_ms,
+            "outcome": self.outcome,
+            "error_category": self.error_category,
+            "error": self.error,
+            "duration_ms": self.duration_ms,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ConnectionHistory {
+    attempts: VecDeque<ConnectionAttempt>,
+}
+
+impl ConnectionHistory {
+    pub fn push(&mut self, attempt: ConnectionAttempt) {
+        if self.attempts.len() == MAX_CONNECTION_HISTORY {
+            self.attempts.pop_front();
+        }
+        self.attempts.push_back(attempt);
+    }
+
+    /// 按时间先后排列
+    pub fn to_json(&self) -> Vec<Value> {
+        self.attempts
+            .iter()
+            .map(ConnectionAttempt::to_json)
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.attempts.clear();
+    }
+}
+
+/// 连接地址中的主机名（含端口），无法解析时原样返回
+pub fn host_of(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    host.to_string()
+}
+
+/// 按错误信息归类：dns / tls / timeout / refused / http / closed / network / other
+pub fn categorize(error: &str) -> &'static str {
+    let error = error.to_lowercase();
+    let has = |patterns: &[&str]| patterns.iter().any(|pattern| error.contains(pattern));
+    if has(&["dns", "lookup", "resolve", "name or service not known"]) {
+        "dns"
+    } else if has(&["tls", "ssl", "certificate"]) {
+        "tls"
+    } else if has(&["timed out", "timeout"]) {
+        "timeout"
+    } else if has(&["refused"]) {
+        "refused"
+    } else if has(&["http error", "http status", "status code"]) {
+        "http"
+    } else if has(&["close", "closed"]) {
+        "closed"
+    } else if has(&["reset", "broken pipe", "io error", "connection"]) {
+        "network"
+    } else {
+        "other"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_latest_attempts() {
+        let mut history = ConnectionHistory::default();
+        for i in 0..MAX_CONNECTION_HISTORY + 3 {
+            history.push(ConnectionAttempt {
+                host: host_of("wss://user@broadcast.chat.bilibili.com:443/sub?x=1"),
+                started_ms: i as i64,
+                outcome: "failed",
+                error_category: categorize("IO error: Connection refused (os error 111)"),
+                error: String::new(),
+                duration_ms: 0,
+            });
+        }
+        let attempts = history.to_json();
+        assert_eq!(attempts.len(), MAX_CONNECTION_HISTORY);
+        assert_eq!(attempts[0]["started_ms"], 3);
+        assert_eq!(attempts[0]["host"], "broadcast.chat.bilibili.com:443");
+        assert_eq!(attempts[0]["error_category"], "refused");
+    }
+
+    #[test]
+    fn categorizes_errors() {
+        assert_eq!(categorize("failed to lookup address information"), "dns");
+        assert_eq!(categorize("TLS error: invalid certificate"), "tls");
+        assert_eq!(categorize("HTTP error: 403 Forbidden"), "http");
+        assert_eq!(categorize("Connection closed normally"), "closed");
+        assert_eq!(
+            categorize("Connection reset without closing handshake"),
+            "network"
+        );
+        assert_eq!(categorize("???"), "other");
+    }
+}
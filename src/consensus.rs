@@ -0,0 +1,193 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// 两条弹幕归为同一说法所需的最低相似度（字符二元组的 Jaccard 系数）
+pub const SIMILARITY: f64 = 0.6;
+/// 两次聚类之间的最短间隔（秒），避免弹幕密集时每条都重新聚类
+pub const CHECK_INTERVAL_SECS: f64 = 0.5;
+
+/// 去掉空白和标点、转小写，并把连续重复的字符合并为一个（"哈哈哈"、"2333" 分别视为 "哈"、"23"）
+pub fn normalize(text: &str) -> String {
+    let mut normalized = String::new();
+    for c in text.chars().filter(|c| c.is_alphanumeric()) {
+        for c in c.to_lowercase() {
+            if !normalized.ends_with(c) {
+                normalized.push(c);
+            }
+        }
+    }
+    normalized
+}
+
+fn bigrams(text: &str) -> HashSet<(char, char)> {
+    let chars: Vec<char> = text.chars().collect();
+    match chars.as_slice() {
+        [] => HashSet::new(),
+        [c] => HashSet::from([(*c, *c)]),
+        _ => chars.windows(2).map(|pair| (pair[0], pair[1])).collect(),
+    }
+}
+
+/// 两段已 normalize 的文本的相似度，0 到 1
+pub fn similarity(a: &str, b: &str) -> f64 {
+    if a == b {
+        return 1.0;
+    }
+    let (a, b) = (bigrams(a), bigrams(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+#[derive(Debug)]
+struct Message {
+    at: f64,
+    user_id: String,
+    text: String,
+    normalized: String,
+}
+
+/// 滑动窗口内的弹幕聚类：每位观众只按最近一条计，最大的一类占窗口内观众的比例达到阈值时
+/// 视为“弹幕都在说同一件事”；同一说法持续达标时只报告一次，跌破阈值后才可再次报告
+#[derive(Debug, Default)]
+pub struct ConsensusDetector {
+    messages: VecDeque<Message>,
+    last_check: Option<f64>,
+    /// 最近一次报告的说法（normalize 后）
+    reported: Option<String>,
+}
+
+impl ConsensusDetector {
+    pub fn observe(&mut self, user_id: &str, text: &str, now: f64) {
+        let normalized = normalize(text);
+        if normalized.is_empty() {
+            return;
+        }
+        self.messages.push_back(Message {
+            at: now,
+            user_id: user_id.to_string(),
+            text: text.trim().to_string(),
+            normalized,
+        });
+    }
+
+    /// 窗口内观众数不少于 min_users 且最大一类的观众占比不低于 min_ratio 时，
+    /// 返回该类中出现最多的原文和占比
+    pub fn check(
+        &mut self,
+        now: f64,
+        window_secs: f64,
+        min_ratio: f64,
+        min_users: usize,
+    ) -> Option<(String, f64)> {
+        if self
+            .last_check
+            .is_some_and(|last| now - last < CHECK_INTERVAL_SECS)
+        {
+            return None;
+        }
+        self.last_check = Some(now);
+        while self
+            .messages
+            .front()
+            .is_some_and(|message| now - message.at > window_secs)
+        {
+            self.messages.pop_front();
+        }
+
+        let mut latest: HashMap<&str, &Message> = HashMap::new();
+        for message in &self.messages {
+            latest.insert(&message.user_id, message);
+        }
+        let users = latest.len();
+        let mut voters: Vec<&Message> = latest.into_values().collect();
+        voters.sort_by(|a, b| a.at.total_cmp(&b.at));
+
+        // 贪心聚类：每条归入第一个与其代表足够相似的类，否则自成一类
+        let mut clusters: Vec<Vec<&Message>> = Vec::new();
+        for message in voters {
+            let cluster = clusters.iter_mut().find(|cluster| {
+                similarity(&cluster[0].normalized, &message.normalized) >= SIMILARITY
+            });
+            match cluster {
+                Some(cluster) => cluster.push(message),
+                None => clusters.push(vec![message]),
+            }
+        }
+        let largest = clusters.into_iter().max_by_key(|cluster| cluster.len());
+        let Some(largest) = largest.filter(|_| users >= min_users.max(1)) else {
+            self.reported = None;
+            return None;
+        };
+        let ratio = largest.len() as f64 / users as f64;
+        if ratio < min_ratio {
+            self.reported = None;
+            return None;
+        }
+        let key = largest[0].normalized.clone();
+        if self.reported.as_ref() == Some(&key) {
+            return None;
+        }
+        self.reported = Some(key);
+
+        // 按 normalize 后的文本计数，同样多时取较早的说法
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for message in &largest {
+            *counts.entry(&message.normalized).or_default() += 1;
+        }
+        let mut text = &largest[0];
+        for message in &largest {
+            if counts[message.normalized.as_str()] > counts[text.normalized.as_str()] {
+                text = message;
+            }
+        }
+        let text = text.text.clone();
+        Some((text, ratio))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_and_compares() {
+        assert_eq!(normalize("哈哈哈哈！"), "哈");
+        assert_eq!(normalize(" Go LEFT!! "), "goleft");
+        assert_eq!(normalize("233333"), "23");
+        assert_eq!(similarity("左边", "左边"), 1.0);
+        assert!(similarity(&normalize("走左边"), &normalize("走左边吧")) >= SIMILARITY);
+        assert!(similarity(&normalize("走左边"), &normalize("走右边")) < SIMILARITY);
+    }
+
+    #[test]
+    fn detects_converging_chat_once() {
+        let mut detector = ConsensusDetector::default();
+        detector.observe("a", "走左边", 0.0);
+        detector.observe("b", "走左边！", 0.1);
+        detector.observe("c", "走左边吧", 0.2);
+        detector.observe("d", "走右边", 0.3);
+        // 同一观众刷屏只算一票
+        for _ in 0..5 {
+            detector.observe("d", "走右边", 0.4);
+        }
+        assert_eq!(
+            detector.check(1.0, 10.0, 0.5, 3),
+            Some(("走左边".into(), 0.75))
+        );
+        // 间隔太短不重新聚类，达标期间不重复报告
+        assert_eq!(detector.check(1.2, 10.0, 0.5, 3), None);
+        assert_eq!(detector.check(2.0, 10.0, 0.5, 3), None);
+
+        // 窗口过后观众不足，解除报告
+        assert_eq!(detector.check(20.0, 10.0, 0.5, 3), None);
+        detector.observe("a", "走左边", 21.0);
+        detector.observe("b", "走左边", 21.0);
+        detector.observe("c", "走左边", 21.0);
+        assert_eq!(
+            detector.check(22.0, 10.0, 0.5, 3),
+            Some(("走左边".into(), 1.0))
+        );
+    }
+}
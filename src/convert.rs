@@ -0,0 +1,76 @@
+use godot::classes::ProjectSettings;
+use godot::prelude::*;
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// 把 JSON 转成 Godot 值：对象转 Dictionary，数组转 Array，整数保持为 int
+pub fn json_to_variant(value: &Value) -> Variant {
+    match value {
+        Value::Null => Variant::nil(),
+        Value::Bool(b) => b.to_variant(),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => i.to_variant(),
+            None => n.as_f64().unwrap_or_default().to_variant(),
+        },
+        Value::String(s) => s.to_variant(),
+        Value::Array(items) => {
+            let mut array = Array::<Variant>::new();
+            for item in items {
+                array.push(&json_to_variant(item));
+            }
+            array.to_variant()
+        }
+        Value::Object(map) => json_to_dictionary(map).to_variant(),
+    }
+}
+
+pub fn json_to_dictionary(map: &serde_json::Map<String, Value>) -> Dictionary {
+    let mut dict = Dictionary::new();
+    for (key, value) in map {
+        dict.set(GString::from(key.as_str()), json_to_variant(value));
+    }
+    dict
+}
+
+/// 把 Godot 值转回 JSON，无法表示的类型（对象、向量等）使用其字符串形式
+pub fn variant_to_json(value: &Variant) -> Value {
+    match value.get_type() {
+        VariantType::NIL => Value::Null,
+        VariantType::BOOL => value.to::<bool>().into(),
+        VariantType::INT => value.to::<i64>().into(),
+        VariantType::FLOAT => value.to::<f64>().into(),
+        VariantType::DICTIONARY => Value::Object(dictionary_to_json_map(&value.to())),
+        VariantType::ARRAY => Value::Array(
+            value
+                .to::<Array<Variant>>()
+                .iter_shared()
+                .map(|item| variant_to_json(&item))
+                .collect(),
+        ),
+        _ => value.stringify().to_string().into(),
+    }
+}
+
+pub fn dictionary_to_json(dict: &Dictionary) -> Value {
+    Value::Object(dictionary_to_json_map(dict))
+}
+
+fn dictionary_to_json_map(dict: &Dictionary) -> serde_json::Map<String, Value> {
+    dict.iter_shared()
+        .map(|(key, value)| (key.stringify().to_string(), variant_to_json(&value)))
+        .collect()
+}
+
+/// 把 `user://`、`res://` 路径转成系统路径，其他路径原样返回
+pub fn globalize_path(path: &GString) -> PathBuf {
+    let text = path.to_string();
+    if text.starts_with("user://") || text.starts_with("res://") {
+        PathBuf::from(
+            ProjectSettings::singleton()
+                .globalize_path(path)
+                .to_string(),
+        )
+    } else {
+        PathBuf::from(text)
+    }
+}
@@ -0,0 +1,208 @@
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// 每帧评估的连接质量
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Quality {
+    /// 距上次长连接心跳回复的秒数，未连接时为负数
+    pub reply_age_secs: f64,
+    /// 主线程待处理的消息数
+    pub queue_depth: usize,
+    /// 项目心跳已连续失败到阈值
+    pub heartbeat_failing: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Degraded(&'static str),
+    Recovered,
+}
+
+/// 降级策略：连接质量变差时只发出摘要信号并降低每帧处理的消息数，持续恢复一段时间后还原
+#[derive(Debug, Clone)]
+pub struct DegradationPolicy {
+    /// 长连接心跳回复超过该秒数视为连接变差（心跳每 30 秒一次）
+    pub reply_age_secs: f64,
+    /// 待处理消息超过该数量视为主线程跟不上
+    pub queue_depth: usize,
+    /// 各项指标恢复正常持续该秒数后退出降级
+    pub recover_secs: f64,
+    /// 正常时每帧最多处理的消息数，0 表示不限
+    pub frame_budget: usize,
+    /// 降级时每帧最多处理的消息数，0 表示不限
+    pub degraded_frame_budget: usize,
+    /// 降级时 `message_digest` 的发出间隔（秒）
+    pub digest_interval: f64,
+    degraded: bool,
+    healthy_for: f64,
+}
+
+impl Default for DegradationPolicy {
+    fn default() -> Self {
+        Self {
+            reply_age_secs: 70.0,
+            queue_depth: 2000,
+            recover_secs: 10.0,
+            frame_budget: 0,
+            degraded_frame_budget: 200,
+            digest_interval: 1.0,
+            degraded: false,
+            healthy_for: 0.0,
+        }
+    }
+}
+
+impl DegradationPolicy {
+    /// 按键名设置参数，未知键或负数返回 false
+    pub fn set(&mut self, key: &str, value: f64) -> bool {
+        if value < 0.0 || !value.is_finite() {
+            return false;
+        }
+        match key {
+            "reply_age_secs" => self.reply_age_secs = value,
+            "queue_depth" => self.queue_depth = value as usize,
+            "recover_secs" => self.recover_secs = value,
+            "frame_budget" => self.frame_budget = value as usize,
+            "degraded_frame_budget" => self.degraded_frame_budget = value as usize,
+            "digest_interval" => self.digest_interval = value,
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.degraded
+    }
+
+    /// 当前每帧最多处理的消息数，0 表示不限
+    pub fn frame_budget(&self) -> usize {
+        if self.degraded {
+            self.degraded_frame_budget
+        } else {
+            self.frame_budget
+        }
+    }
+
+    fn reason(&self, quality: Quality) -> Option<&'static str> {
+        if quality.reply_age_secs > self.reply_age_secs {
+            Some("ws_reply_timeout")
+        } else if quality.queue_depth > self.queue_depth {
+            Some("queue_backlog")
+        } else if quality.heartbeat_failing {
+            Some("heartbeat_failing")
+        } else {
+            None
+        }
+    }
+
+    /// 每帧调用一次，状态切换时返回变化
+    pub fn update(&mut self, quality: Quality, delta: f64) -> Option<Change> {
+        match self.reason(quality) {
+            Some(reason) => {
+                self.healthy_for = 0.0;
+                if self.degraded {
+                    return None;
+                }
+                self.degraded = true;
+                Some(Change::Degraded(reason))
+            }
+            None if self.degraded => {
+                self.healthy_for += delta;
+                if self.healthy_for < self.recover_secs {
+                    return None;
+                }
+                self.degraded = false;
+                self.healthy_for = 0.0;
+                Some(Change::Recovered)
+            }
+            None => None,
+        }
+    }
+
+    /// 立即退出降级，如关闭自动降级时
+    pub fn reset(&mut self) -> bool {
+        self.healthy_for = 0.0;
+        std::mem::replace(&mut self.degraded, false)
+    }
+}
+
+/// 降级期间按 cmd 累计的消息数量，代替逐条的 `ws_message_received`
+#[derive(Debug, Default)]
+pub struct MessageDigest {
+    counts: BTreeMap<String, i64>,
+    since: f64,
+}
+
+impl MessageDigest {
+    pub fn add(&mut self, cmd: &str) {
+        *self.counts.entry(cmd.to_string()).or_default() += 1;
+    }
+
+    /// 距上次发出超过 interval 秒且有累计时取出 cmd → 数量
+    pub fn take_due(&mut self, now: f64, interval: f64) -> Option<Value> {
+        if now - self.since < interval || self.counts.is_empty() {
+            return None;
+        }
+        self.since = now;
+        let counts: Map<String, Value> = std::mem::take(&mut self.counts)
+            .into_iter()
+            .map(|(cmd, count)| (cmd, count.into()))
+            .collect();
+        Some(counts.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn degrades_and_recovers_with_hysteresis() {
+        let mut policy = DegradationPolicy::default();
+        let healthy = Quality {
+            reply_age_secs: 5.0,
+            ..Quality::default()
+        };
+        let stalled = Quality {
+            reply_age_secs: 80.0,
+            ..healthy
+        };
+        assert_eq!(policy.update(healthy, 1.0), None);
+        assert_eq!(policy.frame_budget(), 0);
+        assert_eq!(
+            policy.update(stalled, 1.0),
+            Some(Change::Degraded("ws_reply_timeout"))
+        );
+        assert_eq!(policy.frame_budget(), 200);
+        assert_eq!(policy.update(stalled, 1.0), None);
+        assert_eq!(policy.update(healthy, 6.0), None);
+        assert_eq!(policy.update(stalled, 1.0), None);
+        assert_eq!(policy.update(healthy, 6.0), None);
+        assert_eq!(policy.update(healthy, 6.0), Some(Change::Recovered));
+        assert!(!policy.is_degraded());
+
+        assert!(policy.set("queue_depth", 10.0));
+        assert!(!policy.set("unknown", 1.0));
+        let backlog = Quality {
+            queue_depth: 11,
+            ..healthy
+        };
+        assert_eq!(
+            policy.update(backlog, 1.0),
+            Some(Change::Degraded("queue_backlog"))
+        );
+    }
+
+    #[test]
+    fn digest_counts_by_cmd() {
+        let mut digest = MessageDigest::default();
+        digest.add("DANMU_MSG");
+        digest.add("DANMU_MSG");
+        digest.add("SEND_GIFT");
+        assert_eq!(digest.take_due(0.5, 1.0), None);
+        let counts = digest.take_due(1.0, 1.0).unwrap();
+        assert_eq!(counts["DANMU_MSG"], 2);
+        assert_eq!(counts["SEND_GIFT"], 1);
+        assert_eq!(digest.take_due(5.0, 1.0), None);
+    }
+}
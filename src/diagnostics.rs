@@ -0,0 +1,145 @@
+use serde_json::{json, Value};
+
+/// 本地时钟与平台时钟相差超过该值（毫秒）时，签名时间戳可能被平台拒绝
+pub const MAX_CLOCK_SKEW_MS: i64 = 60_000;
+
+/// 未拿到开放平台长连接地址时检查的默认弹幕服务器
+pub const DEFAULT_WS_HOST: &str = "broadcastlv.chat.bilibili.com";
+
+/// `run_diagnostics` 的检查结果，按执行顺序排列
+#[derive(Debug, Default)]
+pub struct DiagnosticReport {
+    checks: Vec<Value>,
+}
+
+impl DiagnosticReport {
+    pub fn record(&mut self, name: &str, result: Result<String, String>, duration_ms: i64) {
+        let (status, detail) = match result {
+            Ok(detail) => ("ok", detail),
+            Err(detail) => ("failed", detail),
+        };
+        self.push(name, status, detail, duration_ms);
+    }
+
+    pub fn skip(&mut self, name: &str, reason: &str) {
+        self.push(name, "skipped", reason.to_string(), 0);
+    }
+
+    /// skew_ms 为本地时间减平台时间，超出 `MAX_CLOCK_SKEW_MS` 时记为 warning
+    pub fn record_clock_skew(&mut self, skew_ms: i64) {
+        let status = if skew_ms.abs() > MAX_CLOCK_SKEW_MS {
+            "warning"
+        } else {
+            "ok"
+        };
+        let detail = format!(
+            "本地时钟比平台{} {} ms",
+            if skew_ms >= 0 { "快" } else { "慢" },
+            skew_ms.abs()
+        );
+        self.push("clock_skew", status, detail, 0);
+        if let Some(check) = self.checks.last_mut() {
+            check["skew_ms"] = skew_ms.into();
+        }
+    }
+
+    fn push(&mut self, name: &str, status: &str, detail: String, duration_ms: i64) {
+        self.checks.push(json!({
+            "name": name,
+            "status": status,
+            "detail": detail,
+            "duration_ms": duration_ms,
+        }));
+    }
+
+    /// `{"passed": 没有 failed 项, "checks": [...]}`
+    pub fn to_json(&self) -> Value {
+        let passed = self.checks.iter().all(|check| check["status"] != "failed");
+        json!({ "passed": passed, "checks": self.checks })
+    }
+}
+
+/// 主机名没有端口时补上默认端口，用于 DNS 解析
+pub fn with_default_port(host: &str, port: u16) -> String {
+    let has_port = match host.rsplit_once(':') {
+        Some((_, port)) => !host.ends_with(']') && port.parse::<u16>().is_ok(),
+        None => false,
+    };
+    if has_port {
+        host.to_string()
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+/// 解析 HTTP Date 头（`Sun, 06 Nov 1994 08:49:37 GMT`），返回 Unix 毫秒
+pub fn parse_http_date(date: &str) -> Option<i64> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let mut parts = date.split_whitespace().skip(1);
+    let day: i64 = parts.next()?.parse().ok()?;
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as i64 + 1;
+    let year: i64 = parts.next()?.parse().ok()?;
+    let mut time = parts
+        .next()?
+        .split(':')
+        .map(|part| part.parse::<i64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    if parts.next()? != "GMT" {
+        return None;
+    }
+
+    // 公历日期换算为 1970-01-01 起的天数
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+    Some(((days * 24 + hour) * 60 + minute) * 60_000 + second * 1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_http_dates() {
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(784_111_777_000)
+        );
+        assert_eq!(
+            parse_http_date("Thu, 29 Feb 2024 00:00:00 GMT"),
+            Some(1_709_164_800_000)
+        );
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 CST"), None);
+        assert_eq!(parse_http_date("yesterday"), None);
+        assert_eq!(with_default_port("example.com", 443), "example.com:443");
+        assert_eq!(
+            with_default_port("example.com:8443", 443),
+            "example.com:8443"
+        );
+    }
+
+    #[test]
+    fn fails_only_on_failed_checks() {
+        let mut report = DiagnosticReport::default();
+        report.record("config", Ok("配置有效".into()), 0);
+        report.skip("signed_request", "未配置开放平台凭据");
+        report.record_clock_skew(-MAX_CLOCK_SKEW_MS - 1);
+        let json = report.to_json();
+        assert_eq!(json["passed"], true);
+        assert_eq!(json["checks"][2]["status"], "warning");
+        assert_eq!(json["checks"][2]["skew_ms"], -MAX_CLOCK_SKEW_MS - 1);
+
+        report.record("dns_api", Err("failed to lookup address".into()), 12);
+        assert_eq!(report.to_json()["passed"], false);
+    }
+}
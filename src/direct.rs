@@ -0,0 +1,390 @@
+use serde_json::{json, Value};
+
+/// 直连模式使用的公开接口
+const ROOM_INIT_URL: &str = "https://api.live.bilibili.com/room/v1/Room/room_init";
+const DANMU_INFO_URL: &str = "https://api.live.bilibili.com/xlive/web-room/v1/index/getDanmuInfo";
+const DEFAULT_WS_URL: &str = "wss://broadcastlv.chat.bilibili.com/sub";
+const ROOM_INFO_URL: &str = "https://api.live.bilibili.com/room/v1/Room/get_info";
+const SEND_DANMAKU_URL: &str = "https://api.live.bilibili.com/msg/send";
+const GIFT_CONFIG_URL: &str =
+    "https://api.live.bilibili.com/xlive/web-room/v1/giftPanel/giftConfig?platform=pc";
+
+/// 直连模式的登录凭据，从浏览器 Cookie 中提取
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DirectCredentials {
+    pub uid: i64,
+    pub sessdata: String,
+    pub buvid: String,
+    /// Cookie 中的 bili_jct，发送弹幕等写操作需要作为 csrf 参数
+    pub csrf: String,
+}
+
+impl DirectCredentials {
+    /// 解析 `SESSDATA=...; buvid3=...; DedeUserID=...` 形式的 Cookie 字符串，忽略其他字段
+    pub fn from_cookie(cookie: &str) -> Self {
+        let mut credentials = Self::default();
+        for pair in cookie.split(';') {
+            let Some((key, value)) = pair.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().to_string();
+            match key.trim() {
+                "SESSDATA" => credentials.sessdata = value,
+                "buvid3" => credentials.buvid = value,
+                "DedeUserID" => credentials.uid = value.parse().unwrap_or(0),
+                "bili_jct" => credentials.csrf = value,
+                _ => {}
+            }
+        }
+        credentials
+    }
+
+    pub fn is_guest(&self) -> bool {
+        self.sessdata.is_empty()
+    }
+
+    pub fn cookie_header(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.sessdata.is_empty() {
+            parts.push(format!("SESSDATA={}", self.sessdata));
+        }
+        if !self.buvid.is_empty() {
+            parts.push(format!("buvid3={}", self.buvid));
+        }
+        if self.uid > 0 {
+            parts.push(format!("DedeUserID={}", self.uid));
+        }
+        if !self.csrf.is_empty() {
+            parts.push(format!("bili_jct={}", self.csrf));
+        }
+        parts.join("; ")
+    }
+
+    /// 用于日志输出，只保留各字段前 4 个字符
+    pub fn redacted(&self) -> String {
+        format!(
+            "uid={} SESSDATA={} buvid3={}",
+            self.uid,
+            redact(&self.sessdata),
+            redact(&self.buvid)
+        )
+    }
+}
+
+pub fn redact(secret: &str) -> String {
+    if secret.is_empty() {
+        return "<empty>".to_string();
+    }
+    let visible: String = secret.chars().take(4).collect();
+    format!("{}***", visible)
+}
+
+/// 鉴权包中的 key（连接 token）只保留前 4 个字符，无法解析时整体打码
+pub fn redact_auth_body(auth_body: &str) -> String {
+    match serde_json::from_str::<Value>(auth_body) {
+        Ok(mut body) => {
+            if let Some(key) = body["key"].as_str().map(redact) {
+                body["key"] = key.into();
+            }
+            body.to_string()
+        }
+        Err(_) => redact(auth_body),
+    }
+}
+
+/// 直连直播间弹幕服务器所需的信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoomConnection {
+    pub room_id: i64,
+    pub ws_url: String,
+    pub auth_body: String,
+    /// 未携带登录凭据，平台只返回打码的用户名
+    pub guest: bool,
+}
+
+/// 通过房间初始化接口和弹幕信息接口获取连接地址与 token，短号会被换成真实房间号
+///
+/// 提供凭据时会带上 Cookie 请求 token，并以该账号的 uid 鉴权，从而获得完整用户名
+pub fn resolve_room(
+    client: &reqwest::blocking::Client,
+    room_id: i64,
+    credentials: &DirectCredentials,
+) -> Result<RoomConnection, String> {
+    let room_init = get_json(
+        client,
+        &format!("{}?id={}", ROOM_INIT_URL, room_id),
+        credentials,
+    )?;
+    let room_id = parse_room_init(&room_init)?;
+    let danmu_info = get_json(
+        client,
+        &format!("{}?id={}&type=0", DANMU_INFO_URL, room_id),
+        credentials,
+    )?;
+    let (ws_url, token) = parse_danmu_info(&danmu_info)?;
+    Ok(RoomConnection {
+        room_id,
+        ws_url,
+        auth_body: auth_body(room_id, &token, credentials),
+        guest: credentials.is_guest(),
+    })
+}
+
+/// 查询直播间信息，不需要登录
+pub fn fetch_room_info(client: &reqwest::blocking::Client, room_id: i64) -> Result<Value, String> {
+    let response = get_json(
+        client,
+        &format!("{}?room_id={}", ROOM_INFO_URL, room_id),
+        &DirectCredentials::default(),
+    )?;
+    parse_room_info(&response)
+}
+
+/// 查询全站礼物配置（ID、名称、单价、图标），不需要登录
+pub fn fetch_gift_config(client: &reqwest::blocking::Client) -> Result<Value, String> {
+    let response = get_json(client, GIFT_CONFIG_URL, &DirectCredentials::default())?;
+    check_code(&response)?;
+    Ok(response)
+}
+
+/// 提取标题、分区、封面、开播状态等常用字段；live_status 为 0 未开播、1 直播中、2 轮播
+pub fn parse_room_info(response: &Value) -> Result<Value, String> {
+    check_code(response)?;
+    let data = &response["data"];
+    let live_status = data["live_status"].as_i64().unwrap_or(0);
+    Ok(json!({
+        "room_id": data["room_id"].as_i64().unwrap_or(0),
+        "short_id": data["short_id"].as_i64().unwrap_or(0),
+        "uid": data["uid"].as_i64().unwrap_or(0),
+        "title": data["title"].as_str().unwrap_or_default(),
+        "description": data["description"].as_str().unwrap_or_default(),
+        "area": data["area_name"].as_str().unwrap_or_default(),
+        "parent_area": data["parent_area_name"].as_str().unwrap_or_default(),
+        "cover": data["user_cover"].as_str().unwrap_or_default(),
+        "keyframe": data["keyframe"].as_str().unwrap_or_default(),
+        "live_status": live_status,
+        "is_live": live_status == 1,
+        "live_time": data["live_time"].as_str().unwrap_or_default(),
+        "online": data["online"].as_i64().unwrap_or(0),
+    }))
+}
+
+/// 以登录账号向直播间发送一条弹幕
+pub fn send_danmaku(
+    client: &reqwest::blocking::Client,
+    room_id: i64,
+    text: &str,
+    credentials: &DirectCredentials,
+) -> Result<(), String> {
+    if credentials.is_guest() || credentials.csrf.is_empty() {
+        return Err("发送弹幕需要包含 SESSDATA 和 bili_jct 的 Cookie".to_string());
+    }
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        .to_string();
+    let room_id = room_id.to_string();
+    let form = [
+        ("bubble", "0"),
+        ("msg", text),
+        ("color", "16777215"),
+        ("mode", "1"),
+        ("fontsize", "25"),
+        ("rnd", timestamp.as_str()),
+        ("roomid", room_id.as_str()),
+        ("csrf", credentials.csrf.as_str()),
+        ("csrf_token", credentials.csrf.as_str()),
+    ];
+    let text = client
+        .post(SEND_DANMAKU_URL)
+        .header("Cookie", credentials.cookie_header())
+        .form(&form)
+        .send()
+        .map_err(|e| format!("请求发送失败: {}", e))?
+        .text()
+        .map_err(|e| format!("响应读取失败: {}", e))?;
+    let response: Value =
+        serde_json::from_str(&text).map_err(|e| format!("响应解析失败: {}", e))?;
+    check_code(&response)
+}
+
+fn get_json(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    credentials: &DirectCredentials,
+) -> Result<Value, String> {
+    let mut request = client.get(url);
+    if !credentials.is_guest() {
+        request = request.header("Cookie", credentials.cookie_header());
+    }
+    let text = request
+        .send()
+        .map_err(|e| format!("请求发送失败: {}", e))?
+        .text()
+        .map_err(|e| format!("响应读取失败: {}", e))?;
+    serde_json::from_str(&text).map_err(|e| format!("响应解析失败: {}", e))
+}
+
+fn check_code(response: &Value) -> Result<(), String> {
+    match response["code"].as_i64() {
+        Some(0) => Ok(()),
+        code => Err(format!(
+            "接口返回错误: code={} message={}",
+            code.unwrap_or(-1),
+            response["message"].as_str().unwrap_or_default()
+        )),
+    }
+}
+
+pub fn parse_room_init(response: &Value) -> Result<i64, String> {
+    check_code(response)?;
+    response["data"]["room_id"]
+        .as_i64()
+        .ok_or_else(|| "响应中缺少 room_id".to_string())
+}
+
+/// 返回 (长连接地址, token)，没有可用服务器时退回默认地址
+pub fn parse_danmu_info(response: &Value) -> Result<(String, String), String> {
+    check_code(response)?;
+    let data = &response["data"];
+    let token = data["token"]
+        .as_str()
+        .ok_or_else(|| "响应中缺少 token".to_string())?
+        .to_string();
+    let ws_url = data["host_list"]
+        .as_array()
+        .and_then(|hosts| hosts.first())
+        .and_then(|host| {
+            let name = host["host"].as_str()?;
+            let port = host["wss_port"].as_i64().unwrap_or(443);
+            Some(format!("wss://{}:{}/sub", name, port))
+        })
+        .unwrap_or_else(|| DEFAULT_WS_URL.to_string());
+    Ok((ws_url, token))
+}
+
+pub fn auth_body(room_id: i64, token: &str, credentials: &DirectCredentials) -> String {
+    let mut body = json!({
+        "uid": credentials.uid,
+        "roomid": room_id,
+        "protover": 2,
+        "platform": "web",
+        "type": 2,
+        "key": token,
+    });
+    if !credentials.buvid.is_empty() {
+        body["buvid"] = credentials.buvid.clone().into();
+    }
+    body.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn room_init_resolves_short_id() {
+        let response = json!({"code": 0, "data": {"room_id": 21452505, "short_id": 1}});
+        assert_eq!(parse_room_init(&response), Ok(21452505));
+
+        let missing = json!({"code": 60004, "message": "直播间不存在"});
+        assert!(parse_room_init(&missing).unwrap_err().contains("60004"));
+    }
+
+    #[test]
+    fn danmu_info_picks_first_host() {
+        let response = json!({
+            "code": 0,
+            "data": {
+                "token": "tok",
+                "host_list": [
+                    {"host": "hw-sh-live-comet-02.chat.bilibili.com", "wss_port": 443},
+                    {"host": "broadcastlv.chat.bilibili.com", "wss_port": 443}
+                ]
+            }
+        });
+        assert_eq!(
+            parse_danmu_info(&response),
+            Ok((
+                "wss://hw-sh-live-comet-02.chat.bilibili.com:443/sub".to_string(),
+                "tok".to_string()
+            ))
+        );
+
+        let no_hosts = json!({"code": 0, "data": {"token": "tok", "host_list": []}});
+        assert_eq!(parse_danmu_info(&no_hosts).unwrap().0, DEFAULT_WS_URL);
+    }
+
+    #[test]
+    fn room_info_fields() {
+        let response = json!({
+            "code": 0,
+            "data": {
+                "room_id": 21452505, "short_id": 0, "uid": 434334701,
+                "title": "测试直播", "area_name": "单机游戏", "parent_area_name": "游戏",
+                "user_cover": "https://cover", "live_status": 1,
+                "live_time": "2024-01-01 20:00:00", "online": 1234
+            }
+        });
+        let info = parse_room_info(&response).unwrap();
+        assert_eq!(info["title"], "测试直播");
+        assert_eq!(info["area"], "单机游戏");
+        assert_eq!(info["cover"], "https://cover");
+        assert_eq!(info["is_live"], true);
+        assert_eq!(info["online"], 1234);
+
+        assert!(parse_room_info(&json!({"code": 1, "message": "房间不存在"})).is_err());
+    }
+
+    #[test]
+    fn auth_body_fields() {
+        let guest = DirectCredentials::default();
+        let body: Value = serde_json::from_str(&auth_body(42, "tok", &guest)).unwrap();
+        assert_eq!(body["roomid"], 42);
+        assert_eq!(body["uid"], 0);
+        assert_eq!(body["protover"], 2);
+        assert_eq!(body["key"], "tok");
+        assert!(body.get("buvid").is_none());
+
+        let credentials = DirectCredentials::from_cookie("DedeUserID=7; buvid3=B-1; SESSDATA=s");
+        let body: Value = serde_json::from_str(&auth_body(42, "tok", &credentials)).unwrap();
+        assert_eq!(body["uid"], 7);
+        assert_eq!(body["buvid"], "B-1");
+    }
+
+    #[test]
+    fn cookie_parsing_and_redaction() {
+        let credentials = DirectCredentials::from_cookie(
+            " SESSDATA=abcdef123%2C456 ; bili_jct=x; buvid3=XYZW-1234infoc; DedeUserID=12345",
+        );
+        assert_eq!(
+            credentials,
+            DirectCredentials {
+                uid: 12345,
+                sessdata: "abcdef123%2C456".to_string(),
+                buvid: "XYZW-1234infoc".to_string(),
+                csrf: "x".to_string(),
+            }
+        );
+        assert!(!credentials.is_guest());
+        assert_eq!(
+            credentials.cookie_header(),
+            "SESSDATA=abcdef123%2C456; buvid3=XYZW-1234infoc; DedeUserID=12345; bili_jct=x"
+        );
+        assert_eq!(
+            credentials.redacted(),
+            "uid=12345 SESSDATA=abcd*** buvid3=XYZW***"
+        );
+        assert!(DirectCredentials::from_cookie("").is_guest());
+    }
+
+    #[test]
+    fn auth_body_redaction() {
+        let redacted = redact_auth_body(r#"{"roomid":1,"key":"abcdefgh","protoover":2}"#);
+        let body: Value = serde_json::from_str(&redacted).unwrap();
+        assert_eq!(body["key"], "abcd***");
+        assert_eq!(body["roomid"], 1);
+        assert_eq!(redact_auth_body("not json"), "not ***");
+    }
+}
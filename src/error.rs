@@ -0,0 +1,184 @@
+use serde_json::{json, Value};
+use std::fmt;
+
+/// `error_occurred` 的 domain，下标与 `BliveNames.ERROR_*` 常量一致
+pub const ERROR_DOMAINS: [&str; 6] = [
+    "http",
+    "signature",
+    "websocket",
+    "protocol",
+    "api",
+    "timeout",
+];
+
+/// 开放平台表示签名无效、请求过期或 nonce 重复的错误码
+const SIGNATURE_CODES: [i64; 3] = [4002, 4003, 4004];
+/// 开放平台表示身份码错误或已失效的错误码
+pub const INVALID_CODE: i64 = 7007;
+
+/// 按来源分类的错误，通过 `error_occurred(domain, code, message)` 发出
+#[derive(Debug, Clone, PartialEq)]
+pub enum BliveError {
+    /// 请求未发出、未收到响应（status 为 0）或 HTTP 状态码不是 2xx
+    Http {
+        status: u16,
+        message: String,
+    },
+    /// 平台拒绝了请求签名
+    Signature {
+        code: i64,
+        message: String,
+    },
+    WebSocket(String),
+    /// 长连接数据包无法解析或超出大小限制
+    Protocol(String),
+    /// 平台返回的非 0 code
+    Api {
+        code: i64,
+        message: String,
+    },
+    /// 连接或读取响应超过 `http_connect_timeout` / `http_timeout`
+    Timeout(String),
+}
+
+impl BliveError {
+    pub fn domain(&self) -> &'static str {
+        match self {
+            BliveError::Http { .. } => ERROR_DOMAINS[0],
+            BliveError::Signature { .. } => ERROR_DOMAINS[1],
+            BliveError::WebSocket(_) => ERROR_DOMAINS[2],
+            BliveError::Protocol(_) => ERROR_DOMAINS[3],
+            BliveError::Api { .. } => ERROR_DOMAINS[4],
+            BliveError::Timeout(_) => ERROR_DOMAINS[5],
+        }
+    }
+
+    /// HTTP 状态码或平台错误码，其他类型为 0
+    pub fn code(&self) -> i64 {
+        match self {
+            BliveError::Http { status, .. } => *status as i64,
+            BliveError::Signature { code, .. } | BliveError::Api { code, .. } => *code,
+            BliveError::WebSocket(_) | BliveError::Protocol(_) | BliveError::Timeout(_) => 0,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            BliveError::Http { message, .. }
+            | BliveError::Signature { message, .. }
+            | BliveError::Api { message, .. } => message,
+            BliveError::WebSocket(message)
+            | BliveError::Protocol(message)
+            | BliveError::Timeout(message) => message,
+        }
+    }
+
+    /// start 因身份码错误或过期失败，需要主播重新输入身份码
+    pub fn is_invalid_code(&self) -> bool {
+        matches!(self, BliveError::Api { code, .. } if *code == INVALID_CODE)
+    }
+
+    /// 平台响应 code 不为 0 时返回对应错误
+    pub fn from_response(response: &Value) -> Option<Self> {
+        let code = response["code"].as_i64()?;
+        if code == 0 {
+            return None;
+        }
+        let message = response["message"].as_str().unwrap_or_default().to_string();
+        Some(if SIGNATURE_CODES.contains(&code) {
+            BliveError::Signature { code, message }
+        } else {
+            BliveError::Api { code, message }
+        })
+    }
+
+    /// 兼容旧信号的响应 JSON：`{"code":-1,"message":...}`，另带 domain
+    pub fn to_response(&self) -> String {
+        json!({ "code": -1, "message": self.to_string(), "domain": self.domain() }).to_string()
+    }
+}
+
+impl fmt::Display for BliveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BliveError::Http { status: 0, message } => write!(f, "请求失败: {}", message),
+            BliveError::Http { status, message } => write!(f, "HTTP {}: {}", status, message),
+            BliveError::Signature { code, message } => write!(f, "签名错误 {}: {}", code, message),
+            BliveError::WebSocket(message) | BliveError::Protocol(message) => f.write_str(message),
+            BliveError::Timeout(message) => write!(f, "请求超时: {}", message),
+            BliveError::Api { code, message } => write!(f, "接口错误 {}: {}", code, message),
+        }
+    }
+}
+
+/// 把请求结果转换为兼容旧信号的响应文本，并给出其中的错误（请求失败或 code 不为 0）
+pub fn check_response(result: Result<String, BliveError>) -> (String, Option<BliveError>) {
+    match result {
+        Ok(text) => {
+            let error = match serde_json::from_str::<Value>(&text) {
+                Ok(json) => BliveError::from_response(&json),
+                Err(e) => Some(BliveError::Api {
+                    code: -1,
+                    message: format!("响应不是 JSON: {}", e),
+                }),
+            };
+            (text, error)
+        }
+        Err(error) => (error.to_response(), Some(error)),
+    }
+}
+
+/// 在响应 JSON 对象的开头加入 `"request_id"`，其余内容保持原样；响应不是 JSON 对象时不变
+pub fn with_request_id(response: &str, request_id: i64) -> String {
+    if !matches!(
+        serde_json::from_str::<Value>(response),
+        Ok(Value::Object(_))
+    ) {
+        return response.to_string();
+    }
+    let rest = response.trim()[1..].trim_start();
+    let separator = if rest.starts_with('}') { "" } else { "," };
+    format!("{{\"request_id\":{}{}{}", request_id, separator, rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_responses() {
+        let (text, error) = check_response(Ok(r#"{"code":0,"data":{}}"#.to_string()));
+        assert_eq!(text, r#"{"code":0,"data":{}}"#);
+        assert_eq!(error, None);
+
+        let (_, error) = check_response(Ok(r#"{"code":4002,"message":"签名异常"}"#.to_string()));
+        let error = error.unwrap();
+        assert_eq!((error.domain(), error.code()), ("signature", 4002));
+
+        let (_, error) = check_response(Ok(r#"{"code":7002,"message":"重复游戏"}"#.to_string()));
+        assert_eq!(error.unwrap().domain(), "api");
+
+        let (_, error) = check_response(Ok(r#"{"code":7007,"message":"身份码错误"}"#.to_string()));
+        assert!(error.unwrap().is_invalid_code());
+
+        let (text, error) = check_response(Err(BliveError::Http {
+            status: 0,
+            message: "connection refused".into(),
+        }));
+        let legacy: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(legacy["code"], -1);
+        assert_eq!(legacy["domain"], "http");
+        assert_eq!(error.unwrap().code(), 0);
+
+        assert_eq!(
+            with_request_id(r#"{"code":0,"data":{}}"#, 7),
+            r#"{"request_id":7,"code":0,"data":{}}"#
+        );
+        assert_eq!(with_request_id(" { } ", 8), r#"{"request_id":8}"#);
+        assert_eq!(with_request_id("bad gateway", 9), "bad gateway");
+
+        let (text, _) = check_response(Err(BliveError::Timeout("operation timed out".into())));
+        let legacy: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(legacy["domain"], "timeout");
+    }
+}
@@ -0,0 +1,710 @@
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 统一事件类型，开放平台和直连模式的消息都会被转换成这几种
+pub const EVENT_DANMAKU: &str = "danmaku";
+pub const EVENT_GIFT: &str = "gift";
+pub const EVENT_SUPER_CHAT: &str = "super_chat";
+pub const EVENT_GUARD: &str = "guard";
+pub const EVENT_LIKE: &str = "like";
+/// `schedule_announcement` 发出的合成公告
+pub const EVENT_ANNOUNCEMENT: &str = "announcement";
+
+/// 把原始消息转换为 (事件类型, 统一字段)，不认识的 cmd 返回 None
+///
+/// 所有事件都包含 user_id、uname、avatar、medal_level、guard_level、timestamp（秒）
+/// 以及 `stamp` 补充的时间字段；金额字段 price 统一为总价，单位为千分之一元（金瓜子）。
+pub fn normalize(cmd: &str, message: &Value) -> Option<(&'static str, Value)> {
+    let (event_type, mut data) = parse(cmd, message)?;
+    stamp(&mut data, now_ms());
+    Some((event_type, data))
+}
+
+fn parse(cmd: &str, message: &Value) -> Option<(&'static str, Value)> {
+    let data = &message["data"];
+    let event = match cmd {
+        "LIVE_OPEN_PLATFORM_DM" => (
+            EVENT_DANMAKU,
+            with_user(
+                open_platform_user(data),
+                // 开放平台不下发颜色和位置，按默认的白色滚动弹幕处理
+                danmaku_fields(
+                    json!({ "message": str_of(&data["msg"]) }),
+                    DanmakuStyle {
+                        dm_type: int_of(&data["dm_type"]),
+                        emoji_url: str_of(&data["emoji_img_url"]),
+                        ..DanmakuStyle::default()
+                    },
+                ),
+            ),
+        ),
+        "LIVE_OPEN_PLATFORM_SEND_GIFT" => (
+            EVENT_GIFT,
+            with_user(
+                open_platform_user(data),
+                blind_box_fields(
+                    json!({
+                        "gift_id": int_of(&data["gift_id"]),
+                        "gift_name": str_of(&data["gift_name"]),
+                        "gift_num": int_of(&data["gift_num"]),
+                        "price": int_of(&data["price"]) * int_of(&data["gift_num"]),
+                        "paid": data["paid"].as_bool().unwrap_or(false),
+                    }),
+                    // 开放平台只给出盲盒 ID，盲盒名称和价格需要结合礼物目录查询
+                    (data["blind_gift"]["status"].as_bool() == Some(true)).then(|| BlindBox {
+                        gift_id: int_of(&data["blind_gift"]["blind_gift_id"]),
+                        gift_name: String::new(),
+                        unit_price: 0,
+                    }),
+                ),
+            ),
+        ),
+        "LIVE_OPEN_PLATFORM_SUPER_CHAT" => (
+            EVENT_SUPER_CHAT,
+            with_user(
+                open_platform_user(data),
+                json!({
+                    "message_id": int_of(&data["message_id"]),
+                    "message": str_of(&data["message"]),
+                    "price": int_of(&data["rmb"]) * 1000,
+                    "duration": int_of(&data["end_time"]) - int_of(&data["start_time"]),
+                }),
+            ),
+        ),
+        "LIVE_OPEN_PLATFORM_GUARD" => {
+            let user = &data["user_info"];
+            (
+                EVENT_GUARD,
+                with_user(
+                    user_fields(
+                        str_of(&user["open_id"]),
+                        str_of(&user["uname"]),
+                        str_of(&user["uface"]),
+                        int_of(&data["fans_medal_level"]),
+                        int_of(&data["guard_level"]),
+                        int_of(&data["timestamp"]),
+                    ),
+                    json!({
+                        "guard_num": int_of(&data["guard_num"]),
+                        "guard_unit": str_of(&data["guard_unit"]),
+                        "price": int_of(&data["price"]) * int_of(&data["guard_num"]).max(1),
+                    }),
+                ),
+            )
+        }
+        "LIVE_OPEN_PLATFORM_LIKE" => (
+            EVENT_LIKE,
+            with_user(
+                open_platform_user(data),
+                json!({ "like_count": int_of(&data["like_count"]).max(1) }),
+            ),
+        ),
+        "DANMU_MSG" => {
+            let info = &message["info"];
+            let user = &info[2];
+            (
+                EVENT_DANMAKU,
+                with_user(
+                    user_fields(
+                        uid_of(&user[0]),
+                        str_of(&user[1]),
+                        String::new(),
+                        int_of(&info[3][0]),
+                        int_of(&info[7]),
+                        int_of(&info[0][4]) / 1000,
+                    ),
+                    danmaku_fields(
+                        json!({
+                            "message": str_of(&info[1]),
+                            "timestamp_ms": int_of(&info[0][4]),
+                        }),
+                        DanmakuStyle {
+                            mode: int_of(&info[0][1]),
+                            font_size: int_of(&info[0][2]),
+                            color: int_of(&info[0][3]),
+                            dm_type: int_of(&info[0][12]),
+                            emoji_url: str_of(&info[0][13]["url"]),
+                        },
+                    ),
+                ),
+            )
+        }
+        "SEND_GIFT" => (
+            EVENT_GIFT,
+            with_user(
+                user_fields(
+                    uid_of(&data["uid"]),
+                    str_of(&data["uname"]),
+                    str_of(&data["face"]),
+                    int_of(&data["medal_info"]["medal_level"]),
+                    int_of(&data["guard_level"]),
+                    int_of(&data["timestamp"]),
+                ),
+                blind_box_fields(
+                    json!({
+                        "gift_id": int_of(&data["giftId"]),
+                        "gift_name": str_of(&data["giftName"]),
+                        "gift_num": int_of(&data["num"]),
+                        "price": int_of(&data["price"]) * int_of(&data["num"]),
+                        "paid": data["coin_type"].as_str() == Some("gold"),
+                    }),
+                    data["blind_gift"].as_object().map(|blind| BlindBox {
+                        gift_id: int_of(&blind["original_gift_id"]),
+                        gift_name: str_of(&blind["original_gift_name"]),
+                        unit_price: int_of(&blind["original_gift_price"]),
+                    }),
+                ),
+            ),
+        ),
+        "SUPER_CHAT_MESSAGE" => {
+            let user = &data["user_info"];
+            (
+                EVENT_SUPER_CHAT,
+                with_user(
+                    user_fields(
+                        uid_of(&data["uid"]),
+                        str_of(&user["uname"]),
+                        str_of(&user["face"]),
+                        int_of(&data["medal_info"]["medal_level"]),
+                        int_of(&user["guard_level"]),
+                        int_of(&data["start_time"]),
+                    ),
+                    json!({
+                        "message_id": int_of(&data["id"]),
+                        "message": str_of(&data["message"]),
+                        "price": int_of(&data["price"]) * 1000,
+                        "duration": int_of(&data["time"]),
+                    }),
+                ),
+            )
+        }
+        "GUARD_BUY" => (
+            EVENT_GUARD,
+            with_user(
+                user_fields(
+                    uid_of(&data["uid"]),
+                    str_of(&data["username"]),
+                    String::new(),
+                    0,
+                    int_of(&data["guard_level"]),
+                    int_of(&data["start_time"]),
+                ),
+                json!({
+                    "guard_num": int_of(&data["num"]),
+                    "guard_unit": "月",
+                    "price": int_of(&data["price"]) * int_of(&data["num"]).max(1),
+                }),
+            ),
+        ),
+        // 直连模式每次点赞单独推送一条
+        "LIKE_INFO_V3_CLICK" => (
+            EVENT_LIKE,
+            with_user(
+                user_fields(
+                    uid_of(&data["uid"]),
+                    str_of(&data["uname"]),
+                    String::new(),
+                    int_of(&data["fans_medal"]["medal_level"]),
+                    int_of(&data["fans_medal"]["guard_level"]),
+                    0,
+                ),
+                json!({ "like_count": 1 }),
+            ),
+        ),
+        _ => return None,
+    };
+    Some(event)
+}
+
+/// 观众发送弹幕时选择的样式，缺失的字段取平台默认值（白色、滚动、25 号字）
+struct DanmakuStyle {
+    /// 0xRRGGBB
+    color: i64,
+    /// 1 滚动、4 底部、5 顶部
+    mode: i64,
+    font_size: i64,
+    /// 0 文字、1 表情
+    dm_type: i64,
+    emoji_url: String,
+}
+
+impl Default for DanmakuStyle {
+    fn default() -> Self {
+        Self {
+            color: 0xFFFFFF,
+            mode: 1,
+            font_size: 25,
+            dm_type: 0,
+            emoji_url: String::new(),
+        }
+    }
+}
+
+/// 为 danmaku 事件补充 color、mode、font_size、dm_type 和 emoji_url
+fn danmaku_fields(mut danmaku: Value, style: DanmakuStyle) -> Value {
+    let defaults = DanmakuStyle::default();
+    let or_default = |value: i64, default: i64| if value > 0 { value } else { default };
+    danmaku["color"] = style.color.clamp(0, 0xFFFFFF).into();
+    danmaku["mode"] = or_default(style.mode, defaults.mode).into();
+    danmaku["font_size"] = or_default(style.font_size, defaults.font_size).into();
+    danmaku["dm_type"] = style.dm_type.into();
+    danmaku["emoji_url"] = style.emoji_url.into();
+    danmaku
+}
+
+/// 盲盒礼物中观众实际购买的盲盒
+struct BlindBox {
+    gift_id: i64,
+    gift_name: String,
+    /// 盲盒单价（千分之一元），未知时为 0
+    unit_price: i64,
+}
+
+/// 为 gift 事件补充盲盒字段：blind_box 标记是否为盲盒；盲盒礼物另有 original_gift_id、
+/// original_gift_name、original_price（盲盒总价）和 revealed_price（爆出礼物总价值），
+/// 盲盒价格已知时 price 为实际支付的盲盒总价，否则为爆出礼物的价值
+fn blind_box_fields(mut gift: Value, blind_box: Option<BlindBox>) -> Value {
+    let Some(blind_box) = blind_box else {
+        gift["blind_box"] = false.into();
+        return gift;
+    };
+    let revealed_price = int_of(&gift["price"]);
+    let original_price = blind_box.unit_price * int_of(&gift["gift_num"]);
+    gift["blind_box"] = true.into();
+    gift["original_gift_id"] = blind_box.gift_id.into();
+    gift["original_gift_name"] = blind_box.gift_name.into();
+    gift["original_price"] = original_price.into();
+    gift["revealed_price"] = revealed_price.into();
+    if original_price > 0 {
+        gift["price"] = original_price.into();
+    }
+    gift
+}
+
+/// 观众人数类消息（仅直连模式提供）
+#[derive(Debug, Clone, PartialEq)]
+pub enum Audience {
+    /// WATCHED_CHANGE：累计看过人数
+    Watched(i64),
+    /// ONLINE_RANK_COUNT：高能用户（在线）人数
+    OnlineCount(i64),
+    /// ONLINE_RANK_V2：高能榜，每项含 user_id、uname、avatar、score、rank、guard_level
+    OnlineRank(Vec<Value>),
+}
+
+pub fn audience(cmd: &str, message: &Value) -> Option<Audience> {
+    let data = &message["data"];
+    match cmd {
+        "WATCHED_CHANGE" => Some(Audience::Watched(int_of(&data["num"]))),
+        "ONLINE_RANK_COUNT" => Some(Audience::OnlineCount(
+            data["online_count"]
+                .as_i64()
+                .unwrap_or_else(|| int_of(&data["count"])),
+        )),
+        "ONLINE_RANK_V2" => {
+            let list = data["online_list"]
+                .as_array()
+                .or(data["list"].as_array())?
+                .iter()
+                .map(|item| {
+                    json!({
+                        "user_id": uid_of(&item["uid"]),
+                        "uname": str_of(&item["uname"]),
+                        "avatar": str_of(&item["face"]),
+                        "score": item["score"]
+                            .as_str()
+                            .and_then(|s| s.parse().ok())
+                            .unwrap_or_else(|| int_of(&item["score"])),
+                        "rank": int_of(&item["rank"]),
+                        "guard_level": int_of(&item["guard_level"]),
+                    })
+                })
+                .collect();
+            Some(Audience::OnlineRank(list))
+        }
+        _ => None,
+    }
+}
+
+/// 观众互动消息（仅直连模式提供，开放平台不推送关注和分享）
+#[derive(Debug, Clone, PartialEq)]
+pub enum Interaction {
+    /// 关注，包括特别关注和互相关注；(user_id, uname)
+    Followed(String, String),
+    /// 分享直播间；(user_id, uname)
+    Shared(String, String),
+}
+
+/// INTERACT_WORD 的 msg_type：1 进入直播间、2 关注、3 分享、4 特别关注、5 互相关注
+pub fn interaction(cmd: &str, message: &Value) -> Option<Interaction> {
+    if cmd != "INTERACT_WORD" {
+        return None;
+    }
+    let data = &message["data"];
+    let user_id = uid_of(&data["uid"]);
+    let uname = str_of(&data["uname"]);
+    match int_of(&data["msg_type"]) {
+        2 | 4 | 5 => Some(Interaction::Followed(user_id, uname)),
+        3 => Some(Interaction::Shared(user_id, uname)),
+        _ => None,
+    }
+}
+
+/// 醒目留言删除消息，返回被删除的 message_id
+pub fn super_chat_deleted(cmd: &str, message: &Value) -> Option<Vec<i64>> {
+    let data = &message["data"];
+    let ids = match cmd {
+        "LIVE_OPEN_PLATFORM_SUPER_CHAT_DEL" => &data["message_ids"],
+        "SUPER_CHAT_MESSAGE_DELETE" => &data["ids"],
+        _ => return None,
+    };
+    Some(ids.as_array()?.iter().map(int_of).collect())
+}
+
+/// 开播 / 下播消息，返回新的直播状态
+pub fn live_status(cmd: &str) -> Option<bool> {
+    match cmd {
+        "LIVE" | "LIVE_OPEN_PLATFORM_LIVE_START" => Some(true),
+        "PREPARING" | "LIVE_OPEN_PLATFORM_LIVE_END" => Some(false),
+        _ => None,
+    }
+}
+
+fn open_platform_user(data: &Value) -> Value {
+    user_fields(
+        str_of(&data["open_id"]),
+        str_of(&data["uname"]),
+        str_of(&data["uface"]),
+        int_of(&data["fans_medal_level"]),
+        int_of(&data["guard_level"]),
+        int_of(&data["timestamp"]),
+    )
+}
+
+pub fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// 为统一事件补充时间字段，received_ms 为本地收到消息的 Unix 毫秒：
+///
+/// - timestamp_ms：平台时间的 Unix 毫秒，消息只精确到秒时按秒换算，没有平台时间时取 received_ms
+/// - time：timestamp_ms 对应的 UTC 时间，键与 Godot `Time.get_datetime_dict_from_unix_time` 相同
+/// - latency_ms：received_ms 与平台时间之差，没有平台时间时为 0
+pub fn stamp(data: &mut Value, received_ms: i64) {
+    let server_ms = match int_of(&data["timestamp_ms"]) {
+        0 => int_of(&data["timestamp"]) * 1000,
+        ms => ms,
+    };
+    let (timestamp_ms, latency_ms) = if server_ms > 0 {
+        (server_ms, received_ms - server_ms)
+    } else {
+        (received_ms, 0)
+    };
+    data["timestamp_ms"] = timestamp_ms.into();
+    data["time"] = datetime(timestamp_ms.div_euclid(1000));
+    data["latency_ms"] = latency_ms.into();
+}
+
+/// Unix 秒转换为 UTC 日期时间，weekday 0 为星期日
+fn datetime(unix_secs: i64) -> Value {
+    let days = unix_secs.div_euclid(86400);
+    let secs = unix_secs.rem_euclid(86400);
+    // 公历日期换算（H. Hinnant, civil_from_days）
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    json!({
+        "year": year,
+        "month": month,
+        "day": day,
+        "weekday": (days + 4).rem_euclid(7),
+        "hour": secs / 3600,
+        "minute": secs % 3600 / 60,
+        "second": secs % 60,
+    })
+}
+
+/// 所有统一事件共有的用户字段，第三方数据源可用它和 `with_user` 构造事件
+pub fn user_fields(
+    user_id: String,
+    uname: String,
+    avatar: String,
+    medal_level: i64,
+    guard_level: i64,
+    timestamp: i64,
+) -> Value {
+    json!({
+        "user_id": user_id,
+        "uname": uname,
+        "avatar": avatar,
+        "medal_level": medal_level,
+        "guard_level": guard_level,
+        "timestamp": timestamp,
+    })
+}
+
+pub fn with_user(mut user: Value, fields: Value) -> Value {
+    if let (Some(user), Value::Object(fields)) = (user.as_object_mut(), fields) {
+        user.extend(fields);
+    }
+    user
+}
+
+pub(crate) fn str_of(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+pub(crate) fn int_of(value: &Value) -> i64 {
+    value.as_i64().unwrap_or(0)
+}
+
+/// 直连模式的 uid 是数字，统一成字符串以便和 open_id 共用字段
+fn uid_of(value: &Value) -> String {
+    match value {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_platform_danmaku_and_direct_danmaku_match() {
+        let open = json!({
+            "cmd": "LIVE_OPEN_PLATFORM_DM",
+            "data": {
+                "open_id": "o-1", "uname": "观众", "uface": "http://face",
+                "msg": "你好", "fans_medal_level": 5, "guard_level": 3, "timestamp": 1700000000
+            }
+        });
+        let direct = json!({
+            "cmd": "DANMU_MSG",
+            "info": [
+                [0, 1, 25, 16777215, 1700000000123i64],
+                "你好",
+                [42, "观众", 0],
+                [5, "勋章", "主播", 1],
+                [], "", 0, 3
+            ]
+        });
+
+        let (kind, open) = normalize("LIVE_OPEN_PLATFORM_DM", &open).unwrap();
+        assert_eq!(kind, EVENT_DANMAKU);
+        assert_eq!(open["user_id"], "o-1");
+        assert_eq!(open["message"], "你好");
+
+        let (kind, direct) = normalize("DANMU_MSG", &direct).unwrap();
+        assert_eq!(kind, EVENT_DANMAKU);
+        assert_eq!(direct["user_id"], "42");
+        assert_eq!(direct["timestamp"], 1700000000);
+        for key in ["uname", "message", "medal_level", "guard_level"] {
+            assert_eq!(open[key], direct[key], "{}", key);
+        }
+        let mut open_keys: Vec<_> = open.as_object().unwrap().keys().collect();
+        let mut direct_keys: Vec<_> = direct.as_object().unwrap().keys().collect();
+        open_keys.sort();
+        direct_keys.sort();
+        assert_eq!(open_keys, direct_keys);
+    }
+
+    #[test]
+    fn danmaku_style() {
+        let mut info = vec![json!(0); 14];
+        info[1] = json!(5);
+        info[2] = json!(36);
+        info[3] = json!(0xFE0302);
+        info[12] = json!(1);
+        info[13] = json!({"url": "http://emoji"});
+        let direct = json!({"info": [info, "赞", [42, "观众", 0], [], [], "", 0, 0]});
+        let (_, direct) = normalize("DANMU_MSG", &direct).unwrap();
+        assert_eq!(direct["color"], 0xFE0302);
+        assert_eq!(direct["mode"], 5);
+        assert_eq!(direct["font_size"], 36);
+        assert_eq!(direct["dm_type"], 1);
+        assert_eq!(direct["emoji_url"], "http://emoji");
+
+        let open = json!({"data": {"msg": "赞", "dm_type": 1, "emoji_img_url": "http://emoji"}});
+        let (_, open) = normalize("LIVE_OPEN_PLATFORM_DM", &open).unwrap();
+        assert_eq!(open["color"], 0xFFFFFF);
+        assert_eq!(open["mode"], 1);
+        assert_eq!(open["emoji_url"], "http://emoji");
+    }
+
+    #[test]
+    fn gift_price_is_total() {
+        let open =
+            json!({"data": {"gift_name": "小花花", "gift_num": 3, "price": 100, "paid": true}});
+        let (_, open) = normalize("LIVE_OPEN_PLATFORM_SEND_GIFT", &open).unwrap();
+        assert_eq!(open["price"], 300);
+        assert_eq!(open["paid"], true);
+
+        let direct =
+            json!({"data": {"giftName": "辣条", "num": 2, "price": 0, "coin_type": "silver"}});
+        let (kind, direct) = normalize("SEND_GIFT", &direct).unwrap();
+        assert_eq!(kind, EVENT_GIFT);
+        assert_eq!(direct["gift_name"], "辣条");
+        assert_eq!(direct["paid"], false);
+        assert_eq!(direct["blind_box"], false);
+    }
+
+    #[test]
+    fn blind_box_gifts() {
+        let direct = json!({"data": {
+            "giftId": 32125, "giftName": "少女祈愿", "num": 2, "price": 1000, "coin_type": "gold",
+            "blind_gift": {
+                "original_gift_id": 32124, "original_gift_name": "心动盲盒",
+                "original_gift_price": 15000, "gift_action": "爆出"
+            }
+        }});
+        let (_, direct) = normalize("SEND_GIFT", &direct).unwrap();
+        assert_eq!(direct["blind_box"], true);
+        assert_eq!(direct["original_gift_name"], "心动盲盒");
+        assert_eq!(direct["original_price"], 30000);
+        assert_eq!(direct["revealed_price"], 2000);
+        assert_eq!(direct["price"], 30000);
+
+        let open = json!({"data": {
+            "gift_id": 32125, "gift_name": "少女祈愿", "gift_num": 1, "price": 1000, "paid": true,
+            "blind_gift": {"blind_gift_id": 32124, "status": true}
+        }});
+        let (_, open) = normalize("LIVE_OPEN_PLATFORM_SEND_GIFT", &open).unwrap();
+        assert_eq!(open["blind_box"], true);
+        assert_eq!(open["original_gift_id"], 32124);
+        assert_eq!(open["price"], 1000);
+    }
+
+    #[test]
+    fn super_chat_and_guard() {
+        let open =
+            json!({"data": {"message": "加油", "rmb": 30, "start_time": 100, "end_time": 160}});
+        let (kind, open) = normalize("LIVE_OPEN_PLATFORM_SUPER_CHAT", &open).unwrap();
+        assert_eq!(kind, EVENT_SUPER_CHAT);
+        assert_eq!(open["price"], 30000);
+        assert_eq!(open["duration"], 60);
+
+        let direct = json!({"data": {"uid": 9, "username": "舰长", "guard_level": 3, "num": 1, "price": 198000}});
+        let (kind, direct) = normalize("GUARD_BUY", &direct).unwrap();
+        assert_eq!(kind, EVENT_GUARD);
+        assert_eq!(direct["user_id"], "9");
+        assert_eq!(direct["price"], 198000);
+
+        assert!(normalize("INTERACT_WORD", &json!({})).is_none());
+    }
+
+    #[test]
+    fn likes() {
+        let open = json!({"data": {"open_id": "o-2", "uname": "a", "like_count": 5}});
+        let (kind, open) = normalize("LIVE_OPEN_PLATFORM_LIKE", &open).unwrap();
+        assert_eq!(kind, EVENT_LIKE);
+        assert_eq!(open["like_count"], 5);
+
+        let direct = json!({"data": {"uid": 7, "uname": "b", "like_text": "为主播点赞了", "fans_medal": {"medal_level": 2}}});
+        let (kind, direct) = normalize("LIKE_INFO_V3_CLICK", &direct).unwrap();
+        assert_eq!(kind, EVENT_LIKE);
+        assert_eq!(direct["user_id"], "7");
+        assert_eq!(direct["medal_level"], 2);
+    }
+
+    #[test]
+    fn audience_messages() {
+        let watched = json!({"cmd": "WATCHED_CHANGE", "data": {"num": 5321, "text_small": "5321"}});
+        assert_eq!(
+            audience("WATCHED_CHANGE", &watched),
+            Some(Audience::Watched(5321))
+        );
+
+        let count = json!({"data": {"count": 12, "online_count": 30}});
+        assert_eq!(
+            audience("ONLINE_RANK_COUNT", &count),
+            Some(Audience::OnlineCount(30))
+        );
+
+        let rank = json!({"data": {"rank_type": "gold-rank", "online_list": [
+            {"uid": 1, "uname": "a", "face": "f", "score": "100", "rank": 1, "guard_level": 3}
+        ]}});
+        let Some(Audience::OnlineRank(list)) = audience("ONLINE_RANK_V2", &rank) else {
+            panic!("应解析为高能榜");
+        };
+        assert_eq!(list[0]["user_id"], "1");
+        assert_eq!(list[0]["score"], 100);
+
+        assert!(audience("DANMU_MSG", &json!({})).is_none());
+    }
+
+    #[test]
+    fn live_status_messages() {
+        assert_eq!(live_status("LIVE"), Some(true));
+        assert_eq!(live_status("LIVE_OPEN_PLATFORM_LIVE_START"), Some(true));
+        assert_eq!(live_status("PREPARING"), Some(false));
+        assert_eq!(live_status("LIVE_OPEN_PLATFORM_LIVE_END"), Some(false));
+        assert_eq!(live_status("DANMU_MSG"), None);
+    }
+
+    #[test]
+    fn super_chat_deletes() {
+        let open = json!({"data": {"room_id": 1, "message_ids": [11, 12]}});
+        assert_eq!(
+            super_chat_deleted("LIVE_OPEN_PLATFORM_SUPER_CHAT_DEL", &open),
+            Some(vec![11, 12])
+        );
+        let direct = json!({"data": {"ids": [7]}});
+        assert_eq!(
+            super_chat_deleted("SUPER_CHAT_MESSAGE_DELETE", &direct),
+            Some(vec![7])
+        );
+        assert_eq!(super_chat_deleted("DANMU_MSG", &direct), None);
+    }
+
+    #[test]
+    fn follows_and_shares() {
+        let message = |msg_type| json!({"data": {"uid": 42, "uname": "a", "msg_type": msg_type}});
+        assert_eq!(interaction("INTERACT_WORD", &message(1)), None);
+        for msg_type in [2, 4, 5] {
+            assert_eq!(
+                interaction("INTERACT_WORD", &message(msg_type)),
+                Some(Interaction::Followed("42".into(), "a".into()))
+            );
+        }
+        assert_eq!(
+            interaction("INTERACT_WORD", &message(3)),
+            Some(Interaction::Shared("42".into(), "a".into()))
+        );
+        assert_eq!(interaction("DANMU_MSG", &message(2)), None);
+    }
+
+    #[test]
+    fn stamps_times_and_latency() {
+        let mut seconds = json!({"timestamp": 1700000000});
+        stamp(&mut seconds, 1700000000250);
+        assert_eq!(seconds["timestamp_ms"], 1700000000000i64);
+        assert_eq!(seconds["latency_ms"], 250);
+        assert_eq!(
+            seconds["time"],
+            json!({"year": 2023, "month": 11, "day": 14, "weekday": 2,
+                   "hour": 22, "minute": 13, "second": 20})
+        );
+
+        let mut millis = json!({"timestamp": 1700000000, "timestamp_ms": 1700000000123i64});
+        stamp(&mut millis, 1700000000200);
+        assert_eq!(millis["latency_ms"], 77);
+
+        let mut unknown = json!({"timestamp": 0});
+        stamp(&mut unknown, 951782400000);
+        assert_eq!(unknown["timestamp_ms"], 951782400000i64);
+        assert_eq!(unknown["latency_ms"], 0);
+        assert_eq!(unknown["time"]["month"], 2);
+        assert_eq!(unknown["time"]["day"], 29);
+    }
+}
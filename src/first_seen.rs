@@ -0,0 +1,140 @@
+use crate::events;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// 参与首次互动判断的事件类型，对应 `first_interaction` 的 kind
+pub fn kind_of(event_type: &str) -> Option<&'static str> {
+    match event_type {
+        events::EVENT_DANMAKU => Some("danmaku"),
+        events::EVENT_GIFT => Some("gift"),
+        _ => None,
+    }
+}
+
+/// 一次互动是否为该观众在本场次 / 历史上的第一次；未配置持久化时 ever 为 None
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Firsts {
+    pub in_session: bool,
+    pub ever: Option<bool>,
+}
+
+/// 已互动过的观众：本场次的记录在场次变化时清空，持久化记录逐行追加到
+/// `{"kind": ..., "open_id": ...}` 格式的 JSONL 文件
+#[derive(Debug, Default)]
+pub struct FirstInteractions {
+    session_id: String,
+    session: HashSet<(&'static str, String)>,
+    store: Option<Store>,
+}
+
+#[derive(Debug)]
+struct Store {
+    seen: HashSet<(&'static str, String)>,
+    file: File,
+}
+
+impl FirstInteractions {
+    /// session_id 与上一次不同时先清空本场次的记录
+    pub fn observe(&mut self, session_id: &str, kind: &'static str, open_id: &str) -> Firsts {
+        if session_id != self.session_id {
+            self.reset_session();
+            self.session_id = session_id.to_string();
+        }
+        let key = (kind, open_id.to_string());
+        let ever = self.store.as_mut().map(|store| {
+            if store.seen.contains(&key) {
+                return false;
+            }
+            let line = json!({ "kind": kind, "open_id": open_id });
+            // 写入失败只影响下次启动后的判断，本次仍按首次处理
+            let _ = writeln!(store.file, "{}", line);
+            store.seen.insert(key.clone());
+            true
+        });
+        Firsts {
+            in_session: self.session.insert(key),
+            ever,
+        }
+    }
+
+    pub fn reset_session(&mut self) {
+        self.session.clear();
+    }
+
+    /// 打开（不存在时创建）持久化文件并读入已有记录；path 为 None 时只按场次判断
+    pub fn open_store(&mut self, path: Option<&Path>) -> Result<(), String> {
+        let Some(path) = path else {
+            self.store = None;
+            return Ok(());
+        };
+        let mut seen = HashSet::new();
+        if path.exists() {
+            let text = fs::read_to_string(path).map_err(|e| format!("读取互动记录失败: {}", e))?;
+            for line in text.lines() {
+                let Ok(entry) = serde_json::from_str::<Value>(line) else {
+                    continue;
+                };
+                let kind = ["danmaku", "gift"]
+                    .into_iter()
+                    .find(|kind| entry["kind"] == *kind);
+                if let (Some(kind), Some(open_id)) = (kind, entry["open_id"].as_str()) {
+                    seen.insert((kind, open_id.to_string()));
+                }
+            }
+        } else if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("创建互动记录目录失败: {}", e))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("打开互动记录失败: {}", e))?;
+        self.store = Some(Store { seen, file });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_session_and_persistent_firsts() {
+        let path = std::env::temp_dir().join(format!("gdblive_first_{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut firsts = FirstInteractions::default();
+        let first = firsts.observe("g1", "danmaku", "o-1");
+        assert_eq!(
+            first,
+            Firsts {
+                in_session: true,
+                ever: None
+            }
+        );
+        assert!(!firsts.observe("g1", "danmaku", "o-1").in_session);
+        assert!(firsts.observe("g1", "gift", "o-1").in_session);
+        // 新场次重新计算
+        assert!(firsts.observe("g2", "danmaku", "o-1").in_session);
+
+        firsts.open_store(Some(&path)).unwrap();
+        assert_eq!(firsts.observe("g2", "gift", "o-2").ever, Some(true));
+        assert_eq!(firsts.observe("g2", "gift", "o-2").ever, Some(false));
+
+        let mut reopened = FirstInteractions::default();
+        reopened.open_store(Some(&path)).unwrap();
+        let again = reopened.observe("g3", "gift", "o-2");
+        assert_eq!(
+            again,
+            Firsts {
+                in_session: true,
+                ever: Some(false)
+            }
+        );
+        assert_eq!(reopened.observe("g3", "danmaku", "o-2").ever, Some(true));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,212 @@
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// 互动门槛：粉丝勋章等级、是否需要佩戴本房间勋章、大航海等级
+///
+/// `guard_level` 沿用平台编号（1 总督、2 提督、3 舰长），0 表示不要求上舰；
+/// 非 0 时观众的大航海等级须不低于该档位。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Requirement {
+    pub min_medal_level: i64,
+    pub require_room_medal: bool,
+    pub guard_level: i64,
+}
+
+/// 从消息 data 中提取的观众身份信息
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Viewer {
+    pub open_id: String,
+    pub medal_level: i64,
+    pub wearing_room_medal: bool,
+    pub guard_level: i64,
+}
+
+impl Viewer {
+    pub fn from_data(data: &Value) -> Self {
+        Self {
+            open_id: data["open_id"].as_str().unwrap_or_default().to_string(),
+            medal_level: data["fans_medal_level"].as_i64().unwrap_or(0),
+            wearing_room_medal: data["fans_medal_wearing_status"].as_bool().unwrap_or(false),
+            guard_level: data["guard_level"].as_i64().unwrap_or(0),
+        }
+    }
+}
+
+/// 按弹幕指令和子系统名配置的互动门槛
+#[derive(Debug, Clone, Default)]
+pub struct InteractionGate {
+    commands: HashMap<String, Requirement>,
+    subsystems: HashMap<String, Requirement>,
+}
+
+impl InteractionGate {
+    pub fn command_mut(&mut self, command: &str) -> &mut Requirement {
+        self.commands.entry(command.to_string()).or_default()
+    }
+
+    pub fn subsystem_mut(&mut self, subsystem: &str) -> &mut Requirement {
+        self.subsystems.entry(subsystem.to_string()).or_default()
+    }
+
+    pub fn clear(&mut self) {
+        self.commands.clear();
+        self.subsystems.clear();
+    }
+
+    /// 弹幕第一个词命中已配置指令时检查门槛，未配置的弹幕一律放行
+    pub fn check_danmaku(&self, msg: &str, viewer: &Viewer) -> Result<(), String> {
+        let Some(command) = msg.split_whitespace().next() else {
+            return Ok(());
+        };
+        match self.commands.get(command) {
+            Some(requirement) => Self::check(requirement, viewer),
+            None => Ok(()),
+        }
+    }
+
+    pub fn check_subsystem(&self, subsystem: &str, viewer: &Viewer) -> Result<(), String> {
+        match self.subsystems.get(subsystem) {
+            Some(requirement) => Self::check(requirement, viewer),
+            None => Ok(()),
+        }
+    }
+
+    fn check(requirement: &Requirement, viewer: &Viewer) -> Result<(), String> {
+        if requirement.require_room_medal && !viewer.wearing_room_medal {
+            return Err("medal_not_worn".to_string());
+        }
+        if viewer.medal_level < requirement.min_medal_level {
+            return Err(format!(
+                "medal_level_too_low:{}<{}",
+                viewer.medal_level, requirement.min_medal_level
+            ));
+        }
+        if requirement.guard_level > 0 {
+            if viewer.guard_level <= 0 {
+                return Err("guard_required".to_string());
+            }
+            // 编号越小等级越高
+            if viewer.guard_level > requirement.guard_level {
+                return Err(format!(
+                    "guard_level_too_low:{}>{}",
+                    viewer.guard_level, requirement.guard_level
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn viewer(level: i64, wearing: bool) -> Viewer {
+        Viewer {
+            open_id: "abc".to_string(),
+            medal_level: level,
+            wearing_room_medal: wearing,
+            guard_level: 0,
+        }
+    }
+
+    fn guard(guard_level: i64) -> Viewer {
+        Viewer {
+            guard_level,
+            ..viewer(0, false)
+        }
+    }
+
+    #[test]
+    fn viewer_from_danmaku_data() {
+        let data = json!({
+            "open_id": "o-1",
+            "fans_medal_level": 12,
+            "fans_medal_wearing_status": true,
+            "guard_level": 3,
+        });
+        assert_eq!(
+            Viewer::from_data(&data),
+            Viewer {
+                open_id: "o-1".to_string(),
+                medal_level: 12,
+                wearing_room_medal: true,
+                guard_level: 3,
+            }
+        );
+        assert_eq!(Viewer::from_data(&json!({})), Viewer::default());
+    }
+
+    #[test]
+    fn unconfigured_commands_pass() {
+        let gate = InteractionGate::default();
+        assert!(gate.check_danmaku("!join", &viewer(0, false)).is_ok());
+        assert!(gate.check_subsystem("queue", &viewer(0, false)).is_ok());
+    }
+
+    #[test]
+    fn command_requires_medal_level() {
+        let mut gate = InteractionGate::default();
+        gate.command_mut("!join").min_medal_level = 5;
+
+        assert_eq!(
+            gate.check_danmaku("!join red", &viewer(3, true)),
+            Err("medal_level_too_low:3<5".to_string())
+        );
+        assert!(gate.check_danmaku("!join red", &viewer(5, false)).is_ok());
+        assert!(gate.check_danmaku("hello !join", &viewer(0, false)).is_ok());
+    }
+
+    #[test]
+    fn subsystem_requires_room_medal() {
+        let mut gate = InteractionGate::default();
+        gate.subsystem_mut("vote").require_room_medal = true;
+
+        assert_eq!(
+            gate.check_subsystem("vote", &viewer(20, false)),
+            Err("medal_not_worn".to_string())
+        );
+        assert!(gate.check_subsystem("vote", &viewer(1, true)).is_ok());
+    }
+
+    #[test]
+    fn any_guard_level() {
+        let mut gate = InteractionGate::default();
+        gate.subsystem_mut("queue").guard_level = 3;
+
+        assert_eq!(
+            gate.check_subsystem("queue", &guard(0)),
+            Err("guard_required".to_string())
+        );
+        assert!(gate.check_subsystem("queue", &guard(3)).is_ok());
+        assert!(gate.check_subsystem("queue", &guard(1)).is_ok());
+    }
+
+    #[test]
+    fn minimum_guard_level() {
+        let mut gate = InteractionGate::default();
+        gate.command_mut("!boss").guard_level = 2;
+        gate.command_mut("!boss").min_medal_level = 1;
+
+        assert_eq!(
+            gate.check_danmaku(
+                "!boss",
+                &Viewer {
+                    medal_level: 1,
+                    ..guard(3)
+                }
+            ),
+            Err("guard_level_too_low:3>2".to_string())
+        );
+        assert!(gate
+            .check_danmaku(
+                "!boss",
+                &Viewer {
+                    medal_level: 1,
+                    ..guard(2)
+                }
+            )
+            .is_ok());
+    }
+}
@@ -0,0 +1,220 @@
+use crate::direct;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// 平台礼物配置中的一项，price 为单价（千分之一元，与 gift 事件一致）
+#[derive(Debug, Clone, PartialEq)]
+pub struct GiftInfo {
+    pub id: i64,
+    pub name: String,
+    pub price: i64,
+    pub icon: String,
+    pub coin_type: String,
+}
+
+impl GiftInfo {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "name": self.name,
+            "price": self.price,
+            "icon": self.icon,
+            "coin_type": self.coin_type,
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        Some(Self {
+            id: value["id"].as_i64()?,
+            name: value["name"].as_str().unwrap_or_default().to_string(),
+            price: value["price"].as_i64().unwrap_or(0),
+            icon: value["icon"].as_str().unwrap_or_default().to_string(),
+            coin_type: value["coin_type"].as_str().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+/// 礼物 ID → 名称、单价、图标，供只带 ID 的事件补全字段
+#[derive(Debug, Clone, Default)]
+pub struct GiftCatalog {
+    gifts: HashMap<i64, GiftInfo>,
+    /// 从平台获取的时间，Unix 毫秒
+    fetched_at_ms: i64,
+}
+
+impl GiftCatalog {
+    /// 解析 giftConfig 接口的响应（data.list）
+    pub fn parse(response: &Value, now_ms: i64) -> Result<Self, String> {
+        let list = response["data"]["list"]
+            .as_array()
+            .ok_or("响应中缺少礼物列表")?;
+        let gifts = list
+            .iter()
+            .filter_map(|gift| {
+                Some(GiftInfo {
+                    id: gift["id"].as_i64()?,
+                    name: gift["name"].as_str().unwrap_or_default().to_string(),
+                    price: gift["price"].as_i64().unwrap_or(0),
+                    icon: gift["img_basic"].as_str().unwrap_or_default().to_string(),
+                    coin_type: gift["coin_type"].as_str().unwrap_or_default().to_string(),
+                })
+            })
+            .map(|gift| (gift.id, gift))
+            .collect();
+        Ok(Self {
+            gifts,
+            fetched_at_ms: now_ms,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.gifts.len()
+    }
+
+    pub fn get(&self, gift_id: i64) -> Option<&GiftInfo> {
+        self.gifts.get(&gift_id)
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut gifts: Vec<&GiftInfo> = self.gifts.values().collect();
+        gifts.sort_by_key(|gift| gift.id);
+        json!({
+            "fetched_at_ms": self.fetched_at_ms,
+            "gifts": gifts.into_iter().map(GiftInfo::to_json).collect::<Vec<_>>(),
+        })
+    }
+
+    pub fn from_json(value: &Value) -> Self {
+        Self {
+            gifts: value["gifts"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(GiftInfo::from_json)
+                .map(|gift| (gift.id, gift))
+                .collect(),
+            fetched_at_ms: value["fetched_at_ms"].as_i64().unwrap_or(0),
+        }
+    }
+
+    /// 读取磁盘缓存，文件不存在、无法解析或超过 ttl_secs 时返回 None
+    pub fn load(path: &Path, ttl_secs: f64, now_ms: i64) -> Option<Self> {
+        let text = std::fs::read_to_string(path).ok()?;
+        let catalog = Self::from_json(&serde_json::from_str(&text).ok()?);
+        let age_secs = (now_ms - catalog.fetched_at_ms) as f64 / 1000.0;
+        (age_secs <= ttl_secs && catalog.len() > 0).then_some(catalog)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, self.to_json().to_string())
+            .map_err(|e| format!("写入礼物缓存失败: {}", e))
+    }
+
+    /// 优先使用未过期的磁盘缓存，否则从平台获取并写回缓存；获取失败时退回过期的缓存
+    pub fn load_or_fetch(
+        client: &reqwest::blocking::Client,
+        path: &Path,
+        ttl_secs: f64,
+        now_ms: i64,
+    ) -> Result<Self, String> {
+        if let Some(catalog) = Self::load(path, ttl_secs, now_ms) {
+            return Ok(catalog);
+        }
+        match direct::fetch_gift_config(client).and_then(|response| Self::parse(&response, now_ms))
+        {
+            Ok(catalog) => {
+                // 缓存写入失败只影响下次启动，不影响本次使用
+                let _ = catalog.save(path);
+                Ok(catalog)
+            }
+            Err(e) => Self::load(path, f64::INFINITY, now_ms).ok_or(e),
+        }
+    }
+
+    /// 为 gift 事件补全缺失的礼物名称和图标；盲盒礼物补全盲盒名称和价格，
+    /// 盲盒价格由此得知时 price 改为实际支付的盲盒总价
+    pub fn enrich(&self, data: &mut Value) {
+        if let Some(gift) = self.get(data["gift_id"].as_i64().unwrap_or(0)) {
+            if data["gift_name"].as_str().unwrap_or_default().is_empty() {
+                data["gift_name"] = gift.name.clone().into();
+            }
+            data["gift_icon"] = gift.icon.clone().into();
+        }
+        if data["blind_box"].as_bool() != Some(true) {
+            return;
+        }
+        let Some(blind_box) = self.get(data["original_gift_id"].as_i64().unwrap_or(0)) else {
+            return;
+        };
+        if data["original_gift_name"]
+            .as_str()
+            .unwrap_or_default()
+            .is_empty()
+        {
+            data["original_gift_name"] = blind_box.name.clone().into();
+        }
+        if data["original_price"].as_i64().unwrap_or(0) == 0 {
+            let original_price = blind_box.price * data["gift_num"].as_i64().unwrap_or(1);
+            data["original_price"] = original_price.into();
+            if original_price > 0 {
+                data["price"] = original_price.into();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> GiftCatalog {
+        let response = json!({
+            "code": 0,
+            "data": { "list": [
+                { "id": 31036, "name": "小花花", "price": 100, "coin_type": "gold",
+                  "img_basic": "https://i0.hdslb.com/flower.png" },
+                { "id": 32124, "name": "心动盲盒", "price": 15000, "coin_type": "gold",
+                  "img_basic": "https://i0.hdslb.com/box.png" },
+            ] }
+        });
+        GiftCatalog::parse(&response, 1_000).unwrap()
+    }
+
+    #[test]
+    fn parses_and_round_trips() {
+        let catalog = catalog();
+        assert_eq!(catalog.len(), 2);
+        assert_eq!(catalog.get(31036).unwrap().name, "小花花");
+        let restored = GiftCatalog::from_json(&catalog.to_json());
+        assert_eq!(restored.get(32124), catalog.get(32124));
+        assert_eq!(restored.fetched_at_ms, 1_000);
+        assert!(GiftCatalog::parse(&json!({ "code": 0 }), 0).is_err());
+    }
+
+    #[test]
+    fn enriches_open_platform_blind_box() {
+        let mut data = json!({
+            "gift_id": 31036, "gift_name": "", "gift_num": 2, "price": 200,
+            "blind_box": true, "original_gift_id": 32124, "original_gift_name": "",
+            "original_price": 0, "revealed_price": 200,
+        });
+        catalog().enrich(&mut data);
+        assert_eq!(data["gift_name"], "小花花");
+        assert_eq!(data["gift_icon"], "https://i0.hdslb.com/flower.png");
+        assert_eq!(data["original_gift_name"], "心动盲盒");
+        assert_eq!(data["original_price"], 30000);
+        assert_eq!(data["price"], 30000);
+        assert_eq!(data["revealed_price"], 200);
+    }
+
+    #[test]
+    fn disk_cache_honours_ttl() {
+        let path = std::env::temp_dir().join(format!("gdblive_gifts_{}.json", std::process::id()));
+        catalog().save(&path).unwrap();
+        assert!(GiftCatalog::load(&path, 10.0, 5_000).is_some());
+        assert!(GiftCatalog::load(&path, 10.0, 20_000).is_none());
+        std::fs::remove_file(&path).unwrap();
+        assert!(GiftCatalog::load(&path, 10.0, 5_000).is_none());
+    }
+}
@@ -0,0 +1,112 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 交给同一应用的其他进程（如独立的弹幕叠加层）接入当前场次所需的信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionHandle {
+    pub game_id: String,
+    pub ws_links: Vec<String>,
+    pub auth_body: String,
+    /// 过期时间，Unix 毫秒
+    pub expires_at_ms: i64,
+}
+
+impl SessionHandle {
+    /// 编码为 `base64(json).base64(hmac_sha256(json))`，密钥为应用的 access_key_secret
+    pub fn encode(&self, secret: &str) -> String {
+        let payload = json!({
+            "game_id": self.game_id,
+            "ws_links": self.ws_links,
+            "auth_body": self.auth_body,
+            "expires_at_ms": self.expires_at_ms,
+        })
+        .to_string();
+        format!(
+            "{}.{}",
+            BASE64.encode(payload.as_bytes()),
+            BASE64.encode(sign(payload.as_bytes(), secret))
+        )
+    }
+
+    /// 校验签名和有效期后还原；签名不符、格式错误或已过期时返回原因
+    pub fn decode(handle: &str, secret: &str, now_ms: i64) -> Result<Self, String> {
+        let (payload, signature) = handle.trim().split_once('.').ok_or("会话凭据格式错误")?;
+        let payload = BASE64
+            .decode(payload)
+            .map_err(|e| format!("会话凭据格式错误: {}", e))?;
+        let signature = BASE64
+            .decode(signature)
+            .map_err(|e| format!("会话凭据格式错误: {}", e))?;
+        let mut mac =
+            HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+        mac.update(&payload);
+        mac.verify_slice(&signature)
+            .map_err(|_| "会话凭据签名无效".to_string())?;
+
+        let json: Value =
+            serde_json::from_slice(&payload).map_err(|e| format!("会话凭据格式错误: {}", e))?;
+        let handle = Self {
+            game_id: json["game_id"].as_str().unwrap_or_default().to_string(),
+            ws_links: json["ws_links"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|link| link.as_str().map(str::to_string))
+                .collect(),
+            auth_body: json["auth_body"].as_str().unwrap_or_default().to_string(),
+            expires_at_ms: json["expires_at_ms"].as_i64().unwrap_or(0),
+        };
+        if handle.expires_at_ms <= now_ms {
+            return Err("会话凭据已过期".to_string());
+        }
+        if handle.ws_links.is_empty() || handle.auth_body.is_empty() {
+            return Err("会话凭据缺少长连接信息".to_string());
+        }
+        Ok(handle)
+    }
+}
+
+fn sign(payload: &[u8], secret: &str) -> Vec<u8> {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(payload);
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_and_rejects_tampering() {
+        let handle = SessionHandle {
+            game_id: "game-1".to_string(),
+            ws_links: vec!["wss://a.example/sub".to_string()],
+            auth_body: r#"{"key":"k"}"#.to_string(),
+            expires_at_ms: 2_000,
+        };
+        let encoded = handle.encode("secret");
+        assert_eq!(SessionHandle::decode(&encoded, "secret", 1_000), Ok(handle));
+
+        assert!(SessionHandle::decode(&encoded, "other", 1_000).is_err());
+        assert_eq!(
+            SessionHandle::decode(&encoded, "secret", 2_000),
+            Err("会话凭据已过期".to_string())
+        );
+        let (payload, signature) = encoded.split_once('.').unwrap();
+        let forged = BASE64.encode(
+            String::from_utf8(BASE64.decode(payload).unwrap())
+                .unwrap()
+                .replace("2000", "9000"),
+        );
+        assert!(
+            SessionHandle::decode(&format!("{}.{}", forged, signature), "secret", 1_000).is_err()
+        );
+        assert!(SessionHandle::decode("garbage", "secret", 1_000).is_err());
+    }
+}
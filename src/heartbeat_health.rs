@@ -0,0 +1,109 @@
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+
+/// 保留最近多少次心跳结果
+const HISTORY_LEN: usize = 20;
+
+#[derive(Debug, Clone, PartialEq)]
+struct HeartbeatResult {
+    ok: bool,
+    rtt_ms: i64,
+}
+
+/// 项目心跳的往返时间和成功率
+///
+/// 连续失败达到阈值时进入降级状态，下一次成功后恢复
+#[derive(Debug, Default)]
+pub struct HeartbeatHealth {
+    history: VecDeque<HeartbeatResult>,
+    consecutive_failures: u32,
+    degraded: bool,
+    last_error: String,
+}
+
+impl HeartbeatHealth {
+    /// 记录一次心跳结果；本次记录使连续失败次数首次达到 threshold 时返回 true
+    pub fn record(&mut self, ok: bool, rtt_ms: i64, error: &str, threshold: u32) -> bool {
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(HeartbeatResult { ok, rtt_ms });
+        if ok {
+            self.consecutive_failures = 0;
+            self.degraded = false;
+            return false;
+        }
+        self.consecutive_failures += 1;
+        self.last_error = error.to_string();
+        if self.degraded || self.consecutive_failures < threshold.max(1) {
+            return false;
+        }
+        self.degraded = true;
+        true
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.degraded
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    pub fn last_error(&self) -> &str {
+        &self.last_error
+    }
+
+    /// samples、success_rate（0~1，无记录时为 1）、avg_rtt_ms、last_rtt_ms（无记录时为 -1）、
+    /// consecutive_failures、degraded 和 last_error
+    pub fn to_json(&self) -> Value {
+        let samples = self.history.len();
+        let successes = self.history.iter().filter(|result| result.ok).count();
+        let success_rate = if samples == 0 {
+            1.0
+        } else {
+            successes as f64 / samples as f64
+        };
+        let avg_rtt_ms = if samples == 0 {
+            0.0
+        } else {
+            self.history.iter().map(|result| result.rtt_ms).sum::<i64>() as f64 / samples as f64
+        };
+        json!({
+            "samples": samples,
+            "success_rate": success_rate,
+            "avg_rtt_ms": avg_rtt_ms,
+            "last_rtt_ms": self.history.back().map(|result| result.rtt_ms).unwrap_or(-1),
+            "consecutive_failures": self.consecutive_failures,
+            "degraded": self.degraded,
+            "last_error": self.last_error,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn degrades_once_and_recovers() {
+        let mut health = HeartbeatHealth::default();
+        assert!(!health.record(true, 100, "", 2));
+        assert!(!health.record(false, 300, "timeout", 2));
+        assert!(health.record(false, 500, "code 7003", 2));
+        assert!(!health.record(false, 500, "code 7003", 2));
+
+        let json = health.to_json();
+        assert_eq!(json["samples"], 4);
+        assert_eq!(json["success_rate"], 0.25);
+        assert_eq!(json["avg_rtt_ms"], 350.0);
+        assert_eq!(json["consecutive_failures"], 3);
+        assert_eq!(json["degraded"], true);
+        assert_eq!(json["last_error"], "code 7003");
+
+        assert!(!health.record(true, 100, "", 2));
+        assert_eq!(health.to_json()["degraded"], false);
+        assert!(!health.record(false, 100, "timeout", 2));
+        assert!(health.record(false, 100, "timeout", 2));
+    }
+}
@@ -0,0 +1,84 @@
+use serde_json::{Map, Value};
+
+/// 随 `http_response_received` 发出的响应头，其余响应头不保留
+const KEY_HEADERS: [&str; 8] = [
+    "content-type",
+    "date",
+    "retry-after",
+    "x-ratelimit-limit",
+    "x-ratelimit-remaining",
+    "x-ratelimit-reset",
+    "x-bili-trace-id",
+    "bili-status-code",
+];
+
+/// 一次开放平台请求的 HTTP 状态码和关键响应头；请求未发出或未收到响应时 status 为 0
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HttpMeta {
+    pub status: u16,
+    /// 小写的响应头名和值，按 `KEY_HEADERS` 的顺序
+    pub headers: Vec<(String, String)>,
+}
+
+impl HttpMeta {
+    pub fn new<'a>(status: u16, headers: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut headers: Vec<(String, String)> = headers
+            .into_iter()
+            .map(|(name, value)| (name.to_ascii_lowercase(), value.to_string()))
+            .filter(|(name, _)| KEY_HEADERS.contains(&name.as_str()))
+            .collect();
+        headers.sort_by_key(|(name, _)| KEY_HEADERS.iter().position(|key| key == name));
+        headers.dedup_by(|a, b| a.0 == b.0);
+        Self { status, headers }
+    }
+
+    pub fn from_response(response: &reqwest::blocking::Response) -> Self {
+        Self::new(
+            response.status().as_u16(),
+            response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| Some((name.as_str(), value.to_str().ok()?))),
+        )
+    }
+
+    /// 响应头 `{名称: 值}`
+    pub fn headers_json(&self) -> Value {
+        Value::Object(
+            self.headers
+                .iter()
+                .map(|(name, value)| (name.clone(), Value::from(value.as_str())))
+                .collect::<Map<String, Value>>(),
+        )
+    }
+
+    /// `{"status": 状态码, "headers": {...}}`
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({ "status": self.status, "headers": self.headers_json() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_only_key_headers() {
+        let meta = HttpMeta::new(
+            429,
+            [
+                ("Set-Cookie", "secret"),
+                ("Retry-After", "30"),
+                ("Content-Type", "application/json"),
+                ("retry-after", "60"),
+            ],
+        );
+        assert_eq!(
+            meta.to_json(),
+            serde_json::json!({
+                "status": 429,
+                "headers": { "content-type": "application/json", "retry-after": "30" },
+            })
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use crate::convert::dictionary_to_json;
+use crate::events::{EVENT_DANMAKU, EVENT_GIFT, EVENT_GUARD, EVENT_LIKE, EVENT_SUPER_CHAT};
+use godot::prelude::*;
+use serde_json::Value;
+
+/// 热度分数：每个事件按权重加分，随后按半衰期指数衰减
+#[derive(Debug, Clone, PartialEq)]
+pub struct HypeScore {
+    pub score: f64,
+    /// 每条弹幕的分数
+    pub danmaku_weight: f64,
+    /// 每次点赞的分数
+    pub like_weight: f64,
+    /// 礼物、SC、大航海每 1 元的分数（免费礼物不计）
+    pub gift_weight: f64,
+    /// 分数衰减一半所需的秒数
+    pub half_life: f64,
+}
+
+impl Default for HypeScore {
+    fn default() -> Self {
+        Self {
+            score: 0.0,
+            danmaku_weight: 1.0,
+            like_weight: 0.2,
+            gift_weight: 1.0,
+            half_life: 10.0,
+        }
+    }
+}
+
+impl HypeScore {
+    pub fn add_event(&mut self, event_type: &str, data: &Value) {
+        let price_yuan = data["price"].as_i64().unwrap_or(0) as f64 / 1000.0;
+        self.score += match event_type {
+            EVENT_DANMAKU => self.danmaku_weight,
+            EVENT_LIKE => self.like_weight * data["like_count"].as_i64().unwrap_or(1) as f64,
+            EVENT_GIFT if data["paid"].as_bool() == Some(false) => 0.0,
+            EVENT_GIFT | EVENT_SUPER_CHAT | EVENT_GUARD => self.gift_weight * price_yuan,
+            _ => 0.0,
+        };
+    }
+
+    pub fn decay(&mut self, delta: f64) {
+        if self.half_life > 0.0 {
+            self.score *= 0.5f64.powf(delta / self.half_life);
+        }
+    }
+
+    /// 当前分数达到的档位数，thresholds 需按升序排列
+    pub fn level(&self, thresholds: &[f64]) -> i64 {
+        thresholds.iter().take_while(|t| self.score >= **t).count() as i64
+    }
+}
+
+/// 根据近期互动计算的热度计，档位变化时发出 `hype_level_changed(level)`
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct HypeMeter {
+    base: Base<Node>,
+
+    /// 要监听 `live_event` 的 Blive 或 LiveSourceManager 节点，留空时可手动调用 `add_event`
+    #[export]
+    blive_path: NodePath,
+    #[export]
+    danmaku_weight: f64,
+    #[export]
+    like_weight: f64,
+    #[export]
+    gift_weight: f64,
+    #[export]
+    half_life: f64,
+    /// 各档位的起始分数（升序），档位 0 表示低于第一个阈值
+    #[export]
+    level_thresholds: PackedFloat64Array,
+
+    hype: HypeScore,
+    level: i64,
+}
+
+#[godot_api]
+impl INode for HypeMeter {
+    fn init(base: Base<Node>) -> Self {
+        let hype = HypeScore::default();
+        Self {
+            base,
+            blive_path: NodePath::default(),
+            danmaku_weight: hype.danmaku_weight,
+            like_weight: hype.like_weight,
+            gift_weight: hype.gift_weight,
+            half_life: hype.half_life,
+            level_thresholds: PackedFloat64Array::from(&[10.0, 30.0, 60.0, 100.0][..]),
+            hype,
+            level: 0,
+        }
+    }
+
+    fn ready(&mut self) {
+        if self.blive_path.is_empty() {
+            return;
+        }
+        let path = self.blive_path.clone();
+        let Some(mut blive) = self.base().get_node_or_null(&path) else {
+            godot_warn!("HypeMeter: 找不到节点 {}", path);
+            return;
+        };
+        // LiveSourceManager 的 live_event 多一个 platform 参数
+        let method = if blive.has_signal("source_started") {
+            "add_platform_event"
+        } else {
+            "add_event"
+        };
+        let callable = Callable::from_object_method(&self.to_gd(), method);
+        blive.connect("live_event", &callable);
+    }
+
+    fn process(&mut self, delta: f64) {
+        self.sync_weights();
+        self.hype.decay(delta);
+        self.update_level();
+    }
+}
+
+#[godot_api]
+impl HypeMeter {
+    #[signal]
+    fn hype_level_changed(level: i64);
+
+    #[func]
+    fn get_hype(&self) -> f64 {
+        self.hype.score
+    }
+
+    #[func]
+    fn get_hype_level(&self) -> i64 {
+        self.level
+    }
+
+    #[func]
+    fn add_event(&mut self, event_type: GString, data: Dictionary) {
+        self.sync_weights();
+        self.hype
+            .add_event(&event_type.to_string(), &dictionary_to_json(&data));
+        self.update_level();
+    }
+
+    #[func]
+    fn add_platform_event(&mut self, _platform: GString, event_type: GString, data: Dictionary) {
+        self.add_event(event_type, data);
+    }
+
+    /// 直接增加分数，用于游戏内的自定义事件
+    #[func]
+    fn add_hype(&mut self, amount: f64) {
+        self.hype.score = (self.hype.score + amount).max(0.0);
+        self.update_level();
+    }
+
+    #[func]
+    fn reset_hype(&mut self) {
+        self.hype.score = 0.0;
+        self.update_level();
+    }
+
+    fn sync_weights(&mut self) {
+        self.hype.danmaku_weight = self.danmaku_weight;
+        self.hype.like_weight = self.like_weight;
+        self.hype.gift_weight = self.gift_weight;
+        self.hype.half_life = self.half_life;
+    }
+
+    fn update_level(&mut self) {
+        let level = self.hype.level(self.level_thresholds.as_slice());
+        if level != self.level {
+            self.level = level;
+            self.base_mut()
+                .emit_signal("hype_level_changed", &[level.to_variant()]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn weights_and_levels() {
+        let mut hype = HypeScore::default();
+        hype.add_event(EVENT_DANMAKU, &json!({}));
+        hype.add_event(EVENT_LIKE, &json!({ "like_count": 5 }));
+        hype.add_event(EVENT_GIFT, &json!({ "price": 100, "paid": false }));
+        hype.add_event(EVENT_SUPER_CHAT, &json!({ "price": 30000 }));
+        assert!((hype.score - 32.0).abs() < 1e-9);
+        assert_eq!(hype.level(&[10.0, 30.0, 60.0]), 2);
+    }
+
+    #[test]
+    fn decays_by_half_life() {
+        let mut hype = HypeScore {
+            score: 40.0,
+            ..HypeScore::default()
+        };
+        hype.decay(10.0);
+        assert!((hype.score - 20.0).abs() < 1e-9);
+        hype.decay(20.0);
+        assert!((hype.score - 5.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,63 @@
+/// 按文字系统粗略判断弹幕语言，返回 ISO 639-1 代码
+///
+/// 含假名时为 ja，否则取字符最多的文字系统：汉字 zh、谚文 ko、西里尔字母 ru、泰文 th、
+/// 阿拉伯字母 ar、拉丁字母 en（不区分英语以外的拉丁字母语言）；只有数字、符号或表情时为 und
+pub fn detect(text: &str) -> &'static str {
+    // zh, ko, ru, th, ar, en
+    let mut counts = [0usize; 6];
+    for c in text.chars() {
+        let index = match c as u32 {
+            0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9D => return "ja",
+            0x4E00..=0x9FFF | 0x3400..=0x4DBF | 0xF900..=0xFAFF => 0,
+            0xAC00..=0xD7AF | 0x1100..=0x11FF | 0x3130..=0x318F => 1,
+            0x0400..=0x04FF => 2,
+            0x0E00..=0x0E7F => 3,
+            0x0600..=0x06FF => 4,
+            _ if c.is_ascii_alphabetic() => 5,
+            0x00C0..=0x024F if c.is_alphabetic() => 5,
+            _ => continue,
+        };
+        counts[index] += 1;
+    }
+    let languages = ["zh", "ko", "ru", "th", "ar", "en"];
+    counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        // 数量相同时靠前的优先
+        .max_by_key(|(index, count)| (**count, usize::MAX - index))
+        .map(|(index, _)| languages[index])
+        .unwrap_or("und")
+}
+
+/// 把指定语言的弹幕转发给节点组成员：`call_group(group, method, lang, data)`
+#[derive(Debug, Clone)]
+pub struct LanguageRoute {
+    pub lang: String,
+    pub group: String,
+    pub method: String,
+}
+
+impl LanguageRoute {
+    pub fn accepts(&self, lang: &str) -> bool {
+        self.lang == "*" || self.lang == lang
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_by_script() {
+        assert_eq!(detect("主播好厉害"), "zh");
+        assert_eq!(detect("すごい！"), "ja");
+        assert_eq!(detect("草すぎる"), "ja");
+        assert_eq!(detect("안녕하세요"), "ko");
+        assert_eq!(detect("привет"), "ru");
+        assert_eq!(detect("hello streamer"), "en");
+        assert_eq!(detect("GG 主播太强了"), "zh");
+        assert_eq!(detect("233333"), "und");
+        assert_eq!(detect("???"), "und");
+    }
+}
@@ -0,0 +1,65 @@
+use godot::prelude::*;
+
+mod ack;
+mod api;
+mod asset_cache;
+mod audio;
+mod audit;
+mod blive;
+mod channel;
+mod clock;
+mod combo;
+mod conn_history;
+mod consensus;
+mod convert;
+mod degradation;
+mod diagnostics;
+mod direct;
+mod error;
+pub mod events;
+mod first_seen;
+mod gating;
+mod gifts;
+mod handoff;
+mod heartbeat_health;
+mod http_meta;
+mod hype;
+mod language;
+mod load_test;
+mod login;
+mod mock;
+mod names;
+mod obs;
+mod overlay;
+mod parse_pool;
+mod protocol;
+mod rate_limit;
+mod recorder;
+mod rewards;
+mod router;
+mod scheduler;
+mod sequence;
+mod session;
+mod shared;
+mod signing;
+mod simulation;
+pub mod source;
+mod spam;
+mod spawn;
+mod subsystems;
+mod superchat;
+mod tls;
+mod traffic;
+mod translation;
+mod triggers;
+#[cfg(feature = "twitch")]
+mod twitch;
+mod webhook;
+mod ws_stop;
+#[cfg(feature = "youtube")]
+mod youtube;
+
+struct GDBliveExtension;
+
+#[gdextension]
+unsafe impl ExtensionLibrary for GDBliveExtension {}
@@ -0,0 +1,259 @@
+use crate::protocol::Protocol;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64};
+
+/// 合成观众的 open_id 前缀，主线程据此识别压测消息
+pub const USER_PREFIX: &str = "load-test-";
+/// 开始发送前先测量的空载帧时间（秒）
+pub const BASELINE_SECS: f64 = 2.0;
+/// 发送结束后等待队列排空的最长时间（秒）
+pub const DRAIN_SECS: f64 = 3.0;
+/// 生成任务每隔多少毫秒发出一批
+pub const BATCH_INTERVAL_MS: u64 = 50;
+
+/// 生成任务与主线程共享的计数
+#[derive(Debug, Default)]
+pub struct GeneratorCounters {
+    pub generated: AtomicU64,
+    /// 解包失败的批数
+    pub decode_errors: AtomicU64,
+    /// 主线程要求提前停止
+    pub stop: AtomicBool,
+    /// 生成任务已退出
+    pub finished: AtomicBool,
+}
+
+/// 第 index 条合成消息（开放平台格式）：每 10 条中 8 条弹幕、1 条免费礼物、1 条点赞
+pub fn synthetic_message(index: u64) -> Value {
+    let user = json!({
+        "open_id": format!("{}{}", USER_PREFIX, index % 1000),
+        "uname": format!("压测观众{}", index % 1000),
+        "uface": "",
+        "fans_medal_level": 0,
+        "fans_medal_wearing_status": false,
+        "guard_level": 0,
+        // 为 0 时不计入时钟偏差估算
+        "timestamp": 0,
+    });
+    let (cmd, fields) = match index % 10 {
+        8 => (
+            "LIVE_OPEN_PLATFORM_SEND_GIFT",
+            json!({ "gift_id": 0, "gift_name": "压测礼物", "gift_num": 1, "price": 0, "paid": false }),
+        ),
+        9 => ("LIVE_OPEN_PLATFORM_LIKE", json!({ "like_count": 1 })),
+        _ => (
+            "LIVE_OPEN_PLATFORM_DM",
+            json!({ "msg": format!("压测弹幕 {}", index) }),
+        ),
+    };
+    let mut data = user;
+    if let (Some(data), Value::Object(fields)) = (data.as_object_mut(), fields) {
+        data.extend(fields);
+    }
+    json!({ "cmd": cmd, "data": data })
+}
+
+/// 把从 first 开始的 count 条合成消息封成一个 zlib 压缩的嵌套包，与平台下发的格式相同
+pub fn encode_batch(protocol: &Protocol, first: u64, count: u64) -> Vec<u8> {
+    let mut nested = Vec::new();
+    for index in first..first + count {
+        let body = synthetic_message(index).to_string();
+        nested.extend(protocol.encode_packet(body.as_bytes(), protocol.op_message));
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::fast());
+    let compressed = encoder
+        .write_all(&nested)
+        .and_then(|_| encoder.finish())
+        .unwrap_or_default();
+    let mut packet = protocol.encode_packet(&compressed, protocol.op_message);
+    packet[6..8].copy_from_slice(&protocol.zlib_version.to_be_bytes());
+    packet
+}
+
+pub fn is_synthetic(data: &Value) -> bool {
+    data["user_id"]
+        .as_str()
+        .is_some_and(|user_id| user_id.starts_with(USER_PREFIX))
+}
+
+/// 到 elapsed 秒时按速率应已生成的消息数
+pub fn due_count(messages_per_minute: u64, elapsed: f64) -> u64 {
+    (messages_per_minute as f64 * elapsed / 60.0) as u64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    Baseline,
+    Loading,
+    Draining,
+}
+
+/// 压测期间主线程一侧的状态：测量帧时间并统计到达主线程的合成事件
+#[derive(Debug)]
+pub struct LoadTest {
+    pub messages_per_minute: u64,
+    pub duration: f64,
+    phase: Phase,
+    phase_elapsed: f64,
+    baseline_frames: Vec<f64>,
+    loaded_frames: Vec<f64>,
+    dispatched: u64,
+}
+
+/// `LoadTest::tick` 要求调用方执行的动作
+#[derive(Debug, PartialEq)]
+pub enum Step {
+    /// 空载测量结束，开始生成消息
+    StartGenerator,
+    /// 生成时长已到，停止生成
+    StopGenerator,
+}
+
+impl LoadTest {
+    pub fn new(messages_per_minute: u64, duration: f64) -> Self {
+        Self {
+            messages_per_minute,
+            duration,
+            phase: Phase::Baseline,
+            phase_elapsed: 0.0,
+            baseline_frames: Vec::new(),
+            loaded_frames: Vec::new(),
+            dispatched: 0,
+        }
+    }
+
+    /// 每帧调用一次；排空阶段在 drained 或超时后返回 true，表示可以出报告
+    pub fn tick(&mut self, delta: f64, drained: bool) -> (Option<Step>, bool) {
+        self.phase_elapsed += delta;
+        let frame_ms = delta * 1000.0;
+        match self.phase {
+            Phase::Baseline => {
+                self.baseline_frames.push(frame_ms);
+                if self.phase_elapsed >= BASELINE_SECS {
+                    self.enter(Phase::Loading);
+                    return (Some(Step::StartGenerator), false);
+                }
+            }
+            Phase::Loading => {
+                self.loaded_frames.push(frame_ms);
+                if self.phase_elapsed >= self.duration {
+                    self.enter(Phase::Draining);
+                    return (Some(Step::StopGenerator), false);
+                }
+            }
+            Phase::Draining => {
+                self.loaded_frames.push(frame_ms);
+                return (None, drained || self.phase_elapsed >= DRAIN_SECS);
+            }
+        }
+        (None, false)
+    }
+
+    /// 提前结束时跳到排空阶段
+    pub fn stop(&mut self) -> Option<Step> {
+        let step = (self.phase == Phase::Loading).then_some(Step::StopGenerator);
+        self.enter(Phase::Draining);
+        step
+    }
+
+    pub fn is_generating(&self) -> bool {
+        self.phase == Phase::Loading
+    }
+
+    pub fn observe_dispatched(&mut self) {
+        self.dispatched += 1;
+    }
+
+    pub fn dispatched(&self) -> u64 {
+        self.dispatched
+    }
+
+    /// generated 为实际生成数，decode_errors 为解包失败的批数
+    pub fn report(&self, generated: u64, decode_errors: u64) -> Value {
+        let baseline = frame_summary(&self.baseline_frames);
+        let loaded = frame_summary(&self.loaded_frames);
+        json!({
+            "messages_per_minute": self.messages_per_minute,
+            "duration": self.duration,
+            "generated": generated,
+            "dispatched": self.dispatched,
+            "dropped": generated.saturating_sub(self.dispatched),
+            "decode_errors": decode_errors,
+            "frame_time_increase_ms": loaded["avg_ms"].as_f64().unwrap_or(0.0)
+                - baseline["avg_ms"].as_f64().unwrap_or(0.0),
+            "baseline": baseline,
+            "loaded": loaded,
+        })
+    }
+
+    fn enter(&mut self, phase: Phase) {
+        self.phase = phase;
+        self.phase_elapsed = 0.0;
+    }
+}
+
+/// `{"frames", "avg_ms", "p95_ms", "max_ms"}`
+fn frame_summary(frames: &[f64]) -> Value {
+    if frames.is_empty() {
+        return json!({ "frames": 0, "avg_ms": 0.0, "p95_ms": 0.0, "max_ms": 0.0 });
+    }
+    let mut sorted = frames.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let p95 = sorted[((sorted.len() as f64 * 0.95).ceil() as usize).clamp(1, sorted.len()) - 1];
+    json!({
+        "frames": frames.len(),
+        "avg_ms": frames.iter().sum::<f64>() / frames.len() as f64,
+        "p95_ms": p95,
+        "max_ms": sorted[sorted.len() - 1],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batches_decode_to_synthetic_events() {
+        let protocol = Protocol::default();
+        let packets = protocol
+            .decode_packet(&encode_batch(&protocol, 0, 10))
+            .unwrap();
+        assert_eq!(packets.len(), 10);
+        let cmds: Vec<String> = packets
+            .iter()
+            .map(|(_, body)| {
+                let message: Value = serde_json::from_slice(body).unwrap();
+                let (_, data) =
+                    crate::events::normalize(message["cmd"].as_str().unwrap(), &message).unwrap();
+                assert!(is_synthetic(&data));
+                message["cmd"].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(cmds[8], "LIVE_OPEN_PLATFORM_SEND_GIFT");
+        assert_eq!(due_count(60_000, 0.5), 500);
+    }
+
+    #[test]
+    fn reports_frame_times_and_drops() {
+        let mut test = LoadTest::new(60_000, 1.0);
+        assert_eq!(test.tick(1.0, false), (None, false));
+        assert_eq!(test.tick(1.0, false), (Some(Step::StartGenerator), false));
+        assert!(test.is_generating());
+        assert_eq!(test.tick(0.5, false), (None, false));
+        assert_eq!(test.tick(0.5, false), (Some(Step::StopGenerator), false));
+        assert_eq!(test.tick(0.1, true), (None, true));
+        for _ in 0..900 {
+            test.observe_dispatched();
+        }
+        let report = test.report(1000, 0);
+        assert_eq!(report["dropped"], 100);
+        assert_eq!(report["baseline"]["avg_ms"], 1000.0);
+        assert_eq!(report["loaded"]["frames"], 3);
+        assert_eq!(report["loaded"]["max_ms"], 500.0);
+        let increase = report["frame_time_increase_ms"].as_f64().unwrap();
+        assert!((increase - (1100.0 / 3.0 - 1000.0)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,171 @@
+use qrcode::{Color, QrCode};
+use serde_json::Value;
+
+const QR_GENERATE_URL: &str = "https://passport.bilibili.com/x/passport-login/web/qrcode/generate";
+const QR_POLL_URL: &str = "https://passport.bilibili.com/x/passport-login/web/qrcode/poll";
+const BUVID_URL: &str = "https://api.bilibili.com/x/frontend/finger/spi";
+
+/// 扫码登录的轮询结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum PollStatus {
+    /// 未扫码
+    Waiting,
+    /// 已扫码，等待手机端确认
+    Scanned,
+    Expired,
+    /// 登录成功，附带可直接写入 `cookie` 的 Cookie 字符串
+    Succeeded(String),
+}
+
+/// 申请登录二维码，返回 (二维码内容, qrcode_key)
+pub fn generate(client: &reqwest::blocking::Client) -> Result<(String, String), String> {
+    let response = get_json(client, QR_GENERATE_URL)?;
+    parse_generate(&response)
+}
+
+pub fn poll(client: &reqwest::blocking::Client, qrcode_key: &str) -> Result<PollStatus, String> {
+    let response = get_json(
+        client,
+        &format!("{}?qrcode_key={}", QR_POLL_URL, qrcode_key),
+    )?;
+    let status = parse_poll(&response)?;
+    // 登录接口不返回 buvid3，补充获取，失败时不影响登录结果
+    if let PollStatus::Succeeded(cookie) = &status {
+        if let Ok(buvid) = get_json(client, BUVID_URL).and_then(|r| parse_buvid(&r)) {
+            return Ok(PollStatus::Succeeded(format!(
+                "{}; buvid3={}",
+                cookie, buvid
+            )));
+        }
+    }
+    Ok(status)
+}
+
+fn get_json(client: &reqwest::blocking::Client, url: &str) -> Result<Value, String> {
+    let text = client
+        .get(url)
+        .send()
+        .map_err(|e| format!("请求发送失败: {}", e))?
+        .text()
+        .map_err(|e| format!("响应读取失败: {}", e))?;
+    serde_json::from_str(&text).map_err(|e| format!("响应解析失败: {}", e))
+}
+
+pub fn parse_generate(response: &Value) -> Result<(String, String), String> {
+    let data = &response["data"];
+    match (data["url"].as_str(), data["qrcode_key"].as_str()) {
+        (Some(url), Some(key)) if response["code"].as_i64() == Some(0) => {
+            Ok((url.to_string(), key.to_string()))
+        }
+        _ => Err(format!(
+            "申请二维码失败: {}",
+            response["message"].as_str().unwrap_or_default()
+        )),
+    }
+}
+
+/// 成功时 data.url 的查询参数中带有 DedeUserID、SESSDATA、bili_jct 等 Cookie
+pub fn parse_poll(response: &Value) -> Result<PollStatus, String> {
+    let data = &response["data"];
+    match data["code"].as_i64() {
+        Some(0) => {
+            let url = data["url"].as_str().unwrap_or_default();
+            let query = url.split_once('?').map(|(_, q)| q).unwrap_or_default();
+            let cookie = query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .filter(|(key, _)| matches!(*key, "DedeUserID" | "SESSDATA" | "bili_jct"))
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<_>>()
+                .join("; ");
+            if cookie.contains("SESSDATA=") {
+                Ok(PollStatus::Succeeded(cookie))
+            } else {
+                Err("登录成功但未返回 SESSDATA".to_string())
+            }
+        }
+        Some(86101) => Ok(PollStatus::Waiting),
+        Some(86090) => Ok(PollStatus::Scanned),
+        Some(86038) => Ok(PollStatus::Expired),
+        _ => Err(format!(
+            "轮询登录状态失败: {}",
+            data["message"]
+                .as_str()
+                .or(response["message"].as_str())
+                .unwrap_or_default()
+        )),
+    }
+}
+
+fn parse_buvid(response: &Value) -> Result<String, String> {
+    response["data"]["b_3"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| "响应中缺少 b_3".to_string())
+}
+
+/// 生成二维码灰度像素（深色 0、浅色 255），每个模块放大为 scale 像素并带 4 模块留白
+pub fn qr_pixels(text: &str, scale: usize) -> Result<(usize, Vec<u8>), String> {
+    let code = QrCode::new(text.as_bytes()).map_err(|e| format!("生成二维码失败: {}", e))?;
+    let modules = code.width();
+    let colors = code.to_colors();
+    let quiet = 4;
+    let scale = scale.max(1);
+    let size = (modules + quiet * 2) * scale;
+
+    let mut pixels = vec![255u8; size * size];
+    for (index, color) in colors.iter().enumerate() {
+        if *color != Color::Dark {
+            continue;
+        }
+        let x = (index % modules + quiet) * scale;
+        let y = (index / modules + quiet) * scale;
+        for row in y..y + scale {
+            pixels[row * size + x..row * size + x + scale].fill(0);
+        }
+    }
+    Ok((size, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn generate_response() {
+        let response = json!({"code": 0, "data": {"url": "https://qr", "qrcode_key": "k"}});
+        assert_eq!(
+            parse_generate(&response),
+            Ok(("https://qr".to_string(), "k".to_string()))
+        );
+        assert!(parse_generate(&json!({"code": -412, "message": "请求被拦截"})).is_err());
+    }
+
+    #[test]
+    fn poll_states() {
+        let status = |code: i64| parse_poll(&json!({"code": 0, "data": {"code": code}}));
+        assert_eq!(status(86101), Ok(PollStatus::Waiting));
+        assert_eq!(status(86090), Ok(PollStatus::Scanned));
+        assert_eq!(status(86038), Ok(PollStatus::Expired));
+
+        let success = json!({"code": 0, "data": {"code": 0, "url":
+            "https://passport.biligame.com/crossDomain?DedeUserID=12&DedeUserID__ckMd5=ab&Expires=1&SESSDATA=s%2C1&bili_jct=j&gourl=https%3A%2F%2Fwww.bilibili.com"}});
+        assert_eq!(
+            parse_poll(&success),
+            Ok(PollStatus::Succeeded(
+                "DedeUserID=12; SESSDATA=s%2C1; bili_jct=j".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn qr_pixels_are_square_and_scaled() {
+        let (size, pixels) = qr_pixels("https://example.com", 3).unwrap();
+        assert_eq!(pixels.len(), size * size);
+        assert_eq!(size % 3, 0);
+        // 留白区域为浅色，左上角定位点为深色
+        assert_eq!(pixels[0], 255);
+        assert_eq!(pixels[(4 * 3) * size + 4 * 3], 0);
+    }
+}
@@ -0,0 +1,3275 @@
+use crate::ack::AckTracker;
+use crate::api::{self, DeprecationLog};
+use crate::asset_cache::{self, DiskCache, Lru};
+use crate::audit::{self, AuditLog};
+use crate::blive::GroupForward;
+use crate::channel;
+use crate::clock::ClockOffset;
+use crate::combo::ComboTracker;
+use crate::conn_history::{ConnectionAttempt, ConnectionHistory};
+use crate::consensus::ConsensusDetector;
+use crate::convert::{
+    dictionary_to_json, globalize_path, json_to_dictionary, json_to_variant, variant_to_json,
+};
+use crate::degradation::MessageDigest;
+use crate::diagnostics::DiagnosticReport;
+use crate::direct;
+use crate::error;
+use crate::events::{self, Audience, Interaction};
+use crate::first_seen::{self, FirstInteractions};
+use crate::gating::{InteractionGate, Viewer};
+use crate::gifts::GiftCatalog;
+use crate::handoff::SessionHandle;
+use crate::heartbeat_health::HeartbeatHealth;
+use crate::http_meta::HttpMeta;
+use crate::language::{self, LanguageRoute};
+use crate::load_test::{self, LoadTest, Step};
+use crate::login;
+use crate::rate_limit::{SharedLimiter, MAX_QUEUE_WAIT_SECS};
+use crate::recorder::{self, RecorderOptions, SessionRecorder};
+use crate::scheduler::EventScheduler;
+use crate::session::{self, SessionLifecycle, StartInfo, Transition};
+use crate::shared::{SharedAttachment, SharedSession};
+use crate::simulation::{SimAction, SimulationPlayback, SimulationScript};
+use crate::spam::SpamCollapser;
+use crate::subsystems::{self, Subsystems};
+use crate::superchat::SuperChatTimers;
+use crate::tls;
+use crate::traffic::TrafficSnapshot;
+use crate::translation::{Prepared, TranslationPipeline};
+use crate::webhook::{Delivery, Webhook};
+use godot::classes::image::Format;
+use godot::classes::{Image, ImageTexture, Time};
+use godot::prelude::*;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+static NEXT_GAME: AtomicU64 = AtomicU64::new(1);
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// 构造开放平台格式的消息 `{"cmd": cmd, "data": data}`
+fn open_platform_message(cmd: &str, data: Value) -> Value {
+    json!({ "cmd": cmd, "data": data })
+}
+
+fn mock_user(uname: &str) -> Value {
+    json!({
+        "open_id": format!("mock-{}", uname),
+        "uname": uname,
+        "uface": "",
+        "fans_medal_level": 0,
+        "fans_medal_wearing_status": false,
+        "guard_level": 0,
+        "timestamp": now_secs(),
+    })
+}
+
+fn danmaku_message(uname: &str, msg: &str) -> Value {
+    let data = events::with_user(mock_user(uname), json!({ "msg": msg }));
+    open_platform_message("LIVE_OPEN_PLATFORM_DM", data)
+}
+
+/// price 为单个礼物价格（千分之一元），0 表示免费礼物
+fn gift_message(uname: &str, gift_name: &str, gift_num: i64, price: i64) -> Value {
+    let data = events::with_user(
+        mock_user(uname),
+        json!({
+            "gift_id": 0,
+            "gift_name": gift_name,
+            "gift_num": gift_num,
+            "price": price,
+            "paid": price > 0,
+        }),
+    );
+    open_platform_message("LIVE_OPEN_PLATFORM_SEND_GIFT", data)
+}
+
+fn super_chat_message(uname: &str, message: &str, rmb: i64) -> Value {
+    let start = now_secs();
+    let data = events::with_user(
+        mock_user(uname),
+        json!({
+            "message_id": start,
+            "message": message,
+            "rmb": rmb,
+            "start_time": start,
+            "end_time": start + 60,
+        }),
+    );
+    open_platform_message("LIVE_OPEN_PLATFORM_SUPER_CHAT", data)
+}
+
+/// guard_level：1 总督、2 提督、3 舰长
+fn guard_message(uname: &str, guard_level: i64) -> Value {
+    let price = match guard_level {
+        1 => 19_998_000,
+        2 => 1_998_000,
+        _ => 198_000,
+    };
+    let user = mock_user(uname);
+    open_platform_message(
+        "LIVE_OPEN_PLATFORM_GUARD",
+        json!({
+            "user_info": { "open_id": user["open_id"], "uname": uname, "uface": "" },
+            "guard_level": guard_level,
+            "guard_num": 1,
+            "guard_unit": "月",
+            "price": price,
+            "fans_medal_level": 0,
+            "timestamp": user["timestamp"],
+        }),
+    )
+}
+
+/// 直连模式的 INTERACT_WORD，msg_type 2 为关注、3 为分享
+fn interact_message(uname: &str, msg_type: i64) -> Value {
+    json!({
+        "cmd": "INTERACT_WORD",
+        "data": {
+            "uid": format!("mock-{}", uname),
+            "uname": uname,
+            "msg_type": msg_type,
+            "timestamp": now_secs(),
+        },
+    })
+}
+
+/// 直连模式格式的盲盒礼物，开放平台消息不含盲盒名称和价格
+fn blind_box_message(
+    uname: &str,
+    box_name: &str,
+    box_price: i64,
+    revealed_name: &str,
+    revealed_price: i64,
+) -> Value {
+    json!({
+        "cmd": "SEND_GIFT",
+        "data": {
+            "uid": format!("mock-{}", uname),
+            "uname": uname,
+            "face": "",
+            "timestamp": now_secs(),
+            "giftId": 0,
+            "giftName": revealed_name,
+            "num": 1,
+            "price": revealed_price,
+            "coin_type": "gold",
+            "blind_gift": {
+                "original_gift_id": 0,
+                "original_gift_name": box_name,
+                "original_gift_price": box_price,
+                "gift_action": "爆出",
+            },
+        },
+    })
+}
+
+fn like_message(uname: &str, like_count: i64) -> Value {
+    let data = events::with_user(mock_user(uname), json!({ "like_count": like_count }));
+    open_platform_message("LIVE_OPEN_PLATFORM_LIKE", data)
+}
+
+/// 与 Blive 信号和函数完全一致的假节点，不发起任何网络请求，用于在 GDScript 中测试游戏逻辑和界面
+///
+/// start / end / 心跳 / 长连接等函数立即以成功结果发出对应信号，
+/// 直播消息通过 `inject_*` 系列函数注入，经过与 Blive 相同的门槛检查和事件转换。
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct BliveMock {
+    base: Base<Node>,
+
+    #[export]
+    app_id: i64,
+    #[export]
+    access_key_id: GString,
+    #[export]
+    access_key_secret: GString,
+    #[export]
+    api_base_url: GString,
+    /// 同一场景有多个节点时用于区分来源：非空时信号中的字典参数都带有 channel 键；
+    /// 为空时读取节点元数据 `channel_name`
+    #[export]
+    channel_name: GString,
+    #[export]
+    cookie: GString,
+    #[var(no_set)]
+    ws_auth_body: GString,
+    #[var(no_set)]
+    ws_links: PackedStringArray,
+    #[export]
+    auto_end_on_stream_end: bool,
+    /// 默认为空，不写入审计日志
+    #[export]
+    audit_log_path: GString,
+    #[export]
+    first_interaction_store_path: GString,
+    #[export]
+    reliable_delivery: bool,
+    #[export]
+    reliable_event_types: PackedStringArray,
+    #[export]
+    ack_timeout_secs: f64,
+    #[export]
+    max_delivery_attempts: i64,
+    #[export]
+    collapse_repeated_danmaku: bool,
+    #[export]
+    collapse_window_secs: f64,
+    #[export]
+    detect_chat_consensus: bool,
+    #[export]
+    consensus_window_secs: f64,
+    #[export]
+    consensus_ratio: f64,
+    #[export]
+    consensus_min_users: i64,
+    #[export]
+    detect_language: bool,
+    #[export]
+    translation_rate_per_sec: f64,
+    #[export]
+    prefetch_gift_catalog: bool,
+    #[export]
+    gift_catalog_cache_path: GString,
+    #[export]
+    gift_catalog_ttl_secs: f64,
+    #[export]
+    heartbeat_failure_threshold: i64,
+    #[export]
+    auto_degrade: bool,
+    #[export]
+    parse_workers: i64,
+    #[export]
+    bandwidth_report_interval: f64,
+    #[export]
+    tls_ca_cert_path: GString,
+    #[export]
+    ws_insecure_skip_verify: bool,
+    #[export]
+    api_qps: f64,
+    #[export]
+    api_rate_limit_queue: bool,
+    #[export]
+    asset_cache_dir: GString,
+    #[export]
+    asset_memory_cache_mb: f64,
+    #[export]
+    asset_disk_cache_mb: f64,
+    #[export]
+    recording_max_segment_mb: f64,
+    #[export]
+    recording_max_segment_minutes: f64,
+    #[export]
+    recording_keep_sessions: i64,
+    #[export]
+    recording_compress: bool,
+    #[export]
+    invalid_code_retries: i64,
+    #[export]
+    session_cache_ttl_secs: f64,
+    #[export]
+    ws_reply_timeout_secs: f64,
+
+    awaiting_code: Option<i64>,
+    ws_connected: bool,
+    /// `start_websocket_for` 模拟的连接 ID
+    keyed_connections: BTreeSet<String>,
+    guest: bool,
+    heartbeat_game_id: Option<String>,
+    batch_game_ids: Option<Vec<String>>,
+    live_state: Option<bool>,
+    room_info_cache: HashMap<i64, Value>,
+    interaction_gate: InteractionGate,
+    group_forwards: Vec<GroupForward>,
+    game_id: String,
+    session: SessionLifecycle,
+    combos: Option<ComboTracker>,
+    super_chats: Option<SuperChatTimers>,
+    subsystems: Subsystems,
+    asset_textures: Lru<Gd<ImageTexture>>,
+    asset_disk: (Option<PathBuf>, Option<DiskCache>),
+    /// `get_asset` 未命中内存缓存的 url，下一帧加载
+    pending_assets: Vec<String>,
+    /// `set_asset_response` 设置的图片数据
+    asset_responses: HashMap<String, Vec<u8>>,
+    deprecations: DeprecationLog,
+    clock: ClockOffset,
+    elapsed: f64,
+    /// 本次连接内注入消息的序号，对应 live_event 的 data.seq
+    seq: u64,
+    heartbeat_health: HeartbeatHealth,
+    degraded: bool,
+    message_digest: MessageDigest,
+    ready: bool,
+    gift_catalog: GiftCatalog,
+    language_filter: Vec<String>,
+    language_routes: Vec<LanguageRoute>,
+    translation_enabled: bool,
+    webhook: Option<Webhook>,
+    /// 未发送的 webhook 投递，由 `take_webhook_deliveries` 取出
+    webhook_outbox: Vec<Delivery>,
+    recorder: Option<SessionRecorder>,
+    simulation: Option<SimulationPlayback>,
+    load_test: Option<LoadTest>,
+    /// 开始生成合成消息时的 elapsed 和已生成数
+    load_generation: (f64, u64),
+    translation_callable: Option<Callable>,
+    translation: TranslationPipeline,
+    spam: SpamCollapser,
+    consensus: ConsensusDetector,
+    acks: AckTracker,
+    scheduler: EventScheduler,
+    connection_history: Option<ConnectionHistory>,
+    /// 本次模拟连接建立的 Unix 毫秒时间戳
+    connected_at_ms: i64,
+    shared: SharedSession,
+    attachment: Option<SharedAttachment>,
+    /// `end_async` 请求的场次，下一帧处理
+    pending_ends: Vec<(i64, GString)>,
+    /// `request` 的请求，下一帧发出 `request_completed`
+    pending_requests: Vec<(i64, String)>,
+    /// `start_async` 的请求，下一帧开启场次
+    pending_starts: Vec<(i64, String)>,
+    last_request_id: i64,
+    heartbeat_paused_at: Option<(f64, bool)>,
+    result_callbacks: HashMap<i64, Callable>,
+    next_callback: Option<Callable>,
+    api_limiter: SharedLimiter,
+    /// `start_heartbeat` / `start_batch_heartbeat` 返回的请求 ID
+    heartbeat_request_id: i64,
+    batch_request_id: i64,
+    /// `set_request_response` 设置的接口响应，未设置的接口返回 code 0
+    request_responses: HashMap<String, String>,
+    http_responses: HashMap<String, HttpMeta>,
+    audit_log: Option<AuditLog>,
+    first_interactions: FirstInteractions,
+    first_interaction_store_opened: GString,
+}
+
+#[godot_api]
+impl INode for BliveMock {
+    fn init(base: Base<Node>) -> Self {
+        Self {
+            base,
+            app_id: 0,
+            access_key_id: GString::new(),
+            access_key_secret: GString::new(),
+            api_base_url: GString::from("https://live-open.biliapi.com"),
+            channel_name: GString::new(),
+            cookie: GString::new(),
+            ws_auth_body: GString::new(),
+            ws_links: PackedStringArray::new(),
+            auto_end_on_stream_end: false,
+            audit_log_path: GString::new(),
+            first_interaction_store_path: GString::new(),
+            reliable_delivery: false,
+            reliable_event_types: [
+                events::EVENT_GIFT,
+                events::EVENT_SUPER_CHAT,
+                events::EVENT_GUARD,
+            ]
+            .into_iter()
+            .map(GString::from)
+            .collect(),
+            ack_timeout_secs: 5.0,
+            max_delivery_attempts: 5,
+            collapse_repeated_danmaku: false,
+            collapse_window_secs: 3.0,
+            detect_chat_consensus: false,
+            consensus_window_secs: 10.0,
+            consensus_ratio: 0.5,
+            consensus_min_users: 5,
+            detect_language: false,
+            translation_rate_per_sec: 5.0,
+            prefetch_gift_catalog: false,
+            gift_catalog_cache_path: GString::new(),
+            gift_catalog_ttl_secs: 86400.0,
+            heartbeat_failure_threshold: 2,
+            auto_degrade: false,
+            parse_workers: 0,
+            bandwidth_report_interval: 10.0,
+            tls_ca_cert_path: GString::new(),
+            ws_insecure_skip_verify: false,
+            api_qps: 10.0,
+            api_rate_limit_queue: true,
+            asset_cache_dir: GString::from("user://gdblive_assets"),
+            asset_memory_cache_mb: 64.0,
+            asset_disk_cache_mb: 256.0,
+            api_limiter: SharedLimiter::default(),
+            recording_max_segment_mb: 64.0,
+            recording_max_segment_minutes: 60.0,
+            recording_keep_sessions: 10,
+            recording_compress: true,
+            invalid_code_retries: 3,
+            session_cache_ttl_secs: session::EXPIRING_AFTER_SECS,
+            ws_reply_timeout_secs: 60.0,
+            awaiting_code: None,
+            ws_connected: false,
+            keyed_connections: BTreeSet::new(),
+            guest: false,
+            heartbeat_game_id: None,
+            batch_game_ids: None,
+            live_state: None,
+            room_info_cache: HashMap::new(),
+            interaction_gate: InteractionGate::default(),
+            group_forwards: Vec::new(),
+            game_id: String::new(),
+            session: SessionLifecycle::default(),
+            combos: None,
+            super_chats: None,
+            subsystems: Subsystems::default(),
+            asset_textures: Lru::default(),
+            asset_disk: (None, None),
+            pending_assets: Vec::new(),
+            asset_responses: HashMap::new(),
+            deprecations: DeprecationLog::default(),
+            clock: ClockOffset::default(),
+            elapsed: 0.0,
+            seq: 0,
+            heartbeat_health: HeartbeatHealth::default(),
+            degraded: false,
+            message_digest: MessageDigest::default(),
+            ready: false,
+            gift_catalog: GiftCatalog::default(),
+            language_filter: Vec::new(),
+            language_routes: Vec::new(),
+            translation_enabled: false,
+            webhook: None,
+            webhook_outbox: Vec::new(),
+            recorder: None,
+            simulation: None,
+            load_test: None,
+            load_generation: (0.0, 0),
+            translation_callable: None,
+            translation: TranslationPipeline::new("", 5.0),
+            spam: SpamCollapser::default(),
+            consensus: ConsensusDetector::default(),
+            acks: AckTracker::default(),
+            scheduler: EventScheduler::default(),
+            connection_history: None,
+            connected_at_ms: 0,
+            shared: SharedSession::default(),
+            attachment: None,
+            pending_ends: Vec::new(),
+            pending_requests: Vec::new(),
+            pending_starts: Vec::new(),
+            last_request_id: 0,
+            heartbeat_paused_at: None,
+            result_callbacks: HashMap::new(),
+            next_callback: None,
+            heartbeat_request_id: 0,
+            batch_request_id: 0,
+            request_responses: HashMap::new(),
+            http_responses: HashMap::new(),
+            audit_log: None,
+            first_interactions: FirstInteractions::default(),
+            first_interaction_store_opened: GString::new(),
+        }
+    }
+
+    fn ready(&mut self) {
+        self.register_signal_aliases();
+    }
+
+    fn process(&mut self, delta: f64) {
+        self.elapsed += delta;
+        self.drain_attachment();
+        self.check_heartbeat_pause();
+        for (request_id, code) in std::mem::take(&mut self.pending_starts) {
+            self.start_with_retries(code, self.invalid_code_retries.max(0), request_id);
+        }
+        for (request_id, game_id) in std::mem::take(&mut self.pending_ends) {
+            self.end_game(game_id, "ended", request_id);
+        }
+        self.load_pending_assets();
+        for (request_id, path) in std::mem::take(&mut self.pending_requests) {
+            let response = self
+                .request_responses
+                .get(&path)
+                .cloned()
+                .unwrap_or_else(|| json!({ "code": 0, "message": "0", "data": {} }).to_string());
+            let response = error::with_request_id(&response, request_id);
+            self.record_http_response(
+                &path,
+                HttpMeta::new(200, [("content-type", "application/json")]),
+            );
+            self.emit(
+                "request_completed",
+                &[path.to_variant(), response.to_variant()],
+            );
+            self.deliver_result(request_id, &response);
+        }
+        let expired = match self.super_chats.as_mut() {
+            Some(super_chats) => super_chats.expire(self.elapsed),
+            None => Vec::new(),
+        };
+        for message_id in expired {
+            self.emit("super_chat_expired", &[message_id.to_variant()]);
+        }
+        self.redeliver_events();
+        self.fire_scheduled_events();
+        for summary in self.spam.flush(self.elapsed, self.collapse_window_secs) {
+            self.emit("danmaku_collapsed", &[json_to_variant(&summary)]);
+        }
+        self.check_chat_consensus();
+        if self.degraded {
+            if let Some(counts) = self.message_digest.take_due(self.elapsed, 1.0) {
+                self.emit("message_digest", &[json_to_variant(&counts)]);
+            }
+        }
+        self.play_simulation_step();
+        self.tick_load_test(delta);
+    }
+}
+
+#[godot_api]
+impl BliveMock {
+    #[signal]
+    fn start_completed(response_json: GString);
+    #[signal]
+    fn start_succeeded(
+        game_id: GString,
+        wss_links: PackedStringArray,
+        auth_body: GString,
+        anchor_info: Dictionary,
+    );
+    #[signal]
+    fn request_cancelled(request_id: i64);
+    #[signal]
+    fn end_completed(response_json: GString);
+    #[signal]
+    fn request_completed(path: GString, response_json: GString);
+    #[signal]
+    fn http_response_received(path: GString, status: i64, headers: Dictionary);
+    #[signal]
+    fn rate_limited(path: GString, queued: bool, wait_secs: f64);
+    #[signal]
+    fn diagnostics_completed(report: Dictionary);
+    #[signal]
+    fn heartbeat_completed(response_json: GString);
+    #[signal]
+    fn batch_heartbeat_completed(response_json: GString);
+    #[signal]
+    fn ws_connected();
+    #[signal]
+    fn ws_disconnected();
+    #[signal]
+    fn ws_timeout(seconds: f64);
+    #[signal]
+    fn ws_connection_event(conn_id: GString, name: GString, args: Array<Variant>);
+    #[signal]
+    fn ws_raw_packet(operation: i64, body: PackedByteArray);
+    #[signal]
+    fn ws_message_received(cmd: GString, data_json: GString);
+    #[signal]
+    fn ws_error(error_msg: GString);
+    #[signal]
+    fn ws_debug(debug_msg: GString);
+    #[signal]
+    fn error_occurred(domain: GString, code: i64, message: GString);
+    #[signal]
+    fn protocol_error(kind: GString, detail: GString);
+    #[signal]
+    fn heartbeat_debug(debug_msg: GString);
+    #[signal]
+    fn interaction_rejected(open_id: GString, reason: GString);
+    #[signal]
+    fn live_event(event_type: GString, data: Dictionary);
+    #[signal]
+    fn watched_count_updated(count: i64);
+    #[signal]
+    fn online_count_updated(count: i64);
+    #[signal]
+    fn ws_host_selected(url: GString, index: i64);
+    #[signal]
+    fn popularity_updated(value: i64);
+    #[signal]
+    fn ws_sequence_gap(expected: i64, got: i64);
+    #[signal]
+    fn online_rank_updated(list: Array<Variant>);
+    #[signal]
+    fn blind_box_opened(data: Dictionary);
+    #[signal]
+    fn first_interaction(open_id: GString, kind: GString);
+    #[signal]
+    fn super_chat_expired(message_id: i64);
+    #[signal]
+    fn super_chat_deleted(message_id: i64);
+    #[signal]
+    fn viewer_followed(open_id: GString, uname: GString);
+    #[signal]
+    fn viewer_shared(open_id: GString, uname: GString);
+    #[signal]
+    fn stream_went_live();
+    #[signal]
+    fn stream_went_offline();
+    #[signal]
+    fn qr_login_ready(qr_url: GString);
+    #[signal]
+    fn qr_login_status(status: GString);
+    #[signal]
+    fn login_succeeded(cookie_summary: GString);
+    #[signal]
+    fn login_failed(reason: GString);
+    #[signal]
+    fn room_info_received(room_id: i64, info: Dictionary);
+    #[signal]
+    fn room_info_failed(room_id: i64, error_msg: GString);
+    #[signal]
+    fn danmaku_sent(text: GString);
+    #[signal]
+    fn danmaku_send_failed(text: GString, reason: GString);
+    #[signal]
+    fn session_closed(reason: GString);
+    #[signal]
+    fn session_switch_progress(stage: GString, previous_game_id: GString, new_game_id: GString);
+    #[signal]
+    fn session_switch_failed(previous_game_id: GString, error: GString);
+    #[signal]
+    fn bandwidth_report(report: Dictionary);
+    #[signal]
+    fn load_test_completed(report: Dictionary);
+    #[signal]
+    fn session_started(game_id: GString);
+    #[signal]
+    fn session_heartbeat_ok(game_id: GString);
+    #[signal]
+    fn session_expiring(game_id: GString);
+    /// 脚本第一次使用已弃用的信号名时发出，since_version 为开始弃用的 API 版本（同时输出警告）
+    #[signal]
+    fn deprecation_warning(old_name: GString, new_name: GString, since_version: i64);
+    #[signal]
+    fn blive_ready(success: bool, error: GString);
+    #[signal]
+    fn gift_catalog_updated(count: i64);
+    #[signal]
+    fn gift_catalog_failed(error: GString);
+    #[signal]
+    fn danmaku_translated(data: Dictionary);
+    #[signal]
+    fn danmaku_collapsed(data: Dictionary);
+    #[signal]
+    fn chat_consensus(text: GString, ratio: f64);
+    #[signal]
+    fn audit_log_failed(error: GString);
+    #[signal]
+    fn translation_failed(data: Dictionary, error: GString);
+    #[signal]
+    fn webhook_failed(delivery_id: GString, event_type: GString, error: GString);
+    #[signal]
+    fn recording_segment_closed(path: GString);
+    #[signal]
+    fn recording_failed(error: GString);
+    #[signal]
+    fn asset_loaded(url: GString, texture: Gd<ImageTexture>);
+    #[signal]
+    fn asset_failed(url: GString, error: GString);
+    #[signal]
+    fn invalid_code(code: GString, error_code: i64, message: GString, retries_left: i64);
+    /// 非循环播放的模拟脚本已发出全部消息
+    #[signal]
+    fn simulation_finished();
+    #[signal]
+    fn heartbeat_degraded(consecutive_failures: i64, last_error: GString);
+    #[signal]
+    fn heartbeat_pause_too_long(paused_secs: f64);
+    #[signal]
+    fn degradation_changed(degraded: bool, reason: GString);
+    #[signal]
+    fn message_digest(counts: Dictionary);
+    #[signal]
+    fn session_ended(game_id: GString, reason: GString);
+
+    /// 立即发出 `blive_ready(true, "")`
+    #[func]
+    fn initialize(&mut self) {
+        if self.prefetch_gift_catalog {
+            self.fetch_gift_catalog(false);
+        }
+        self.ready = true;
+        self.emit(
+            "blive_ready",
+            &[true.to_variant(), GString::new().to_variant()],
+        );
+    }
+
+    #[func]
+    fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    /// 立即以当前注入的礼物目录发出 `gift_catalog_updated`
+    #[func]
+    fn fetch_gift_catalog(&mut self, _force: bool) {
+        let count = self.gift_catalog.len() as i64;
+        self.emit("gift_catalog_updated", &[count.to_variant()]);
+    }
+
+    #[func]
+    fn get_gift_info(&self, gift_id: i64) -> Dictionary {
+        self.gift_catalog
+            .get(gift_id)
+            .and_then(|gift| gift.to_json().as_object().map(json_to_dictionary))
+            .unwrap_or_default()
+    }
+
+    /// 设置礼物目录，每项包含 id、name、price（单价，千分之一元）、icon 和 coin_type
+    #[func]
+    fn inject_gift_catalog(&mut self, gifts: Array<Dictionary>) {
+        let gifts: Vec<Value> = gifts
+            .iter_shared()
+            .map(|gift| dictionary_to_json(&gift))
+            .collect();
+        self.gift_catalog = GiftCatalog::from_json(&json!({ "gifts": gifts }));
+        self.fetch_gift_catalog(false);
+    }
+
+    /// 立即以成功响应发出 `start_completed` 和 `start_succeeded`，场次 ID 为 mock-game-N；
+    /// code 为空白时模拟身份码错误（7007）并发出 `invalid_code`
+    #[func]
+    fn start(&mut self, code: GString) -> i64 {
+        if self.reject_read_only("start") {
+            return 0;
+        }
+        self.awaiting_code = None;
+        let request_id = self.next_request_id();
+        self.start_with_retries(
+            code.to_string(),
+            self.invalid_code_retries.max(0),
+            request_id,
+        );
+        request_id
+    }
+
+    #[func]
+    fn start_async(&mut self, code: GString) -> i64 {
+        if self.reject_read_only("start_async") {
+            return 0;
+        }
+        self.awaiting_code = None;
+        let request_id = self.next_request_id();
+        self.pending_starts.push((request_id, code.to_string()));
+        request_id
+    }
+
+    /// 下一帧之前的 `start_async` / `end_async` / `request` 可以取消
+    #[func]
+    fn cancel_request(&mut self, request_id: i64) -> bool {
+        let before =
+            self.pending_starts.len() + self.pending_ends.len() + self.pending_requests.len();
+        self.pending_starts.retain(|(id, _)| *id != request_id);
+        self.pending_ends.retain(|(id, _)| *id != request_id);
+        self.pending_requests.retain(|(id, _)| *id != request_id);
+        let after =
+            self.pending_starts.len() + self.pending_ends.len() + self.pending_requests.len();
+        if before == after {
+            return false;
+        }
+        let response = json!({
+            "request_id": request_id,
+            "code": -1,
+            "message": "请求已取消",
+        })
+        .to_string();
+        self.emit("request_cancelled", &[request_id.to_variant()]);
+        self.deliver_result(request_id, &response);
+        true
+    }
+
+    #[func]
+    fn cancel_all_requests(&mut self) -> i64 {
+        let mut request_ids: Vec<i64> = self
+            .pending_starts
+            .iter()
+            .map(|(id, _)| *id)
+            .chain(self.pending_ends.iter().map(|(id, _)| *id))
+            .chain(self.pending_requests.iter().map(|(id, _)| *id))
+            .collect();
+        request_ids.sort_unstable();
+        request_ids
+            .into_iter()
+            .filter(|request_id| self.cancel_request(*request_id))
+            .count() as i64
+    }
+
+    #[func]
+    fn set_code(&mut self, code: GString) -> i64 {
+        let Some(retries_left) = self.awaiting_code.take() else {
+            godot_warn!("BliveMock: 当前没有等待重新输入的身份码");
+            return 0;
+        };
+        let request_id = self.next_request_id();
+        self.start_with_retries(code.to_string(), retries_left - 1, request_id);
+        request_id
+    }
+
+    #[func]
+    fn is_awaiting_code(&self) -> bool {
+        self.awaiting_code.is_some()
+    }
+
+    #[func]
+    fn cancel_code_retry(&mut self) {
+        self.awaiting_code = None;
+    }
+
+    fn start_with_retries(&mut self, code: String, retries_left: i64, request_id: i64) {
+        if !code.trim().is_empty() {
+            self.start_game(request_id);
+            return;
+        }
+        let message = "身份码错误";
+        let response = json!({
+            "request_id": request_id,
+            "code": error::INVALID_CODE,
+            "message": message,
+        });
+        self.emit("start_completed", &[response.to_string().to_variant()]);
+        self.deliver_result(request_id, &response.to_string());
+        self.inject_error("api".into(), error::INVALID_CODE, message.into());
+        self.awaiting_code = (retries_left > 0).then_some(retries_left);
+        let args = [
+            code.to_variant(),
+            error::INVALID_CODE.to_variant(),
+            message.to_variant(),
+            retries_left.max(0).to_variant(),
+        ];
+        self.emit("invalid_code", &args);
+    }
+
+    fn start_game(&mut self, request_id: i64) {
+        let game_id = format!("mock-game-{}", NEXT_GAME.fetch_add(1, Ordering::SeqCst));
+        let auth_body = r#"{"key":"mock"}"#;
+        let link = "wss://mock.invalid/sub";
+        let response = json!({
+            "code": 0,
+            "message": "0",
+            "data": {
+                "game_info": { "game_id": game_id },
+                "websocket_info": { "auth_body": auth_body, "wss_link": [link] },
+                "anchor_info": { "room_id": 0, "uname": "mock", "uface": "", "open_id": "mock-anchor" },
+            },
+        });
+        self.ws_auth_body = GString::from(auth_body);
+        self.ws_links = [GString::from(link)].into_iter().collect();
+        self.game_id = game_id.clone();
+        for transition in self.session.start(&game_id) {
+            self.emit_transition(transition);
+        }
+        if let Some(info) = StartInfo::parse(&response) {
+            session::SESSION_CACHE.store(self.app_id, &info, Instant::now());
+        }
+        let response_json = error::with_request_id(&response.to_string(), request_id);
+        self.emit("start_completed", &[response_json.to_variant()]);
+        self.deliver_result(request_id, &response_json);
+        if let Some(info) = StartInfo::parse(&response) {
+            let args = [
+                info.game_id.to_variant(),
+                self.ws_links.to_variant(),
+                info.auth_body.to_variant(),
+                json_to_variant(&info.anchor_info),
+            ];
+            self.emit("start_succeeded", &args);
+        }
+    }
+
+    /// 与 Blive 共用进程内的场次缓存
+    #[func]
+    fn get_cached_session(&self) -> Dictionary {
+        let Some((info, age)) =
+            session::SESSION_CACHE.get(self.app_id, self.session_cache_ttl_secs, Instant::now())
+        else {
+            return Dictionary::new();
+        };
+        json!({
+            "game_id": info.game_id,
+            "wss_links": info.wss_links,
+            "auth_body": direct::redact_auth_body(&info.auth_body),
+            "anchor_info": info.anchor_info,
+            "age_secs": age,
+        })
+        .as_object()
+        .map(json_to_dictionary)
+        .unwrap_or_default()
+    }
+
+    /// 恢复缓存的场次后立即连接并开始心跳
+    #[func]
+    fn reconnect_websocket_from_cache(&mut self) -> bool {
+        if self.reject_read_only("reconnect_websocket_from_cache") {
+            return false;
+        }
+        let cached =
+            session::SESSION_CACHE.get(self.app_id, self.session_cache_ttl_secs, Instant::now());
+        let Some((info, _)) = cached.filter(|(info, _)| !info.wss_links.is_empty()) else {
+            godot_warn!("BliveMock: 没有可用的场次缓存");
+            return false;
+        };
+        self.ws_auth_body = GString::from(info.auth_body.as_str());
+        self.ws_links = info
+            .wss_links
+            .iter()
+            .map(|link| GString::from(link.as_str()))
+            .collect();
+        self.game_id = info.game_id.clone();
+        for transition in self.session.start(&info.game_id) {
+            self.emit_transition(transition);
+        }
+        self.connect_mock(false);
+        self.start_heartbeat(GString::from(info.game_id.as_str()));
+        true
+    }
+
+    /// 与 Blive 相同的切换流程，立即完成；new_code 为空时模拟新场次开启失败
+    #[func]
+    fn switch_session(&mut self, new_code: GString) -> bool {
+        if self.reject_read_only("switch_session") {
+            return false;
+        }
+        let previous = self.game_id.clone();
+        self.emit_switch_progress("starting", &previous, "");
+        if new_code.is_empty() {
+            let error = "接口错误 7007: 身份码错误";
+            self.emit(
+                "session_switch_failed",
+                &[previous.to_variant(), error.to_variant()],
+            );
+            return false;
+        }
+        self.start(new_code);
+        let next = self.game_id.clone();
+        if !previous.is_empty() {
+            for game_id in self
+                .heartbeat_game_id
+                .iter_mut()
+                .chain(self.batch_game_ids.iter_mut().flatten())
+            {
+                if *game_id == previous {
+                    *game_id = next.clone();
+                }
+            }
+        }
+        if self.ws_connected {
+            self.emit_switch_progress("connecting", &previous, &next);
+        }
+        if !previous.is_empty() {
+            self.emit_switch_progress("ending_previous", &previous, &next);
+            let request_id = self.next_request_id();
+            self.end_game(GString::from(previous.as_str()), "ended", request_id);
+        }
+        self.emit_switch_progress("completed", &previous, &next);
+        true
+    }
+
+    #[func]
+    fn end(&mut self, game_id: GString) -> i64 {
+        if self.reject_read_only("end") {
+            return 0;
+        }
+        let request_id = self.next_request_id();
+        self.end_game(game_id, "ended", request_id)
+    }
+
+    /// 与 `end` 相同，`end_completed` 延迟到下一帧发出
+    #[func]
+    fn end_async(&mut self, game_id: GString) -> i64 {
+        if self.reject_read_only("end_async") {
+            return 0;
+        }
+        let request_id = self.next_request_id();
+        self.pending_ends.push((request_id, game_id));
+        request_id
+    }
+
+    #[func]
+    fn start_with_callback(&mut self, code: GString, callback: Callable) -> i64 {
+        self.with_callback(callback, |this| this.start(code))
+    }
+
+    #[func]
+    fn end_with_callback(&mut self, game_id: GString, callback: Callable) -> i64 {
+        self.with_callback(callback, |this| this.end_async(game_id))
+    }
+
+    #[func]
+    fn request_with_callback(
+        &mut self,
+        path: GString,
+        body_json: GString,
+        callback: Callable,
+    ) -> i64 {
+        self.with_callback(callback, |this| this.request(path, body_json))
+    }
+
+    /// 不发送请求，下一帧以 `set_request_response` 设置的响应（默认 code 0）发出 `request_completed`
+    #[func]
+    fn request(&mut self, path: GString, body_json: GString) -> i64 {
+        if self.reject_read_only("request") {
+            return 0;
+        }
+        let body = body_json.to_string();
+        if !body.trim().is_empty() && serde_json::from_str::<Value>(&body).is_err() {
+            godot_error!("BliveMock: 请求体不是合法的 JSON");
+            return 0;
+        }
+        let request_id = self.next_request_id();
+        // 与 Blive 共用限流规则；排队的请求不实际等待，仍在下一帧完成
+        self.api_limiter.configure(self.api_qps.max(0.0));
+        let max_wait = if self.api_rate_limit_queue {
+            MAX_QUEUE_WAIT_SECS
+        } else {
+            0.0
+        };
+        let wait_secs = self.api_limiter.reserve(max_wait);
+        if wait_secs != Some(0.0) {
+            let args = [
+                path.to_variant(),
+                wait_secs.is_some().to_variant(),
+                wait_secs.unwrap_or(0.0).to_variant(),
+            ];
+            self.emit("rate_limited", &args);
+        }
+        if wait_secs.is_none() {
+            let message = "超出本地限流，请求未发送";
+            self.inject_error("http".into(), 429, message.into());
+            let response = json!({
+                "request_id": request_id,
+                "code": -1,
+                "message": message,
+                "domain": "http",
+            });
+            self.emit(
+                "request_completed",
+                &[path.to_variant(), response.to_string().to_variant()],
+            );
+            self.deliver_result(request_id, &response.to_string());
+            return request_id;
+        }
+        self.pending_requests.push((request_id, path.to_string()));
+        request_id
+    }
+
+    /// 与 `request` 相同，不发送请求；query 不影响响应，只保持与 Blive 相同的参数
+    #[func]
+    fn request_get(&mut self, path: GString, query: Dictionary) -> i64 {
+        let _ = query;
+        self.request(path, GString::new())
+    }
+
+    #[func]
+    fn request_get_with_callback(
+        &mut self,
+        path: GString,
+        query: Dictionary,
+        callback: Callable,
+    ) -> i64 {
+        self.with_callback(callback, |this| this.request_get(path, query))
+    }
+
+    /// 只校验配置，网络相关的检查记为 skipped
+    #[func]
+    fn run_diagnostics(&mut self) {
+        let mut report = DiagnosticReport::default();
+        report.record("config", Ok("BliveMock 不需要配置".to_string()), 0);
+        for name in ["dns_api", "dns_ws", "tls", "clock_skew", "signed_request"] {
+            report.skip(name, "BliveMock 不访问网络");
+        }
+        self.emit(
+            "diagnostics_completed",
+            &[json_to_variant(&report.to_json())],
+        );
+    }
+
+    /// 设置 `request` 对某个接口路径返回的响应
+    #[func]
+    fn set_request_response(&mut self, path: GString, response_json: GString) {
+        self.request_responses
+            .insert(path.to_string(), response_json.to_string());
+    }
+
+    #[func]
+    fn get_last_http_response(&self, path: GString) -> Dictionary {
+        self.http_responses
+            .get(&path.to_string())
+            .and_then(|meta| meta.to_json().as_object().map(json_to_dictionary))
+            .unwrap_or_default()
+    }
+
+    /// 模拟一次开放平台响应的状态码和响应头，如 429 与 retry-after；headers 中只保留关键响应头
+    #[func]
+    fn inject_http_response(&mut self, path: GString, status: i64, headers: Dictionary) {
+        let headers: Vec<(String, String)> = headers
+            .iter_shared()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let meta = HttpMeta::new(
+            status.clamp(0, u16::MAX as i64) as u16,
+            headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str())),
+        );
+        self.record_http_response(&path.to_string(), meta);
+    }
+
+    /// 立即发出一次成功的 `heartbeat_completed`，之后的心跳可用 `inject_heartbeat_ok` 模拟
+    #[func]
+    fn start_heartbeat(&mut self, game_id: GString) -> i64 {
+        if self.reject_read_only("start_heartbeat") {
+            return 0;
+        }
+        let Some(game_id) = self.resolve_game_id(game_id) else {
+            return 0;
+        };
+        if self.heartbeat_game_id.is_some() {
+            return 0;
+        }
+        self.heartbeat_game_id = Some(game_id.clone());
+        self.heartbeat_request_id = self.next_request_id();
+        self.inject_heartbeat_ok(GString::from(game_id.as_str()));
+        self.heartbeat_request_id
+    }
+
+    /// 公开接口的版本，随函数、信号或导出属性的新增和改名递增
+    #[func]
+    fn get_api_version(&self) -> i64 {
+        api::API_VERSION
+    }
+
+    // 旧信号名的转发入口，由 `register_signal_aliases` 按参数个数连接，脚本不应直接调用
+    #[func]
+    fn _forward_signal_alias_0(&mut self, old: GString) {
+        self.forward_signal_alias(old, &[]);
+    }
+
+    #[func]
+    fn _forward_signal_alias_1(&mut self, a: Variant, old: GString) {
+        self.forward_signal_alias(old, &[a]);
+    }
+
+    #[func]
+    fn _forward_signal_alias_2(&mut self, a: Variant, b: Variant, old: GString) {
+        self.forward_signal_alias(old, &[a, b]);
+    }
+
+    #[func]
+    fn _forward_signal_alias_3(&mut self, a: Variant, b: Variant, c: Variant, old: GString) {
+        self.forward_signal_alias(old, &[a, b, c]);
+    }
+
+    #[func]
+    fn _forward_signal_alias_4(
+        &mut self,
+        a: Variant,
+        b: Variant,
+        c: Variant,
+        d: Variant,
+        old: GString,
+    ) {
+        self.forward_signal_alias(old, &[a, b, c, d]);
+    }
+
+    #[func]
+    fn get_ws_auth_body(&self) -> GString {
+        self.ws_auth_body.clone()
+    }
+
+    #[func]
+    fn get_game_id(&self) -> GString {
+        GString::from(self.game_id.as_str())
+    }
+
+    /// 与 Blive 相同的签名凭据，可由另一个 Blive 或 BliveMock 导入
+    #[func]
+    fn export_session_handle(&self, ttl_secs: i64) -> GString {
+        if self.game_id.is_empty() {
+            godot_error!("BliveMock: 没有进行中的场次");
+            return GString::new();
+        }
+        let handle = SessionHandle {
+            game_id: self.game_id.clone(),
+            ws_links: self
+                .ws_links
+                .as_slice()
+                .iter()
+                .map(|link| link.to_string())
+                .collect(),
+            auth_body: self.ws_auth_body.to_string(),
+            expires_at_ms: events::now_ms() + ttl_secs.clamp(1, 86400) * 1000,
+        };
+        GString::from(handle.encode(&self.access_key_secret.to_string()).as_str())
+    }
+
+    /// 校验凭据后立即发出 `ws_connected`
+    #[func]
+    fn import_session_handle(&mut self, handle: GString) -> bool {
+        if self.reject_read_only("import_session_handle") {
+            return false;
+        }
+        match SessionHandle::decode(
+            &handle.to_string(),
+            &self.access_key_secret.to_string(),
+            events::now_ms(),
+        ) {
+            Ok(handle) => {
+                self.game_id = handle.game_id;
+                self.ws_auth_body = GString::from(handle.auth_body.as_str());
+                self.ws_links = handle
+                    .ws_links
+                    .iter()
+                    .map(|link| GString::from(link.as_str()))
+                    .collect();
+                self.connect_mock(false);
+                true
+            }
+            Err(e) => {
+                godot_error!("BliveMock: {}", e);
+                false
+            }
+        }
+    }
+
+    #[func]
+    fn stop_heartbeat(&mut self) {
+        self.heartbeat_game_id = None;
+    }
+
+    #[func]
+    fn start_batch_heartbeat(&mut self, game_ids: Array<GString>) -> i64 {
+        if self.reject_read_only("start_batch_heartbeat") {
+            return 0;
+        }
+        if game_ids.is_empty() || self.batch_game_ids.is_some() {
+            return 0;
+        }
+        self.batch_game_ids = Some(game_ids.iter_shared().map(|id| id.to_string()).collect());
+        self.batch_request_id = self.next_request_id();
+        let response = json!({
+            "request_id": self.batch_request_id,
+            "code": 0,
+            "message": "0",
+            "data": { "failed_game_ids": [] },
+        });
+        self.emit(
+            "batch_heartbeat_completed",
+            &[response.to_string().to_variant()],
+        );
+        for game_id in game_ids.iter_shared() {
+            session::SESSION_CACHE.touch(&game_id.to_string(), Instant::now());
+            if let Some(transition) = self.session.heartbeat_ok(&game_id.to_string()) {
+                self.emit_transition(transition);
+            }
+        }
+        self.batch_request_id
+    }
+
+    /// 与 Blive 相同，暂停超过 45 秒时发出 `heartbeat_pause_too_long`
+    #[func]
+    fn pause_heartbeats(&mut self) {
+        if self.heartbeat_paused_at.is_none() {
+            self.heartbeat_paused_at = Some((self.elapsed, false));
+        }
+    }
+
+    #[func]
+    fn resume_heartbeats(&mut self) {
+        if self.heartbeat_paused_at.take().is_some() {
+            if let Some(game_id) = self.heartbeat_game_id.clone() {
+                self.inject_heartbeat_ok(GString::from(game_id.as_str()));
+            }
+        }
+    }
+
+    #[func]
+    fn is_heartbeats_paused(&self) -> bool {
+        self.heartbeat_paused_at.is_some()
+    }
+
+    #[func]
+    fn stop_batch_heartbeat(&mut self) {
+        self.batch_game_ids = None;
+    }
+
+    #[func]
+    fn update_batch_heartbeat_game_ids(&mut self, game_ids: Array<GString>) {
+        if let Some(batch) = self.batch_game_ids.as_mut() {
+            *batch = game_ids.iter_shared().map(|id| id.to_string()).collect();
+        }
+    }
+
+    /// 立即发出 `ws_connected`
+    #[func]
+    fn start_websocket(&mut self, _ws_url: GString, _auth_body: GString) {
+        if self.reject_read_only("start_websocket") {
+            return;
+        }
+        self.connect_mock(false);
+    }
+
+    /// 模拟连接总是选中第一个非空地址
+    #[func]
+    fn start_websocket_links(&mut self, links: PackedStringArray, _auth_body: GString) {
+        if self.reject_read_only("start_websocket_links") {
+            return;
+        }
+        let Some(link) = links
+            .as_slice()
+            .iter()
+            .find(|link| !link.is_empty())
+            .cloned()
+        else {
+            godot_error!("BliveMock: 没有可用的长连接地址");
+            return;
+        };
+        self.emit("ws_host_selected", &[link.to_variant(), 0.to_variant()]);
+        self.connect_mock(false);
+    }
+
+    /// 只校验证书格式，模拟连接不使用 TLS
+    #[func]
+    fn set_tls_ca_certificate(&mut self, pem: PackedByteArray) -> bool {
+        let pem = pem.to_vec();
+        if pem.is_empty() {
+            return true;
+        }
+        match tls::pem_certificates(&pem) {
+            Ok(_) => true,
+            Err(e) => {
+                godot_error!("BliveMock: 根证书无效: {}", e);
+                false
+            }
+        }
+    }
+
+    /// 立即发出 `ws_connected`，cookie 为空时视为游客连接
+    #[func]
+    fn start_room_websocket(&mut self, _room_id: i64) {
+        if self.reject_read_only("start_room_websocket") {
+            return;
+        }
+        let guest = self.cookie.is_empty();
+        self.connect_mock(guest);
+    }
+
+    #[func]
+    fn set_direct_credentials(
+        &mut self,
+        sessdata: GString,
+        buvid: GString,
+        uid: i64,
+        bili_jct: GString,
+    ) {
+        self.cookie = GString::from(
+            format!(
+                "SESSDATA={}; buvid3={}; DedeUserID={}; bili_jct={}",
+                sessdata, buvid, uid, bili_jct
+            )
+            .as_str(),
+        );
+    }
+
+    #[func]
+    fn is_guest_session(&self) -> bool {
+        self.guest
+    }
+
+    /// 发出 `qr_login_ready`，之后用 `inject_login_status` / `inject_login_succeeded` 推进流程
+    #[func]
+    fn start_qr_login(&mut self) {
+        self.emit(
+            "qr_login_ready",
+            &["https://mock.invalid/qrcode".to_variant()],
+        );
+    }
+
+    /// 连接中立即发出 `danmaku_sent`，未连接时发出 `danmaku_send_failed(text, "not_connected")`
+    #[func]
+    fn send_danmaku(&mut self, text: GString) -> bool {
+        let reason = if self.attachment.is_some() {
+            Some("read_only")
+        } else if !self.ws_connected {
+            Some("not_connected")
+        } else if text.is_empty() {
+            Some("empty_text")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            self.emit(
+                "danmaku_send_failed",
+                &[text.to_variant(), reason.to_variant()],
+            );
+            return false;
+        }
+        self.emit("danmaku_sent", &[text.to_variant()]);
+        true
+    }
+
+    /// 返回 `inject_room_info` 设置的信息，没有时返回只含 room_id 和直播状态的默认信息
+    #[func]
+    fn fetch_room_info(&mut self, room_id: i64) {
+        let live = self.live_state.unwrap_or(false);
+        let info = self
+            .room_info_cache
+            .entry(room_id)
+            .or_insert_with(|| {
+                json!({
+                    "room_id": room_id,
+                    "title": "mock",
+                    "live_status": live as i64,
+                    "is_live": live,
+                })
+            })
+            .clone();
+        self.emit(
+            "room_info_received",
+            &[room_id.to_variant(), json_to_variant(&info)],
+        );
+    }
+
+    /// 直播状态只由 `inject_live_status` 改变
+    #[func]
+    fn start_live_status_polling(&mut self, _room_id: i64, _interval_secs: f64) {}
+
+    #[func]
+    fn stop_live_status_polling(&mut self) {}
+
+    #[func]
+    fn is_live(&self) -> bool {
+        self.live_state.unwrap_or(false)
+    }
+
+    #[func]
+    fn get_cached_room_info(&self, room_id: i64) -> Dictionary {
+        self.room_info_cache
+            .get(&room_id)
+            .and_then(|info| info.as_object())
+            .map(json_to_dictionary)
+            .unwrap_or_default()
+    }
+
+    #[func]
+    fn get_clock_offset_ms(&self) -> i64 {
+        self.clock.offset_ms().unwrap_or(0)
+    }
+
+    #[func]
+    fn get_server_time_ms(&self) -> i64 {
+        self.clock.server_time_ms(events::now_ms())
+    }
+
+    /// 注入的心跳结果统计，往返时间始终为 0
+    #[func]
+    fn get_heartbeat_health(&self) -> Dictionary {
+        self.heartbeat_health
+            .to_json()
+            .as_object()
+            .map(json_to_dictionary)
+            .unwrap_or_default()
+    }
+
+    #[func]
+    fn reset_first_interactions(&mut self) {
+        self.first_interactions.reset_session();
+    }
+
+    #[func]
+    fn verify_audit_log(&self, path: GString) -> Dictionary {
+        let path = if path.is_empty() {
+            &self.audit_log_path
+        } else {
+            &path
+        };
+        let result = match audit::verify(&globalize_path(path)) {
+            Ok((entries, _)) => json!({ "ok": true, "entries": entries, "error": "" }),
+            Err(e) => json!({ "ok": false, "entries": 0, "error": e }),
+        };
+        result
+            .as_object()
+            .map(json_to_dictionary)
+            .unwrap_or_default()
+    }
+
+    #[func]
+    fn ack_event(&mut self, event_id: i64) -> bool {
+        self.acks.ack(event_id)
+    }
+
+    #[func]
+    fn get_unacked_events(&self) -> Array<Dictionary> {
+        self.acks
+            .unacked()
+            .iter()
+            .filter_map(|event| event.as_object())
+            .map(json_to_dictionary)
+            .collect()
+    }
+
+    #[func]
+    fn clear_unacked_events(&mut self) {
+        self.acks.clear();
+    }
+
+    /// 注册一个定时合成事件，到期后与平台事件一样经 `live_event` 发出，data 另有 synthetic = true、
+    /// schedule_id 和 fire_count；interval_secs > 0 时周期重复，repeat 为总次数（0 表示不限）。返回计划 ID
+    #[func]
+    fn schedule_event(
+        &mut self,
+        event_type: GString,
+        data: Dictionary,
+        delay_secs: f64,
+        interval_secs: f64,
+        repeat: i64,
+    ) -> i64 {
+        self.scheduler.schedule(
+            &event_type.to_string(),
+            dictionary_to_json(&data),
+            self.elapsed,
+            delay_secs,
+            interval_secs,
+            repeat,
+        )
+    }
+
+    /// delay_secs 秒后发出一条 announcement 事件，data 为 {message}
+    #[func]
+    fn schedule_announcement(&mut self, message: GString, delay_secs: f64) -> i64 {
+        self.scheduler.schedule(
+            events::EVENT_ANNOUNCEMENT,
+            json!({ "message": message.to_string() }),
+            self.elapsed,
+            delay_secs,
+            0.0,
+            1,
+        )
+    }
+
+    #[func]
+    fn cancel_scheduled_event(&mut self, schedule_id: i64) -> bool {
+        self.scheduler.cancel(schedule_id)
+    }
+
+    #[func]
+    fn clear_scheduled_events(&mut self) {
+        self.scheduler.clear();
+    }
+
+    /// 以只读方式附加到另一个 BliveMock 节点的会话：不建立连接，直接收到对方已解析的
+    /// live_event、ws_message_received、连接状态等信号。附加期间 start / end / 心跳 / 连接 /
+    /// 发送类方法会被忽略；对方释放后自动解除并发出 ws_disconnected
+    #[func]
+    fn attach_to_session(&mut self, primary: Gd<BliveMock>) -> bool {
+        if primary.instance_id() == self.to_gd().instance_id() {
+            godot_error!("BliveMock: 不能附加到自身的会话");
+            return false;
+        }
+        let (attachment, connected) = {
+            let primary = primary.bind();
+            (primary.shared.subscribe(), primary.ws_connected)
+        };
+        self.attachment = Some(attachment);
+        if connected && !std::mem::replace(&mut self.ws_connected, true) {
+            self.emit("ws_connected", &[]);
+        }
+        true
+    }
+
+    #[func]
+    fn detach_session(&mut self) {
+        if self.attachment.take().is_some() && std::mem::take(&mut self.ws_connected) {
+            self.emit("ws_disconnected", &[]);
+        }
+    }
+
+    #[func]
+    fn is_attached(&self) -> bool {
+        self.attachment.is_some()
+    }
+
+    /// 模拟连接每次断开记录一条 outcome 为 stopped 的尝试
+    #[func]
+    fn get_connection_history(&self) -> Array<Dictionary> {
+        self.connection_history
+            .as_ref()
+            .map(ConnectionHistory::to_json)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|attempt| attempt.as_object())
+            .map(json_to_dictionary)
+            .collect()
+    }
+
+    #[func]
+    fn clear_connection_history(&mut self) {
+        if let Some(history) = self.connection_history.as_mut() {
+            history.clear();
+        }
+    }
+
+    #[func]
+    fn set_subsystem_enabled(&mut self, name: GString, enabled: bool) -> bool {
+        let name = match subsystems::lookup(&name.to_string()) {
+            Ok(name) => name,
+            Err(e) => {
+                godot_error!("BliveMock: {}", e);
+                return false;
+            }
+        };
+        if !self.subsystems.set(name, enabled) || enabled {
+            return true;
+        }
+        match name {
+            subsystems::STATS => self.connection_history = None,
+            subsystems::RECORDER => self.stop_recording(),
+            subsystems::WEBHOOKS => self.clear_webhook(),
+            subsystems::COMBOS => self.combos = None,
+            subsystems::SUPER_CHAT_TIMERS => self.super_chats = None,
+            _ => {}
+        }
+        true
+    }
+
+    #[func]
+    fn is_subsystem_enabled(&self, name: GString) -> bool {
+        self.subsystems.is_enabled(&name.to_string())
+    }
+
+    #[func]
+    fn get_enabled_subsystems(&self) -> PackedStringArray {
+        self.subsystems
+            .enabled()
+            .into_iter()
+            .map(GString::from)
+            .collect()
+    }
+
+    /// 内存缓存命中时直接返回；否则下一帧依次读取磁盘缓存和 `set_asset_response` 设置的数据，
+    /// 都没有时发出 `asset_failed`
+    #[func]
+    fn get_asset(&mut self, url: GString) -> Option<Gd<ImageTexture>> {
+        let url = url.to_string();
+        if url.is_empty() {
+            return None;
+        }
+        if let Some(texture) = self.asset_textures.get(&url) {
+            return Some(texture);
+        }
+        if !self.pending_assets.contains(&url) {
+            self.pending_assets.push(url);
+        }
+        None
+    }
+
+    /// 设置 `get_asset` 对某个 url “下载”到的图片数据
+    #[func]
+    fn set_asset_response(&mut self, url: GString, data: PackedByteArray) {
+        self.asset_responses
+            .insert(url.to_string(), data.as_slice().to_vec());
+    }
+
+    #[func]
+    fn clear_asset_cache(&mut self) {
+        self.asset_textures.clear();
+        if let Some(disk) = self.asset_disk.1.as_mut() {
+            disk.clear();
+        }
+    }
+
+    #[func]
+    fn get_asset_cache_stats(&self) -> Dictionary {
+        let (disk_entries, disk_bytes) = match self.asset_disk.1.as_ref() {
+            Some(disk) => (disk.len() as i64, disk.total_size() as i64),
+            None => (0, 0),
+        };
+        let mut stats = Dictionary::new();
+        stats.set("memory_entries", self.asset_textures.len() as i64);
+        stats.set("memory_bytes", self.asset_textures.total_size() as i64);
+        stats.set("disk_entries", disk_entries);
+        stats.set("disk_bytes", disk_bytes);
+        stats
+    }
+
+    /// 尚未结束的定时事件，每项包含 id、event_type、data、remaining（距下次触发的秒数）、interval 和 fired
+    #[func]
+    fn get_scheduled_events(&self) -> Array<Dictionary> {
+        self.scheduler
+            .pending(self.elapsed)
+            .iter()
+            .filter_map(|event| event.as_object())
+            .map(json_to_dictionary)
+            .collect()
+    }
+
+    #[func]
+    fn get_spam_stats(&self) -> Dictionary {
+        self.spam
+            .stats()
+            .as_object()
+            .map(json_to_dictionary)
+            .unwrap_or_default()
+    }
+
+    /// 注入的消息总是按注入顺序发出
+    #[func]
+    fn is_global_order_relaxed(&self) -> bool {
+        false
+    }
+
+    /// 与 Blive 相同的压测阶段和报告，合成消息直接注入，不经过解包，decode_errors 始终为 0
+    #[func]
+    fn start_load_test(&mut self, messages_per_minute: i64, duration: f64) -> bool {
+        if self.load_test.is_some() {
+            return false;
+        }
+        self.load_test = Some(LoadTest::new(
+            messages_per_minute.clamp(1, 1_000_000) as u64,
+            duration.clamp(0.1, 3600.0),
+        ));
+        self.load_generation = (self.elapsed, 0);
+        true
+    }
+
+    #[func]
+    fn stop_load_test(&mut self) {
+        if let Some(test) = self.load_test.as_mut() {
+            test.stop();
+        }
+    }
+
+    #[func]
+    fn is_load_test_running(&self) -> bool {
+        self.load_test.is_some()
+    }
+
+    /// 不产生流量，计数始终为 0
+    #[func]
+    fn get_ws_stats(&self) -> Dictionary {
+        let mut stats = TrafficSnapshot::default().to_json();
+        stats["ws_message_rate"] = 0.0.into();
+        stats
+            .as_object()
+            .map(json_to_dictionary)
+            .unwrap_or_default()
+    }
+
+    #[func]
+    fn get_active_combos(&mut self) -> Array<Dictionary> {
+        let Some(combos) = self.combos.as_mut() else {
+            return Array::new();
+        };
+        combos.prune(self.elapsed);
+        combos
+            .active(self.elapsed)
+            .iter()
+            .filter_map(|combo| combo.as_object())
+            .map(json_to_dictionary)
+            .collect()
+    }
+
+    #[func]
+    fn cancel_qr_login(&mut self) {}
+
+    #[func]
+    fn make_qr_image(&self, text: GString, scale: i64) -> Option<Gd<Image>> {
+        let (size, pixels) = login::qr_pixels(&text.to_string(), scale.max(1) as usize).ok()?;
+        Image::create_from_data(
+            size as i32,
+            size as i32,
+            false,
+            Format::L8,
+            &PackedByteArray::from(pixels),
+        )
+    }
+
+    /// 与 Blive 相同的字段，后台任务始终为空
+    #[func]
+    fn health_check(&self) -> Dictionary {
+        let ws_state = if self.ws_connected {
+            "connected"
+        } else {
+            "disconnected"
+        };
+        let health = json!({
+            "runtime_alive": true,
+            "ws_state": ws_state,
+            "seconds_since_ws_heartbeat_reply": -1.0,
+            "seconds_since_api_heartbeat_ok": -1.0,
+            "queue_depth": 0,
+            "active_tasks": [],
+            "traffic": TrafficSnapshot::default().to_json(),
+            "global_order_relaxed": false,
+            "attached": self.attachment.is_some(),
+            "shared_attachments": self.shared.attachments(),
+        });
+        health
+            .as_object()
+            .map(json_to_dictionary)
+            .unwrap_or_default()
+    }
+
+    #[func]
+    fn stop_websocket(&mut self) {
+        self.guest = false;
+        if std::mem::take(&mut self.ws_connected) {
+            let enabled = self.subsystems.is_enabled(subsystems::STATS);
+            if let Some(history) = subsystems::lazy(&mut self.connection_history, enabled) {
+                history.push(ConnectionAttempt {
+                    host: "mock.invalid".to_string(),
+                    started_ms: self.connected_at_ms,
+                    outcome: "stopped",
+                    error_category: "",
+                    error: String::new(),
+                    duration_ms: events::now_ms() - self.connected_at_ms,
+                });
+            }
+            self.shared.publish("ws_disconnected", Vec::new);
+            self.emit("ws_disconnected", &[]);
+        }
+    }
+
+    /// 立即模拟连接成功，发出该连接的 ws_connected
+    #[func]
+    fn start_websocket_for(&mut self, conn_id: GString, url: GString, _auth_body: GString) -> bool {
+        if self.attachment.is_some() || conn_id.is_empty() || url.is_empty() {
+            return false;
+        }
+        if !self.keyed_connections.insert(conn_id.to_string()) {
+            return false;
+        }
+        self.inject_connection_event(conn_id, GString::from("ws_connected"), Array::new());
+        true
+    }
+
+    #[func]
+    fn stop_websocket_for(&mut self, conn_id: GString) -> bool {
+        if !self.keyed_connections.remove(&conn_id.to_string()) {
+            return false;
+        }
+        self.inject_connection_event(conn_id, GString::from("ws_disconnected"), Array::new());
+        true
+    }
+
+    #[func]
+    fn get_websocket_connections(&self) -> PackedStringArray {
+        self.keyed_connections.iter().map(GString::from).collect()
+    }
+
+    /// 连接存在时与 `get_ws_stats` 相同，计数始终为 0
+    #[func]
+    fn get_ws_stats_for(&self, conn_id: GString) -> Dictionary {
+        if !self.keyed_connections.contains(&conn_id.to_string()) {
+            return Dictionary::new();
+        }
+        self.get_ws_stats()
+    }
+
+    /// 不发送任何数据，连接存在时返回 true
+    #[func]
+    fn send_raw_packet_for(
+        &mut self,
+        conn_id: GString,
+        _operation: i64,
+        _body: PackedByteArray,
+    ) -> bool {
+        self.keyed_connections.contains(&conn_id.to_string())
+    }
+
+    /// 模拟 `start_websocket_for` 启动的连接发出的信号，例如
+    /// `inject_connection_event("room_b", "live_event", ["danmaku", {...}])`
+    #[func]
+    fn inject_connection_event(&mut self, conn_id: GString, name: GString, args: Array<Variant>) {
+        self.emit(
+            "ws_connection_event",
+            &[conn_id.to_variant(), name.to_variant(), args.to_variant()],
+        );
+    }
+
+    /// 不发送任何数据，连接中返回 true
+    #[func]
+    fn send_raw_packet(&mut self, _operation: i64, _body: PackedByteArray) -> bool {
+        self.attachment.is_none() && self.ws_connected
+    }
+
+    #[func]
+    fn send_ws_packet(&mut self, operation: i64, body: PackedByteArray) -> bool {
+        self.send_raw_packet(operation, body)
+    }
+
+    #[func]
+    fn set_protocol_options(&mut self, _options: Dictionary) {}
+
+    #[func]
+    fn reset_protocol_options(&mut self) {}
+
+    #[func]
+    fn set_degradation_options(&mut self, _options: Dictionary) {}
+
+    #[func]
+    fn is_degraded(&self) -> bool {
+        self.degraded
+    }
+
+    /// 模拟进入或退出降级模式，降级期间 `ws_message_received` 改为每秒一次的 `message_digest`
+    #[func]
+    fn inject_degradation(&mut self, degraded: bool, reason: GString) {
+        if std::mem::replace(&mut self.degraded, degraded) == degraded {
+            return;
+        }
+        if !degraded {
+            if let Some(counts) = self.message_digest.take_due(self.elapsed, 0.0) {
+                self.emit("message_digest", &[json_to_variant(&counts)]);
+            }
+        }
+        self.emit(
+            "degradation_changed",
+            &[degraded.to_variant(), reason.to_variant()],
+        );
+    }
+
+    #[func]
+    fn set_command_medal_requirement(
+        &mut self,
+        command: GString,
+        min_medal_level: i64,
+        require_room_medal: bool,
+    ) {
+        let requirement = self.interaction_gate.command_mut(&command.to_string());
+        requirement.min_medal_level = min_medal_level;
+        requirement.require_room_medal = require_room_medal;
+    }
+
+    #[func]
+    fn set_subsystem_medal_requirement(
+        &mut self,
+        subsystem: GString,
+        min_medal_level: i64,
+        require_room_medal: bool,
+    ) {
+        let requirement = self.interaction_gate.subsystem_mut(&subsystem.to_string());
+        requirement.min_medal_level = min_medal_level;
+        requirement.require_room_medal = require_room_medal;
+    }
+
+    #[func]
+    fn set_command_guard_requirement(&mut self, command: GString, guard_level: i64) {
+        self.interaction_gate
+            .command_mut(&command.to_string())
+            .guard_level = guard_level;
+    }
+
+    #[func]
+    fn set_subsystem_guard_requirement(&mut self, subsystem: GString, guard_level: i64) {
+        self.interaction_gate
+            .subsystem_mut(&subsystem.to_string())
+            .guard_level = guard_level;
+    }
+
+    #[func]
+    fn add_group_forward(&mut self, group: GString, method: GString, cmds: PackedStringArray) {
+        self.group_forwards.push(GroupForward {
+            group: group.to_string(),
+            method: method.to_string(),
+            cmds: cmds.as_slice().iter().map(|cmd| cmd.to_string()).collect(),
+        });
+    }
+
+    #[func]
+    fn clear_group_forwards(&mut self) {
+        self.group_forwards.clear();
+    }
+
+    #[func]
+    fn set_language_filter(&mut self, langs: PackedStringArray) {
+        self.language_filter = langs
+            .as_slice()
+            .iter()
+            .map(|lang| lang.to_string())
+            .collect();
+    }
+
+    #[func]
+    fn add_language_route(&mut self, lang: GString, group: GString, method: GString) {
+        self.language_routes.push(LanguageRoute {
+            lang: lang.to_string(),
+            group: group.to_string(),
+            method: method.to_string(),
+        });
+    }
+
+    #[func]
+    fn clear_language_routes(&mut self) {
+        self.language_routes.clear();
+    }
+
+    /// 不发起请求：匹配的事件按 Blive 的格式签名后留在队列中，用 `take_webhook_deliveries` 取出
+    #[func]
+    fn set_webhook(
+        &mut self,
+        url: GString,
+        secret: GString,
+        event_types: PackedStringArray,
+        max_attempts: i64,
+    ) {
+        if url.is_empty() {
+            self.webhook = None;
+            return;
+        }
+        if !self.subsystems.is_enabled(subsystems::WEBHOOKS) {
+            godot_error!("BliveMock: webhooks 子系统已关闭");
+            return;
+        }
+        let event_types = event_types
+            .as_slice()
+            .iter()
+            .map(|event_type| event_type.to_string())
+            .collect();
+        self.webhook = Some(Webhook::new(
+            &url.to_string(),
+            &secret.to_string(),
+            event_types,
+            max_attempts.clamp(1, 10) as u32,
+        ));
+    }
+
+    #[func]
+    fn clear_webhook(&mut self) {
+        self.webhook = None;
+        self.webhook_outbox.clear();
+    }
+
+    /// 与 Blive 相同地录制注入的消息，分段在主线程上同步压缩
+    #[func]
+    fn start_recording(&mut self, dir: GString) -> bool {
+        self.stop_recording();
+        if !self.subsystems.is_enabled(subsystems::RECORDER) {
+            godot_error!("BliveMock: recorder 子系统已关闭");
+            return false;
+        }
+        let options = RecorderOptions {
+            max_segment_bytes: (self.recording_max_segment_mb.max(0.0) * 1024.0 * 1024.0) as u64,
+            max_segment_ms: (self.recording_max_segment_minutes.max(0.0) * 60_000.0) as i64,
+            keep_sessions: self.recording_keep_sessions.max(0) as usize,
+        };
+        match SessionRecorder::start(&globalize_path(&dir), options, events::now_ms()) {
+            Ok(recorder) => {
+                self.recorder = Some(recorder);
+                true
+            }
+            Err(e) => {
+                self.emit("recording_failed", &[e.to_variant()]);
+                false
+            }
+        }
+    }
+
+    #[func]
+    fn stop_recording(&mut self) {
+        let Some(recorder) = self.recorder.take() else {
+            return;
+        };
+        let result = recorder.finish();
+        self.close_segment(result);
+    }
+
+    #[func]
+    fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    #[func]
+    fn get_recording_dir(&self) -> GString {
+        self.recorder
+            .as_ref()
+            .map(|recorder| GString::from(recorder.session_dir().to_string_lossy().as_ref()))
+            .unwrap_or_default()
+    }
+
+    /// 取出并清空待发送的投递，每项含 delivery_id、event_type、body、timestamp_ms 和 signature
+    #[func]
+    fn take_webhook_deliveries(&mut self) -> Array<Dictionary> {
+        self.webhook_outbox
+            .drain(..)
+            .filter_map(|delivery| {
+                json!({
+                    "delivery_id": delivery.id,
+                    "event_type": delivery.event_type,
+                    "body": delivery.body,
+                    "timestamp_ms": delivery.timestamp_ms,
+                    "signature": delivery.signature,
+                })
+                .as_object()
+                .map(json_to_dictionary)
+            })
+            .collect()
+    }
+
+    /// 模拟一次最终失败的 webhook 投递
+    #[func]
+    fn inject_webhook_failure(
+        &mut self,
+        delivery_id: GString,
+        event_type: GString,
+        error: GString,
+    ) {
+        self.emit(
+            "webhook_failed",
+            &[
+                delivery_id.to_variant(),
+                event_type.to_variant(),
+                error.to_variant(),
+            ],
+        );
+    }
+
+    /// 不发起请求：等待翻译的弹幕通过 `submit_translation` 给出译文，request_id 从 1 开始递增
+    #[func]
+    fn set_translation_endpoint(&mut self, url: GString, target_lang: GString) {
+        self.translation_enabled = !url.is_empty();
+        self.translation_callable = None;
+        self.translation =
+            TranslationPipeline::new(&target_lang.to_string(), self.translation_rate_per_sec);
+    }
+
+    #[func]
+    fn set_translation_callable(&mut self, callable: Callable, target_lang: GString) {
+        self.translation_enabled = true;
+        self.translation_callable = Some(callable);
+        self.translation =
+            TranslationPipeline::new(&target_lang.to_string(), self.translation_rate_per_sec);
+    }
+
+    #[func]
+    fn submit_translation(&mut self, request_id: i64, text: GString) {
+        let result = if text.is_empty() {
+            Err("翻译失败".to_string())
+        } else {
+            Ok(text.to_string())
+        };
+        let Some(data) = self.translation.finish(request_id, &result) else {
+            return;
+        };
+        match result {
+            Ok(translated) => {
+                let data = self.translation.translated(data, &translated);
+                self.emit("danmaku_translated", &[json_to_variant(&data)]);
+            }
+            Err(e) => {
+                self.emit(
+                    "translation_failed",
+                    &[json_to_variant(&data), e.to_variant()],
+                );
+            }
+        }
+    }
+
+    #[func]
+    fn clear_translation_cache(&mut self) {
+        self.translation.clear_cache();
+    }
+
+    #[func]
+    fn get_translation_stats(&self) -> Dictionary {
+        self.translation
+            .stats()
+            .as_object()
+            .map(json_to_dictionary)
+            .unwrap_or_default()
+    }
+
+    #[func]
+    fn clear_interaction_requirements(&mut self) {
+        self.interaction_gate.clear();
+    }
+
+    #[func]
+    fn check_subsystem_access(&mut self, subsystem: GString, data_json: GString) -> bool {
+        let data: Value = serde_json::from_str(&data_json.to_string()).unwrap_or_default();
+        let viewer = Viewer::from_data(&data);
+        match self
+            .interaction_gate
+            .check_subsystem(&subsystem.to_string(), &viewer)
+        {
+            Ok(()) => true,
+            Err(reason) => {
+                self.emit(
+                    "interaction_rejected",
+                    &[viewer.open_id.to_variant(), reason.to_variant()],
+                );
+                false
+            }
+        }
+    }
+
+    /// 读取 path 处的模拟脚本（语法见 `SimulationScript`）并从当前帧开始按时间注入消息，
+    /// 替换正在播放的脚本；looped 为 true 时播完后从头重播。脚本有误时返回 false
+    #[func]
+    fn play_simulation(&mut self, path: GString, looped: bool) -> bool {
+        let path = globalize_path(&path);
+        match std::fs::read_to_string(&path) {
+            Ok(source) => self.play_simulation_source(&source, looped),
+            Err(e) => {
+                godot_error!("BliveMock: 无法读取模拟脚本 {}: {}", path.display(), e);
+                false
+            }
+        }
+    }
+
+    /// 与 `play_simulation` 相同，脚本内容直接以字符串给出
+    #[func]
+    fn play_simulation_script(&mut self, script: GString, looped: bool) -> bool {
+        self.play_simulation_source(&script.to_string(), looped)
+    }
+
+    #[func]
+    fn stop_simulation(&mut self) {
+        self.simulation = None;
+    }
+
+    #[func]
+    fn is_simulation_playing(&self) -> bool {
+        self.simulation.is_some()
+    }
+
+    /// 注入一条原始消息（开放平台或直连模式格式均可），按 Blive 收到长连接消息的流程发出信号
+    #[func]
+    fn inject_message(&mut self, cmd: GString, data: Dictionary) {
+        let message = json!({ "cmd": cmd.to_string(), "data": dictionary_to_json(&data) });
+        self.dispatch_message(message);
+    }
+
+    /// 注入一条原始消息 JSON，可用于回放录制的直连模式消息（如 DANMU_MSG 的 info 数组）
+    #[func]
+    fn inject_message_json(&mut self, message_json: GString) {
+        match serde_json::from_str(&message_json.to_string()) {
+            Ok(message) => self.dispatch_message(message),
+            Err(e) => godot_error!("BliveMock: 无效的消息 JSON: {}", e),
+        }
+    }
+
+    #[func]
+    fn inject_danmaku(&mut self, uname: GString, msg: GString) {
+        self.dispatch_message(danmaku_message(&uname.to_string(), &msg.to_string()));
+    }
+
+    /// price 为单个礼物价格（千分之一元），0 表示免费礼物
+    #[func]
+    fn inject_gift(&mut self, uname: GString, gift_name: GString, gift_num: i64, price: i64) {
+        self.dispatch_message(gift_message(
+            &uname.to_string(),
+            &gift_name.to_string(),
+            gift_num,
+            price,
+        ));
+    }
+
+    /// 注入一个盲盒礼物：box_price 为盲盒单价，revealed_price 为爆出礼物单价（均为千分之一元）
+    #[func]
+    fn inject_blind_box(
+        &mut self,
+        uname: GString,
+        box_name: GString,
+        box_price: i64,
+        revealed_name: GString,
+        revealed_price: i64,
+    ) {
+        self.dispatch_message(blind_box_message(
+            &uname.to_string(),
+            &box_name.to_string(),
+            box_price,
+            &revealed_name.to_string(),
+            revealed_price,
+        ));
+    }
+
+    /// rmb 为 SC 金额（元）
+    #[func]
+    fn inject_super_chat(&mut self, uname: GString, message: GString, rmb: i64) {
+        self.dispatch_message(super_chat_message(
+            &uname.to_string(),
+            &message.to_string(),
+            rmb,
+        ));
+    }
+
+    /// guard_level：1 总督、2 提督、3 舰长
+    #[func]
+    fn inject_guard(&mut self, uname: GString, guard_level: i64) {
+        self.dispatch_message(guard_message(&uname.to_string(), guard_level));
+    }
+
+    #[func]
+    fn inject_like(&mut self, uname: GString, like_count: i64) {
+        self.dispatch_message(like_message(&uname.to_string(), like_count));
+    }
+
+    #[func]
+    fn inject_follow(&mut self, uname: GString) {
+        self.dispatch_message(interact_message(&uname.to_string(), 2));
+    }
+
+    #[func]
+    fn inject_share(&mut self, uname: GString) {
+        self.dispatch_message(interact_message(&uname.to_string(), 3));
+    }
+
+    /// 模拟服务端删除醒目留言
+    #[func]
+    fn inject_super_chat_deleted(&mut self, message_id: i64) {
+        self.dispatch_message(open_platform_message(
+            "LIVE_OPEN_PLATFORM_SUPER_CHAT_DEL",
+            json!({ "message_ids": [message_id] }),
+        ));
+    }
+
+    /// 模拟开播 / 下播消息，下播时同样遵循 `auto_end_on_stream_end`
+    #[func]
+    fn inject_live_status(&mut self, live: bool) {
+        let cmd = if live {
+            "LIVE_OPEN_PLATFORM_LIVE_START"
+        } else {
+            "LIVE_OPEN_PLATFORM_LIVE_END"
+        };
+        self.dispatch_message(open_platform_message(cmd, json!({})));
+    }
+
+    #[func]
+    fn inject_watched_count(&mut self, count: i64) {
+        self.dispatch_message(json!({ "cmd": "WATCHED_CHANGE", "data": { "num": count } }));
+    }
+
+    #[func]
+    fn inject_online_count(&mut self, count: i64) {
+        self.dispatch_message(
+            json!({ "cmd": "ONLINE_RANK_COUNT", "data": { "online_count": count } }),
+        );
+    }
+
+    /// 模拟一次长连接心跳回复
+    #[func]
+    fn inject_popularity(&mut self, value: i64) {
+        self.emit("popularity_updated", &[value.to_variant()]);
+    }
+
+    #[func]
+    fn inject_sequence_gap(&mut self, expected: i64, got: i64) {
+        self.emit(
+            "ws_sequence_gap",
+            &[expected.to_variant(), got.to_variant()],
+        );
+    }
+
+    #[func]
+    fn inject_connected(&mut self) {
+        self.connect_mock(self.guest);
+    }
+
+    /// 模拟连接意外断开
+    #[func]
+    fn inject_disconnected(&mut self) {
+        self.stop_websocket();
+    }
+
+    /// 模拟收到未处理操作码的包
+    #[func]
+    fn inject_raw_packet(&mut self, operation: i64, body: PackedByteArray) {
+        self.emit(
+            "ws_raw_packet",
+            &[operation.to_variant(), body.to_variant()],
+        );
+    }
+
+    /// 模拟心跳回复超时：发出 `ws_timeout`，断开后重新连接
+    #[func]
+    fn inject_ws_timeout(&mut self) {
+        if !self.ws_connected {
+            return;
+        }
+        let guest = self.guest;
+        self.emit("ws_timeout", &[self.ws_reply_timeout_secs.to_variant()]);
+        self.stop_websocket();
+        self.connect_mock(guest);
+    }
+
+    /// 模拟一次分类错误，domain 为 http / signature / websocket / protocol / api / timeout
+    #[func]
+    fn inject_error(&mut self, domain: GString, code: i64, message: GString) {
+        self.emit(
+            "error_occurred",
+            &[domain.to_variant(), code.to_variant(), message.to_variant()],
+        );
+    }
+
+    /// 模拟一条因超出大小限制被丢弃的消息
+    #[func]
+    fn inject_protocol_error(&mut self, kind: GString, detail: GString) {
+        self.emit("protocol_error", &[kind.to_variant(), detail.to_variant()]);
+    }
+
+    #[func]
+    fn inject_ws_error(&mut self, error_msg: GString) {
+        self.emit("ws_error", &[error_msg.to_variant()]);
+    }
+
+    /// 模拟一次成功的项目心跳，game_id 为空时使用当前场次
+    #[func]
+    fn inject_heartbeat_ok(&mut self, game_id: GString) {
+        let Some(game_id) = self.resolve_game_id(game_id) else {
+            return;
+        };
+        let response = json!({
+            "request_id": self.heartbeat_request_id,
+            "code": 0,
+            "message": "0",
+            "data": {},
+        });
+        self.heartbeat_health.record(true, 0, "", 1);
+        self.emit("heartbeat_completed", &[response.to_string().to_variant()]);
+        session::SESSION_CACHE.touch(&game_id, Instant::now());
+        if let Some(transition) = self.session.heartbeat_ok(&game_id) {
+            self.emit_transition(transition);
+        }
+    }
+
+    /// 模拟一次失败的项目心跳，连续失败达到 `heartbeat_failure_threshold` 时发出 `heartbeat_degraded`
+    #[func]
+    fn inject_heartbeat_failed(&mut self, code: i64, message: GString) {
+        let response = json!({
+            "request_id": self.heartbeat_request_id,
+            "code": code,
+            "message": message.to_string(),
+        });
+        self.emit("heartbeat_completed", &[response.to_string().to_variant()]);
+        let error = format!("code {}: {}", code, message);
+        let threshold = self.heartbeat_failure_threshold.max(1) as u32;
+        if self.heartbeat_health.record(false, 0, &error, threshold) {
+            let failures = self.heartbeat_health.consecutive_failures() as i64;
+            self.emit(
+                "heartbeat_degraded",
+                &[failures.to_variant(), error.to_variant()],
+            );
+        }
+    }
+
+    /// 模拟长时间没有成功的项目心跳
+    #[func]
+    fn inject_session_expiring(&mut self) {
+        if let Some(transition) = self.session.tick(f64::INFINITY) {
+            self.emit_transition(transition);
+        }
+    }
+
+    /// status：waiting / scanned / expired
+    #[func]
+    fn inject_login_status(&mut self, status: GString) {
+        self.emit("qr_login_status", &[status.to_variant()]);
+    }
+
+    #[func]
+    fn inject_login_succeeded(&mut self, cookie: GString) {
+        self.cookie = cookie.clone();
+        self.emit("login_succeeded", &[cookie.to_variant()]);
+    }
+
+    #[func]
+    fn inject_login_failed(&mut self, reason: GString) {
+        self.emit("login_failed", &[reason.to_variant()]);
+    }
+
+    /// 设置 `fetch_room_info` / `get_cached_room_info` 返回的房间信息
+    #[func]
+    fn inject_room_info(&mut self, room_id: i64, info: Dictionary) {
+        self.room_info_cache
+            .insert(room_id, dictionary_to_json(&info));
+    }
+
+    #[func]
+    fn inject_room_info_failed(&mut self, room_id: i64, error_msg: GString) {
+        self.emit(
+            "room_info_failed",
+            &[room_id.to_variant(), error_msg.to_variant()],
+        );
+    }
+}
+
+impl BliveMock {
+    fn connect_mock(&mut self, guest: bool) {
+        self.guest = guest;
+        if !std::mem::replace(&mut self.ws_connected, true) {
+            self.seq = 0;
+            self.connected_at_ms = events::now_ms();
+            self.shared.publish("ws_connected", Vec::new);
+            self.emit("ws_connected", &[]);
+        }
+    }
+
+    fn resolve_game_id(&self, game_id: GString) -> Option<String> {
+        if !game_id.is_empty() {
+            return Some(game_id.to_string());
+        }
+        if self.game_id.is_empty() {
+            godot_error!("BliveMock: 没有进行中的场次");
+            return None;
+        }
+        Some(self.game_id.clone())
+    }
+
+    /// 返回 request_id，未指定场次时返回 0
+    fn end_game(&mut self, game_id: GString, reason: &str, request_id: i64) -> i64 {
+        let Some(game_id) = self.resolve_game_id(game_id) else {
+            return 0;
+        };
+        if self.heartbeat_game_id.as_ref() == Some(&game_id) {
+            self.heartbeat_game_id = None;
+        }
+        if let Some(batch) = self.batch_game_ids.as_mut() {
+            batch.retain(|id| *id != game_id);
+        }
+        if self.game_id == game_id {
+            self.game_id.clear();
+        }
+        let response = json!({ "request_id": request_id, "code": 0, "message": "0", "data": {} });
+        self.emit("end_completed", &[response.to_string().to_variant()]);
+        self.deliver_result(request_id, &response.to_string());
+        session::SESSION_CACHE.clear(&game_id);
+        if let Some(transition) = self.session.end(&game_id, reason) {
+            self.emit_transition(transition);
+        }
+        request_id
+    }
+
+    fn next_request_id(&mut self) -> i64 {
+        self.last_request_id += 1;
+        if let Some(callback) = self.next_callback.take() {
+            self.result_callbacks.insert(self.last_request_id, callback);
+        }
+        self.last_request_id
+    }
+
+    fn check_heartbeat_pause(&mut self) {
+        let Some((paused_at, warned)) = self.heartbeat_paused_at.as_mut() else {
+            return;
+        };
+        let paused_secs = self.elapsed - *paused_at;
+        if *warned || paused_secs < session::EXPIRING_AFTER_SECS {
+            return;
+        }
+        *warned = true;
+        self.emit("heartbeat_pause_too_long", &[paused_secs.to_variant()]);
+    }
+
+    fn with_callback(&mut self, callback: Callable, call: impl FnOnce(&mut Self) -> i64) -> i64 {
+        self.next_callback = Some(callback);
+        let request_id = call(self);
+        if let Some(callback) = self.next_callback.take() {
+            let response = json!({ "code": -1, "message": "请求未发送" });
+            callback.call_deferred(&[json_to_variant(&response)]);
+        }
+        request_id
+    }
+
+    fn deliver_result(&mut self, request_id: i64, response: &str) {
+        let Some(callback) = self.result_callbacks.remove(&request_id) else {
+            return;
+        };
+        let response = serde_json::from_str(response)
+            .unwrap_or_else(|_| json!({ "code": -1, "message": response }));
+        callback.call_deferred(&[json_to_variant(&response)]);
+    }
+
+    fn emit_switch_progress(&mut self, stage: &str, previous: &str, next: &str) {
+        self.emit(
+            "session_switch_progress",
+            &[stage.to_variant(), previous.to_variant(), next.to_variant()],
+        );
+    }
+
+    fn emit_transition(&mut self, transition: Transition) {
+        let (signal, args) = match transition {
+            Transition::Started(game_id) => ("session_started", vec![game_id.to_variant()]),
+            Transition::HeartbeatOk(game_id) => {
+                ("session_heartbeat_ok", vec![game_id.to_variant()])
+            }
+            Transition::Expiring(game_id) => ("session_expiring", vec![game_id.to_variant()]),
+            Transition::Ended(game_id, reason) => (
+                "session_ended",
+                vec![game_id.to_variant(), reason.to_variant()],
+            ),
+        };
+        self.emit(signal, &args);
+    }
+
+    fn update_live_state(&mut self, live: bool) {
+        if self.live_state.replace(live) == Some(live) {
+            return;
+        }
+        let signal = if live {
+            "stream_went_live"
+        } else {
+            "stream_went_offline"
+        };
+        self.emit(signal, &[]);
+    }
+
+    /// 与 Blive 处理长连接消息的顺序一致：门槛检查、直播状态、观众人数、原始消息、统一事件
+    fn dispatch_message(&mut self, mut message: Value) {
+        let cmd = message["cmd"]
+            .as_str()
+            .and_then(|cmd| cmd.split(':').next())
+            .unwrap_or("UNKNOWN")
+            .to_string();
+        if cmd == "LIVE_OPEN_PLATFORM_DM" {
+            let data = &message["data"];
+            let viewer = Viewer::from_data(data);
+            let msg = data["msg"].as_str().unwrap_or_default();
+            if let Err(reason) = self.interaction_gate.check_danmaku(msg, &viewer) {
+                self.emit(
+                    "interaction_rejected",
+                    &[viewer.open_id.to_variant(), reason.to_variant()],
+                );
+                return;
+            }
+        }
+        if self.guest {
+            if let Some(object) = message.as_object_mut() {
+                object.insert("guest".to_string(), true.into());
+            }
+        }
+
+        if let Some(message_ids) = events::super_chat_deleted(&cmd, &message) {
+            let removed = match self.super_chats.as_mut() {
+                Some(super_chats) => super_chats.remove(&message_ids),
+                None => Vec::new(),
+            };
+            for message_id in removed {
+                self.emit("super_chat_deleted", &[message_id.to_variant()]);
+            }
+        }
+        let interaction = match events::interaction(&cmd, &message) {
+            Some(Interaction::Followed(user_id, uname)) => {
+                Some(("viewer_followed", user_id, uname))
+            }
+            Some(Interaction::Shared(user_id, uname)) => Some(("viewer_shared", user_id, uname)),
+            None => None,
+        };
+        if let Some((signal, user_id, uname)) = interaction {
+            self.emit(signal, &[user_id.to_variant(), uname.to_variant()]);
+        }
+        if let Some(live) = events::live_status(&cmd) {
+            self.update_live_state(live);
+            if !live && self.auto_end_on_stream_end {
+                self.close_session("stream_ended");
+            }
+        }
+        let audience = match events::audience(&cmd, &message) {
+            Some(Audience::Watched(count)) => Some(("watched_count_updated", count.to_variant())),
+            Some(Audience::OnlineCount(count)) => {
+                Some(("online_count_updated", count.to_variant()))
+            }
+            Some(Audience::OnlineRank(list)) => {
+                Some(("online_rank_updated", json_to_variant(&list.into())))
+            }
+            None => None,
+        };
+        if let Some((signal, value)) = audience {
+            self.shared
+                .publish(signal, || vec![variant_to_json(&value)]);
+            self.emit(signal, &[value]);
+        }
+        if self.degraded {
+            self.message_digest.add(&cmd);
+        } else {
+            let text = message.to_string();
+            self.shared.publish("ws_message_received", || {
+                vec![cmd.clone().into(), text.clone().into()]
+            });
+            self.emit(
+                "ws_message_received",
+                &[cmd.to_variant(), text.to_variant()],
+            );
+        }
+        self.record_message(&cmd, &message.to_string());
+        self.forward_to_groups(&cmd, &message);
+        self.seq += 1;
+        if let Some((event_type, mut data)) = events::normalize(&cmd, &message) {
+            let synthetic = load_test::is_synthetic(&data);
+            if let Some(test) = self.load_test.as_mut().filter(|_| synthetic) {
+                test.observe_dispatched();
+            }
+            data["seq"] = self.seq.into();
+            data["received_ticks_msec"] = Time::singleton().get_ticks_msec().into();
+            if event_type == events::EVENT_GIFT {
+                self.gift_catalog.enrich(&mut data);
+            }
+            if audit::is_paid_event(event_type, &data) {
+                self.audit_paid_event(event_type, &data);
+            }
+            if event_type == events::EVENT_DANMAKU && self.detect_chat_consensus {
+                self.consensus.observe(
+                    data["user_id"].as_str().unwrap_or_default(),
+                    data["message"].as_str().unwrap_or_default(),
+                    self.elapsed,
+                );
+            }
+            if event_type == events::EVENT_DANMAKU
+                && self.collapse_repeated_danmaku
+                && self
+                    .spam
+                    .observe(&data, self.elapsed, self.collapse_window_secs)
+            {
+                return;
+            }
+            if event_type == events::EVENT_DANMAKU && self.detect_language {
+                let lang = language::detect(data["message"].as_str().unwrap_or_default());
+                data["lang"] = lang.into();
+                if !self.language_filter.is_empty()
+                    && !self.language_filter.iter().any(|allowed| allowed == lang)
+                {
+                    return;
+                }
+                self.route_language(lang, &data);
+            }
+            if event_type == events::EVENT_DANMAKU && self.translation_enabled {
+                self.request_translation(&data);
+            }
+            if data["timestamp"].as_i64().unwrap_or(0) > 0 {
+                self.clock
+                    .add_sample(data["latency_ms"].as_i64().unwrap_or(0));
+            }
+            if !synthetic {
+                self.mark_first_interaction(event_type, &mut data);
+            }
+            self.track_timed_events(event_type, &data);
+            if self.reliable_delivery
+                && self
+                    .reliable_event_types
+                    .as_slice()
+                    .iter()
+                    .any(|reliable| reliable.to_string() == event_type)
+            {
+                data = self.acks.track(event_type, data, self.elapsed);
+            }
+            if let Some(webhook) = self
+                .webhook
+                .as_mut()
+                .filter(|w| !synthetic && w.accepts(event_type))
+            {
+                let delivery = webhook.delivery(event_type, &data, events::now_ms());
+                self.webhook_outbox.push(delivery);
+            }
+            self.shared
+                .publish("live_event", || vec![event_type.into(), data.clone()]);
+            self.emit(
+                "live_event",
+                &[event_type.to_variant(), json_to_variant(&data)],
+            );
+            if data["blind_box"].as_bool() == Some(true) {
+                self.emit("blind_box_opened", &[json_to_variant(&data)]);
+            }
+        }
+    }
+
+    /// Callable 翻译器与 Blive 相同；设置了翻译接口时不发请求，等待 `submit_translation`
+    fn request_translation(&mut self, data: &Value) {
+        match self.translation.prepare(data, self.elapsed) {
+            Prepared::Request {
+                request_id,
+                text,
+                source,
+                target,
+            } => {
+                let Some(callable) = self.translation_callable.clone() else {
+                    return;
+                };
+                let result = callable.call(&[
+                    request_id.to_variant(),
+                    text.to_variant(),
+                    source.to_variant(),
+                    target.to_variant(),
+                ]);
+                if let Ok(translated) = result.try_to::<GString>() {
+                    if !translated.is_empty() {
+                        self.submit_translation(request_id, translated);
+                    }
+                }
+            }
+            Prepared::Cached(translated) => {
+                let data = self.translation.translated(data.clone(), &translated);
+                self.emit("danmaku_translated", &[json_to_variant(&data)]);
+            }
+            Prepared::Skip | Prepared::RateLimited => {}
+        }
+    }
+
+    fn play_simulation_source(&mut self, source: &str, looped: bool) -> bool {
+        match SimulationScript::parse(source).and_then(|script| script.expand()) {
+            Ok(timeline) => {
+                self.simulation = Some(SimulationPlayback::new(timeline, self.elapsed, looped));
+                self.play_simulation_step();
+                true
+            }
+            Err(e) => {
+                godot_error!("BliveMock: 模拟脚本有误: {}", e);
+                false
+            }
+        }
+    }
+
+    fn play_simulation_step(&mut self) {
+        let Some(playback) = self.simulation.as_mut() else {
+            return;
+        };
+        let actions = playback.due(self.elapsed);
+        let finished = playback.is_finished();
+        if finished {
+            self.simulation = None;
+        }
+        for action in actions {
+            let message = match action {
+                SimAction::Danmaku { uname, msg } => danmaku_message(&uname, &msg),
+                SimAction::Gift {
+                    uname,
+                    gift_name,
+                    gift_num,
+                    price,
+                } => gift_message(&uname, &gift_name, gift_num, price),
+                SimAction::SuperChat {
+                    uname,
+                    rmb,
+                    message,
+                } => super_chat_message(&uname, &message, rmb),
+                SimAction::Guard { uname, guard_level } => guard_message(&uname, guard_level),
+                SimAction::Like { uname, like_count } => like_message(&uname, like_count),
+                SimAction::Follow { uname } => interact_message(&uname, 2),
+                SimAction::Share { uname } => interact_message(&uname, 3),
+                SimAction::LiveStatus(live) => open_platform_message(
+                    if live {
+                        "LIVE_OPEN_PLATFORM_LIVE_START"
+                    } else {
+                        "LIVE_OPEN_PLATFORM_LIVE_END"
+                    },
+                    json!({}),
+                ),
+                SimAction::WatchedCount(count) => {
+                    json!({ "cmd": "WATCHED_CHANGE", "data": { "num": count } })
+                }
+                SimAction::OnlineCount(count) => {
+                    json!({ "cmd": "ONLINE_RANK_COUNT", "data": { "online_count": count } })
+                }
+                SimAction::Raw(message_json) => match serde_json::from_str(&message_json) {
+                    Ok(message) => message,
+                    Err(_) => continue,
+                },
+            };
+            self.dispatch_message(message);
+        }
+        if finished {
+            self.emit("simulation_finished", &[]);
+        }
+    }
+
+    fn tick_load_test(&mut self, delta: f64) {
+        let Some(test) = self.load_test.as_mut() else {
+            return;
+        };
+        let (step, done) = test.tick(delta, true);
+        let (rate, duration) = (test.messages_per_minute, test.duration);
+        let generating = test.is_generating();
+        if step == Some(Step::StartGenerator) {
+            self.load_generation = (self.elapsed, 0);
+        }
+        if generating || step == Some(Step::StopGenerator) {
+            let (started, generated) = self.load_generation;
+            let elapsed = (self.elapsed - started).min(duration);
+            let due = load_test::due_count(rate, elapsed);
+            for index in generated..due {
+                self.dispatch_message(load_test::synthetic_message(index));
+            }
+            self.load_generation.1 = due.max(generated);
+        }
+        if done {
+            if let Some(test) = self.load_test.take() {
+                let report = test.report(self.load_generation.1, 0);
+                self.emit("load_test_completed", &[json_to_variant(&report)]);
+            }
+        }
+    }
+
+    fn load_pending_assets(&mut self) {
+        if self.pending_assets.is_empty() {
+            return;
+        }
+        let dir = (!self.asset_cache_dir.is_empty()).then(|| globalize_path(&self.asset_cache_dir));
+        asset_cache::switch_dir(&mut self.asset_disk, dir);
+        let max_disk_bytes = (self.asset_disk_cache_mb.max(0.0) * 1024.0 * 1024.0) as u64;
+        let capacity = (self.asset_memory_cache_mb.max(0.0) * 1024.0 * 1024.0) as usize;
+        for url in std::mem::take(&mut self.pending_assets) {
+            let cached = self.asset_disk.1.as_mut().and_then(|disk| disk.read(&url));
+            let result = match (cached, self.asset_responses.get(&url)) {
+                (Some(bytes), _) => Ok(bytes),
+                (None, Some(bytes)) => {
+                    if let Some(disk) = self.asset_disk.1.as_mut() {
+                        if let Err(e) = disk.write(&url, bytes, max_disk_bytes) {
+                            godot_warn!("{}", e);
+                        }
+                    }
+                    Ok(bytes.clone())
+                }
+                (None, None) => {
+                    Err("BliveMock 不访问网络，且没有用 set_asset_response 设置该资源".to_string())
+                }
+            };
+            match result.and_then(|bytes| asset_cache::decode_texture(&bytes)) {
+                Ok((texture, size)) => {
+                    self.asset_textures
+                        .insert(&url, texture.clone(), size, capacity);
+                    self.emit("asset_loaded", &[url.to_variant(), texture.to_variant()]);
+                }
+                Err(e) => self.emit("asset_failed", &[url.to_variant(), e.to_variant()]),
+            }
+        }
+    }
+
+    fn record_http_response(&mut self, path: &str, meta: HttpMeta) {
+        let headers = json_to_variant(&meta.headers_json());
+        self.emit(
+            "http_response_received",
+            &[
+                path.to_variant(),
+                (meta.status as i64).to_variant(),
+                headers,
+            ],
+        );
+        self.http_responses.insert(path.to_string(), meta);
+    }
+
+    fn record_message(&mut self, cmd: &str, message_json: &str) {
+        let Some(recorder) = self.recorder.as_mut() else {
+            return;
+        };
+        match recorder.record(cmd, message_json, events::now_ms()) {
+            Ok(Some(finished)) => self.close_segment(Ok(finished)),
+            Ok(None) => {}
+            Err(e) => {
+                self.recorder = None;
+                self.close_segment(Err(e));
+            }
+        }
+    }
+
+    fn close_segment(&mut self, result: Result<std::path::PathBuf, String>) {
+        let result = match result {
+            Ok(path) if self.recording_compress => recorder::compress_segment(&path),
+            other => other,
+        };
+        match result {
+            Ok(path) => {
+                let path = path.to_string_lossy().into_owned();
+                self.emit("recording_segment_closed", &[path.to_variant()]);
+            }
+            Err(e) => {
+                self.emit("recording_failed", &[e.to_variant()]);
+            }
+        }
+    }
+
+    fn mark_first_interaction(&mut self, event_type: &str, data: &mut Value) {
+        let Some(kind) = first_seen::kind_of(event_type) else {
+            return;
+        };
+        let Some(open_id) = data["user_id"].as_str().map(str::to_string) else {
+            return;
+        };
+        if self.first_interaction_store_opened != self.first_interaction_store_path {
+            self.first_interaction_store_opened = self.first_interaction_store_path.clone();
+            let path = (!self.first_interaction_store_path.is_empty())
+                .then(|| globalize_path(&self.first_interaction_store_path));
+            if let Err(e) = self.first_interactions.open_store(path.as_deref()) {
+                godot_error!("BliveMock: {}", e);
+            }
+        }
+        let firsts = self
+            .first_interactions
+            .observe(&self.game_id, kind, &open_id);
+        data["first_in_session"] = firsts.in_session.into();
+        if let Some(ever) = firsts.ever {
+            data["first_ever"] = ever.into();
+        }
+        if firsts.in_session {
+            self.emit(
+                "first_interaction",
+                &[open_id.to_variant(), kind.to_variant()],
+            );
+        }
+    }
+
+    /// 与 Blive 相同的审计记录，mode 为 mock
+    fn audit_paid_event(&mut self, event_type: &str, data: &Value) {
+        if self.audit_log_path.is_empty() {
+            return;
+        }
+        let path = globalize_path(&self.audit_log_path);
+        if self.audit_log.as_ref().is_none_or(|log| log.path() != path) {
+            self.audit_log = AuditLog::open(&path).ok();
+        }
+        let session = json!({ "mode": "mock", "app_id": self.app_id, "game_id": self.game_id });
+        let entry = audit::paid_event_entry(event_type, data, &session);
+        let result = match self.audit_log.as_mut() {
+            Some(log) => log.append(entry, events::now_ms()),
+            None => Err(format!("无法打开审计日志: {}", path.display())),
+        };
+        if let Err(e) = result {
+            godot_error!("BliveMock: {}", e);
+            self.emit("audit_log_failed", &[e.to_variant()]);
+        }
+    }
+
+    /// 附加节点：转发主节点广播的信号
+    fn drain_attachment(&mut self) {
+        let Some(attachment) = self.attachment.as_mut() else {
+            return;
+        };
+        let drained = attachment.drain();
+        if drained.lagged > 0 {
+            godot_warn!(
+                "BliveMock: 附加会话积压过多，丢弃了 {} 个信号",
+                drained.lagged
+            );
+        }
+        for signal in drained.signals {
+            match signal.name.as_str() {
+                "ws_connected" => self.ws_connected = true,
+                "ws_disconnected" => self.ws_connected = false,
+                _ => {}
+            }
+            let variants: Vec<Variant> = signal.args.iter().map(json_to_variant).collect();
+            self.emit(signal.name.as_str(), &variants);
+            self.shared.publish(&signal.name, || signal.args.clone());
+            if signal.name == "live_event" && signal.args[1]["blind_box"].as_bool() == Some(true) {
+                self.emit("blind_box_opened", &[variants[1].clone()]);
+            }
+        }
+        if drained.closed {
+            self.detach_session();
+        }
+    }
+
+    /// 附加到其他节点的会话时拒绝会改变会话状态的调用
+    fn reject_read_only(&self, action: &str) -> bool {
+        if self.attachment.is_none() {
+            return false;
+        }
+        godot_warn!(
+            "BliveMock: 已以只读方式附加到其他节点的会话，忽略 {}",
+            action
+        );
+        true
+    }
+
+    fn check_chat_consensus(&mut self) {
+        if !self.detect_chat_consensus {
+            return;
+        }
+        let consensus = self.consensus.check(
+            self.elapsed,
+            self.consensus_window_secs,
+            self.consensus_ratio,
+            self.consensus_min_users.max(1) as usize,
+        );
+        if let Some((text, ratio)) = consensus {
+            self.emit("chat_consensus", &[text.to_variant(), ratio.to_variant()]);
+        }
+    }
+
+    /// 发出信号，设置了频道名时字典参数带上 channel 键
+    fn emit(&mut self, signal: &str, args: &[Variant]) {
+        let channel = channel::resolve(&self.base(), &self.channel_name);
+        let args = channel::tag(&channel, args);
+        self.base_mut().emit_signal(signal, &args);
+    }
+
+    /// 为 `api::SIGNAL_ALIASES` 中的旧信号名注册同名信号，新信号发出时以相同参数转发过去
+    fn register_signal_aliases(&mut self) {
+        let this = self.to_gd();
+        for alias in api::SIGNAL_ALIASES {
+            if alias.args > api::MAX_ALIAS_ARGS {
+                godot_error!("信号 {} 的参数过多，无法转发", alias.old);
+                continue;
+            }
+            if !self.base().has_signal(alias.old) {
+                self.base_mut().add_user_signal(alias.old);
+            }
+            let forwarder = format!("_forward_signal_alias_{}", alias.args);
+            let callable = Callable::from_object_method(&this, forwarder.as_str())
+                .bind(&[alias.old.to_variant()]);
+            self.base_mut().connect(alias.new, &callable);
+        }
+    }
+
+    /// 只在有脚本连接旧信号时转发；第一次转发时提示改用新名并发出 deprecation_warning
+    fn forward_signal_alias(&mut self, old: GString, args: &[Variant]) {
+        let Some(alias) = api::alias(&old.to_string()) else {
+            return;
+        };
+        if self.base().get_signal_connection_list(alias.old).is_empty() {
+            return;
+        }
+        if let Some(warning) = self.deprecations.first_use(alias) {
+            godot_warn!("{}", warning);
+            self.emit(
+                "deprecation_warning",
+                &[
+                    alias.old.to_variant(),
+                    alias.new.to_variant(),
+                    alias.since.to_variant(),
+                ],
+            );
+        }
+        self.emit(alias.old, args);
+    }
+
+    fn track_timed_events(&mut self, event_type: &str, data: &Value) {
+        match event_type {
+            events::EVENT_GIFT => {
+                let enabled = self.subsystems.is_enabled(subsystems::COMBOS);
+                if let Some(combos) = subsystems::lazy(&mut self.combos, enabled) {
+                    combos.record(data, self.elapsed);
+                }
+            }
+            events::EVENT_SUPER_CHAT => {
+                let enabled = self.subsystems.is_enabled(subsystems::SUPER_CHAT_TIMERS);
+                if let Some(super_chats) = subsystems::lazy(&mut self.super_chats, enabled) {
+                    super_chats.add(
+                        data["message_id"].as_i64().unwrap_or(0),
+                        data["duration"].as_i64().unwrap_or(0),
+                        self.elapsed,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn fire_scheduled_events(&mut self) {
+        let ticks = Time::singleton().get_ticks_msec();
+        for (event_type, mut data) in self.scheduler.due(self.elapsed) {
+            data["received_ticks_msec"] = ticks.into();
+            self.emit(
+                "live_event",
+                &[event_type.to_variant(), json_to_variant(&data)],
+            );
+        }
+    }
+
+    fn redeliver_events(&mut self) {
+        if !self.reliable_delivery {
+            return;
+        }
+        let max_attempts = self.max_delivery_attempts.max(1) as u32;
+        for (event_type, data) in self
+            .acks
+            .due(self.elapsed, self.ack_timeout_secs, max_attempts)
+        {
+            self.emit(
+                "live_event",
+                &[event_type.to_variant(), json_to_variant(&data)],
+            );
+        }
+    }
+
+    fn route_language(&self, lang: &str, data: &Value) {
+        if !self.language_routes.iter().any(|route| route.accepts(lang)) {
+            return;
+        }
+        let Some(mut tree) = self.base().get_tree() else {
+            return;
+        };
+        let args = [lang.to_variant(), json_to_variant(data)];
+        for route in self.language_routes.iter().filter(|r| r.accepts(lang)) {
+            tree.call_group(route.group.as_str(), route.method.as_str(), &args);
+        }
+    }
+
+    fn forward_to_groups(&self, cmd: &str, message: &Value) {
+        if !self
+            .group_forwards
+            .iter()
+            .any(|forward| forward.accepts(cmd))
+        {
+            return;
+        }
+        let Some(mut tree) = self.base().get_tree() else {
+            return;
+        };
+        let args = [cmd.to_variant(), json_to_variant(&message["data"])];
+        for forward in self.group_forwards.iter().filter(|f| f.accepts(cmd)) {
+            tree.call_group(forward.group.as_str(), forward.method.as_str(), &args);
+        }
+    }
+
+    fn close_session(&mut self, reason: &str) {
+        self.stop_heartbeat();
+        self.stop_websocket();
+        let game_id = std::mem::take(&mut self.game_id);
+        if !game_id.is_empty() {
+            let request_id = self.next_request_id();
+            self.end_game(GString::from(game_id.as_str()), reason, request_id);
+        }
+        self.emit("session_closed", &[reason.to_variant()]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{EVENT_DANMAKU, EVENT_GIFT, EVENT_GUARD, EVENT_LIKE, EVENT_SUPER_CHAT};
+
+    #[test]
+    fn injected_messages_normalize_like_real_ones() {
+        let (event, data) =
+            events::normalize("LIVE_OPEN_PLATFORM_DM", &danmaku_message("alice", "hi")).unwrap();
+        assert_eq!(event, EVENT_DANMAKU);
+        assert_eq!(data["uname"], "alice");
+        assert_eq!(data["user_id"], "mock-alice");
+        assert_eq!(data["message"], "hi");
+
+        let gift = gift_message("bob", "辣条", 3, 100);
+        let (event, data) = events::normalize("LIVE_OPEN_PLATFORM_SEND_GIFT", &gift).unwrap();
+        assert_eq!(event, EVENT_GIFT);
+        assert_eq!(data["price"], 300);
+        assert_eq!(data["paid"], true);
+
+        let sc = super_chat_message("carol", "加油", 30);
+        let (event, data) = events::normalize("LIVE_OPEN_PLATFORM_SUPER_CHAT", &sc).unwrap();
+        assert_eq!(event, EVENT_SUPER_CHAT);
+        assert_eq!(data["price"], 30000);
+        assert_eq!(data["duration"], 60);
+
+        let (event, data) =
+            events::normalize("LIVE_OPEN_PLATFORM_GUARD", &guard_message("dave", 3)).unwrap();
+        assert_eq!(event, EVENT_GUARD);
+        assert_eq!(data["uname"], "dave");
+        assert_eq!(data["guard_level"], 3);
+        assert_eq!(data["price"], 198_000);
+
+        let (event, data) =
+            events::normalize("LIVE_OPEN_PLATFORM_LIKE", &like_message("erin", 5)).unwrap();
+        assert_eq!(event, EVENT_LIKE);
+        assert_eq!(data["like_count"], 5);
+
+        let blind_box = blind_box_message("fred", "心动盲盒", 15000, "少女祈愿", 1000);
+        let (event, data) = events::normalize("SEND_GIFT", &blind_box).unwrap();
+        assert_eq!(event, EVENT_GIFT);
+        assert_eq!(data["user_id"], "mock-fred");
+        assert_eq!(data["blind_box"], true);
+        assert_eq!(data["price"], 15000);
+        assert_eq!(data["revealed_price"], 1000);
+    }
+}
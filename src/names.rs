@@ -0,0 +1,286 @@
+use crate::error::ERROR_DOMAINS;
+use crate::events;
+use godot::classes::{IRefCounted, RefCounted};
+use godot::prelude::*;
+
+/// 统一事件类型，下标与 `BliveNames.EVENT_*` 常量一致
+const EVENT_TYPES: [&str; 6] = [
+    events::EVENT_DANMAKU,
+    events::EVENT_GIFT,
+    events::EVENT_SUPER_CHAT,
+    events::EVENT_GUARD,
+    events::EVENT_LIKE,
+    events::EVENT_ANNOUNCEMENT,
+];
+
+/// `ws_message_received` 中可能出现的 cmd，下标与 `BliveNames.CMD_*` 常量一致
+const CMDS: [&str; 19] = [
+    "LIVE_OPEN_PLATFORM_DM",
+    "LIVE_OPEN_PLATFORM_SEND_GIFT",
+    "LIVE_OPEN_PLATFORM_SUPER_CHAT",
+    "LIVE_OPEN_PLATFORM_SUPER_CHAT_DEL",
+    "LIVE_OPEN_PLATFORM_GUARD",
+    "LIVE_OPEN_PLATFORM_LIKE",
+    "LIVE_OPEN_PLATFORM_LIVE_START",
+    "LIVE_OPEN_PLATFORM_LIVE_END",
+    "DANMU_MSG",
+    "SEND_GIFT",
+    "SUPER_CHAT_MESSAGE",
+    "SUPER_CHAT_MESSAGE_DELETE",
+    "GUARD_BUY",
+    "INTERACT_WORD",
+    "WATCHED_CHANGE",
+    "ONLINE_RANK_COUNT",
+    "ONLINE_RANK_V2",
+    "LIVE",
+    "PREPARING",
+];
+
+/// `live_event` data 的常用键，下标与 `BliveNames.KEY_*` 常量一致
+const KEYS: [&str; 22] = [
+    "user_id",
+    "uname",
+    "avatar",
+    "medal_level",
+    "guard_level",
+    "timestamp",
+    "timestamp_ms",
+    "latency_ms",
+    "seq",
+    "message",
+    "gift_id",
+    "gift_name",
+    "gift_num",
+    "price",
+    "message_id",
+    "duration",
+    "event_id",
+    "lang",
+    "synthetic",
+    "color",
+    "mode",
+    "font_size",
+];
+
+fn name_at(table: &[&str], id: i64) -> StringName {
+    usize::try_from(id)
+        .ok()
+        .and_then(|index| table.get(index))
+        .map(|name| StringName::from(*name))
+        .unwrap_or_default()
+}
+
+fn index_of(table: &[&str], name: &str) -> i64 {
+    table
+        .iter()
+        .position(|entry| *entry == name)
+        .map_or(-1, |index| index as i64)
+}
+
+/// 事件类型、cmd 和 data 键的常量表，供 C# 等静态语言避免手写字符串
+///
+/// 信号名由绑定生成器从 `Blive` 的信号直接生成，这里只提供信号参数中出现的字符串值。
+#[derive(GodotClass)]
+#[class(base=RefCounted)]
+pub struct BliveNames {
+    base: Base<RefCounted>,
+}
+
+#[godot_api]
+impl IRefCounted for BliveNames {
+    fn init(base: Base<RefCounted>) -> Self {
+        Self { base }
+    }
+}
+
+#[godot_api]
+impl BliveNames {
+    #[constant]
+    const EVENT_DANMAKU: i64 = 0;
+    #[constant]
+    const EVENT_GIFT: i64 = 1;
+    #[constant]
+    const EVENT_SUPER_CHAT: i64 = 2;
+    #[constant]
+    const EVENT_GUARD: i64 = 3;
+    #[constant]
+    const EVENT_LIKE: i64 = 4;
+    #[constant]
+    const EVENT_ANNOUNCEMENT: i64 = 5;
+
+    #[constant]
+    const CMD_OPEN_PLATFORM_DM: i64 = 0;
+    #[constant]
+    const CMD_OPEN_PLATFORM_SEND_GIFT: i64 = 1;
+    #[constant]
+    const CMD_OPEN_PLATFORM_SUPER_CHAT: i64 = 2;
+    #[constant]
+    const CMD_OPEN_PLATFORM_SUPER_CHAT_DEL: i64 = 3;
+    #[constant]
+    const CMD_OPEN_PLATFORM_GUARD: i64 = 4;
+    #[constant]
+    const CMD_OPEN_PLATFORM_LIKE: i64 = 5;
+    #[constant]
+    const CMD_OPEN_PLATFORM_LIVE_START: i64 = 6;
+    #[constant]
+    const CMD_OPEN_PLATFORM_LIVE_END: i64 = 7;
+    #[constant]
+    const CMD_DANMU_MSG: i64 = 8;
+    #[constant]
+    const CMD_SEND_GIFT: i64 = 9;
+    #[constant]
+    const CMD_SUPER_CHAT_MESSAGE: i64 = 10;
+    #[constant]
+    const CMD_SUPER_CHAT_MESSAGE_DELETE: i64 = 11;
+    #[constant]
+    const CMD_GUARD_BUY: i64 = 12;
+    #[constant]
+    const CMD_INTERACT_WORD: i64 = 13;
+    #[constant]
+    const CMD_WATCHED_CHANGE: i64 = 14;
+    #[constant]
+    const CMD_ONLINE_RANK_COUNT: i64 = 15;
+    #[constant]
+    const CMD_ONLINE_RANK_V2: i64 = 16;
+    #[constant]
+    const CMD_LIVE: i64 = 17;
+    #[constant]
+    const CMD_PREPARING: i64 = 18;
+
+    #[constant]
+    const KEY_USER_ID: i64 = 0;
+    #[constant]
+    const KEY_UNAME: i64 = 1;
+    #[constant]
+    const KEY_AVATAR: i64 = 2;
+    #[constant]
+    const KEY_MEDAL_LEVEL: i64 = 3;
+    #[constant]
+    const KEY_GUARD_LEVEL: i64 = 4;
+    #[constant]
+    const KEY_TIMESTAMP: i64 = 5;
+    #[constant]
+    const KEY_TIMESTAMP_MS: i64 = 6;
+    #[constant]
+    const KEY_LATENCY_MS: i64 = 7;
+    #[constant]
+    const KEY_SEQ: i64 = 8;
+    #[constant]
+    const KEY_MESSAGE: i64 = 9;
+    #[constant]
+    const KEY_GIFT_ID: i64 = 10;
+    #[constant]
+    const KEY_GIFT_NAME: i64 = 11;
+    #[constant]
+    const KEY_GIFT_NUM: i64 = 12;
+    #[constant]
+    const KEY_PRICE: i64 = 13;
+    #[constant]
+    const KEY_MESSAGE_ID: i64 = 14;
+    #[constant]
+    const KEY_DURATION: i64 = 15;
+    #[constant]
+    const KEY_EVENT_ID: i64 = 16;
+    #[constant]
+    const KEY_LANG: i64 = 17;
+    #[constant]
+    const KEY_SYNTHETIC: i64 = 18;
+    #[constant]
+    const KEY_COLOR: i64 = 19;
+    #[constant]
+    const KEY_MODE: i64 = 20;
+    #[constant]
+    const KEY_FONT_SIZE: i64 = 21;
+
+    #[constant]
+    const ERROR_HTTP: i64 = 0;
+    #[constant]
+    const ERROR_SIGNATURE: i64 = 1;
+    #[constant]
+    const ERROR_WEBSOCKET: i64 = 2;
+    #[constant]
+    const ERROR_PROTOCOL: i64 = 3;
+    #[constant]
+    const ERROR_API: i64 = 4;
+    #[constant]
+    const ERROR_TIMEOUT: i64 = 5;
+
+    /// `live_event` 的 event_type 字符串，未知 ID 返回空
+    #[func]
+    fn event_type_name(event_type: i64) -> StringName {
+        name_at(&EVENT_TYPES, event_type)
+    }
+
+    /// event_type 字符串对应的 `EVENT_*` 常量，未知时返回 -1
+    #[func]
+    fn event_type_id(event_type: StringName) -> i64 {
+        index_of(&EVENT_TYPES, &event_type.to_string())
+    }
+
+    #[func]
+    fn cmd_name(cmd: i64) -> StringName {
+        name_at(&CMDS, cmd)
+    }
+
+    /// cmd 字符串对应的 `CMD_*` 常量，未知时返回 -1
+    #[func]
+    fn cmd_id(cmd: StringName) -> i64 {
+        index_of(&CMDS, &cmd.to_string())
+    }
+
+    /// `error_occurred` 的 domain 字符串
+    #[func]
+    fn error_domain_name(domain: i64) -> StringName {
+        name_at(&ERROR_DOMAINS, domain)
+    }
+
+    /// domain 字符串对应的 `ERROR_*` 常量，未知时返回 -1
+    #[func]
+    fn error_domain_id(domain: StringName) -> i64 {
+        index_of(&ERROR_DOMAINS, &domain.to_string())
+    }
+
+    /// `KEY_*` 常量对应的 Dictionary 键
+    #[func]
+    fn key_name(key: i64) -> StringName {
+        name_at(&KEYS, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn constants_match_tables() {
+        assert_eq!(
+            EVENT_TYPES[BliveNames::EVENT_SUPER_CHAT as usize],
+            "super_chat"
+        );
+        assert_eq!(EVENT_TYPES.len() as i64, BliveNames::EVENT_ANNOUNCEMENT + 1);
+        assert_eq!(CMDS[BliveNames::CMD_GUARD_BUY as usize], "GUARD_BUY");
+        assert_eq!(CMDS.len() as i64, BliveNames::CMD_PREPARING + 1);
+        assert_eq!(KEYS[BliveNames::KEY_PRICE as usize], "price");
+        assert_eq!(
+            ERROR_DOMAINS[BliveNames::ERROR_WEBSOCKET as usize],
+            "websocket"
+        );
+        assert_eq!(ERROR_DOMAINS.len() as i64, BliveNames::ERROR_TIMEOUT + 1);
+        assert_eq!(KEYS.len() as i64, BliveNames::KEY_FONT_SIZE + 1);
+        assert_eq!(index_of(&CMDS, "DANMU_MSG"), BliveNames::CMD_DANMU_MSG);
+        assert_eq!(index_of(&CMDS, "NOPE"), -1);
+    }
+
+    #[test]
+    fn keys_cover_normalized_events() {
+        let message =
+            json!({"cmd": "LIVE_OPEN_PLATFORM_DM", "data": {"msg": "hi", "open_id": "a"}});
+        let (_, data) = events::normalize(CMDS[0], &message).unwrap();
+        for key in &KEYS[..=BliveNames::KEY_MESSAGE as usize] {
+            if *key != "seq" {
+                assert!(data.get(*key).is_some(), "missing {}", key);
+            }
+        }
+    }
+}
@@ -0,0 +1,549 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use godot::prelude::*;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+// obs-websocket v5 操作码
+const OP_HELLO: u64 = 0;
+const OP_IDENTIFY: u64 = 1;
+const OP_IDENTIFIED: u64 = 2;
+const OP_REQUEST: u64 = 6;
+const OP_REQUEST_RESPONSE: u64 = 7;
+const RPC_VERSION: u64 = 1;
+
+/// 后台连接发往主线程的消息
+#[derive(Debug)]
+enum ObsMessage {
+    Connected,
+    Disconnected,
+    Error(String),
+    Response(Value),
+}
+
+/// 事件到 OBS 操作的映射：event_type 匹配且金额达到 min_price 时执行
+#[derive(Debug, Clone)]
+struct ObsRule {
+    event_type: String,
+    min_price: i64,
+    action: ObsAction,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ObsAction {
+    SwitchScene(String),
+    SetSourceVisible {
+        scene: String,
+        source: String,
+        visible: bool,
+    },
+    /// text 中的 {uname}、{message}、{gift_name} 等占位符会被替换为事件字段
+    SetText {
+        input: String,
+        text: String,
+    },
+}
+
+/// 按 obs-websocket 规范计算鉴权串：base64(sha256(base64(sha256(password + salt)) + challenge))
+pub fn auth_response(password: &str, salt: &str, challenge: &str) -> String {
+    let secret = BASE64.encode(Sha256::digest(format!("{}{}", password, salt)));
+    BASE64.encode(Sha256::digest(format!("{}{}", secret, challenge)))
+}
+
+/// 根据 Hello 消息构造 Identify 消息，服务器要求鉴权时附带鉴权串
+pub fn identify_message(hello: &Value, password: &str) -> Value {
+    let mut data = json!({ "rpcVersion": RPC_VERSION, "eventSubscriptions": 0 });
+    let auth = &hello["d"]["authentication"];
+    if let (Some(challenge), Some(salt)) = (auth["challenge"].as_str(), auth["salt"].as_str()) {
+        data["authentication"] = auth_response(password, salt, challenge).into();
+    }
+    json!({ "op": OP_IDENTIFY, "d": data })
+}
+
+pub fn request_message(request_id: &str, request_type: &str, request_data: Value) -> Value {
+    json!({
+        "op": OP_REQUEST,
+        "d": {
+            "requestType": request_type,
+            "requestId": request_id,
+            "requestData": request_data,
+        }
+    })
+}
+
+/// 把 `{key}` 占位符替换为事件字段
+fn fill_template(template: &str, data: &Value) -> String {
+    let mut text = template.to_string();
+    if let Some(fields) = data.as_object() {
+        for (key, value) in fields {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            text = text.replace(&format!("{{{}}}", key), &value);
+        }
+    }
+    text
+}
+
+/// obs-websocket v5 客户端，把 `Blive.live_event` 映射为切换场景、显示 / 隐藏来源、修改文字
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct ObsBridge {
+    base: Base<Node>,
+
+    #[export]
+    host: GString,
+    #[export]
+    port: i64,
+    #[export]
+    password: GString,
+    /// 要监听 `live_event` 的 Blive 节点，留空时可手动调用 `handle_live_event`
+    #[export]
+    blive_path: NodePath,
+
+    rules: Vec<ObsRule>,
+    running: Arc<AtomicBool>,
+    request_counter: Arc<AtomicU64>,
+    outbound_tx: Option<mpsc::UnboundedSender<Value>>,
+    message_tx: mpsc::UnboundedSender<ObsMessage>,
+    message_rx: Arc<Mutex<mpsc::UnboundedReceiver<ObsMessage>>>,
+}
+
+#[godot_api]
+impl INode for ObsBridge {
+    fn init(base: Base<Node>) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            base,
+            host: GString::from("127.0.0.1"),
+            port: 4455,
+            password: GString::new(),
+            blive_path: NodePath::default(),
+            rules: Vec::new(),
+            running: Arc::new(AtomicBool::new(false)),
+            request_counter: Arc::new(AtomicU64::new(0)),
+            outbound_tx: None,
+            message_tx: tx,
+            message_rx: Arc::new(Mutex::new(rx)),
+        }
+    }
+
+    fn ready(&mut self) {
+        if self.blive_path.is_empty() {
+            return;
+        }
+        let path = self.blive_path.clone();
+        let Some(mut blive) = self.base().get_node_or_null(&path) else {
+            godot_warn!("ObsBridge: 找不到节点 {}", path);
+            return;
+        };
+        let callable = Callable::from_object_method(&self.to_gd(), "handle_live_event");
+        blive.connect("live_event", &callable);
+    }
+
+    fn process(&mut self, _delta: f64) {
+        let messages: Vec<ObsMessage> = {
+            let mut rx = self.message_rx.lock().unwrap();
+            let mut messages = Vec::new();
+            while let Ok(message) = rx.try_recv() {
+                messages.push(message);
+            }
+            messages
+        };
+        for message in messages {
+            match message {
+                ObsMessage::Connected => self.base_mut().emit_signal("obs_connected", &[]),
+                ObsMessage::Disconnected => {
+                    self.outbound_tx = None;
+                    self.base_mut().emit_signal("obs_disconnected", &[])
+                }
+                ObsMessage::Error(e) => self.base_mut().emit_signal("obs_error", &[e.to_variant()]),
+                ObsMessage::Response(response) => {
+                    let status = &response["requestStatus"];
+                    let args = [
+                        response["requestType"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_variant(),
+                        status["result"].as_bool().unwrap_or(false).to_variant(),
+                        status["comment"].as_str().unwrap_or_default().to_variant(),
+                    ];
+                    self.base_mut().emit_signal("obs_request_completed", &args)
+                }
+            };
+        }
+    }
+}
+
+#[godot_api]
+impl ObsBridge {
+    #[signal]
+    fn obs_connected();
+    #[signal]
+    fn obs_disconnected();
+    #[signal]
+    fn obs_error(error_msg: GString);
+    #[signal]
+    fn obs_request_completed(request_type: GString, success: bool, comment: GString);
+
+    #[func]
+    fn connect_obs(&mut self) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            godot_print!("ObsBridge 已经在运行中");
+            return;
+        }
+        let url = format!("ws://{}:{}", self.host, self.port);
+        let password = self.password.to_string();
+        let running = self.running.clone();
+        let sender = self.message_tx.clone();
+        let (outbound_tx, outbound_rx) = mpsc::unbounded_channel();
+        self.outbound_tx = Some(outbound_tx);
+
+        std::thread::Builder::new()
+            .name("obs-websocket-thread".to_string())
+            .spawn(move || {
+                match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => runtime.block_on(Self::run(
+                        url,
+                        password,
+                        running.clone(),
+                        sender.clone(),
+                        outbound_rx,
+                    )),
+                    Err(e) => {
+                        let _ = sender.send(ObsMessage::Error(format!("创建 runtime 失败: {}", e)));
+                    }
+                }
+                running.store(false, Ordering::SeqCst);
+                let _ = sender.send(ObsMessage::Disconnected);
+            })
+            .expect("Failed to spawn obs websocket thread");
+    }
+
+    #[func]
+    fn disconnect_obs(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        // 丢弃发送端后写任务结束，连接随之关闭
+        self.outbound_tx = None;
+    }
+
+    #[func]
+    fn is_obs_connected(&self) -> bool {
+        self.running.load(Ordering::SeqCst) && self.outbound_tx.is_some()
+    }
+
+    /// 发送任意 obs-websocket 请求，request_data 为 Dictionary 形式的参数
+    #[func]
+    fn send_request(&mut self, request_type: GString, request_data: Dictionary) -> bool {
+        let data = crate::convert::dictionary_to_json(&request_data);
+        self.send(&request_type.to_string(), data)
+    }
+
+    #[func]
+    fn switch_scene(&mut self, scene_name: GString) -> bool {
+        self.run_action(
+            &ObsAction::SwitchScene(scene_name.to_string()),
+            &Value::Null,
+        )
+    }
+
+    #[func]
+    fn set_source_visible(
+        &mut self,
+        scene_name: GString,
+        source_name: GString,
+        visible: bool,
+    ) -> bool {
+        self.run_action(
+            &ObsAction::SetSourceVisible {
+                scene: scene_name.to_string(),
+                source: source_name.to_string(),
+                visible,
+            },
+            &Value::Null,
+        )
+    }
+
+    #[func]
+    fn set_text(&mut self, input_name: GString, text: GString) -> bool {
+        self.run_action(
+            &ObsAction::SetText {
+                input: input_name.to_string(),
+                text: text.to_string(),
+            },
+            &Value::Null,
+        )
+    }
+
+    /// event_type 为 `live_event` 的类型（danmaku / gift / super_chat / guard），price 单位为千分之一元
+    #[func]
+    fn map_event_to_scene(&mut self, event_type: GString, min_price: i64, scene_name: GString) {
+        self.rules.push(ObsRule {
+            event_type: event_type.to_string(),
+            min_price,
+            action: ObsAction::SwitchScene(scene_name.to_string()),
+        });
+    }
+
+    #[func]
+    fn map_event_to_source_visibility(
+        &mut self,
+        event_type: GString,
+        min_price: i64,
+        scene_name: GString,
+        source_name: GString,
+        visible: bool,
+    ) {
+        self.rules.push(ObsRule {
+            event_type: event_type.to_string(),
+            min_price,
+            action: ObsAction::SetSourceVisible {
+                scene: scene_name.to_string(),
+                source: source_name.to_string(),
+                visible,
+            },
+        });
+    }
+
+    /// text 支持 `{uname}`、`{message}`、`{gift_name}` 等事件字段占位符
+    #[func]
+    fn map_event_to_text(
+        &mut self,
+        event_type: GString,
+        min_price: i64,
+        input_name: GString,
+        text: GString,
+    ) {
+        self.rules.push(ObsRule {
+            event_type: event_type.to_string(),
+            min_price,
+            action: ObsAction::SetText {
+                input: input_name.to_string(),
+                text: text.to_string(),
+            },
+        });
+    }
+
+    #[func]
+    fn clear_event_mappings(&mut self) {
+        self.rules.clear();
+    }
+
+    /// 连接到 `Blive.live_event`，执行所有匹配的映射
+    #[func]
+    fn handle_live_event(&mut self, event_type: GString, data: Dictionary) {
+        let event_type = event_type.to_string();
+        let data = crate::convert::dictionary_to_json(&data);
+        let price = data["price"].as_i64().unwrap_or(0);
+        let actions: Vec<ObsAction> = self
+            .rules
+            .iter()
+            .filter(|rule| rule.event_type == event_type && price >= rule.min_price)
+            .map(|rule| rule.action.clone())
+            .collect();
+        for action in actions {
+            self.run_action(&action, &data);
+        }
+    }
+}
+
+impl ObsBridge {
+    fn run_action(&mut self, action: &ObsAction, data: &Value) -> bool {
+        match action {
+            ObsAction::SwitchScene(scene) => {
+                self.send("SetCurrentProgramScene", json!({ "sceneName": scene }))
+            }
+            // 来源按名称指定，由连接任务先查询 sceneItemId 再设置
+            ObsAction::SetSourceVisible {
+                scene,
+                source,
+                visible,
+            } => self.send(
+                "SetSceneItemEnabled",
+                json!({ "sceneName": scene, "sourceName": source, "sceneItemEnabled": visible }),
+            ),
+            ObsAction::SetText { input, text } => self.send(
+                "SetInputSettings",
+                json!({ "inputName": input, "inputSettings": { "text": fill_template(text, data) } }),
+            ),
+        }
+    }
+
+    fn send(&mut self, request_type: &str, request_data: Value) -> bool {
+        let Some(outbound) = self.outbound_tx.as_ref() else {
+            godot_warn!("ObsBridge 未连接，忽略请求 {}", request_type);
+            return false;
+        };
+        let id = self.request_counter.fetch_add(1, Ordering::SeqCst);
+        outbound
+            .send(request_message(
+                &format!("gdblive-{}", id),
+                request_type,
+                request_data,
+            ))
+            .is_ok()
+    }
+
+    async fn run(
+        url: String,
+        password: String,
+        running: Arc<AtomicBool>,
+        sender: mpsc::UnboundedSender<ObsMessage>,
+        mut outbound_rx: mpsc::UnboundedReceiver<Value>,
+    ) {
+        let error = |msg: String| {
+            let _ = sender.send(ObsMessage::Error(msg));
+        };
+        let stream = match connect_async(url.as_str()).await {
+            Ok((stream, _)) => stream,
+            Err(e) => return error(format!("连接 OBS 失败: {}", e)),
+        };
+        let (mut write, mut read) = stream.split();
+
+        // 握手：Hello -> Identify -> Identified
+        loop {
+            let Some(Ok(Message::Text(text))) = read.next().await else {
+                return error("OBS 握手失败".to_string());
+            };
+            let message: Value = serde_json::from_str(&text).unwrap_or_default();
+            match message["op"].as_u64() {
+                Some(OP_HELLO) => {
+                    let identify = identify_message(&message, &password).to_string();
+                    if let Err(e) = write.send(Message::Text(identify)).await {
+                        return error(format!("发送 Identify 失败: {}", e));
+                    }
+                }
+                Some(OP_IDENTIFIED) => break,
+                _ => {}
+            }
+        }
+        let _ = sender.send(ObsMessage::Connected);
+
+        while running.load(Ordering::SeqCst) {
+            tokio::select! {
+                outbound = outbound_rx.recv() => {
+                    let Some(mut request) = outbound else { break };
+                    if let Err(e) = Self::resolve_scene_item(&mut request, &mut write, &mut read).await {
+                        error(e);
+                        continue;
+                    }
+                    if let Err(e) = write.send(Message::Text(request.to_string())).await {
+                        error(format!("发送请求失败: {}", e));
+                        break;
+                    }
+                }
+                incoming = read.next() => match incoming {
+                    Some(Ok(Message::Text(text))) => {
+                        let message: Value = serde_json::from_str(&text).unwrap_or_default();
+                        if message["op"].as_u64() == Some(OP_REQUEST_RESPONSE) {
+                            let _ = sender.send(ObsMessage::Response(message["d"].clone()));
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => {
+                        error(format!("接收失败: {}", e));
+                        break;
+                    }
+                },
+            }
+        }
+        let _ = write.send(Message::Close(None)).await;
+    }
+
+    /// SetSceneItemEnabled 需要 sceneItemId，先用 GetSceneItemId 按来源名称查询
+    async fn resolve_scene_item<W, R>(
+        request: &mut Value,
+        write: &mut W,
+        read: &mut R,
+    ) -> Result<(), String>
+    where
+        W: futures_util::Sink<Message> + Unpin,
+        W::Error: std::fmt::Display,
+        R: futures_util::Stream<Item = Result<Message, tokio_tungstenite::tungstenite::Error>>
+            + Unpin,
+    {
+        if request["d"]["requestType"] != "SetSceneItemEnabled" {
+            return Ok(());
+        }
+        let lookup_id = format!(
+            "{}-lookup",
+            request["d"]["requestId"].as_str().unwrap_or_default()
+        );
+        let request_data = &mut request["d"]["requestData"];
+        let Some(source) = request_data
+            .as_object_mut()
+            .and_then(|fields| fields.remove("sourceName"))
+        else {
+            return Ok(());
+        };
+        let lookup = request_message(
+            &lookup_id,
+            "GetSceneItemId",
+            json!({ "sceneName": request_data["sceneName"], "sourceName": source }),
+        );
+        write
+            .send(Message::Text(lookup.to_string()))
+            .await
+            .map_err(|e| format!("查询来源失败: {}", e))?;
+        while let Some(Ok(message)) = read.next().await {
+            let Message::Text(text) = message else {
+                continue;
+            };
+            let response: Value = serde_json::from_str(&text).unwrap_or_default();
+            if response["d"]["requestId"] != lookup_id.as_str() {
+                continue;
+            }
+            let id = response["d"]["responseData"]["sceneItemId"]
+                .as_i64()
+                .ok_or_else(|| format!("找不到来源: {}", source))?;
+            request_data["sceneItemId"] = id.into();
+            return Ok(());
+        }
+        Err("查询来源时连接已断开".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auth_matches_protocol_example() {
+        // obs-websocket 协议文档中的示例：password "supersecretpassword"
+        assert_eq!(
+            auth_response(
+                "supersecretpassword",
+                "lM1GncleQOaCu9lT1yeUZhFYnqhsLLP1G5lAGo3ixaI=",
+                "+IxH4CnCiqpX1rM9scsNynZzbOe4KhDeYcTNS3PDaeY="
+            ),
+            "1Ct943GAT+6YQUUX47Ia/ncufilbe6+oD6lY+5kaCu4="
+        );
+    }
+
+    #[test]
+    fn identify_without_auth() {
+        let hello = json!({"op": 0, "d": {"obsWebSocketVersion": "5.0.0", "rpcVersion": 1}});
+        let identify = identify_message(&hello, "");
+        assert_eq!(identify["op"], OP_IDENTIFY);
+        assert!(identify["d"].get("authentication").is_none());
+    }
+
+    #[test]
+    fn template_fills_event_fields() {
+        let data = json!({"uname": "观众", "gift_num": 3, "gift_name": "小花花"});
+        assert_eq!(
+            fill_template("感谢 {uname} 送出 {gift_num} 个{gift_name}", &data),
+            "感谢 观众 送出 3 个小花花"
+        );
+    }
+}
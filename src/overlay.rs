@@ -0,0 +1,282 @@
+use crate::events::EVENT_DANMAKU;
+use godot::classes::{Control, IControl, Label};
+use godot::prelude::*;
+
+/// 弹幕显示位置，与 danmaku 事件的 mode 字段一致
+const MODE_SCROLL: i64 = 1;
+const MODE_BOTTOM: i64 = 4;
+const MODE_TOP: i64 = 5;
+/// 平台默认字号，轨道高度按该字号计算
+const BASE_FONT_SIZE: f64 = 25.0;
+/// 同一轨道上相邻两条滚动弹幕之间的间距（像素）
+const SCROLL_GAP: f64 = 24.0;
+
+/// 估算一行文字的宽度：全角字符按字号计，半角字符按半个字号计
+fn estimate_width(text: &str, font_px: f64) -> f64 {
+    text.chars()
+        .map(|c| if c.is_ascii() { 0.5 } else { 1.0 })
+        .sum::<f64>()
+        * font_px
+}
+
+/// 一组轨道，记录每条轨道在什么时间之后可以放下一条弹幕
+#[derive(Debug, Default)]
+struct Lanes {
+    free_at: Vec<f64>,
+}
+
+impl Lanes {
+    fn resize(&mut self, count: usize) {
+        self.free_at.resize(count.max(1), 0.0);
+    }
+
+    /// 取最靠前的空闲轨道，全部占满时取最早空出的一条，并占用到 busy_until
+    fn acquire(&mut self, now: f64, busy_until: f64) -> usize {
+        let lane = self
+            .free_at
+            .iter()
+            .position(|free_at| *free_at <= now)
+            .unwrap_or_else(|| {
+                self.free_at
+                    .iter()
+                    .enumerate()
+                    .min_by(|a, b| a.1.total_cmp(b.1))
+                    .map_or(0, |(lane, _)| lane)
+            });
+        self.free_at[lane] = busy_until;
+        lane
+    }
+
+    fn clear(&mut self) {
+        self.free_at.iter_mut().for_each(|free_at| *free_at = 0.0);
+    }
+}
+
+struct ShownDanmaku {
+    label: Gd<Label>,
+    /// 滚动弹幕的水平速度（像素/秒），固定弹幕为 0
+    velocity: f64,
+    width: f64,
+    /// 固定弹幕的剩余显示秒数
+    remaining: f64,
+}
+
+/// 弹幕层：按观众设置的颜色、字号和位置（滚动 / 顶部 / 底部）显示 danmaku 事件
+///
+/// 放在覆盖画面的 Control 下，设置 `blive_path` 后自动监听 `live_event`。
+#[derive(GodotClass)]
+#[class(base=Control)]
+pub struct DanmakuOverlay {
+    base: Base<Control>,
+
+    /// 要监听 `live_event` 的 Blive 节点，留空时可手动调用 `handle_live_event`
+    #[export]
+    blive_path: NodePath,
+    /// 滚动弹幕的速度（像素/秒）
+    #[export]
+    scroll_speed: f64,
+    /// 顶部 / 底部弹幕的停留秒数
+    #[export]
+    fixed_duration: f64,
+    /// 字号缩放，平台的 25 号字对应 25 × font_scale 像素
+    #[export]
+    font_scale: f64,
+    /// 为 false 时忽略观众设置的样式，全部显示为默认的白色滚动弹幕
+    #[export]
+    honor_style: bool,
+    /// 同时显示的最大弹幕数，超出时移除最早的一条
+    #[export]
+    max_danmaku: i64,
+
+    shown: Vec<ShownDanmaku>,
+    scroll_lanes: Lanes,
+    top_lanes: Lanes,
+    bottom_lanes: Lanes,
+    elapsed: f64,
+}
+
+#[godot_api]
+impl IControl for DanmakuOverlay {
+    fn init(base: Base<Control>) -> Self {
+        Self {
+            base,
+            blive_path: NodePath::default(),
+            scroll_speed: 160.0,
+            fixed_duration: 5.0,
+            font_scale: 1.0,
+            honor_style: true,
+            max_danmaku: 100,
+            shown: Vec::new(),
+            scroll_lanes: Lanes::default(),
+            top_lanes: Lanes::default(),
+            bottom_lanes: Lanes::default(),
+            elapsed: 0.0,
+        }
+    }
+
+    fn ready(&mut self) {
+        if self.blive_path.is_empty() {
+            return;
+        }
+        let path = self.blive_path.clone();
+        let Some(mut blive) = self.base().get_node_or_null(&path) else {
+            godot_warn!("DanmakuOverlay: 找不到节点 {}", path);
+            return;
+        };
+        let callable = Callable::from_object_method(&self.to_gd(), "handle_live_event");
+        blive.connect("live_event", &callable);
+    }
+
+    fn process(&mut self, delta: f64) {
+        self.elapsed += delta;
+        self.shown.retain_mut(|danmaku| {
+            if !danmaku.label.is_instance_valid() {
+                return false;
+            }
+            let expired = if danmaku.velocity != 0.0 {
+                let mut position = danmaku.label.get_position();
+                position.x += (danmaku.velocity * delta) as f32;
+                danmaku.label.set_position(position);
+                (position.x as f64) + danmaku.width < 0.0
+            } else {
+                danmaku.remaining -= delta;
+                danmaku.remaining <= 0.0
+            };
+            if expired {
+                danmaku.label.queue_free();
+            }
+            !expired
+        });
+    }
+}
+
+#[godot_api]
+impl DanmakuOverlay {
+    /// 显示一条 danmaku 事件，其他事件类型被忽略
+    #[func]
+    fn handle_live_event(&mut self, event_type: GString, data: Dictionary) {
+        if event_type.to_string() != EVENT_DANMAKU {
+            return;
+        }
+        let int_of = |key: &str, default: i64| {
+            data.get(key)
+                .and_then(|value| value.try_to::<i64>().ok())
+                .unwrap_or(default)
+        };
+        let text = data
+            .get("message")
+            .map(|value| value.to_string())
+            .unwrap_or_default();
+        if text.is_empty() {
+            return;
+        }
+        let (color, mode, font_size) = if self.honor_style {
+            (
+                int_of("color", 0xFFFFFF),
+                int_of("mode", MODE_SCROLL),
+                int_of("font_size", BASE_FONT_SIZE as i64),
+            )
+        } else {
+            (0xFFFFFF, MODE_SCROLL, BASE_FONT_SIZE as i64)
+        };
+
+        let size = self.base().get_size();
+        let (view_width, view_height) = (size.x as f64, size.y as f64);
+        let lane_height = BASE_FONT_SIZE * self.font_scale.max(0.1) * 1.25;
+        let lane_count = (view_height / lane_height).floor() as usize;
+        let font_px = font_size as f64 * self.font_scale.max(0.1);
+        let width = estimate_width(&text, font_px);
+
+        let mut label = Label::new_alloc();
+        label.set_text(&text);
+        label.add_theme_color_override(
+            "font_color",
+            Color::from_rgba8(
+                (color >> 16 & 0xFF) as u8,
+                (color >> 8 & 0xFF) as u8,
+                (color & 0xFF) as u8,
+                255,
+            ),
+        );
+        label.add_theme_font_size_override("font_size", font_px.round() as i32);
+
+        let now = self.elapsed;
+        let (x, y, velocity) = match mode {
+            MODE_TOP | MODE_BOTTOM => {
+                let lanes = if mode == MODE_TOP {
+                    &mut self.top_lanes
+                } else {
+                    &mut self.bottom_lanes
+                };
+                lanes.resize(lane_count / 2);
+                let lane = lanes.acquire(now, now + self.fixed_duration) as f64;
+                let y = if mode == MODE_TOP {
+                    lane * lane_height
+                } else {
+                    view_height - (lane + 1.0) * lane_height
+                };
+                ((view_width - width).max(0.0) / 2.0, y, 0.0)
+            }
+            _ => {
+                let speed = self.scroll_speed.max(1.0);
+                self.scroll_lanes.resize(lane_count);
+                // 轨道在这条弹幕完全进入画面并留出间距后空出
+                let lane = self
+                    .scroll_lanes
+                    .acquire(now, now + (width + SCROLL_GAP) / speed);
+                (view_width, lane as f64 * lane_height, -speed)
+            }
+        };
+        label.set_position(Vector2::new(x as f32, y as f32));
+        self.base_mut().add_child(&label);
+        self.shown.push(ShownDanmaku {
+            label,
+            velocity,
+            width,
+            remaining: self.fixed_duration,
+        });
+
+        let limit = self.max_danmaku.max(1) as usize;
+        if self.shown.len() > limit {
+            let excess = self.shown.len() - limit;
+            for mut danmaku in self.shown.drain(..excess) {
+                if danmaku.label.is_instance_valid() {
+                    danmaku.label.queue_free();
+                }
+            }
+        }
+    }
+
+    /// 立即移除所有正在显示的弹幕
+    #[func]
+    fn clear_danmaku(&mut self) {
+        for mut danmaku in self.shown.drain(..) {
+            if danmaku.label.is_instance_valid() {
+                danmaku.label.queue_free();
+            }
+        }
+        self.scroll_lanes.clear();
+        self.top_lanes.clear();
+        self.bottom_lanes.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lanes_reuse_the_earliest_free_lane() {
+        let mut lanes = Lanes::default();
+        lanes.resize(2);
+        assert_eq!(lanes.acquire(0.0, 3.0), 0);
+        assert_eq!(lanes.acquire(0.0, 1.0), 1);
+        // 全部占满时取最早空出的轨道
+        assert_eq!(lanes.acquire(0.5, 2.0), 1);
+        assert_eq!(lanes.acquire(3.0, 4.0), 0);
+
+        lanes.resize(0);
+        assert_eq!(lanes.acquire(0.0, 1.0), 0);
+        assert_eq!(estimate_width("ab弹幕", 20.0), 60.0);
+    }
+}
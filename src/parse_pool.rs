@@ -0,0 +1,194 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Instant;
+use tokio::sync::mpsc;
+
+/// 消息所属观众的路由键：同一观众的消息总是交给同一个解析线程，保证先后顺序
+///
+/// 只做字节扫描，不解析 JSON：直连弹幕取 `info[2][0]`，其余依次查找 `"open_id":"`、`"uid":`，
+/// 找不到时返回 0。直连弹幕和礼物的 uid 是同样的数字文本，因此同一观众的弹幕和礼物也落在同一线程。
+pub fn routing_key(body: &[u8]) -> u64 {
+    let value = danmaku_uid(body)
+        .or_else(|| find_value(body, br#""open_id":""#))
+        .or_else(|| find_value(body, br#""uid":"#));
+    let Some(value) = value else {
+        return 0;
+    };
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 返回 key 之后到下一个引号、逗号或右括号之前的字节
+fn find_value<'a>(body: &'a [u8], key: &[u8]) -> Option<&'a [u8]> {
+    let start = body.windows(key.len()).position(|w| w == key)? + key.len();
+    let rest = &body[start..];
+    let end = rest
+        .iter()
+        .position(|b| matches!(b, b'"' | b',' | b'}' | b']'))
+        .unwrap_or(rest.len());
+    Some(&rest[..end])
+}
+
+/// 直连 DANMU_MSG 的发送者 uid，位于 `info[2][0]`
+fn danmaku_uid(body: &[u8]) -> Option<&[u8]> {
+    find_value(body, br#""cmd":"DANMU_MSG"#)?;
+    let key = br#""info":"#;
+    let mut i = body.windows(key.len()).position(|w| w == key)? + key.len();
+    i = skip_whitespace(body, i);
+    if body.get(i) != Some(&b'[') {
+        return None;
+    }
+    i += 1;
+    for _ in 0..2 {
+        i = skip_value(body, skip_whitespace(body, i))?;
+        i = skip_whitespace(body, i);
+        if body.get(i) != Some(&b',') {
+            return None;
+        }
+        i += 1;
+    }
+    i = skip_whitespace(body, i);
+    if body.get(i) != Some(&b'[') {
+        return None;
+    }
+    let start = skip_whitespace(body, i + 1);
+    let end = start
+        + body[start..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+    (end > start).then(|| &body[start..end])
+}
+
+fn skip_whitespace(body: &[u8], mut i: usize) -> usize {
+    while body.get(i).is_some_and(|b| b.is_ascii_whitespace()) {
+        i += 1;
+    }
+    i
+}
+
+/// 跳过从 i 开始的一个 JSON 值，返回其后的位置
+fn skip_value(body: &[u8], mut i: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    while let Some(&b) = body.get(i) {
+        i += 1;
+        if in_string {
+            match b {
+                b'\\' => i += 1,
+                b'"' => {
+                    in_string = false;
+                    if depth == 0 {
+                        return Some(i);
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'[' | b'{' => depth += 1,
+            b']' | b'}' if depth == 0 => return Some(i - 1),
+            b']' | b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            b',' if depth == 0 => return Some(i - 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 长连接消息的解析线程池，每个线程一个队列，按 `routing_key` 分配
+pub struct ParsePool {
+    workers: Vec<mpsc::UnboundedSender<(u64, Instant, Vec<u8>)>>,
+}
+
+impl ParsePool {
+    /// 在 blocking 线程池中启动 size 个解析线程，每条消息以 (到达序号, 到达时刻, 消息体) 调用一次 handle；
+    /// ParsePool 被丢弃后线程处理完剩余消息即退出
+    pub fn spawn<F>(size: usize, handle: F) -> Self
+    where
+        F: Fn(u64, Instant, Vec<u8>) + Clone + Send + 'static,
+    {
+        let workers = (0..size.max(1))
+            .map(|_| {
+                let (tx, mut rx) = mpsc::unbounded_channel::<(u64, Instant, Vec<u8>)>();
+                let handle = handle.clone();
+                tokio::task::spawn_blocking(move || {
+                    while let Some((seq, received_at, body)) = rx.blocking_recv() {
+                        handle(seq, received_at, body);
+                    }
+                });
+                tx
+            })
+            .collect();
+        Self { workers }
+    }
+
+    /// 同一路由键的消息进入同一线程的队列，按 dispatch 的先后处理
+    pub fn dispatch(&self, seq: u64, received_at: Instant, body: Vec<u8>) {
+        let index = (routing_key(&body) % self.workers.len() as u64) as usize;
+        let _ = self.workers[index].send((seq, received_at, body));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_by_user() {
+        let a1 = br#"{"cmd":"LIVE_OPEN_PLATFORM_DM","data":{"open_id":"abc","msg":"1"}}"#;
+        let a2 = br#"{"cmd":"LIVE_OPEN_PLATFORM_SEND_GIFT","data":{"gift_num":1,"open_id":"abc"}}"#;
+        let b = br#"{"cmd":"LIVE_OPEN_PLATFORM_DM","data":{"open_id":"abd","msg":"1"}}"#;
+        assert_eq!(routing_key(a1), routing_key(a2));
+        assert_ne!(routing_key(a1), routing_key(b));
+
+        let direct = br#"{"cmd":"SEND_GIFT","data":{"uid":42,"uname":"a"}}"#;
+        let direct_again = br#"{"cmd":"INTERACT_WORD","data":{"uid":42}}"#;
+        assert_eq!(routing_key(direct), routing_key(direct_again));
+        assert_eq!(routing_key(br#"{"cmd":"WATCHED_CHANGE"}"#), 0);
+
+        let danmaku = br#"{"cmd":"DANMU_MSG:4:0:2:2:2:0","info":[[0,1,25,16777215,1700000000000,0,0,"a",0,0,0,"",0,"{}","{}",{"mode":0,"extra":"{\"content\":\"[x],\"}"}],"vote 1",[42,"a",0,0,0,10000,1,""],[],[]]}"#;
+        assert_eq!(routing_key(danmaku), routing_key(direct));
+    }
+
+    #[test]
+    fn keeps_per_user_order() {
+        use std::sync::{Arc, Mutex};
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .build()
+            .unwrap();
+        let handled = Arc::new(Mutex::new(Vec::new()));
+        let pool = {
+            let _guard = runtime.enter();
+            let handled = handled.clone();
+            ParsePool::spawn(4, move |seq, _, body| {
+                handled.lock().unwrap().push((routing_key(&body), seq));
+            })
+        };
+        for seq in 0..200u64 {
+            let body = format!(r#"{{"data":{{"open_id":"user{}"}}}}"#, seq % 7);
+            pool.dispatch(seq, Instant::now(), body.into_bytes());
+        }
+        drop(pool);
+        drop(runtime);
+
+        let handled = handled.lock().unwrap();
+        assert_eq!(handled.len(), 200);
+        let mut last = std::collections::HashMap::new();
+        for (key, seq) in handled.iter() {
+            if let Some(previous) = last.insert(key, seq) {
+                assert!(previous < seq);
+            }
+        }
+    }
+}
@@ -0,0 +1,446 @@
+use flate2::read::ZlibDecoder;
+use serde_json::Value;
+use std::fmt;
+use std::io::{Cursor, Read};
+
+/// 解包失败的原因；超出大小限制的包被整体丢弃，连接保持
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+    Malformed(String),
+    /// 单条 WebSocket 消息的字节数
+    MessageTooLarge(usize),
+    /// 解压后累计超出限制时已解压的字节数
+    DecompressedTooLarge(usize),
+}
+
+impl DecodeError {
+    /// `protocol_error` 信号的 kind
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DecodeError::Malformed(_) => "malformed",
+            DecodeError::MessageTooLarge(_) => "message_too_large",
+            DecodeError::DecompressedTooLarge(_) => "decompressed_too_large",
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Malformed(e) => f.write_str(e),
+            DecodeError::MessageTooLarge(size) => write!(f, "消息过大: {} 字节", size),
+            DecodeError::DecompressedTooLarge(size) => {
+                write!(f, "解压后数据过大: 超过 {} 字节", size)
+            }
+        }
+    }
+}
+
+/// 长连接协议参数，默认值对应官方弹幕服务器
+///
+/// 经自建网关转发时，操作码、头长度和版本号可能与官方不同，可按连接单独配置。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Protocol {
+    pub header_length: u16,
+    pub version: u16,
+    /// 包体为 zlib 压缩嵌套包时使用的版本号
+    pub zlib_version: u16,
+    /// 包体为 brotli 压缩嵌套包时使用的版本号
+    pub brotli_version: u16,
+    /// 鉴权包中请求的 protover（0 不压缩、2 zlib、3 brotli），None 时保持鉴权包原样
+    pub auth_protover: Option<u16>,
+    pub op_heartbeat: u32,
+    pub op_heartbeat_reply: u32,
+    pub op_message: u32,
+    pub op_auth: u32,
+    pub op_auth_reply: u32,
+    /// 单条 WebSocket 消息的最大字节数
+    pub max_message_size: usize,
+    /// 一条消息中所有压缩包体解压后的最大总字节数，防止压缩炸弹
+    pub max_decompressed_size: usize,
+}
+
+impl Default for Protocol {
+    fn default() -> Self {
+        Self {
+            header_length: 16,
+            version: 0,
+            zlib_version: 2,
+            brotli_version: 3,
+            auth_protover: None,
+            op_heartbeat: 2,
+            op_heartbeat_reply: 3,
+            op_message: 5,
+            op_auth: 7,
+            op_auth_reply: 8,
+            max_message_size: 1 << 20,
+            max_decompressed_size: 8 << 20,
+        }
+    }
+}
+
+impl Protocol {
+    /// 头中固定字段的长度，`header_length` 不能小于它
+    pub const MIN_HEADER_LENGTH: u16 = 16;
+
+    /// 按名称设置单个参数，名称未知或取值越界时返回 false
+    pub fn set(&mut self, key: &str, value: i64) -> bool {
+        match key {
+            "header_length" => match u16::try_from(value) {
+                Ok(v) if v >= Self::MIN_HEADER_LENGTH => self.header_length = v,
+                _ => return false,
+            },
+            "version" => return set_u16(&mut self.version, value),
+            "zlib_version" => return set_u16(&mut self.zlib_version, value),
+            "brotli_version" => return set_u16(&mut self.brotli_version, value),
+            // 负数表示恢复为不改写鉴权包
+            "auth_protover" if value < 0 => self.auth_protover = None,
+            "auth_protover" => match u16::try_from(value) {
+                Ok(v) => self.auth_protover = Some(v),
+                Err(_) => return false,
+            },
+            "op_heartbeat" => return set_u32(&mut self.op_heartbeat, value),
+            "op_heartbeat_reply" => return set_u32(&mut self.op_heartbeat_reply, value),
+            "op_message" => return set_u32(&mut self.op_message, value),
+            "op_auth" => return set_u32(&mut self.op_auth, value),
+            "op_auth_reply" => return set_u32(&mut self.op_auth_reply, value),
+            "max_message_size" => return set_size(&mut self.max_message_size, value),
+            "max_decompressed_size" => return set_size(&mut self.max_decompressed_size, value),
+            _ => return false,
+        }
+        true
+    }
+
+    /// 按 `auth_protover` 改写鉴权包的 protover 字段，鉴权包不是 JSON 对象时原样返回
+    pub fn auth_body(&self, auth_body: &str) -> String {
+        let Some(protover) = self.auth_protover else {
+            return auth_body.to_string();
+        };
+        match serde_json::from_str::<Value>(auth_body) {
+            Ok(Value::Object(mut body)) => {
+                body.insert("protover".to_string(), protover.into());
+                Value::Object(body).to_string()
+            }
+            _ => auth_body.to_string(),
+        }
+    }
+
+    /// 封包：头（包长、头长、版本、操作码、序列号，超出 16 字节的部分补 0）+ 包体
+    pub fn encode_packet(&self, body: &[u8], operation: u32) -> Vec<u8> {
+        self.encode_packet_with_sequence(body, operation, 1)
+    }
+
+    pub fn encode_packet_with_sequence(
+        &self,
+        body: &[u8],
+        operation: u32,
+        sequence: u32,
+    ) -> Vec<u8> {
+        let packet_length = self.header_length as u32 + body.len() as u32;
+        let mut packet = Vec::with_capacity(packet_length as usize);
+        packet.extend_from_slice(&packet_length.to_be_bytes());
+        packet.extend_from_slice(&self.header_length.to_be_bytes());
+        packet.extend_from_slice(&self.version.to_be_bytes());
+        packet.extend_from_slice(&operation.to_be_bytes());
+        packet.extend_from_slice(&sequence.to_be_bytes());
+        packet.resize(self.header_length as usize, 0);
+        packet.extend_from_slice(body);
+        packet
+    }
+
+    /// 解包，返回 (操作码, 包体) 列表；压缩版本的包体为 zlib 或 brotli 压缩的嵌套包
+    pub fn decode_packet(&self, data: &[u8]) -> Result<Vec<(u32, Vec<u8>)>, DecodeError> {
+        let packets = self.decode_packet_with_sequence(data)?;
+        Ok(packets
+            .into_iter()
+            .map(|(operation, _, body)| (operation, body))
+            .collect())
+    }
+
+    /// 与 `decode_packet` 相同，另返回每个包的序列号：(操作码, 序列号, 包体)；
+    /// 压缩包只返回解压出的内层包
+    pub fn decode_packet_with_sequence(
+        &self,
+        data: &[u8],
+    ) -> Result<Vec<(u32, u32, Vec<u8>)>, DecodeError> {
+        if data.len() > self.max_message_size {
+            return Err(DecodeError::MessageTooLarge(data.len()));
+        }
+        let mut budget = self.max_decompressed_size;
+        self.decode_nested(data, &mut budget)
+    }
+
+    /// budget 为剩余可解压的字节数，嵌套包共用
+    fn decode_nested(
+        &self,
+        data: &[u8],
+        budget: &mut usize,
+    ) -> Result<Vec<(u32, u32, Vec<u8>)>, DecodeError> {
+        let mut packets = Vec::new();
+        let mut cursor = Cursor::new(data);
+
+        while (cursor.position() as usize) < data.len() {
+            let mut buf4 = [0u8; 4];
+            let mut buf2 = [0u8; 2];
+
+            cursor
+                .read_exact(&mut buf4)
+                .map_err(|e| DecodeError::Malformed(format!("读取包长度失败: {}", e)))?;
+            let packet_length = u32::from_be_bytes(buf4);
+            cursor
+                .read_exact(&mut buf2)
+                .map_err(|e| DecodeError::Malformed(format!("读取头长度失败: {}", e)))?;
+            let header_length = u16::from_be_bytes(buf2);
+            cursor
+                .read_exact(&mut buf2)
+                .map_err(|e| DecodeError::Malformed(format!("读取版本失败: {}", e)))?;
+            let version = u16::from_be_bytes(buf2);
+            cursor
+                .read_exact(&mut buf4)
+                .map_err(|e| DecodeError::Malformed(format!("读取操作码失败: {}", e)))?;
+            let operation = u32::from_be_bytes(buf4);
+            cursor
+                .read_exact(&mut buf4)
+                .map_err(|e| DecodeError::Malformed(format!("读取序列号失败: {}", e)))?;
+            let sequence = u32::from_be_bytes(buf4);
+
+            if header_length < Self::MIN_HEADER_LENGTH {
+                return Err(DecodeError::Malformed(format!(
+                    "头长度无效: {}",
+                    header_length
+                )));
+            }
+            let mut extra = vec![0u8; (header_length - Self::MIN_HEADER_LENGTH) as usize];
+            cursor
+                .read_exact(&mut extra)
+                .map_err(|e| DecodeError::Malformed(format!("读取扩展头失败: {}", e)))?;
+
+            let body_length = (packet_length as usize).saturating_sub(header_length as usize);
+            let mut body = vec![0u8; body_length];
+            cursor
+                .read_exact(&mut body)
+                .map_err(|e| DecodeError::Malformed(format!("读取包体失败: {}", e)))?;
+
+            if version == self.zlib_version {
+                let decompressed = self.decompress(ZlibDecoder::new(&body[..]), budget)?;
+                packets.extend(self.decode_nested(&decompressed, budget)?);
+            } else if version == self.brotli_version {
+                let decompressed =
+                    self.decompress(brotli::Decompressor::new(&body[..], 4096), budget)?;
+                packets.extend(self.decode_nested(&decompressed, budget)?);
+            } else {
+                packets.push((operation, sequence, body));
+            }
+        }
+
+        Ok(packets)
+    }
+
+    /// 心跳回复包体中的人气值（大端 u32），包体不足 4 字节时返回错误
+    pub fn decode_popularity(body: &[u8]) -> Result<u32, DecodeError> {
+        let bytes: [u8; 4] = body
+            .get(..4)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| {
+                DecodeError::Malformed(format!("心跳回复包体过短: {} 字节", body.len()))
+            })?;
+        Ok(u32::from_be_bytes(bytes))
+    }
+
+    /// 最多读取 budget + 1 字节，超出即放弃，不会为压缩炸弹分配更多内存
+    fn decompress(&self, reader: impl Read, budget: &mut usize) -> Result<Vec<u8>, DecodeError> {
+        let mut decompressed = Vec::new();
+        reader
+            .take(*budget as u64 + 1)
+            .read_to_end(&mut decompressed)
+            .map_err(|e| DecodeError::Malformed(format!("解压失败: {}", e)))?;
+        if decompressed.len() > *budget {
+            return Err(DecodeError::DecompressedTooLarge(
+                self.max_decompressed_size,
+            ));
+        }
+        *budget -= decompressed.len();
+        Ok(decompressed)
+    }
+}
+
+fn set_size(field: &mut usize, value: i64) -> bool {
+    match usize::try_from(value) {
+        Ok(v) if v > 0 => {
+            *field = v;
+            true
+        }
+        _ => false,
+    }
+}
+
+fn set_u16(field: &mut u16, value: i64) -> bool {
+    match u16::try_from(value) {
+        Ok(v) => {
+            *field = v;
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+fn set_u32(field: &mut u32, value: i64) -> bool {
+    match u32::try_from(value) {
+        Ok(v) => {
+            *field = v;
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    #[test]
+    fn round_trip_default_protocol() {
+        let protocol = Protocol::default();
+        let packet = protocol.encode_packet(b"{}", 7);
+        assert_eq!(
+            &packet[..16],
+            &[0, 0, 0, 18, 0, 16, 0, 0, 0, 0, 0, 7, 0, 0, 0, 1]
+        );
+        assert_eq!(
+            protocol.decode_packet(&packet).unwrap(),
+            vec![(7, b"{}".to_vec())]
+        );
+    }
+
+    #[test]
+    fn decodes_heartbeat_reply_popularity() {
+        // 服务器心跳回复的原始帧：版本 1、操作码 3、序列号 0，包体为大端人气值 14895
+        let frame = [
+            0x00, 0x00, 0x00, 0x14, 0x00, 0x10, 0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x3a, 0x2f,
+        ];
+        let protocol = Protocol::default();
+        let packets = protocol.decode_packet(&frame).unwrap();
+        assert_eq!(
+            packets,
+            vec![(protocol.op_heartbeat_reply, vec![0, 0, 0x3a, 0x2f])]
+        );
+        assert_eq!(Protocol::decode_popularity(&packets[0].1).unwrap(), 14895);
+        assert!(Protocol::decode_popularity(&[0, 1]).is_err());
+    }
+
+    #[test]
+    fn decodes_zlib_nested_packets() {
+        let protocol = Protocol::default();
+        let mut inner = protocol.encode_packet(b"a", 5);
+        inner.extend(protocol.encode_packet(b"bc", 5));
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&inner).unwrap();
+
+        let compressed = Protocol {
+            version: 2,
+            ..Protocol::default()
+        }
+        .encode_packet(&encoder.finish().unwrap(), 5);
+        assert_eq!(
+            protocol.decode_packet(&compressed).unwrap(),
+            vec![(5, b"a".to_vec()), (5, b"bc".to_vec())]
+        );
+    }
+
+    #[test]
+    fn decodes_brotli_nested_packets() {
+        let protocol = Protocol::default();
+        let inner = protocol.encode_packet(b"hi", 5);
+        let mut compressed = Vec::new();
+        brotli::CompressorWriter::new(&mut compressed, 4096, 5, 22)
+            .write_all(&inner)
+            .unwrap();
+
+        let packet = Protocol {
+            version: 3,
+            ..Protocol::default()
+        }
+        .encode_packet(&compressed, 5);
+        assert_eq!(
+            protocol.decode_packet(&packet).unwrap(),
+            vec![(5, b"hi".to_vec())]
+        );
+    }
+
+    #[test]
+    fn rewrites_auth_protover() {
+        let mut protocol = Protocol::default();
+        let body = r#"{"roomid":1,"protover":2,"key":"k"}"#;
+        assert_eq!(protocol.auth_body(body), body);
+
+        assert!(protocol.set("auth_protover", 3));
+        let rewritten: Value = serde_json::from_str(&protocol.auth_body(body)).unwrap();
+        assert_eq!(rewritten["protover"], 3);
+        assert_eq!(rewritten["key"], "k");
+        assert_eq!(protocol.auth_body("not json"), "not json");
+
+        assert!(protocol.set("auth_protover", -1));
+        assert_eq!(protocol.auth_protover, None);
+    }
+
+    #[test]
+    fn custom_header_length_and_ops() {
+        let mut protocol = Protocol::default();
+        assert!(protocol.set("header_length", 20));
+        assert!(protocol.set("op_auth", 107));
+        assert!(!protocol.set("header_length", 8));
+        assert!(!protocol.set("op_auth", -1));
+        assert!(!protocol.set("unknown", 1));
+
+        let packet = protocol.encode_packet(b"x", protocol.op_auth);
+        assert_eq!(packet.len(), 21);
+        assert_eq!(
+            protocol.decode_packet(&packet).unwrap(),
+            vec![(107, b"x".to_vec())]
+        );
+        let packet = protocol.encode_packet_with_sequence(b"y", protocol.op_message, 42);
+        assert_eq!(
+            protocol.decode_packet_with_sequence(&packet).unwrap(),
+            vec![(5, 42, b"y".to_vec())]
+        );
+    }
+
+    #[test]
+    fn drops_oversized_messages_and_zlib_bombs() {
+        let mut protocol = Protocol::default();
+        assert!(protocol.set("max_message_size", 64));
+        assert!(protocol.set("max_decompressed_size", 1024));
+        assert!(!protocol.set("max_decompressed_size", 0));
+
+        let large = protocol.encode_packet(&[b'x'; 100], 5);
+        assert_eq!(
+            protocol.decode_packet(&large),
+            Err(DecodeError::MessageTooLarge(116))
+        );
+
+        // 30 字节左右的压缩包体解压后为 4 KiB
+        let inner = protocol.encode_packet(&[0u8; 4096], 5);
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&inner).unwrap();
+        let mut bomb = protocol.encode_packet(&encoder.finish().unwrap(), 5);
+        bomb[6..8].copy_from_slice(&protocol.zlib_version.to_be_bytes());
+        let error = protocol.decode_packet(&bomb).unwrap_err();
+        assert_eq!(error, DecodeError::DecompressedTooLarge(1024));
+        assert_eq!(error.kind(), "decompressed_too_large");
+
+        assert!(protocol.set("max_decompressed_size", 8192));
+        assert_eq!(protocol.decode_packet(&bomb).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn truncated_packet_is_an_error() {
+        let packet = Protocol::default().encode_packet(b"hello", 5);
+        assert!(Protocol::default()
+            .decode_packet(&packet[..packet.len() - 1])
+            .is_err());
+    }
+}
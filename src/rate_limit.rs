@@ -0,0 +1,128 @@
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// 开放平台请求限流排队的最长等待（秒），超过后直接拒绝
+pub const MAX_QUEUE_WAIT_SECS: f64 = 10.0;
+
+/// 令牌桶：每秒补充 rate 个令牌，最多积攒 burst 个
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    last: f64,
+}
+
+impl TokenBucket {
+    /// rate 为 0 或负数时不限流
+    pub fn new(rate: f64, burst: f64) -> Self {
+        let burst = burst.max(1.0);
+        Self {
+            rate,
+            burst,
+            tokens: burst,
+            last: 0.0,
+        }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.rate <= 0.0
+    }
+
+    fn refill(&mut self, now: f64) {
+        let elapsed = (now - self.last).max(0.0);
+        self.last = now;
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+    }
+
+    /// 有令牌时取走一个并返回 true；now 为单调递增的秒数
+    pub fn try_take(&mut self, now: f64) -> bool {
+        if self.is_unlimited() {
+            return true;
+        }
+        self.refill(now);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+
+    /// 预占一个令牌并返回需要等待的秒数（有令牌时为 0）；需要等待超过 max_wait 秒时不预占，返回 None
+    pub fn reserve(&mut self, now: f64, max_wait: f64) -> Option<f64> {
+        if self.is_unlimited() {
+            return Some(0.0);
+        }
+        self.refill(now);
+        let wait = ((1.0 - self.tokens) / self.rate).max(0.0);
+        if wait > max_wait {
+            return None;
+        }
+        self.tokens -= 1.0;
+        Some(wait)
+    }
+}
+
+/// 多个线程共用的令牌桶，时间从创建时开始计算
+#[derive(Debug)]
+pub struct SharedLimiter {
+    bucket: Mutex<(f64, TokenBucket)>,
+    epoch: Instant,
+}
+
+impl Default for SharedLimiter {
+    fn default() -> Self {
+        Self {
+            bucket: Mutex::new((0.0, TokenBucket::new(0.0, 1.0))),
+            epoch: Instant::now(),
+        }
+    }
+}
+
+impl SharedLimiter {
+    /// 每秒 rate 次、最多连续 rate 次；与当前速率不同时重建令牌桶，rate 为 0 表示不限
+    pub fn configure(&self, rate: f64) {
+        let mut bucket = self.bucket.lock().unwrap();
+        if bucket.0 != rate {
+            *bucket = (rate, TokenBucket::new(rate, rate));
+        }
+    }
+
+    /// 见 `TokenBucket::reserve`
+    pub fn reserve(&self, max_wait: f64) -> Option<f64> {
+        let now = self.epoch.elapsed().as_secs_f64();
+        self.bucket.lock().unwrap().1.reserve(now, max_wait)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_to_rate_after_burst() {
+        let mut bucket = TokenBucket::new(2.0, 3.0);
+        assert!((0..3).all(|_| bucket.try_take(0.0)));
+        assert!(!bucket.try_take(0.0));
+        assert!(!bucket.try_take(0.4));
+        assert!(bucket.try_take(0.5));
+        assert!(!bucket.try_take(0.5));
+        assert!(bucket.try_take(10.0));
+
+        let mut unlimited = TokenBucket::new(0.0, 1.0);
+        assert!((0..100).all(|_| unlimited.try_take(0.0)));
+    }
+
+    #[test]
+    fn queues_reservations_up_to_max_wait() {
+        let mut bucket = TokenBucket::new(2.0, 2.0);
+        assert_eq!(bucket.reserve(0.0, 0.0), Some(0.0));
+        assert_eq!(bucket.reserve(0.0, 0.0), Some(0.0));
+        // 不排队时直接拒绝
+        assert_eq!(bucket.reserve(0.0, 0.0), None);
+        assert_eq!(bucket.reserve(0.0, 1.0), Some(0.5));
+        assert_eq!(bucket.reserve(0.0, 1.0), Some(1.0));
+        assert_eq!(bucket.reserve(0.0, 1.0), None);
+        assert_eq!(bucket.reserve(1.0, 1.0), Some(0.5));
+    }
+}
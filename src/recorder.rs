@@ -0,0 +1,212 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// 录制会话目录名的前缀，后接开始录制的 Unix 毫秒
+const SESSION_PREFIX: &str = "session-";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecorderOptions {
+    /// 单个分段的最大字节数，0 表示不限
+    pub max_segment_bytes: u64,
+    /// 单个分段的最长时长（毫秒），0 表示不限
+    pub max_segment_ms: i64,
+    /// 最多保留多少个录制会话（含本次），0 表示不清理
+    pub keep_sessions: usize,
+}
+
+/// 把长连接消息逐行写入 `<root>/session-<毫秒>/segment-0001.jsonl`，
+/// 每行为 `{"t": Unix 毫秒, "cmd": ..., "data": 原始消息}`；
+/// 分段超过大小或时长限制后切换到下一个分段，结束的分段由调用方决定是否压缩
+pub struct SessionRecorder {
+    session_dir: PathBuf,
+    options: RecorderOptions,
+    segment: usize,
+    segment_path: PathBuf,
+    writer: BufWriter<File>,
+    segment_bytes: u64,
+    segment_started_ms: i64,
+}
+
+impl SessionRecorder {
+    /// 在 root 下新建本次的会话目录，并按 keep_sessions 删除最早的会话
+    pub fn start(root: &Path, options: RecorderOptions, now_ms: i64) -> Result<Self, String> {
+        fs::create_dir_all(root).map_err(|e| format!("创建录制目录失败: {}", e))?;
+        let mut session_ms = now_ms;
+        let session_dir = loop {
+            let dir = root.join(format!("{}{}", SESSION_PREFIX, session_ms));
+            if !dir.exists() {
+                break dir;
+            }
+            session_ms += 1;
+        };
+        fs::create_dir(&session_dir).map_err(|e| format!("创建录制目录失败: {}", e))?;
+        if options.keep_sessions > 0 {
+            prune_sessions(root, options.keep_sessions)?;
+        }
+        let segment_path = segment_path(&session_dir, 1);
+        Ok(Self {
+            writer: open_segment(&segment_path)?,
+            session_dir,
+            options,
+            segment: 1,
+            segment_path,
+            segment_bytes: 0,
+            segment_started_ms: now_ms,
+        })
+    }
+
+    pub fn session_dir(&self) -> &Path {
+        &self.session_dir
+    }
+
+    /// 追加一条消息，data_json 为原始消息的 JSON 文本；需要换分段时先切换，返回刚结束的分段
+    pub fn record(
+        &mut self,
+        cmd: &str,
+        data_json: &str,
+        now_ms: i64,
+    ) -> Result<Option<PathBuf>, String> {
+        let line = format!(
+            "{{\"t\":{},\"cmd\":{},\"data\":{}}}\n",
+            now_ms,
+            serde_json::Value::from(cmd),
+            data_json
+        );
+        let too_large = self.options.max_segment_bytes > 0
+            && self.segment_bytes + line.len() as u64 > self.options.max_segment_bytes;
+        let too_long = self.options.max_segment_ms > 0
+            && now_ms - self.segment_started_ms >= self.options.max_segment_ms;
+        let finished = if self.segment_bytes > 0 && (too_large || too_long) {
+            Some(self.rotate(now_ms)?)
+        } else {
+            None
+        };
+        self.writer
+            .write_all(line.as_bytes())
+            .map_err(|e| format!("写入录制文件失败: {}", e))?;
+        self.segment_bytes += line.len() as u64;
+        Ok(finished)
+    }
+
+    /// 停止录制，返回最后一个分段
+    pub fn finish(mut self) -> Result<PathBuf, String> {
+        self.writer
+            .flush()
+            .map_err(|e| format!("写入录制文件失败: {}", e))?;
+        Ok(self.segment_path)
+    }
+
+    fn rotate(&mut self, now_ms: i64) -> Result<PathBuf, String> {
+        self.segment += 1;
+        let next_path = segment_path(&self.session_dir, self.segment);
+        let next = open_segment(&next_path)?;
+        std::mem::replace(&mut self.writer, next)
+            .flush()
+            .map_err(|e| format!("写入录制文件失败: {}", e))?;
+        self.segment_bytes = 0;
+        self.segment_started_ms = now_ms;
+        Ok(std::mem::replace(&mut self.segment_path, next_path))
+    }
+}
+
+fn segment_path(session_dir: &Path, segment: usize) -> PathBuf {
+    session_dir.join(format!("segment-{:04}.jsonl", segment))
+}
+
+fn open_segment(path: &Path) -> Result<BufWriter<File>, String> {
+    File::create(path)
+        .map(BufWriter::new)
+        .map_err(|e| format!("创建录制文件失败: {}", e))
+}
+
+/// 把结束的分段压缩为同名 .gz 文件并删除原文件，返回压缩后的路径
+pub fn compress_segment(path: &Path) -> Result<PathBuf, String> {
+    let mut gz_name = path.as_os_str().to_os_string();
+    gz_name.push(".gz");
+    let gz_path = PathBuf::from(gz_name);
+    let mut input = File::open(path).map_err(|e| format!("读取录制文件失败: {}", e))?;
+    let output = File::create(&gz_path).map_err(|e| format!("创建压缩文件失败: {}", e))?;
+    let mut encoder = GzEncoder::new(BufWriter::new(output), Compression::default());
+    std::io::copy(&mut input, &mut encoder)
+        .and_then(|_| encoder.finish())
+        .and_then(|mut writer| writer.flush())
+        .map_err(|e| format!("压缩录制文件失败: {}", e))?;
+    fs::remove_file(path).map_err(|e| format!("删除录制文件失败: {}", e))?;
+    Ok(gz_path)
+}
+
+/// 只保留最近 keep 个会话目录，返回被删除的目录
+fn prune_sessions(root: &Path, keep: usize) -> Result<Vec<PathBuf>, String> {
+    let mut sessions: Vec<(i64, PathBuf)> = fs::read_dir(root)
+        .map_err(|e| format!("读取录制目录失败: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let started_ms = name.strip_prefix(SESSION_PREFIX)?.parse().ok()?;
+            Some((started_ms, entry.path()))
+        })
+        .collect();
+    sessions.sort();
+    let excess = sessions.len().saturating_sub(keep);
+    let mut removed = Vec::new();
+    for (_, dir) in sessions.into_iter().take(excess) {
+        fs::remove_dir_all(&dir).map_err(|e| format!("删除过期录制失败: {}", e))?;
+        removed.push(dir);
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn rotates_compresses_and_prunes() {
+        let root = std::env::temp_dir().join(format!("gdblive_recorder_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let options = RecorderOptions {
+            max_segment_bytes: 120,
+            max_segment_ms: 60_000,
+            keep_sessions: 2,
+        };
+        for started_ms in [1_000, 2_000] {
+            SessionRecorder::start(&root, options.clone(), started_ms).unwrap();
+        }
+
+        let mut recorder = SessionRecorder::start(&root, options, 3_000).unwrap();
+        let data = r#"{"cmd":"DANMU_MSG","info":[]}"#;
+        assert_eq!(recorder.record("DANMU_MSG", data, 3_000).unwrap(), None);
+        // 超出大小限制
+        let first = recorder.record("DANMU_MSG", data, 3_001).unwrap().unwrap();
+        assert!(first.ends_with("segment-0001.jsonl"));
+        // 超出时长限制
+        let second = recorder.record("LIVE", "{}", 63_001).unwrap().unwrap();
+        assert!(second.ends_with("segment-0002.jsonl"));
+        let last = recorder.finish().unwrap();
+        assert!(last.ends_with("segment-0003.jsonl"));
+
+        let gz = compress_segment(&first).unwrap();
+        assert!(!first.exists());
+        let mut text = String::new();
+        GzDecoder::new(File::open(&gz).unwrap())
+            .read_to_string(&mut text)
+            .unwrap();
+        let line: serde_json::Value = serde_json::from_str(text.trim()).unwrap();
+        assert_eq!(line["cmd"], "DANMU_MSG");
+        assert_eq!(line["t"], 3_000);
+
+        let mut sessions: Vec<_> = fs::read_dir(&root)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        sessions.sort();
+        assert_eq!(sessions, ["session-2000", "session-3000"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
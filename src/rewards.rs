@@ -0,0 +1,419 @@
+use crate::convert::{dictionary_to_json, json_to_dictionary, json_to_variant};
+use godot::classes::{IResource, Resource};
+use godot::prelude::*;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// 付费事件到奖励的映射规则
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RewardRuleSpec {
+    pub name: String,
+    pub event_type: String,
+    /// 只匹配该礼物名，空字符串表示任意礼物
+    pub gift_name: String,
+    /// 同一观众累计金额（千分之一元）达到该值发放一次奖励，0 表示每个匹配事件发放一次
+    pub threshold: i64,
+    /// 一次累计跨过多个阈值时是否叠加发放多份
+    pub stackable: bool,
+    /// 叠加时单次最多发放的份数，0 表示不限制
+    pub max_stack: i64,
+    /// 同一观众两次获得该奖励的最短间隔（秒），冷却期间金额继续累计
+    pub cooldown: f64,
+    /// 该规则发放后，同一事件不再评估后面的规则
+    pub exclusive: bool,
+    pub payload: Value,
+}
+
+impl RewardRuleSpec {
+    fn matches(&self, event_type: &str, data: &Value) -> bool {
+        self.event_type == event_type
+            && (self.gift_name.is_empty() || data["gift_name"].as_str() == Some(&self.gift_name))
+    }
+}
+
+/// 一次奖励发放
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reward {
+    pub rule_name: String,
+    pub user: Value,
+    pub payload: Value,
+}
+
+/// 按观众累计金额并处理叠加和冷却
+#[derive(Debug, Default)]
+pub struct RewardLedger {
+    /// (规则下标, user_id) -> 尚未兑换的累计金额
+    progress: HashMap<(usize, String), i64>,
+    last_fired: HashMap<(usize, String), f64>,
+}
+
+impl RewardLedger {
+    /// 按顺序评估规则，返回本次事件触发的奖励
+    pub fn evaluate(
+        &mut self,
+        rules: &[RewardRuleSpec],
+        event_type: &str,
+        data: &Value,
+        now: f64,
+    ) -> Vec<Reward> {
+        let user_id = match &data["user_id"] {
+            Value::String(id) => id.clone(),
+            Value::Null => String::new(),
+            other => other.to_string(),
+        };
+        let price = data["price"].as_i64().unwrap_or(0).max(0);
+        let mut rewards = Vec::new();
+
+        for (index, rule) in rules.iter().enumerate() {
+            if !rule.matches(event_type, data) {
+                continue;
+            }
+            let key = (index, user_id.clone());
+            let count = if rule.threshold <= 0 {
+                1
+            } else {
+                let total = self.progress.entry(key.clone()).or_insert(0);
+                *total += price;
+                *total / rule.threshold
+            };
+            if count == 0 {
+                continue;
+            }
+            let cooling = self
+                .last_fired
+                .get(&key)
+                .is_some_and(|last| now - last < rule.cooldown);
+            if cooling {
+                continue;
+            }
+
+            let count = match (rule.stackable, rule.max_stack) {
+                (false, _) => 1,
+                (true, max) if max > 0 => count.min(max),
+                (true, _) => count,
+            };
+            if rule.threshold > 0 {
+                let total = self.progress.entry(key.clone()).or_insert(0);
+                // 不叠加时一次兑换清空累计，叠加时保留不足一份的余额
+                *total = if rule.stackable {
+                    *total - count * rule.threshold
+                } else {
+                    0
+                };
+            }
+            self.last_fired.insert(key, now);
+
+            let mut payload = match &rule.payload {
+                Value::Object(map) => map.clone(),
+                _ => serde_json::Map::new(),
+            };
+            payload.insert("count".into(), json!(count));
+            payload.insert("event_type".into(), json!(event_type));
+            payload.insert("event".into(), data.clone());
+            rewards.push(Reward {
+                rule_name: rule.name.clone(),
+                user: json!({
+                    "user_id": user_id,
+                    "uname": data["uname"].as_str().unwrap_or_default(),
+                }),
+                payload: Value::Object(payload),
+            });
+            if rule.exclusive {
+                break;
+            }
+        }
+        rewards
+    }
+
+    pub fn clear(&mut self) {
+        self.progress.clear();
+        self.last_fired.clear();
+    }
+
+    /// 某位观众在各规则上尚未兑换的累计金额
+    pub fn progress(
+        &self,
+        rules: &[RewardRuleSpec],
+        user_id: &str,
+    ) -> serde_json::Map<String, Value> {
+        let mut progress = serde_json::Map::new();
+        for ((index, user), total) in &self.progress {
+            if user == user_id {
+                if let Some(rule) = rules.get(*index) {
+                    progress.insert(rule.name.clone(), json!(total));
+                }
+            }
+        }
+        progress
+    }
+}
+
+/// 一条奖励规则
+#[derive(GodotClass)]
+#[class(base=Resource)]
+pub struct RewardRule {
+    base: Base<Resource>,
+
+    #[export]
+    rule_name: GString,
+    /// gift / super_chat / guard 等付费事件
+    #[export]
+    event_type: GString,
+    /// 只匹配该礼物名，留空表示任意礼物
+    #[export]
+    gift_name: GString,
+    /// 同一观众累计金额（千分之一元）达到该值发放一次，0 表示每个事件发放
+    #[export]
+    threshold: i64,
+    #[export]
+    stackable: bool,
+    /// 叠加时单次最多发放的份数，0 表示不限制
+    #[export]
+    max_stack: i64,
+    #[export]
+    cooldown: f64,
+    /// 发放后不再评估后面的规则
+    #[export]
+    exclusive: bool,
+    /// 原样附加到 reward_triggered 的 payload 中
+    #[export]
+    payload: Dictionary,
+}
+
+#[godot_api]
+impl IResource for RewardRule {
+    fn init(base: Base<Resource>) -> Self {
+        Self {
+            base,
+            rule_name: GString::new(),
+            event_type: GString::from("gift"),
+            gift_name: GString::new(),
+            threshold: 0,
+            stackable: false,
+            max_stack: 0,
+            cooldown: 0.0,
+            exclusive: false,
+            payload: Dictionary::new(),
+        }
+    }
+}
+
+impl RewardRule {
+    fn spec(&self) -> RewardRuleSpec {
+        RewardRuleSpec {
+            name: self.rule_name.to_string(),
+            event_type: self.event_type.to_string(),
+            gift_name: self.gift_name.to_string(),
+            threshold: self.threshold,
+            stackable: self.stackable,
+            max_stack: self.max_stack,
+            cooldown: self.cooldown,
+            exclusive: self.exclusive,
+            payload: dictionary_to_json(&self.payload),
+        }
+    }
+}
+
+/// 奖励规则表，按顺序评估，可保存为 .tres 在多个场景间共用
+#[derive(GodotClass)]
+#[class(base=Resource)]
+pub struct RewardRuleSet {
+    base: Base<Resource>,
+
+    #[export]
+    rules: Array<Gd<RewardRule>>,
+}
+
+#[godot_api]
+impl IResource for RewardRuleSet {
+    fn init(base: Base<Resource>) -> Self {
+        Self {
+            base,
+            rules: Array::new(),
+        }
+    }
+}
+
+/// 按 RewardRuleSet 把付费事件转换为奖励信号
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct RewardEngine {
+    base: Base<Node>,
+
+    /// 要监听 `live_event` 的 Blive 节点，留空时可手动调用 `handle_live_event`
+    #[export]
+    blive_path: NodePath,
+    #[export]
+    rule_set: Option<Gd<RewardRuleSet>>,
+
+    ledger: RewardLedger,
+    elapsed: f64,
+}
+
+#[godot_api]
+impl INode for RewardEngine {
+    fn init(base: Base<Node>) -> Self {
+        Self {
+            base,
+            blive_path: NodePath::default(),
+            rule_set: None,
+            ledger: RewardLedger::default(),
+            elapsed: 0.0,
+        }
+    }
+
+    fn ready(&mut self) {
+        if self.blive_path.is_empty() {
+            return;
+        }
+        let path = self.blive_path.clone();
+        let Some(mut blive) = self.base().get_node_or_null(&path) else {
+            godot_warn!("RewardEngine: 找不到节点 {}", path);
+            return;
+        };
+        let callable = Callable::from_object_method(&self.to_gd(), "handle_live_event");
+        blive.connect("live_event", &callable);
+    }
+
+    fn process(&mut self, delta: f64) {
+        self.elapsed += delta;
+    }
+}
+
+#[godot_api]
+impl RewardEngine {
+    #[signal]
+    fn reward_triggered(rule_name: GString, user: Dictionary, payload: Dictionary);
+
+    /// 评估一个事件，返回发放的奖励数量
+    #[func]
+    fn handle_live_event(&mut self, event_type: GString, data: Dictionary) -> i64 {
+        let rules = self.specs();
+        let rewards = self.ledger.evaluate(
+            &rules,
+            &event_type.to_string(),
+            &dictionary_to_json(&data),
+            self.elapsed,
+        );
+        for reward in &rewards {
+            self.base_mut().emit_signal(
+                "reward_triggered",
+                &[
+                    GString::from(reward.rule_name.as_str()).to_variant(),
+                    json_to_variant(&reward.user),
+                    json_to_variant(&reward.payload),
+                ],
+            );
+        }
+        rewards.len() as i64
+    }
+
+    /// 某位观众在各规则上尚未兑换的累计金额，键为规则名
+    #[func]
+    fn get_progress(&self, user_id: GString) -> Dictionary {
+        let progress = self.ledger.progress(&self.specs(), &user_id.to_string());
+        json_to_dictionary(&progress)
+    }
+
+    /// 清空所有累计金额和冷却
+    #[func]
+    fn reset_progress(&mut self) {
+        self.ledger.clear();
+    }
+}
+
+impl RewardEngine {
+    fn specs(&self) -> Vec<RewardRuleSpec> {
+        let Some(rule_set) = &self.rule_set else {
+            return Vec::new();
+        };
+        let rules = rule_set.bind().rules.clone();
+        rules.iter_shared().map(|rule| rule.bind().spec()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, threshold: i64) -> RewardRuleSpec {
+        RewardRuleSpec {
+            name: name.into(),
+            event_type: "gift".into(),
+            threshold,
+            payload: json!({"item": name}),
+            ..Default::default()
+        }
+    }
+
+    fn gift(user_id: &str, price: i64) -> Value {
+        json!({"user_id": user_id, "uname": user_id, "gift_name": "辣条", "price": price})
+    }
+
+    #[test]
+    fn accumulates_towards_threshold_per_user() {
+        let rules = vec![rule("potion", 1000)];
+        let mut ledger = RewardLedger::default();
+        assert!(ledger
+            .evaluate(&rules, "gift", &gift("a", 600), 0.0)
+            .is_empty());
+        assert!(ledger
+            .evaluate(&rules, "gift", &gift("b", 600), 0.0)
+            .is_empty());
+        assert_eq!(ledger.progress(&rules, "a")["potion"], 600);
+
+        let rewards = ledger.evaluate(&rules, "gift", &gift("a", 600), 1.0);
+        assert_eq!(rewards.len(), 1);
+        assert_eq!(rewards[0].rule_name, "potion");
+        assert_eq!(rewards[0].user["user_id"], "a");
+        assert_eq!(rewards[0].payload["item"], "potion");
+        assert_eq!(rewards[0].payload["count"], 1);
+        assert_eq!(ledger.progress(&rules, "a")["potion"], 0);
+        assert!(ledger
+            .evaluate(&rules, "danmaku", &gift("a", 5000), 2.0)
+            .is_empty());
+    }
+
+    #[test]
+    fn stacks_up_to_max_and_keeps_remainder() {
+        let mut stacked = rule("coin", 100);
+        stacked.stackable = true;
+        stacked.max_stack = 3;
+        let rules = vec![stacked];
+        let mut ledger = RewardLedger::default();
+
+        let rewards = ledger.evaluate(&rules, "gift", &gift("a", 250), 0.0);
+        assert_eq!(rewards[0].payload["count"], 2);
+        assert_eq!(ledger.progress(&rules, "a")["coin"], 50);
+
+        let rewards = ledger.evaluate(&rules, "gift", &gift("a", 1000), 1.0);
+        assert_eq!(rewards[0].payload["count"], 3);
+        assert_eq!(ledger.progress(&rules, "a")["coin"], 750);
+    }
+
+    #[test]
+    fn cooldown_defers_reward_and_exclusive_stops_later_rules() {
+        let mut big = rule("big", 1000);
+        big.cooldown = 10.0;
+        big.exclusive = true;
+        let rules = vec![big, rule("any", 0)];
+        let mut ledger = RewardLedger::default();
+
+        let names = |rewards: Vec<Reward>| -> Vec<String> {
+            rewards.into_iter().map(|reward| reward.rule_name).collect()
+        };
+        assert_eq!(
+            names(ledger.evaluate(&rules, "gift", &gift("a", 1000), 0.0)),
+            ["big"]
+        );
+        assert_eq!(
+            names(ledger.evaluate(&rules, "gift", &gift("a", 1000), 5.0)),
+            ["any"]
+        );
+        assert_eq!(ledger.progress(&rules, "a")["big"], 1000);
+        assert_eq!(
+            names(ledger.evaluate(&rules, "gift", &gift("a", 0), 10.0)),
+            ["big"]
+        );
+    }
+}
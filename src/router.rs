@@ -0,0 +1,110 @@
+use crate::channel;
+use crate::convert::json_to_variant;
+use godot::prelude::*;
+
+/// 按 cmd 把长连接消息分发给子节点
+///
+/// 子节点通过两种方式声明要处理的 cmd：
+/// - 元数据 `blive_cmds`（字符串数组，`"*"` 表示全部），命中后调用 `handler_method(cmd, data)`
+/// - 定义 `_on_<cmd 小写>(data)` 方法，例如 `_on_live_open_platform_dm`
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct BliveRouter {
+    base: Base<Node>,
+
+    /// 要监听的 Blive 节点，留空时需手动把 `ws_message_received` 连接到 `route`
+    #[export]
+    blive_path: NodePath,
+    #[export]
+    handler_method: GString,
+    /// 同一场景有多个节点时用于区分来源：非空时信号中的字典参数都带有 channel 键；
+    /// 为空时读取节点元数据 `channel_name`
+    #[export]
+    channel_name: GString,
+}
+
+#[godot_api]
+impl INode for BliveRouter {
+    fn init(base: Base<Node>) -> Self {
+        Self {
+            base,
+            blive_path: NodePath::default(),
+            handler_method: GString::from("_on_blive_event"),
+            channel_name: GString::new(),
+        }
+    }
+
+    fn ready(&mut self) {
+        if self.blive_path.is_empty() {
+            return;
+        }
+        let path = self.blive_path.clone();
+        let Some(mut blive) = self.base().get_node_or_null(&path) else {
+            godot_warn!("BliveRouter: 找不到节点 {}", path);
+            return;
+        };
+        let callable = Callable::from_object_method(&self.to_gd(), "route");
+        blive.connect("ws_message_received", &callable);
+    }
+}
+
+#[godot_api]
+impl BliveRouter {
+    #[signal]
+    fn unhandled_event(cmd: GString, data: Variant);
+
+    /// 分发一条消息，返回处理它的子节点数量
+    #[func]
+    fn route(&mut self, cmd: GString, data_json: GString) -> i64 {
+        let json: serde_json::Value =
+            serde_json::from_str(&data_json.to_string()).unwrap_or_default();
+        let data = json_to_variant(&json["data"]);
+        let cmd_string = cmd.to_string();
+        let direct_method = format!("_on_{}", cmd_string.to_lowercase());
+        let handler_method = self.handler_method.to_string();
+
+        let mut handled = 0;
+        for mut child in self.base().get_children().iter_shared() {
+            if Self::declares_cmd(&child, &cmd_string) {
+                child.call(handler_method.as_str(), &[cmd.to_variant(), data.clone()]);
+                handled += 1;
+            } else if child.has_method(direct_method.as_str()) {
+                child.call(direct_method.as_str(), std::slice::from_ref(&data));
+                handled += 1;
+            }
+        }
+
+        if handled == 0 {
+            self.emit("unhandled_event", &[cmd.to_variant(), data]);
+        }
+        handled
+    }
+}
+
+impl BliveRouter {
+    /// 发出信号，设置了频道名时字典参数带上 channel 键
+    fn emit(&mut self, signal: &str, args: &[Variant]) {
+        let channel = channel::resolve(&self.base(), &self.channel_name);
+        let args = channel::tag(&channel, args);
+        self.base_mut().emit_signal(signal, &args);
+    }
+
+    fn declares_cmd(child: &Gd<Node>, cmd: &str) -> bool {
+        if !child.has_meta("blive_cmds") {
+            return false;
+        }
+        let meta = child.get_meta("blive_cmds");
+        let cmds: Vec<String> = if let Ok(array) = meta.try_to::<PackedStringArray>() {
+            array.as_slice().iter().map(|s| s.to_string()).collect()
+        } else if let Ok(array) = meta.try_to::<Array<Variant>>() {
+            array
+                .iter_shared()
+                .map(|item| item.stringify().to_string())
+                .collect()
+        } else {
+            vec![meta.stringify().to_string()]
+        };
+        cmds.iter()
+            .any(|declared| declared == cmd || declared == "*")
+    }
+}
@@ -0,0 +1,160 @@
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+struct ScheduledEvent {
+    id: i64,
+    event_type: String,
+    data: Value,
+    next_fire: f64,
+    /// 重复间隔（秒），0 表示只触发一次
+    interval: f64,
+    /// 剩余触发次数，负数表示不限次数
+    remaining: i64,
+    fired: i64,
+}
+
+/// 游戏注册的定时/周期合成事件，到期后与平台事件一样经 `live_event` 发出
+#[derive(Debug, Default)]
+pub struct EventScheduler {
+    events: Vec<ScheduledEvent>,
+    next_id: i64,
+}
+
+impl EventScheduler {
+    /// 注册一个合成事件，delay 秒后首次触发；interval > 0 时按该间隔重复，
+    /// repeat 为总触发次数（0 表示不限），返回计划 ID
+    pub fn schedule(
+        &mut self,
+        event_type: &str,
+        data: Value,
+        now: f64,
+        delay: f64,
+        interval: f64,
+        repeat: i64,
+    ) -> i64 {
+        self.next_id += 1;
+        let interval = interval.max(0.0);
+        let remaining = if interval <= 0.0 {
+            1
+        } else if repeat > 0 {
+            repeat
+        } else {
+            -1
+        };
+        self.events.push(ScheduledEvent {
+            id: self.next_id,
+            event_type: event_type.to_string(),
+            data: if data.is_object() { data } else { json!({}) },
+            next_fire: now + delay.max(0.0),
+            interval,
+            remaining,
+            fired: 0,
+        });
+        self.next_id
+    }
+
+    pub fn cancel(&mut self, id: i64) -> bool {
+        let before = self.events.len();
+        self.events.retain(|event| event.id != id);
+        self.events.len() != before
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// 取出到期的事件，按触发时间排序；data 附加 synthetic、schedule_id 和 fire_count。
+    /// 一帧跨过多个周期时只补发一次，避免游戏暂停后集中刷屏
+    pub fn due(&mut self, now: f64) -> Vec<(String, Value)> {
+        let mut due: Vec<(f64, String, Value)> = Vec::new();
+        for event in &mut self.events {
+            if event.next_fire > now {
+                continue;
+            }
+            event.fired += 1;
+            let mut data = event.data.clone();
+            data["synthetic"] = true.into();
+            data["schedule_id"] = event.id.into();
+            data["fire_count"] = event.fired.into();
+            due.push((event.next_fire, event.event_type.clone(), data));
+
+            if event.remaining > 0 {
+                event.remaining -= 1;
+            }
+            if event.interval > 0.0 {
+                while event.next_fire <= now {
+                    event.next_fire += event.interval;
+                }
+            }
+        }
+        self.events.retain(|event| event.remaining != 0);
+        due.sort_by(|a, b| a.0.total_cmp(&b.0));
+        due.into_iter()
+            .map(|(_, event_type, data)| (event_type, data))
+            .collect()
+    }
+
+    /// 尚未结束的计划，包含 id、event_type、data、remaining（距下次触发的秒数）、interval 和 fired
+    pub fn pending(&self, now: f64) -> Vec<Value> {
+        self.events
+            .iter()
+            .map(|event| {
+                json!({
+                    "id": event.id,
+                    "event_type": event.event_type,
+                    "data": event.data,
+                    "remaining": (event.next_fire - now).max(0.0),
+                    "interval": event.interval,
+                    "fired": event.fired,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fires_once_and_repeats() {
+        let mut scheduler = EventScheduler::default();
+        let once = scheduler.schedule(
+            "announcement",
+            json!({"message": "投票 30 秒后截止"}),
+            0.0,
+            30.0,
+            0.0,
+            0,
+        );
+        let tick = scheduler.schedule("tick", json!(null), 0.0, 1.0, 10.0, 2);
+
+        assert!(scheduler.due(0.5).is_empty());
+        let fired = scheduler.due(1.0);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].0, "tick");
+        assert_eq!(fired[0].1["schedule_id"], tick);
+        assert_eq!(fired[0].1["synthetic"], true);
+
+        // 一帧跨过多个周期时只补发一次
+        let fired = scheduler.due(31.0);
+        assert_eq!(fired.len(), 2);
+        assert_eq!(fired[0].0, "tick");
+        assert_eq!(fired[0].1["fire_count"], 2);
+        assert_eq!(fired[1].1["message"], "投票 30 秒后截止");
+        assert_eq!(fired[1].1["schedule_id"], once);
+        assert!(scheduler.pending(31.0).is_empty());
+    }
+
+    #[test]
+    fn cancels_unlimited_repeats() {
+        let mut scheduler = EventScheduler::default();
+        let id = scheduler.schedule("tick", json!({}), 0.0, 0.0, 5.0, 0);
+        assert_eq!(scheduler.due(0.0).len(), 1);
+        assert_eq!(scheduler.due(100.0).len(), 1);
+        assert_eq!(scheduler.pending(100.0)[0]["remaining"], 5.0);
+        assert!(scheduler.cancel(id));
+        assert!(!scheduler.cancel(id));
+        assert!(scheduler.due(200.0).is_empty());
+    }
+}
@@ -0,0 +1,48 @@
+/// 单条连接上服务器包头序列号的跟踪；序列号为 0 的包（服务器未编号）不参与检查
+#[derive(Debug, Default)]
+pub struct SequenceTracker {
+    last: Option<u32>,
+}
+
+impl SequenceTracker {
+    /// 序列号不连续（跳号、重复或乱序）时返回 (期望值, 实际值)。
+    /// 跳号后以实际值为准继续跟踪；重复或回退的包不改变已记录的位置
+    pub fn observe(&mut self, sequence: u32) -> Option<(u32, u32)> {
+        if sequence == 0 {
+            return None;
+        }
+        let Some(last) = self.last else {
+            self.last = Some(sequence);
+            return None;
+        };
+        let expected = last.wrapping_add(1);
+        if sequence == expected {
+            self.last = Some(sequence);
+            return None;
+        }
+        if sequence > expected {
+            self.last = Some(sequence);
+        }
+        Some((expected, sequence))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_gaps_and_duplicates() {
+        let mut tracker = SequenceTracker::default();
+        assert_eq!(tracker.observe(0), None);
+        assert_eq!(tracker.observe(5), None);
+        assert_eq!(tracker.observe(6), None);
+        assert_eq!(tracker.observe(9), Some((7, 9)));
+        assert_eq!(tracker.observe(10), None);
+        // 重复或回退的包只报告，不回退位置
+        assert_eq!(tracker.observe(10), Some((11, 10)));
+        assert_eq!(tracker.observe(4), Some((11, 4)));
+        assert_eq!(tracker.observe(11), None);
+        assert_eq!(tracker.observe(0), None);
+    }
+}
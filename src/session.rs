@@ -0,0 +1,263 @@
+use serde_json::Value;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// 距上次心跳成功超过该秒数视为即将过期（平台约 60 秒无心跳关闭场次，心跳间隔 20 秒）
+pub const EXPIRING_AFTER_SECS: f64 = 45.0;
+
+/// `/v2/app/start` 成功响应中连接长连接所需的字段
+#[derive(Debug, Clone, PartialEq)]
+pub struct StartInfo {
+    pub game_id: String,
+    pub wss_links: Vec<String>,
+    pub auth_body: String,
+    pub anchor_info: Value,
+}
+
+impl StartInfo {
+    /// code 不为 0 或缺少场次 ID、鉴权包时返回 None
+    pub fn parse(response: &Value) -> Option<Self> {
+        if response["code"].as_i64() != Some(0) {
+            return None;
+        }
+        let data = &response["data"];
+        let game_id = data["game_info"]["game_id"]
+            .as_str()
+            .filter(|id| !id.is_empty())?;
+        let websocket_info = &data["websocket_info"];
+        let auth_body = websocket_info["auth_body"].as_str()?;
+        Some(Self {
+            game_id: game_id.to_string(),
+            wss_links: websocket_info["wss_link"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|link| link.as_str())
+                .map(str::to_string)
+                .collect(),
+            auth_body: auth_body.to_string(),
+            anchor_info: match &data["anchor_info"] {
+                Value::Object(info) => Value::Object(info.clone()),
+                _ => Value::Object(Default::default()),
+            },
+        })
+    }
+}
+
+/// 会话状态变化，对应 Blive 的 session_* 信号
+#[derive(Debug, Clone, PartialEq)]
+pub enum Transition {
+    Started(String),
+    HeartbeatOk(String),
+    Expiring(String),
+    /// (game_id, reason)
+    Ended(String, String),
+}
+
+/// 当前场次的生命周期：start 后进入活动状态，心跳成功刷新计时，
+/// 长时间没有成功心跳时进入即将过期状态，end 或被新场次替换时结束
+#[derive(Debug, Default)]
+pub struct SessionLifecycle {
+    game_id: Option<String>,
+    since_heartbeat_ok: f64,
+    expiring: bool,
+}
+
+impl SessionLifecycle {
+    pub fn start(&mut self, game_id: &str) -> Vec<Transition> {
+        let mut transitions = Vec::new();
+        if let Some(previous) = self.game_id.take() {
+            if previous != game_id {
+                transitions.push(Transition::Ended(previous, "replaced".to_string()));
+            }
+        }
+        self.game_id = Some(game_id.to_string());
+        self.since_heartbeat_ok = 0.0;
+        self.expiring = false;
+        transitions.push(Transition::Started(game_id.to_string()));
+        transitions
+    }
+
+    pub fn heartbeat_ok(&mut self, game_id: &str) -> Option<Transition> {
+        if self.game_id.as_deref() != Some(game_id) {
+            return None;
+        }
+        self.since_heartbeat_ok = 0.0;
+        self.expiring = false;
+        Some(Transition::HeartbeatOk(game_id.to_string()))
+    }
+
+    pub fn tick(&mut self, delta: f64) -> Option<Transition> {
+        let game_id = self.game_id.as_ref()?;
+        self.since_heartbeat_ok += delta;
+        if self.expiring || self.since_heartbeat_ok < EXPIRING_AFTER_SECS {
+            return None;
+        }
+        self.expiring = true;
+        Some(Transition::Expiring(game_id.clone()))
+    }
+
+    /// 结束指定场次，不是当前场次时返回 None
+    pub fn end(&mut self, game_id: &str, reason: &str) -> Option<Transition> {
+        if self.game_id.as_deref() != Some(game_id) {
+            return None;
+        }
+        self.game_id = None;
+        Some(Transition::Ended(game_id.to_string(), reason.to_string()))
+    }
+}
+
+/// 进程内缓存的 start 结果：节点释放后场次仍在平台上保持到心跳超时，重新创建的节点
+/// （如重新加载场景后）可以直接取回并重连，无需再次 start
+#[derive(Debug, Default)]
+pub struct SessionCache {
+    entry: Mutex<Option<CachedSession>>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedSession {
+    app_id: i64,
+    info: StartInfo,
+    /// start 成功或最近一次心跳成功的时刻
+    refreshed_at: Instant,
+}
+
+/// 所有 Blive 节点共用的缓存
+pub static SESSION_CACHE: SessionCache = SessionCache {
+    entry: Mutex::new(None),
+};
+
+impl SessionCache {
+    pub fn store(&self, app_id: i64, info: &StartInfo, now: Instant) {
+        *self.entry.lock().unwrap() = Some(CachedSession {
+            app_id,
+            info: info.clone(),
+            refreshed_at: now,
+        });
+    }
+
+    /// 心跳成功时顺延缓存的有效期
+    pub fn touch(&self, game_id: &str, now: Instant) {
+        if let Some(entry) = self.entry.lock().unwrap().as_mut() {
+            if entry.info.game_id == game_id {
+                entry.refreshed_at = now;
+            }
+        }
+    }
+
+    pub fn clear(&self, game_id: &str) {
+        let mut entry = self.entry.lock().unwrap();
+        if entry
+            .as_ref()
+            .is_some_and(|cached| cached.info.game_id == game_id)
+        {
+            *entry = None;
+        }
+    }
+
+    /// 同一 app_id 且未超过 ttl_secs 的缓存及其已缓存的秒数，过期的缓存同时被清除
+    pub fn get(&self, app_id: i64, ttl_secs: f64, now: Instant) -> Option<(StartInfo, f64)> {
+        let mut entry = self.entry.lock().unwrap();
+        let cached = entry.as_ref()?;
+        let age = now
+            .saturating_duration_since(cached.refreshed_at)
+            .as_secs_f64();
+        if age > ttl_secs {
+            *entry = None;
+            return None;
+        }
+        (cached.app_id == app_id).then(|| (cached.info.clone(), age))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_start_response() {
+        let response = json!({
+            "code": 0,
+            "data": {
+                "game_info": { "game_id": "g1" },
+                "websocket_info": { "auth_body": "{}", "wss_link": ["wss://a/sub", "wss://b/sub"] },
+                "anchor_info": { "room_id": 1, "uname": "主播" },
+            },
+        });
+        let info = StartInfo::parse(&response).unwrap();
+        assert_eq!(info.game_id, "g1");
+        assert_eq!(info.wss_links, ["wss://a/sub", "wss://b/sub"]);
+        assert_eq!(info.auth_body, "{}");
+        assert_eq!(info.anchor_info["uname"], "主播");
+
+        assert_eq!(
+            StartInfo::parse(&json!({"code": 7002, "message": "重复游戏"})),
+            None
+        );
+        let mut no_game = response.clone();
+        no_game["data"]["game_info"]["game_id"] = "".into();
+        assert_eq!(StartInfo::parse(&no_game), None);
+    }
+
+    #[test]
+    fn lifecycle_transitions() {
+        let mut session = SessionLifecycle::default();
+        assert_eq!(session.tick(100.0), None);
+        assert_eq!(session.start("g1"), vec![Transition::Started("g1".into())]);
+        assert_eq!(session.tick(30.0), None);
+        assert_eq!(session.tick(20.0), Some(Transition::Expiring("g1".into())));
+        assert_eq!(session.tick(20.0), None);
+
+        assert_eq!(session.heartbeat_ok("other"), None);
+        assert_eq!(
+            session.heartbeat_ok("g1"),
+            Some(Transition::HeartbeatOk("g1".into()))
+        );
+        assert_eq!(session.tick(30.0), None);
+
+        assert_eq!(
+            session.start("g2"),
+            vec![
+                Transition::Ended("g1".into(), "replaced".into()),
+                Transition::Started("g2".into())
+            ]
+        );
+        assert_eq!(session.end("g1", "ended"), None);
+        assert_eq!(
+            session.end("g2", "ended"),
+            Some(Transition::Ended("g2".into(), "ended".into()))
+        );
+        assert_eq!(session.tick(100.0), None);
+    }
+
+    #[test]
+    fn session_cache_expires_after_ttl() {
+        let cache = SessionCache::default();
+        let info = StartInfo {
+            game_id: "g1".to_string(),
+            wss_links: vec!["wss://a/sub".to_string()],
+            auth_body: "{}".to_string(),
+            anchor_info: json!({}),
+        };
+        let start = Instant::now();
+        let later = |secs: u64| start + std::time::Duration::from_secs(secs);
+        cache.store(7, &info, start);
+        assert_eq!(cache.get(8, 45.0, later(1)), None);
+        assert_eq!(cache.get(7, 45.0, later(10)), Some((info.clone(), 10.0)));
+        cache.touch("g1", later(40));
+        assert_eq!(
+            cache.get(7, 45.0, later(80)).map(|(_, age)| age),
+            Some(40.0)
+        );
+        assert_eq!(cache.get(7, 45.0, later(90)), None);
+        // 过期后不会因为 ttl 变大而恢复
+        assert_eq!(cache.get(7, 1000.0, later(90)), None);
+
+        cache.store(7, &info, later(100));
+        cache.clear("other");
+        assert!(cache.get(7, 45.0, later(100)).is_some());
+        cache.clear("g1");
+        assert_eq!(cache.get(7, 45.0, later(100)), None);
+    }
+}
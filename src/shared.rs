@@ -0,0 +1,121 @@
+use serde_json::Value;
+use tokio::sync::broadcast::{self, error::TryRecvError};
+
+/// 每个附加节点最多积压的信号数，超出后最旧的信号被丢弃
+pub const SHARED_CHANNEL_CAPACITY: usize = 1024;
+
+/// 主节点已解析并发出的信号，参数以 JSON 表示
+#[derive(Debug, Clone, PartialEq)]
+pub struct SharedSignal {
+    pub name: String,
+    pub args: Vec<Value>,
+}
+
+/// 主节点一侧的进程内广播，没有附加节点时不复制参数
+pub struct SharedSession {
+    tx: broadcast::Sender<SharedSignal>,
+}
+
+impl Default for SharedSession {
+    fn default() -> Self {
+        let (tx, _) = broadcast::channel(SHARED_CHANNEL_CAPACITY);
+        Self { tx }
+    }
+}
+
+impl SharedSession {
+    pub fn publish(&self, name: &str, args: impl FnOnce() -> Vec<Value>) {
+        if self.tx.receiver_count() == 0 {
+            return;
+        }
+        let _ = self.tx.send(SharedSignal {
+            name: name.to_string(),
+            args: args(),
+        });
+    }
+
+    pub fn subscribe(&self) -> SharedAttachment {
+        SharedAttachment {
+            rx: self.tx.subscribe(),
+        }
+    }
+
+    pub fn attachments(&self) -> usize {
+        self.tx.receiver_count()
+    }
+}
+
+/// 一次取出的结果：lagged 为因积压被丢弃的信号数，closed 表示主节点已释放
+#[derive(Debug, Default)]
+pub struct Drained {
+    pub signals: Vec<SharedSignal>,
+    pub lagged: u64,
+    pub closed: bool,
+}
+
+/// 附加节点一侧的只读订阅
+pub struct SharedAttachment {
+    rx: broadcast::Receiver<SharedSignal>,
+}
+
+impl SharedAttachment {
+    pub fn drain(&mut self) -> Drained {
+        let mut drained = Drained::default();
+        loop {
+            match self.rx.try_recv() {
+                Ok(signal) => drained.signals.push(signal),
+                Err(TryRecvError::Lagged(count)) => drained.lagged += count,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Closed) => {
+                    drained.closed = true;
+                    break;
+                }
+            }
+        }
+        drained
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn broadcasts_to_every_attachment() {
+        let session = SharedSession::default();
+        session.publish("live_event", || panic!("没有附加节点时不应复制参数"));
+
+        let mut first = session.subscribe();
+        let mut second = session.subscribe();
+        assert_eq!(session.attachments(), 2);
+        session.publish("live_event", || {
+            vec![json!("danmaku"), json!({"message": "hi"})]
+        });
+
+        for attachment in [&mut first, &mut second] {
+            let drained = attachment.drain();
+            assert_eq!(drained.signals.len(), 1);
+            assert_eq!(drained.signals[0].name, "live_event");
+            assert_eq!(drained.signals[0].args[1]["message"], "hi");
+            assert!(!drained.closed);
+        }
+        assert!(first.drain().signals.is_empty());
+    }
+
+    #[test]
+    fn reports_lag_and_closed_primary() {
+        let session = SharedSession::default();
+        let mut attachment = session.subscribe();
+        for i in 0..SHARED_CHANNEL_CAPACITY + 10 {
+            session.publish("ws_message_received", || vec![json!(i)]);
+        }
+        drop(session);
+
+        let drained = attachment.drain();
+        assert_eq!(drained.lagged, 10);
+        assert_eq!(drained.signals.len(), SHARED_CHANNEL_CAPACITY);
+        assert_eq!(drained.signals[0].args[0], 10);
+        assert!(drained.closed);
+    }
+}
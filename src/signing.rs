@@ -0,0 +1,188 @@
+use hmac::{Hmac, Mac};
+use md5::{Digest, Md5};
+use serde_json::json;
+use sha2::Sha256;
+use std::collections::BTreeMap;
+
+/// `/v2/app/start` 的请求体，身份码为空时返回错误
+pub fn start_body(code: &str, app_id: i64) -> Result<String, String> {
+    let code = code.trim();
+    if code.is_empty() {
+        return Err("身份码为空".to_string());
+    }
+    Ok(json!({ "code": code, "app_id": app_id }).to_string())
+}
+
+/// `/v2/app/end` 的请求体
+pub fn end_body(app_id: i64, game_id: &str) -> String {
+    json!({ "app_id": app_id, "game_id": game_id }).to_string()
+}
+
+/// `/v2/app/heartbeat` 的请求体
+pub fn heartbeat_body(game_id: &str) -> String {
+    json!({ "game_id": game_id }).to_string()
+}
+
+/// `/v2/app/batchHeartbeat` 的请求体
+pub fn batch_heartbeat_body(game_ids: &[String]) -> String {
+    json!({ "game_ids": game_ids }).to_string()
+}
+
+/// 开放平台签名头：对 `x-bili-*` 头按 key 排序拼接后做 HMAC-SHA256，
+/// 其中 `x-bili-content-md5` 为请求体原样字节的 MD5，因此签名时与发送时必须使用同一个字符串
+pub fn signed_headers(
+    body: &str,
+    access_key_id: &str,
+    access_key_secret: &str,
+    nonce: &str,
+    timestamp: &str,
+) -> BTreeMap<String, String> {
+    let mut headers = BTreeMap::new();
+    headers.insert("x-bili-accesskeyid".to_string(), access_key_id.to_string());
+    headers.insert("x-bili-content-md5".to_string(), content_md5(body));
+    headers.insert(
+        "x-bili-signature-method".to_string(),
+        "HMAC-SHA256".to_string(),
+    );
+    headers.insert("x-bili-signature-nonce".to_string(), nonce.to_string());
+    headers.insert("x-bili-signature-version".to_string(), "1.0".to_string());
+    headers.insert("x-bili-timestamp".to_string(), timestamp.to_string());
+
+    let sign_string = headers
+        .iter()
+        .map(|(key, value)| format!("{}:{}", key, value))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let signature = signature(&sign_string, access_key_secret);
+
+    headers.insert("Accept".to_string(), "application/json".to_string());
+    headers.insert("Content-Type".to_string(), "application/json".to_string());
+    headers.insert("Authorization".to_string(), signature);
+    headers
+}
+
+/// GET 请求的签名头：没有请求体，`x-bili-content-md5` 为空字节串的 MD5，也不带 Content-Type。
+/// 查询参数不参与签名，发送时使用 `canonical_query` 的结果
+pub fn signed_get_headers(
+    access_key_id: &str,
+    access_key_secret: &str,
+    nonce: &str,
+    timestamp: &str,
+) -> BTreeMap<String, String> {
+    let mut headers = signed_headers("", access_key_id, access_key_secret, nonce, timestamp);
+    headers.remove("Content-Type");
+    headers
+}
+
+/// 规范化的查询串：按键排序，键和值按 RFC 3986 编码（只保留字母、数字和 `-._~`），不含开头的 `?`
+pub fn canonical_query(params: &BTreeMap<String, String>) -> String {
+    params
+        .iter()
+        .map(|(key, value)| format!("{}={}", percent_encode(key), percent_encode(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+pub fn content_md5(body: &str) -> String {
+    let mut hasher = Md5::new();
+    hasher.update(body.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn signature(sign_string: &str, access_key_secret: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(access_key_secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(sign_string.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn escapes_identity_codes_and_game_ids() {
+        let code = r#"AB"C\D"#;
+        let body = start_body(code, 42).unwrap();
+        assert_eq!(body, r#"{"app_id":42,"code":"AB\"C\\D"}"#);
+        let parsed: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(parsed["code"], code);
+        assert_eq!(start_body("  ", 42), Err("身份码为空".to_string()));
+
+        let game_id = "g\"1\n";
+        let parsed: Value = serde_json::from_str(&end_body(42, game_id)).unwrap();
+        assert_eq!(parsed["game_id"], game_id);
+        let batch = batch_heartbeat_body(&["a".into(), "b\"".into()]);
+        assert_eq!(batch, r#"{"game_ids":["a","b\""]}"#);
+    }
+
+    #[test]
+    fn signs_the_exact_body_bytes() {
+        let body = heartbeat_body(r#"id"with\quotes"#);
+        let headers = signed_headers(&body, "key", "secret", "1", "1700000000");
+        // 签名头中的 MD5 与实际发送的字节一致
+        assert_eq!(headers["x-bili-content-md5"], content_md5(&body));
+        assert_eq!(
+            content_md5(r#"{"game_id":"id\"with\\quotes"}"#),
+            headers["x-bili-content-md5"]
+        );
+        assert_eq!(
+            headers["x-bili-content-md5"],
+            format!("{:x}", Md5::digest(body.as_bytes()))
+        );
+        let expected_sign_string = format!(
+            "x-bili-accesskeyid:key\nx-bili-content-md5:{}\nx-bili-signature-method:HMAC-SHA256\n\
+             x-bili-signature-nonce:1\nx-bili-signature-version:1.0\nx-bili-timestamp:1700000000",
+            headers["x-bili-content-md5"]
+        );
+        assert_eq!(
+            headers["Authorization"],
+            signature(&expected_sign_string, "secret")
+        );
+        // 任何一个字节不同都会改变签名
+        let other = signed_headers(&format!("{} ", body), "key", "secret", "1", "1700000000");
+        assert_ne!(other["Authorization"], headers["Authorization"]);
+    }
+
+    #[test]
+    fn signs_get_requests_with_an_empty_body() {
+        let headers = signed_get_headers("key", "secret", "1", "1700000000");
+        assert_eq!(
+            headers["x-bili-content-md5"],
+            "d41d8cd98f00b204e9800998ecf8427e"
+        );
+        assert!(!headers.contains_key("Content-Type"));
+        assert_eq!(
+            headers["Authorization"],
+            signed_headers("", "key", "secret", "1", "1700000000")["Authorization"]
+        );
+
+        let params = BTreeMap::from([
+            ("room_id".to_string(), "42".to_string()),
+            ("name".to_string(), "主播 A&B".to_string()),
+            ("a~b".to_string(), "x=y".to_string()),
+        ]);
+        assert_eq!(
+            canonical_query(&params),
+            "a~b=x%3Dy&name=%E4%B8%BB%E6%92%AD%20A%26B&room_id=42"
+        );
+        assert_eq!(canonical_query(&BTreeMap::new()), "");
+    }
+}
@@ -0,0 +1,440 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// 一份脚本展开后的最大事件数，防止嵌套 repeat 生成过大的时间线
+const MAX_EVENTS: usize = 100_000;
+
+/// 脚本中的一条直播消息，由 BliveMock 转换为与 `inject_*` 相同的消息
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimAction {
+    Danmaku {
+        uname: String,
+        msg: String,
+    },
+    Gift {
+        uname: String,
+        gift_name: String,
+        gift_num: i64,
+        price: i64,
+    },
+    SuperChat {
+        uname: String,
+        rmb: i64,
+        message: String,
+    },
+    Guard {
+        uname: String,
+        guard_level: i64,
+    },
+    Like {
+        uname: String,
+        like_count: i64,
+    },
+    Follow {
+        uname: String,
+    },
+    Share {
+        uname: String,
+    },
+    LiveStatus(bool),
+    WatchedCount(i64),
+    OnlineCount(i64),
+    /// 原始消息 JSON，与 `inject_message_json` 相同
+    Raw(String),
+}
+
+/// 展开后的脚本
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeline {
+    /// 按时间排序的 (相对开始的秒数, 消息)
+    pub events: Vec<(f64, SimAction)>,
+    /// 脚本总时长（含最后一条消息之后的 wait），循环播放时为一轮的长度
+    pub duration: f64,
+}
+
+#[derive(Debug, Clone)]
+enum Stmt {
+    Wait { min: f64, max: f64 },
+    Emit { line: usize, text: String },
+    Repeat { count: usize, body: Vec<Stmt> },
+    Random { choices: Vec<Stmt> },
+}
+
+/// 模拟直播脚本：每行一条语句，`#` 开头为注释
+///
+/// ```text
+/// seed 42                      # 随机种子，相同种子每次展开结果相同，默认为 0
+/// live on
+/// danmaku 观众A 大家好          # 最后一个参数取到行尾
+/// wait 0.5                     # 时间前进 0.5 秒，也可写成 wait 0.2-1.0 取随机值
+/// repeat 20                    # 重复块内语句，{i} 替换为从 1 开始的次数
+///   gift 观众{i} 小心心 1 0
+///   wait 0.1
+/// end
+/// random                       # 每次执行时随机选一条语句（或一个块）
+///   super_chat 土豪 30 加油
+///   guard 舰长 3
+/// end
+/// ```
+///
+/// 其他语句：`like <uname> <count>`、`follow <uname>`、`share <uname>`、`live on|off`、
+/// `watched <n>`、`online <n>`、`message <JSON>`。
+#[derive(Debug, Clone)]
+pub struct SimulationScript {
+    seed: u64,
+    body: Vec<Stmt>,
+}
+
+impl SimulationScript {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut seed = 0;
+        // 栈底为脚本本身，其余为尚未遇到 end 的块：(开始行号, 关键字, 次数, 语句)
+        let mut stack: Vec<(usize, &str, usize, Vec<Stmt>)> = vec![(0, "", 0, Vec::new())];
+        for (index, raw) in source.lines().enumerate() {
+            let line = index + 1;
+            let text = strip_comment(raw).trim();
+            if text.is_empty() {
+                continue;
+            }
+            let (keyword, rest) = split_word(text);
+            let stmt = match keyword {
+                "seed" => {
+                    seed = rest
+                        .parse()
+                        .map_err(|_| format!("第 {} 行: seed 需要非负整数", line))?;
+                    continue;
+                }
+                "wait" => {
+                    let (min, max) = parse_wait(rest)
+                        .ok_or_else(|| format!("第 {} 行: wait 需要秒数或 最小-最大", line))?;
+                    Stmt::Wait { min, max }
+                }
+                "repeat" => {
+                    let count = rest
+                        .parse()
+                        .map_err(|_| format!("第 {} 行: repeat 需要非负整数", line))?;
+                    stack.push((line, "repeat", count, Vec::new()));
+                    continue;
+                }
+                "random" if rest.is_empty() => {
+                    stack.push((line, "random", 0, Vec::new()));
+                    continue;
+                }
+                "end" => {
+                    if stack.len() == 1 {
+                        return Err(format!("第 {} 行: 多余的 end", line));
+                    }
+                    let (start, kind, count, body) = stack.pop().unwrap();
+                    if kind == "random" && body.is_empty() {
+                        return Err(format!("第 {} 行: random 块为空", start));
+                    }
+                    if kind == "repeat" {
+                        Stmt::Repeat { count, body }
+                    } else {
+                        Stmt::Random { choices: body }
+                    }
+                }
+                _ => {
+                    // 先按 {i} = 1 检查参数，展开时再按实际次数转换
+                    parse_action(&text.replace("{i}", "1"))
+                        .map_err(|e| format!("第 {} 行: {}", line, e))?;
+                    Stmt::Emit {
+                        line,
+                        text: text.to_string(),
+                    }
+                }
+            };
+            stack.last_mut().unwrap().3.push(stmt);
+        }
+        if stack.len() > 1 {
+            let (start, kind, _, _) = stack.last().unwrap();
+            return Err(format!("第 {} 行: {} 缺少 end", start, kind));
+        }
+        Ok(Self {
+            seed,
+            body: stack.pop().unwrap().3,
+        })
+    }
+
+    pub fn expand(&self) -> Result<Timeline, String> {
+        let mut expansion = Expansion {
+            rng: StdRng::seed_from_u64(self.seed),
+            cursor: 0.0,
+            events: Vec::new(),
+        };
+        expansion.run(&self.body, 1)?;
+        Ok(Timeline {
+            events: expansion.events,
+            duration: expansion.cursor,
+        })
+    }
+}
+
+struct Expansion {
+    rng: StdRng,
+    cursor: f64,
+    events: Vec<(f64, SimAction)>,
+}
+
+impl Expansion {
+    fn run(&mut self, body: &[Stmt], iteration: usize) -> Result<(), String> {
+        for stmt in body {
+            self.step(stmt, iteration)?;
+        }
+        Ok(())
+    }
+
+    fn step(&mut self, stmt: &Stmt, iteration: usize) -> Result<(), String> {
+        match stmt {
+            Stmt::Wait { min, max } => {
+                self.cursor += if max > min {
+                    self.rng.gen_range(*min..*max)
+                } else {
+                    *min
+                };
+            }
+            Stmt::Emit { line, text } => {
+                if self.events.len() >= MAX_EVENTS {
+                    return Err(format!("脚本展开后超过 {} 条消息", MAX_EVENTS));
+                }
+                let action = parse_action(&text.replace("{i}", &iteration.to_string()))
+                    .map_err(|e| format!("第 {} 行: {}", line, e))?;
+                self.events.push((self.cursor, action));
+            }
+            Stmt::Repeat { count, body } => {
+                for iteration in 1..=*count {
+                    self.run(body, iteration)?;
+                }
+            }
+            Stmt::Random { choices } => {
+                let choice = self.rng.gen_range(0..choices.len());
+                self.step(&choices[choice], iteration)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 按时间线播放展开后的脚本
+#[derive(Debug)]
+pub struct SimulationPlayback {
+    timeline: Timeline,
+    next: usize,
+    started_at: f64,
+    looped: bool,
+}
+
+impl SimulationPlayback {
+    pub fn new(timeline: Timeline, now: f64, looped: bool) -> Self {
+        Self {
+            timeline,
+            next: 0,
+            started_at: now,
+            looped,
+        }
+    }
+
+    /// 取出到 now 为止应发出的消息；循环播放时，每轮在上一轮开始后 duration 秒开始
+    pub fn due(&mut self, now: f64) -> Vec<SimAction> {
+        let mut due = Vec::new();
+        while let Some((offset, action)) = self.timeline.events.get(self.next) {
+            if self.started_at + offset > now {
+                break;
+            }
+            due.push(action.clone());
+            self.next += 1;
+            if self.next == self.timeline.events.len() && self.looped {
+                self.next = 0;
+                // 一轮时长为 0 时每帧最多播放一轮，避免一帧内无限循环
+                if self.timeline.duration <= 0.0 {
+                    self.started_at = now + f64::EPSILON;
+                    break;
+                }
+                self.started_at += self.timeline.duration;
+            }
+        }
+        due
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.timeline.events.len()
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(index) if line[..index].trim().is_empty() || line[..index].ends_with(' ') => {
+            &line[..index]
+        }
+        _ => line,
+    }
+}
+
+fn split_word(text: &str) -> (&str, &str) {
+    match text.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim_start()),
+        None => (text, ""),
+    }
+}
+
+fn parse_wait(text: &str) -> Option<(f64, f64)> {
+    let (min, max) = match text.split_once('-') {
+        Some((min, max)) => (min.trim().parse().ok()?, max.trim().parse().ok()?),
+        None => {
+            let secs = text.parse().ok()?;
+            (secs, secs)
+        }
+    };
+    (min >= 0.0 && max >= min).then_some((min, max))
+}
+
+fn parse_action(text: &str) -> Result<SimAction, String> {
+    let (keyword, rest) = split_word(text);
+    let mut args = rest.split_whitespace();
+    let mut word = |name: &str| {
+        args.next()
+            .map(str::to_string)
+            .ok_or_else(|| format!("{} 缺少参数 {}", keyword, name))
+    };
+    let int = |value: String, name: &str| {
+        value
+            .parse::<i64>()
+            .map_err(|_| format!("{} 的参数 {} 不是整数: {}", keyword, name, value))
+    };
+    // 最后一个参数取到行尾，允许包含空格
+    let tail = |skip: usize| {
+        let mut tail = rest;
+        for _ in 0..skip {
+            tail = split_word(tail).1;
+        }
+        tail.to_string()
+    };
+    let action = match keyword {
+        "danmaku" => {
+            let uname = word("uname")?;
+            let msg = tail(1);
+            if msg.is_empty() {
+                return Err("danmaku 缺少弹幕内容".to_string());
+            }
+            SimAction::Danmaku { uname, msg }
+        }
+        "gift" => SimAction::Gift {
+            uname: word("uname")?,
+            gift_name: word("gift_name")?,
+            gift_num: int(word("gift_num")?, "gift_num")?,
+            price: int(word("price")?, "price")?,
+        },
+        "super_chat" => SimAction::SuperChat {
+            uname: word("uname")?,
+            rmb: int(word("rmb")?, "rmb")?,
+            message: tail(2),
+        },
+        "guard" => SimAction::Guard {
+            uname: word("uname")?,
+            guard_level: int(word("guard_level")?, "guard_level")?,
+        },
+        "like" => SimAction::Like {
+            uname: word("uname")?,
+            like_count: int(word("like_count")?, "like_count")?,
+        },
+        "follow" => SimAction::Follow {
+            uname: word("uname")?,
+        },
+        "share" => SimAction::Share {
+            uname: word("uname")?,
+        },
+        "live" => match word("on|off")?.as_str() {
+            "on" => SimAction::LiveStatus(true),
+            "off" => SimAction::LiveStatus(false),
+            other => return Err(format!("live 的参数应为 on 或 off: {}", other)),
+        },
+        "watched" => SimAction::WatchedCount(int(word("count")?, "count")?),
+        "online" => SimAction::OnlineCount(int(word("count")?, "count")?),
+        "message" => {
+            serde_json::from_str::<serde_json::Value>(rest)
+                .map_err(|e| format!("message 不是有效的 JSON: {}", e))?;
+            SimAction::Raw(rest.to_string())
+        }
+        _ => return Err(format!("未知语句: {}", keyword)),
+    };
+    Ok(action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "
+        # 礼物风暴
+        seed 7
+        live on
+        repeat 3
+          gift 观众{i} 小心心 1 0  # 免费礼物
+          wait 0.5
+        end
+        random
+          danmaku 观众 好耶 好耶
+          wait 0.1-0.2
+        end
+        message {\"cmd\":\"LIVE\"}
+    ";
+
+    #[test]
+    fn expands_loops_and_random_blocks_reproducibly() {
+        let script = SimulationScript::parse(SCRIPT).unwrap();
+        let timeline = script.expand().unwrap();
+        assert_eq!(timeline.duration, timeline.events.last().unwrap().0);
+        let events = &timeline.events;
+        assert_eq!(events[0], (0.0, SimAction::LiveStatus(true)));
+        assert_eq!(
+            events[3],
+            (
+                1.0,
+                SimAction::Gift {
+                    uname: "观众3".into(),
+                    gift_name: "小心心".into(),
+                    gift_num: 1,
+                    price: 0,
+                }
+            )
+        );
+        assert_eq!(
+            events.last().unwrap().1,
+            SimAction::Raw("{\"cmd\":\"LIVE\"}".into())
+        );
+        assert!(events.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+        // 相同种子展开结果相同
+        assert_eq!(script.expand().unwrap(), timeline);
+
+        let mut playback = SimulationPlayback::new(timeline.clone(), 10.0, false);
+        assert_eq!(playback.due(10.0).len(), 2);
+        assert_eq!(playback.due(11.0).len(), 2);
+        playback.due(100.0);
+        assert!(playback.is_finished());
+
+        let round = Timeline {
+            events: events[..2].to_vec(),
+            duration: 2.0,
+        };
+        let mut looped = SimulationPlayback::new(round, 0.0, true);
+        assert_eq!(looped.due(0.0).len(), 2);
+        assert!(looped.due(1.0).is_empty());
+        assert_eq!(looped.due(2.0).len(), 2);
+        assert!(!looped.is_finished());
+    }
+
+    #[test]
+    fn reports_errors_with_line_numbers() {
+        let error = |source: &str| SimulationScript::parse(source).unwrap_err();
+        assert_eq!(
+            error("live on\ngift a b x 0"),
+            "第 2 行: gift 的参数 gift_num 不是整数: x"
+        );
+        assert_eq!(error("repeat 2\nlive on"), "第 1 行: repeat 缺少 end");
+        assert_eq!(error("end"), "第 1 行: 多余的 end");
+        assert_eq!(error("wait 2-1"), "第 1 行: wait 需要秒数或 最小-最大");
+        assert_eq!(error("dance"), "第 1 行: 未知语句: dance");
+        let huge = "repeat 1000\nrepeat 1000\nlike a 1\nend\nend";
+        assert!(SimulationScript::parse(huge).unwrap().expand().is_err());
+    }
+}
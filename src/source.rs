@@ -0,0 +1,378 @@
+use crate::channel;
+use crate::convert::{dictionary_to_json, json_to_variant};
+use crate::events;
+use godot::prelude::*;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::mpsc;
+
+/// 直播平台数据源
+///
+/// 实现者在后台线程中连接平台，把原始消息转换为 `events` 模块的统一事件
+/// （danmaku / gift / super_chat / guard 及相同的字段），这样同一套游戏逻辑可以接入多个平台。
+pub trait LiveSource: Send {
+    /// 平台名，作为 `live_event` 信号的 platform 参数，同一时间每个平台只运行一个数据源
+    fn platform(&self) -> &str;
+
+    /// 阻塞运行直到 running 变为 false 或连接出错，每条统一事件调用一次 emit
+    fn run(
+        &mut self,
+        running: &AtomicBool,
+        emit: &mut dyn FnMut(&'static str, Value),
+    ) -> Result<(), String>;
+}
+
+/// 根据 `start_registered` 传入的选项创建数据源
+pub type LiveSourceFactory = fn(&Value) -> Result<Box<dyn LiveSource>, String>;
+
+fn registry() -> &'static Mutex<HashMap<String, LiveSourceFactory>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, LiveSourceFactory>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut factories: HashMap<String, LiveSourceFactory> = HashMap::new();
+        #[cfg(feature = "twitch")]
+        factories.insert("twitch".to_string(), |options| {
+            Ok(Box::new(crate::twitch::TwitchChat::new(
+                options["channel"].as_str().ok_or("缺少 channel")?,
+            )))
+        });
+        #[cfg(feature = "youtube")]
+        factories.insert("youtube".to_string(), |options| {
+            Ok(Box::new(crate::youtube::YouTubeChat::new(
+                options["api_key"].as_str().ok_or("缺少 api_key")?,
+                options["video_id"].as_str().ok_or("缺少 video_id")?,
+            )))
+        });
+        Mutex::new(factories)
+    })
+}
+
+/// 注册第三方平台数据源，之后可通过 `LiveSourceManager.start_registered(platform, options)` 启动
+///
+/// 其他 GDExtension crate 以 rlib 依赖本 crate，在 `ExtensionLibrary::on_level_init` 中调用即可；
+/// 同名平台会覆盖之前的注册（包括内置的 twitch / youtube）。
+pub fn register_live_source(platform: &str, factory: LiveSourceFactory) {
+    registry()
+        .lock()
+        .unwrap()
+        .insert(platform.to_string(), factory);
+}
+
+/// 已注册的平台名，按字母排序
+pub fn registered_platforms() -> Vec<String> {
+    let mut platforms: Vec<String> = registry().lock().unwrap().keys().cloned().collect();
+    platforms.sort();
+    platforms
+}
+
+fn create_source(platform: &str, options: &Value) -> Result<Box<dyn LiveSource>, String> {
+    // 先取出工厂再调用，工厂内部可以再次注册
+    let factory = registry()
+        .lock()
+        .unwrap()
+        .get(platform)
+        .copied()
+        .ok_or_else(|| format!("未注册的平台: {}", platform))?;
+    factory(options)
+}
+
+/// 后台数据源发往主线程的消息
+enum SourceMessage {
+    Event {
+        platform: String,
+        event_type: &'static str,
+        data: Value,
+    },
+    Stopped {
+        platform: String,
+        running: Arc<AtomicBool>,
+        error: Option<String>,
+    },
+}
+
+/// 多平台数据源管理节点，所有平台的互动事件都从 `live_event(platform, event_type, data)` 发出
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct LiveSourceManager {
+    base: Base<Node>,
+
+    /// 设置后把该 Blive 节点的 `live_event` 以 platform = "bilibili" 转发
+    #[export]
+    blive_path: NodePath,
+    /// 同一场景有多个节点时用于区分来源：非空时信号中的字典参数都带有 channel 键；
+    /// 为空时读取节点元数据 `channel_name`
+    #[export]
+    channel_name: GString,
+
+    sources: HashMap<String, Arc<AtomicBool>>,
+    message_tx: mpsc::UnboundedSender<SourceMessage>,
+    message_rx: Arc<Mutex<mpsc::UnboundedReceiver<SourceMessage>>>,
+}
+
+#[godot_api]
+impl INode for LiveSourceManager {
+    fn init(base: Base<Node>) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            base,
+            blive_path: NodePath::default(),
+            channel_name: GString::new(),
+            sources: HashMap::new(),
+            message_tx: tx,
+            message_rx: Arc::new(Mutex::new(rx)),
+        }
+    }
+
+    fn ready(&mut self) {
+        if self.blive_path.is_empty() {
+            return;
+        }
+        let path = self.blive_path.clone();
+        let Some(mut blive) = self.base().get_node_or_null(&path) else {
+            godot_warn!("LiveSourceManager: 找不到节点 {}", path);
+            return;
+        };
+        let callable = Callable::from_object_method(&self.to_gd(), "forward_blive_event");
+        blive.connect("live_event", &callable);
+    }
+
+    fn process(&mut self, _delta: f64) {
+        let messages: Vec<SourceMessage> = {
+            let mut rx = self.message_rx.lock().unwrap();
+            let mut messages = Vec::new();
+            while let Ok(message) = rx.try_recv() {
+                messages.push(message);
+            }
+            messages
+        };
+        for message in messages {
+            match message {
+                SourceMessage::Event {
+                    platform,
+                    event_type,
+                    data,
+                } => {
+                    self.emit(
+                        "live_event",
+                        &[
+                            platform.to_variant(),
+                            event_type.to_variant(),
+                            json_to_variant(&data),
+                        ],
+                    );
+                }
+                SourceMessage::Stopped {
+                    platform,
+                    running,
+                    error,
+                } => {
+                    // 同名数据源可能已被重新启动，只移除已结束的那一个
+                    if self
+                        .sources
+                        .get(&platform)
+                        .is_some_and(|current| Arc::ptr_eq(current, &running))
+                    {
+                        self.sources.remove(&platform);
+                    }
+                    if let Some(e) = error {
+                        self.emit("source_error", &[platform.to_variant(), e.to_variant()]);
+                    }
+                    self.emit("source_stopped", &[platform.to_variant()]);
+                }
+            }
+        }
+    }
+}
+
+#[godot_api]
+impl LiveSourceManager {
+    #[signal]
+    fn live_event(platform: GString, event_type: GString, data: Dictionary);
+    #[signal]
+    fn source_started(platform: GString);
+    #[signal]
+    fn source_stopped(platform: GString);
+    #[signal]
+    fn source_error(platform: GString, error_msg: GString);
+
+    /// 以匿名身份接入 Twitch 频道聊天
+    #[func]
+    fn start_twitch(&mut self, channel: GString) -> bool {
+        #[cfg(feature = "twitch")]
+        {
+            self.start_source(Box::new(crate::twitch::TwitchChat::new(
+                &channel.to_string(),
+            )))
+        }
+        #[cfg(not(feature = "twitch"))]
+        {
+            godot_error!("未启用 twitch 功能，无法接入频道 {}", channel);
+            false
+        }
+    }
+
+    /// 通过 Data API 轮询 YouTube 直播聊天
+    #[func]
+    fn start_youtube(&mut self, api_key: GString, video_id: GString) -> bool {
+        #[cfg(feature = "youtube")]
+        {
+            self.start_source(Box::new(crate::youtube::YouTubeChat::new(
+                &api_key.to_string(),
+                &video_id.to_string(),
+            )))
+        }
+        #[cfg(not(feature = "youtube"))]
+        {
+            let _ = api_key;
+            godot_error!("未启用 youtube 功能，无法接入视频 {}", video_id);
+            false
+        }
+    }
+
+    /// 按平台名启动已注册的数据源，options 的键由各平台自行定义
+    #[func]
+    fn start_registered(&mut self, platform: GString, options: Dictionary) -> bool {
+        match create_source(&platform.to_string(), &dictionary_to_json(&options)) {
+            Ok(source) => self.start_source(source),
+            Err(e) => {
+                let args = [platform.to_variant(), e.to_variant()];
+                self.emit("source_error", &args);
+                false
+            }
+        }
+    }
+
+    #[func]
+    fn get_registered_platforms(&self) -> PackedStringArray {
+        registered_platforms()
+            .iter()
+            .map(|p| GString::from(p.as_str()))
+            .collect()
+    }
+
+    #[func]
+    fn stop_source(&mut self, platform: GString) {
+        if let Some(running) = self.sources.remove(&platform.to_string()) {
+            running.store(false, Ordering::SeqCst);
+        }
+    }
+
+    #[func]
+    fn stop_all_sources(&mut self) {
+        for (_, running) in self.sources.drain() {
+            running.store(false, Ordering::SeqCst);
+        }
+    }
+
+    #[func]
+    fn get_active_sources(&self) -> PackedStringArray {
+        self.sources
+            .keys()
+            .map(|p| GString::from(p.as_str()))
+            .collect()
+    }
+
+    /// 把 Blive 的统一事件以 platform = "bilibili" 发出
+    #[func]
+    fn forward_blive_event(&mut self, event_type: GString, data: Dictionary) {
+        self.emit(
+            "live_event",
+            &[
+                "bilibili".to_variant(),
+                event_type.to_variant(),
+                data.to_variant(),
+            ],
+        );
+    }
+
+    /// 在后台线程中运行数据源，同一平台已有数据源在运行时返回 false
+    pub fn start_source(&mut self, mut source: Box<dyn LiveSource>) -> bool {
+        let platform = source.platform().to_string();
+        if self.sources.contains_key(&platform) {
+            godot_print!("{} 数据源已经在运行中", platform);
+            return false;
+        }
+        let running = Arc::new(AtomicBool::new(true));
+        self.sources.insert(platform.clone(), running.clone());
+        let sender = self.message_tx.clone();
+        let started = platform.clone();
+
+        let spawned = std::thread::Builder::new()
+            .name(format!("live-source-{}", platform))
+            .spawn(move || {
+                let mut emit = |event_type: &'static str, mut data: Value| {
+                    events::stamp(&mut data, events::now_ms());
+                    let _ = sender.send(SourceMessage::Event {
+                        platform: platform.clone(),
+                        event_type,
+                        data,
+                    });
+                };
+                let error = source.run(&running, &mut emit).err();
+                running.store(false, Ordering::SeqCst);
+                let _ = sender.send(SourceMessage::Stopped {
+                    platform,
+                    running,
+                    error,
+                });
+            });
+        if let Err(e) = spawned {
+            godot_error!("启动数据源线程失败: {}", e);
+            self.sources.remove(&started);
+            return false;
+        }
+        self.emit("source_started", &[started.to_variant()]);
+        true
+    }
+
+    /// 发出信号，设置了频道名时字典参数带上 channel 键
+    fn emit(&mut self, signal: &str, args: &[Variant]) {
+        let channel = channel::resolve(&self.base(), &self.channel_name);
+        let args = channel::tag(&channel, args);
+        self.base_mut().emit_signal(signal, &args);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct Echo(String);
+
+    impl LiveSource for Echo {
+        fn platform(&self) -> &str {
+            "echo"
+        }
+
+        fn run(
+            &mut self,
+            _running: &AtomicBool,
+            emit: &mut dyn FnMut(&'static str, Value),
+        ) -> Result<(), String> {
+            emit("danmaku", json!({ "message": self.0 }));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn registered_factory_creates_source() {
+        register_live_source("echo", |options| {
+            Ok(Box::new(Echo(
+                options["text"].as_str().unwrap_or_default().to_string(),
+            )))
+        });
+        assert!(registered_platforms().contains(&"echo".to_string()));
+
+        let mut source = create_source("echo", &json!({ "text": "hi" })).unwrap();
+        let mut events = Vec::new();
+        source
+            .run(&AtomicBool::new(true), &mut |event_type, data| {
+                events.push((event_type, data))
+            })
+            .unwrap();
+        assert_eq!(events, vec![("danmaku", json!({ "message": "hi" }))]);
+        assert!(create_source("missing", &json!({})).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+
+/// 一轮刷屏最长持续多少个窗口后先结算一次，避免持续刷屏时一直没有汇总
+const MAX_WINDOWS_PER_BURST: f64 = 5.0;
+
+#[derive(Debug)]
+struct Burst {
+    /// 这一轮第一条弹幕的事件 data
+    first: Value,
+    /// 被折叠（未发出）的条数
+    collapsed: i64,
+    users: HashSet<String>,
+    started: f64,
+    last: f64,
+}
+
+/// 把短时间内重复的相同弹幕折叠为一条汇总
+///
+/// 每轮的第一条照常发出，窗口内重复的弹幕不再单独发出，
+/// 超过窗口没有重复后由 `flush` 返回带 repeat_count 的汇总
+#[derive(Debug, Default)]
+pub struct SpamCollapser {
+    bursts: HashMap<String, Burst>,
+    received: i64,
+    collapsed: i64,
+}
+
+impl SpamCollapser {
+    /// 记录一条弹幕事件，需要折叠（不单独发出）时返回 true
+    pub fn observe(&mut self, data: &Value, now: f64, window_secs: f64) -> bool {
+        self.received += 1;
+        let text = data["message"].as_str().unwrap_or_default().trim();
+        if text.is_empty() {
+            return false;
+        }
+        let user_id = data["user_id"].as_str().unwrap_or_default().to_string();
+        match self.bursts.get_mut(text) {
+            Some(burst) if now - burst.last <= window_secs => {
+                burst.collapsed += 1;
+                burst.last = now;
+                burst.users.insert(user_id);
+                self.collapsed += 1;
+                true
+            }
+            _ => {
+                self.bursts.insert(
+                    text.to_string(),
+                    Burst {
+                        first: data.clone(),
+                        collapsed: 0,
+                        users: HashSet::from([user_id]),
+                        started: now,
+                        last: now,
+                    },
+                );
+                false
+            }
+        }
+    }
+
+    /// 结束超过窗口没有重复、或持续过久的刷屏，返回有折叠的汇总：第一条的 data
+    /// 加上 repeat_count（含第一条的总条数）、unique_users 和 duration（秒）
+    pub fn flush(&mut self, now: f64, window_secs: f64) -> Vec<Value> {
+        let mut summaries = Vec::new();
+        self.bursts.retain(|_, burst| {
+            let quiet = now - burst.last > window_secs;
+            let long = now - burst.started > window_secs * MAX_WINDOWS_PER_BURST;
+            if !quiet && !long {
+                return true;
+            }
+            if burst.collapsed > 0 {
+                let mut summary = burst.first.clone();
+                summary["repeat_count"] = (burst.collapsed + 1).into();
+                summary["unique_users"] = burst.users.len().into();
+                summary["duration"] = (burst.last - burst.started).into();
+                summaries.push(summary);
+            }
+            // 持续刷屏时从下一条重新开始一轮
+            if !quiet {
+                burst.collapsed = 0;
+                burst.users.clear();
+                burst.started = now;
+                return true;
+            }
+            false
+        });
+        summaries
+    }
+
+    /// received（收到的弹幕总数，含被折叠的）和 collapsed（被折叠的条数）
+    pub fn stats(&self) -> Value {
+        json!({ "received": self.received, "collapsed": self.collapsed })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn danmaku(user_id: &str, message: &str) -> Value {
+        json!({ "user_id": user_id, "message": message })
+    }
+
+    #[test]
+    fn collapses_repeats_within_window() {
+        let mut spam = SpamCollapser::default();
+        assert!(!spam.observe(&danmaku("a", "233"), 0.0, 3.0));
+        assert!(spam.observe(&danmaku("b", "233 "), 1.0, 3.0));
+        assert!(spam.observe(&danmaku("a", "233"), 2.0, 3.0));
+        assert!(!spam.observe(&danmaku("c", "hello"), 2.0, 3.0));
+        assert!(spam.flush(4.0, 3.0).is_empty());
+
+        let summaries = spam.flush(5.5, 3.0);
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0]["message"], "233");
+        assert_eq!(summaries[0]["repeat_count"], 3);
+        assert_eq!(summaries[0]["unique_users"], 2);
+        assert_eq!(summaries[0]["duration"], 2.0);
+        assert_eq!(spam.stats(), json!({ "received": 4, "collapsed": 2 }));
+
+        // 窗口过后同样的弹幕重新开始一轮
+        assert!(!spam.observe(&danmaku("a", "233"), 9.0, 3.0));
+    }
+
+    #[test]
+    fn settles_long_storms_periodically() {
+        let mut spam = SpamCollapser::default();
+        spam.observe(&danmaku("a", "awsl"), 0.0, 1.0);
+        for i in 1..=6 {
+            spam.observe(&danmaku("b", "awsl"), i as f64, 1.0);
+        }
+        let summaries = spam.flush(6.0, 1.0);
+        assert_eq!(summaries[0]["repeat_count"], 7);
+        assert!(spam.observe(&danmaku("c", "awsl"), 6.5, 1.0));
+        assert_eq!(spam.flush(8.0, 1.0)[0]["repeat_count"], 2);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::convert::dictionary_to_json;
+use crate::triggers::{select_trigger, TriggerRule};
+use godot::classes::{IResource, PackedScene, Resource};
+use godot::prelude::*;
+use std::collections::HashMap;
+
+/// 一条事件弹窗：事件满足条件时在 anchor 下实例化 scene
+#[derive(GodotClass)]
+#[class(base=Resource)]
+pub struct SpawnTrigger {
+    base: Base<Resource>,
+
+    /// danmaku / gift / super_chat / guard / like
+    #[export]
+    event_type: GString,
+    /// 只匹配该礼物名，留空表示任意礼物
+    #[export]
+    gift_name: GString,
+    /// 事件总价（千分之一元）达到该值才生成，例如 SC 满 30 元填 30000
+    #[export]
+    min_price: i64,
+    #[export]
+    scene: Option<Gd<PackedScene>>,
+    /// 实例的父节点，相对 AlertSpawner 解析，留空时挂在 AlertSpawner 下
+    #[export]
+    anchor: NodePath,
+    /// 实例存活秒数，到期自动释放，0 表示由场景自行释放
+    #[export]
+    lifetime: f64,
+    #[export]
+    cooldown: f64,
+}
+
+#[godot_api]
+impl IResource for SpawnTrigger {
+    fn init(base: Base<Resource>) -> Self {
+        Self {
+            base,
+            event_type: GString::from("gift"),
+            gift_name: GString::new(),
+            min_price: 0,
+            scene: None,
+            anchor: NodePath::default(),
+            lifetime: 5.0,
+            cooldown: 0.0,
+        }
+    }
+}
+
+impl SpawnTrigger {
+    fn rule(&self) -> TriggerRule {
+        TriggerRule {
+            event_type: self.event_type.to_string(),
+            gift_name: self.gift_name.to_string(),
+            min_price: self.min_price,
+            cooldown: self.cooldown,
+        }
+    }
+}
+
+/// 事件弹窗表，可保存为 .tres 在多个场景间共用
+#[derive(GodotClass)]
+#[class(base=Resource)]
+pub struct SpawnTriggerMap {
+    base: Base<Resource>,
+
+    #[export]
+    triggers: Array<Gd<SpawnTrigger>>,
+}
+
+#[godot_api]
+impl IResource for SpawnTriggerMap {
+    fn init(base: Base<Resource>) -> Self {
+        Self {
+            base,
+            triggers: Array::new(),
+        }
+    }
+}
+
+/// 按 SpawnTriggerMap 为事件生成弹窗场景
+///
+/// 实例加入场景树前会写入元数据 `event_type` 和 `event_data`，
+/// 若场景根节点定义了 `payload_method`（默认 `setup(event_type, data)`）也会被调用。
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct AlertSpawner {
+    base: Base<Node>,
+
+    /// 要监听 `live_event` 的 Blive 节点，留空时可手动调用 `handle_live_event`
+    #[export]
+    blive_path: NodePath,
+    #[export]
+    trigger_map: Option<Gd<SpawnTriggerMap>>,
+    #[export]
+    payload_method: StringName,
+
+    /// 设置了 lifetime 的实例及其剩余秒数
+    expiring: Vec<(Gd<Node>, f64)>,
+    last_spawned: HashMap<usize, f64>,
+    elapsed: f64,
+}
+
+#[godot_api]
+impl INode for AlertSpawner {
+    fn init(base: Base<Node>) -> Self {
+        Self {
+            base,
+            blive_path: NodePath::default(),
+            trigger_map: None,
+            payload_method: StringName::from("setup"),
+            expiring: Vec::new(),
+            last_spawned: HashMap::new(),
+            elapsed: 0.0,
+        }
+    }
+
+    fn ready(&mut self) {
+        if self.blive_path.is_empty() {
+            return;
+        }
+        let path = self.blive_path.clone();
+        let Some(mut blive) = self.base().get_node_or_null(&path) else {
+            godot_warn!("AlertSpawner: 找不到节点 {}", path);
+            return;
+        };
+        let callable = Callable::from_object_method(&self.to_gd(), "handle_live_event");
+        blive.connect("live_event", &callable);
+    }
+
+    fn process(&mut self, delta: f64) {
+        self.elapsed += delta;
+        self.expiring.retain_mut(|(node, remaining)| {
+            if !node.is_instance_valid() {
+                return false;
+            }
+            *remaining -= delta;
+            if *remaining > 0.0 {
+                return true;
+            }
+            node.queue_free();
+            false
+        });
+    }
+}
+
+#[godot_api]
+impl AlertSpawner {
+    #[signal]
+    fn alert_spawned(event_type: GString, node: Gd<Node>);
+
+    /// 根据事件生成弹窗，返回生成的节点，没有匹配的规则时返回 null
+    #[func]
+    fn handle_live_event(&mut self, event_type: GString, data: Dictionary) -> Option<Gd<Node>> {
+        let map = self.trigger_map.clone()?;
+        let triggers = map.bind().triggers.clone();
+        let rules: Vec<TriggerRule> = triggers.iter_shared().map(|t| t.bind().rule()).collect();
+        let index = select_trigger(
+            &rules,
+            &event_type.to_string(),
+            &dictionary_to_json(&data),
+            self.elapsed,
+            &self.last_spawned,
+        )?;
+        let trigger = triggers.get(index)?;
+        let (scene, anchor, lifetime) = {
+            let trigger = trigger.bind();
+            (
+                trigger.scene.clone()?,
+                trigger.anchor.clone(),
+                trigger.lifetime,
+            )
+        };
+
+        let mut parent = if anchor.is_empty() {
+            self.to_gd().upcast::<Node>()
+        } else {
+            let Some(parent) = self.base().get_node_or_null(&anchor) else {
+                godot_warn!("AlertSpawner: 找不到锚点 {}", anchor);
+                return None;
+            };
+            parent
+        };
+        let mut instance = scene.instantiate()?;
+        instance.set_meta("event_type", &event_type.to_variant());
+        instance.set_meta("event_data", &data.to_variant());
+        parent.add_child(&instance);
+        let method = self.payload_method.clone();
+        if !method.is_empty() && instance.has_method(&method) {
+            instance.call(&method, &[event_type.to_variant(), data.to_variant()]);
+        }
+
+        self.last_spawned.insert(index, self.elapsed);
+        if lifetime > 0.0 {
+            self.expiring.push((instance.clone(), lifetime));
+        }
+        self.base_mut().emit_signal(
+            "alert_spawned",
+            &[event_type.to_variant(), instance.to_variant()],
+        );
+        Some(instance)
+    }
+
+    /// 立即释放所有设置了 lifetime 且尚未到期的实例
+    #[func]
+    fn clear_alerts(&mut self) {
+        for (mut node, _) in self.expiring.drain(..) {
+            if node.is_instance_valid() {
+                node.queue_free();
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+/// 连接记录（`get_connection_history`）和 `bandwidth_report`
+pub const STATS: &str = "stats";
+/// `start_recording` 的会话录制
+pub const RECORDER: &str = "recorder";
+/// `set_webhook` 的事件投递
+pub const WEBHOOKS: &str = "webhooks";
+/// 礼物连击跟踪（`get_active_combos`）
+pub const COMBOS: &str = "combos";
+/// 醒目留言的展示计时（`super_chat_expired` / `super_chat_deleted`）
+pub const SUPER_CHAT_TIMERS: &str = "super_chat_timers";
+
+pub const ALL: [&str; 5] = [STATS, RECORDER, WEBHOOKS, COMBOS, SUPER_CHAT_TIMERS];
+
+/// 可选子系统的开关，默认全部启用；关闭的子系统不分配状态，也不处理事件
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Subsystems {
+    disabled: Vec<&'static str>,
+}
+
+/// 按名称查找子系统，未知名称的错误信息中列出可用的名称
+pub fn lookup(name: &str) -> Result<&'static str, String> {
+    ALL.into_iter()
+        .find(|known| *known == name)
+        .ok_or_else(|| format!("未知的子系统 {}，可用: {}", name, ALL.join(", ")))
+}
+
+/// 启用时按需创建子系统的状态，关闭时释放并返回 None
+pub fn lazy<T: Default>(slot: &mut Option<T>, enabled: bool) -> Option<&mut T> {
+    if !enabled {
+        *slot = None;
+        return None;
+    }
+    Some(slot.get_or_insert_with(T::default))
+}
+
+impl Subsystems {
+    /// 返回开关是否发生了变化
+    pub fn set(&mut self, name: &'static str, enabled: bool) -> bool {
+        if self.is_enabled(name) == enabled {
+            return false;
+        }
+        if enabled {
+            self.disabled.retain(|disabled| *disabled != name);
+        } else {
+            self.disabled.push(name);
+        }
+        true
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        !self.disabled.contains(&name)
+    }
+
+    /// 按 `ALL` 的顺序
+    pub fn enabled(&self) -> Vec<&'static str> {
+        ALL.into_iter()
+            .filter(|name| self.is_enabled(name))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggles_and_allocates_lazily() {
+        let mut subsystems = Subsystems::default();
+        assert_eq!(subsystems.enabled(), ALL);
+        assert!(subsystems.set(lookup("combos").unwrap(), false));
+        assert!(!subsystems.set(COMBOS, false));
+        assert_eq!(
+            subsystems.enabled(),
+            [STATS, RECORDER, WEBHOOKS, SUPER_CHAT_TIMERS]
+        );
+        assert!(lookup("leaderboard").unwrap_err().contains("stats"));
+
+        let mut slot: Option<Vec<i64>> = None;
+        assert!(lazy(&mut slot, false).is_none());
+        assert!(slot.is_none());
+        lazy(&mut slot, true).unwrap().push(1);
+        assert_eq!(slot, Some(vec![1]));
+        assert!(lazy(&mut slot, false).is_none());
+        assert!(slot.is_none());
+    }
+}
@@ -0,0 +1,64 @@
+/// 醒目留言的展示计时：按付费时长到期，服务端删除的留言不再计时
+#[derive(Debug, Default)]
+pub struct SuperChatTimers {
+    /// (message_id, 到期时刻)
+    pending: Vec<(i64, f64)>,
+}
+
+impl SuperChatTimers {
+    /// 记录一条 super_chat 事件，duration 为展示秒数，不大于 0 时不计时
+    pub fn add(&mut self, message_id: i64, duration: i64, now: f64) {
+        if duration <= 0 {
+            return;
+        }
+        self.pending.retain(|(id, _)| *id != message_id);
+        self.pending.push((message_id, now + duration as f64));
+    }
+
+    /// 取消计时，返回确实在计时中的留言
+    pub fn remove(&mut self, message_ids: &[i64]) -> Vec<i64> {
+        let removed = self
+            .pending
+            .iter()
+            .map(|(id, _)| *id)
+            .filter(|id| message_ids.contains(id))
+            .collect();
+        self.pending.retain(|(id, _)| !message_ids.contains(id));
+        removed
+    }
+
+    /// 取出已到期的留言，按到期先后排序
+    pub fn expire(&mut self, now: f64) -> Vec<i64> {
+        let mut expired: Vec<(i64, f64)> = self
+            .pending
+            .iter()
+            .copied()
+            .filter(|(_, at)| *at <= now)
+            .collect();
+        if expired.is_empty() {
+            return Vec::new();
+        }
+        self.pending.retain(|(_, at)| *at > now);
+        expired.sort_by(|a, b| a.1.total_cmp(&b.1));
+        expired.into_iter().map(|(id, _)| id).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expires_in_order_and_skips_deleted() {
+        let mut timers = SuperChatTimers::default();
+        timers.add(1, 60, 0.0);
+        timers.add(2, 30, 10.0);
+        timers.add(3, 120, 0.0);
+        timers.add(4, 0, 0.0);
+
+        assert_eq!(timers.expire(39.0), Vec::<i64>::new());
+        assert_eq!(timers.remove(&[3, 4]), vec![3]);
+        assert_eq!(timers.expire(60.0), vec![2, 1]);
+        assert_eq!(timers.expire(1000.0), Vec::<i64>::new());
+    }
+}
@@ -0,0 +1,198 @@
+use base64::Engine;
+use tokio_tungstenite::Connector;
+
+#[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+compile_error!("需要启用 native-tls 或 rustls 特性之一");
+
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+/// HTTP 客户端和长连接共用的 TLS 设置
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TlsOptions {
+    /// PEM 格式的额外根证书，可包含多张；为空时只信任系统（或内置）根证书
+    pub ca_pem: Vec<u8>,
+    /// 长连接不校验服务器证书和域名，仅用于自签名证书的测试环境；HTTP 请求不受影响
+    pub insecure_skip_verify: bool,
+}
+
+/// 取出 PEM 中所有 CERTIFICATE 块的 DER 字节，没有证书或 base64 无效时返回错误
+pub fn pem_certificates(pem: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+    let text = String::from_utf8_lossy(pem);
+    let mut certificates = Vec::new();
+    let mut rest = text.as_ref();
+    while let Some(start) = rest.find(PEM_BEGIN) {
+        let body = &rest[start + PEM_BEGIN.len()..];
+        let end = body
+            .find(PEM_END)
+            .ok_or_else(|| "证书缺少 END CERTIFICATE".to_string())?;
+        let base64: String = body[..end].chars().filter(|c| !c.is_whitespace()).collect();
+        let der = base64::engine::general_purpose::STANDARD
+            .decode(base64)
+            .map_err(|e| format!("证书内容无效: {}", e))?;
+        certificates.push(der);
+        rest = &body[end + PEM_END.len()..];
+    }
+    if certificates.is_empty() {
+        return Err("未找到 PEM 格式的证书".to_string());
+    }
+    Ok(certificates)
+}
+
+impl TlsOptions {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn certificates(&self) -> Result<Vec<Vec<u8>>, String> {
+        if self.ca_pem.is_empty() {
+            return Ok(Vec::new());
+        }
+        pem_certificates(&self.ca_pem)
+    }
+
+    /// 选择编译时启用的 TLS 后端并加入额外根证书
+    pub fn apply_to_http(
+        &self,
+        builder: reqwest::blocking::ClientBuilder,
+    ) -> Result<reqwest::blocking::ClientBuilder, String> {
+        #[cfg(feature = "native-tls")]
+        let mut builder = builder.use_native_tls();
+        #[cfg(all(feature = "rustls", not(feature = "native-tls")))]
+        let mut builder = builder.use_rustls_tls();
+        for der in self.certificates()? {
+            let certificate =
+                reqwest::Certificate::from_der(&der).map_err(|e| format!("证书无效: {}", e))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        Ok(builder)
+    }
+
+    /// 长连接使用的 TLS 连接器；默认设置时返回 None，由 tokio-tungstenite 按系统设置连接
+    #[cfg(feature = "native-tls")]
+    pub fn ws_connector(&self) -> Result<Option<Connector>, String> {
+        if self.is_default() {
+            return Ok(None);
+        }
+        let mut builder = native_tls::TlsConnector::builder();
+        builder
+            .danger_accept_invalid_certs(self.insecure_skip_verify)
+            .danger_accept_invalid_hostnames(self.insecure_skip_verify);
+        for der in self.certificates()? {
+            let certificate =
+                native_tls::Certificate::from_der(&der).map_err(|e| format!("证书无效: {}", e))?;
+            builder.add_root_certificate(certificate);
+        }
+        let connector = builder
+            .build()
+            .map_err(|e| format!("创建 TLS 连接器失败: {}", e))?;
+        Ok(Some(Connector::NativeTls(connector)))
+    }
+
+    #[cfg(all(feature = "rustls", not(feature = "native-tls")))]
+    pub fn ws_connector(&self) -> Result<Option<Connector>, String> {
+        use std::sync::Arc;
+
+        if self.is_default() {
+            return Ok(None);
+        }
+        let mut roots = rustls::RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        for der in self.certificates()? {
+            roots
+                .add(der.into())
+                .map_err(|e| format!("证书无效: {}", e))?;
+        }
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let builder = rustls::ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| format!("创建 TLS 连接器失败: {}", e))?;
+        let config = if self.insecure_skip_verify {
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(SkipVerification(provider)))
+                .with_no_client_auth()
+        } else {
+            builder.with_root_certificates(roots).with_no_client_auth()
+        };
+        Ok(Some(Connector::Rustls(Arc::new(config))))
+    }
+}
+
+/// `insecure_skip_verify` 时接受任何服务器证书，握手签名仍按正常流程校验
+#[cfg(all(feature = "rustls", not(feature = "native-tls")))]
+#[derive(Debug)]
+struct SkipVerification(std::sync::Arc<rustls::crypto::CryptoProvider>);
+
+#[cfg(all(feature = "rustls", not(feature = "native-tls")))]
+impl rustls::client::danger::ServerCertVerifier for SkipVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::pki_types::CertificateDer<'_>,
+        _intermediates: &[rustls::pki_types::CertificateDer<'_>],
+        _server_name: &rustls::pki_types::ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_every_certificate_block() {
+        let pem = format!(
+            "junk\n{}\nAQID\nBA==\n{}\n{}\n/w==\n{}\n",
+            PEM_BEGIN, PEM_END, PEM_BEGIN, PEM_END
+        );
+        assert_eq!(
+            pem_certificates(pem.as_bytes()),
+            Ok(vec![vec![1, 2, 3, 4], vec![255]])
+        );
+        assert!(pem_certificates(b"not a certificate").is_err());
+        assert!(pem_certificates(format!("{}\n!!\n{}", PEM_BEGIN, PEM_END).as_bytes()).is_err());
+        assert!(TlsOptions::default().is_default());
+        assert_eq!(TlsOptions::default().certificates(), Ok(Vec::new()));
+        let insecure = TlsOptions {
+            insecure_skip_verify: true,
+            ..TlsOptions::default()
+        };
+        assert!(!insecure.is_default());
+        assert!(insecure.ws_connector().unwrap().is_some());
+    }
+}
@@ -0,0 +1,218 @@
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// `RateWindow` 统计速率的时间窗口（秒）
+pub const RATE_WINDOW_SECS: f64 = 5.0;
+/// 两次采样的最小间隔（秒），窗口内最多保留约 RATE_WINDOW_SECS / RATE_SAMPLE_SECS 个样本
+const RATE_SAMPLE_SECS: f64 = 0.5;
+
+/// 长连接和开放平台 HTTP 请求的流量计数，后台任务直接累加
+#[derive(Debug, Default)]
+pub struct TrafficStats {
+    ws_bytes_received: AtomicU64,
+    ws_bytes_sent: AtomicU64,
+    ws_frames_received: AtomicU64,
+    ws_frames_sent: AtomicU64,
+    ws_packets_decoded: AtomicU64,
+    ws_messages: AtomicU64,
+    ws_connections: AtomicU64,
+    http_requests: AtomicU64,
+    http_bytes_sent: AtomicU64,
+    http_bytes_received: AtomicU64,
+}
+
+impl TrafficStats {
+    pub fn ws_received(&self, bytes: usize) {
+        self.ws_frames_received.fetch_add(1, Ordering::Relaxed);
+        self.ws_bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn ws_sent(&self, bytes: usize) {
+        self.ws_frames_sent.fetch_add(1, Ordering::Relaxed);
+        self.ws_bytes_sent
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// 一帧解出的包数（压缩包按解压出的内层包计）
+    pub fn ws_decoded(&self, packets: usize) {
+        self.ws_packets_decoded
+            .fetch_add(packets as u64, Ordering::Relaxed);
+    }
+
+    /// 一条业务消息（op_message）
+    pub fn ws_message(&self) {
+        self.ws_messages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 一次建立成功的连接
+    pub fn ws_connected(&self) {
+        self.ws_connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 一次 HTTP 请求，sent 为请求体字节数，received 为响应体字节数
+    pub fn http_exchange(&self, sent: usize, received: usize) {
+        self.http_requests.fetch_add(1, Ordering::Relaxed);
+        self.http_bytes_sent
+            .fetch_add(sent as u64, Ordering::Relaxed);
+        self.http_bytes_received
+            .fetch_add(received as u64, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> TrafficSnapshot {
+        TrafficSnapshot {
+            ws_bytes_received: self.ws_bytes_received.load(Ordering::Relaxed),
+            ws_bytes_sent: self.ws_bytes_sent.load(Ordering::Relaxed),
+            ws_frames_received: self.ws_frames_received.load(Ordering::Relaxed),
+            ws_frames_sent: self.ws_frames_sent.load(Ordering::Relaxed),
+            ws_packets_decoded: self.ws_packets_decoded.load(Ordering::Relaxed),
+            ws_messages: self.ws_messages.load(Ordering::Relaxed),
+            ws_connections: self.ws_connections.load(Ordering::Relaxed),
+            http_requests: self.http_requests.load(Ordering::Relaxed),
+            http_bytes_sent: self.http_bytes_sent.load(Ordering::Relaxed),
+            http_bytes_received: self.http_bytes_received.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TrafficSnapshot {
+    pub ws_bytes_received: u64,
+    pub ws_bytes_sent: u64,
+    pub ws_frames_received: u64,
+    pub ws_frames_sent: u64,
+    pub ws_packets_decoded: u64,
+    pub ws_messages: u64,
+    pub ws_connections: u64,
+    pub http_requests: u64,
+    pub http_bytes_sent: u64,
+    pub http_bytes_received: u64,
+}
+
+impl TrafficSnapshot {
+    /// ws_reconnects 为第一次之后建立的连接数
+    pub fn to_json(self) -> Value {
+        json!({
+            "ws_bytes_received": self.ws_bytes_received,
+            "ws_bytes_sent": self.ws_bytes_sent,
+            "ws_frames_received": self.ws_frames_received,
+            "ws_frames_sent": self.ws_frames_sent,
+            "ws_packets_decoded": self.ws_packets_decoded,
+            "ws_messages": self.ws_messages,
+            "ws_connections": self.ws_connections,
+            "ws_reconnects": self.ws_connections.saturating_sub(1),
+            "http_requests": self.http_requests,
+            "http_bytes_sent": self.http_bytes_sent,
+            "http_bytes_received": self.http_bytes_received,
+        })
+    }
+
+    /// 累计值加上自 previous 以来的平均速率（字节 / 秒，ws_message_rate 为消息数 / 秒）
+    pub fn report(self, previous: TrafficSnapshot, secs: f64) -> Value {
+        let rate = |now: u64, before: u64| {
+            if secs > 0.0 {
+                now.saturating_sub(before) as f64 / secs
+            } else {
+                0.0
+            }
+        };
+        let mut report = self.to_json();
+        report["interval"] = secs.into();
+        report["ws_receive_rate"] = rate(self.ws_bytes_received, previous.ws_bytes_received).into();
+        report["ws_send_rate"] = rate(self.ws_bytes_sent, previous.ws_bytes_sent).into();
+        report["ws_message_rate"] = rate(self.ws_messages, previous.ws_messages).into();
+        report["http_receive_rate"] =
+            rate(self.http_bytes_received, previous.http_bytes_received).into();
+        report["http_send_rate"] = rate(self.http_bytes_sent, previous.http_bytes_sent).into();
+        report
+    }
+}
+
+/// 累计计数在最近 `RATE_WINDOW_SECS` 秒内的平均速率，由主线程定期采样
+#[derive(Debug, Default)]
+pub struct RateWindow {
+    samples: VecDeque<(f64, u64)>,
+}
+
+impl RateWindow {
+    /// 记录 at 秒时的累计计数；距上次采样不足 RATE_SAMPLE_SECS 时忽略，
+    /// 窗口之外的样本只保留最近的一个作为窗口起点
+    pub fn sample(&mut self, at: f64, count: u64) {
+        if self
+            .samples
+            .back()
+            .is_some_and(|&(last, _)| at - last < RATE_SAMPLE_SECS)
+        {
+            return;
+        }
+        self.samples.push_back((at, count));
+        while self.samples.len() > 1 && at - self.samples[1].0 >= RATE_WINDOW_SECS {
+            self.samples.pop_front();
+        }
+    }
+
+    /// at 秒时累计为 count，相对窗口起点平均每秒的增量；还没有更早的样本时为 0
+    pub fn rate(&self, at: f64, count: u64) -> f64 {
+        match self.samples.front() {
+            Some(&(start, before)) if at > start => {
+                count.saturating_sub(before) as f64 / (at - start)
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_and_rates() {
+        let stats = TrafficStats::default();
+        stats.ws_received(100);
+        stats.ws_sent(31);
+        let previous = stats.snapshot();
+        stats.ws_received(500);
+        stats.ws_received(500);
+        stats.ws_decoded(3);
+        for _ in 0..20 {
+            stats.ws_message();
+        }
+        stats.ws_connected();
+        stats.ws_connected();
+        stats.http_exchange(40, 200);
+
+        let report = stats.snapshot().report(previous, 10.0);
+        assert_eq!(report["ws_bytes_received"], 1100);
+        assert_eq!(report["ws_frames_received"], 3);
+        assert_eq!(report["ws_frames_sent"], 1);
+        assert_eq!(report["http_requests"], 1);
+        assert_eq!(report["ws_receive_rate"], 100.0);
+        assert_eq!(report["ws_send_rate"], 0.0);
+        assert_eq!(report["http_receive_rate"], 20.0);
+        assert_eq!(report["ws_packets_decoded"], 3);
+        assert_eq!(report["ws_message_rate"], 2.0);
+        assert_eq!(report["ws_reconnects"], 1);
+    }
+
+    #[test]
+    fn rate_window_slides() {
+        let mut window = RateWindow::default();
+        assert_eq!(window.rate(0.0, 0), 0.0);
+        for tick in 0..=100 {
+            let at = tick as f64 * 0.1;
+            window.sample(at, tick * 3);
+        }
+        // 前 5 秒的样本已滑出窗口，剩下的起点在 5 秒附近
+        assert!((window.rate(10.0, 300) - 30.0).abs() < 1e-9);
+        assert!(window.samples.len() <= 12);
+        assert!(window.samples.front().unwrap().0 >= 10.0 - RATE_WINDOW_SECS - RATE_SAMPLE_SECS);
+
+        // 之后没有新消息，速率随窗口滑动降为 0
+        for tick in 101..=200 {
+            window.sample(tick as f64 * 0.1, 300);
+        }
+        assert_eq!(window.rate(20.0, 300), 0.0);
+    }
+}
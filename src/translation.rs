@@ -0,0 +1,281 @@
+use crate::language;
+use crate::rate_limit::TokenBucket;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+
+/// 等待译文的弹幕超过该秒数视为翻译失败
+pub const TRANSLATION_TIMEOUT_SECS: f64 = 10.0;
+
+/// 一条弹幕是否需要翻译
+#[derive(Debug, Clone, PartialEq)]
+pub enum Prepared {
+    /// 原文为空、无法判断语言或已是目标语言
+    Skip,
+    /// 超出限流
+    RateLimited,
+    Cached(String),
+    /// 需要发出翻译请求
+    Request {
+        request_id: i64,
+        text: String,
+        source: String,
+        target: String,
+    },
+}
+
+/// 弹幕翻译的缓存、限流和等待中的请求，由 Blive / BliveMock 在主线程驱动
+#[derive(Debug)]
+pub struct TranslationPipeline {
+    pub target: String,
+    cache: TranslationCache,
+    limiter: TokenBucket,
+    /// 请求 ID → (事件 data, 请求时间)
+    pending: HashMap<i64, (Value, f64)>,
+    next_request_id: i64,
+    rate_limited: i64,
+}
+
+impl TranslationPipeline {
+    pub fn new(target: &str, rate_per_sec: f64) -> Self {
+        Self {
+            target: target.to_string(),
+            cache: TranslationCache::new(1000),
+            limiter: TokenBucket::new(rate_per_sec, rate_per_sec * 2.0),
+            pending: HashMap::new(),
+            next_request_id: 1,
+            rate_limited: 0,
+        }
+    }
+
+    /// 判断弹幕事件 data 是否需要翻译，需要时记录为等待中的请求
+    pub fn prepare(&mut self, data: &Value, now: f64) -> Prepared {
+        let text = data["message"].as_str().unwrap_or_default();
+        let source = match data["lang"].as_str() {
+            Some(lang) => lang,
+            None => language::detect(text),
+        };
+        if text.is_empty() || source == "und" || source == self.target {
+            return Prepared::Skip;
+        }
+        if let Some(translated) = self.cache.get(text, &self.target) {
+            return Prepared::Cached(translated.clone());
+        }
+        if !self.limiter.try_take(now) {
+            self.rate_limited += 1;
+            return Prepared::RateLimited;
+        }
+        let request_id = self.next_request_id;
+        self.next_request_id += 1;
+        self.pending.insert(request_id, (data.clone(), now));
+        Prepared::Request {
+            request_id,
+            text: text.to_string(),
+            source: source.to_string(),
+            target: self.target.clone(),
+        }
+    }
+
+    /// 结束一个请求，返回原事件 data；成功时写入缓存。请求不存在（已超时）时返回 None
+    pub fn finish(&mut self, request_id: i64, result: &Result<String, String>) -> Option<Value> {
+        let (data, _) = self.pending.remove(&request_id)?;
+        if let Ok(translated) = result {
+            let text = data["message"].as_str().unwrap_or_default();
+            self.cache.insert(text, &self.target, translated);
+        }
+        Some(data)
+    }
+
+    /// 超时的请求 ID
+    pub fn expired(&self, now: f64) -> Vec<i64> {
+        self.pending
+            .iter()
+            .filter(|(_, (_, requested_at))| now - requested_at > TRANSLATION_TIMEOUT_SECS)
+            .map(|(request_id, _)| *request_id)
+            .collect()
+    }
+
+    /// 在事件 data 上附加 translated_message 和 translated_lang
+    pub fn translated(&self, mut data: Value, translated: &str) -> Value {
+        data["translated_message"] = translated.into();
+        data["translated_lang"] = self.target.clone().into();
+        data
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    /// cache_size、pending 和 rate_limited
+    pub fn stats(&self) -> Value {
+        json!({
+            "cache_size": self.cache.len(),
+            "pending": self.pending.len(),
+            "rate_limited": self.rate_limited,
+        })
+    }
+}
+
+/// 译文缓存，按 (原文, 目标语言) 索引，超出容量时丢弃最早写入的
+#[derive(Debug)]
+pub struct TranslationCache {
+    capacity: usize,
+    entries: HashMap<(String, String), String>,
+    order: VecDeque<(String, String)>,
+}
+
+impl TranslationCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub fn get(&self, text: &str, target: &str) -> Option<&String> {
+        self.entries.get(&(text.to_string(), target.to_string()))
+    }
+
+    pub fn insert(&mut self, text: &str, target: &str, translated: &str) {
+        let key = (text.to_string(), target.to_string());
+        if self
+            .entries
+            .insert(key.clone(), translated.to_string())
+            .is_some()
+        {
+            return;
+        }
+        self.order.push_back(key);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+/// 翻译接口的请求体：`{"text": ..., "source": ..., "target": ...}`
+pub fn request_body(text: &str, source: &str, target: &str) -> String {
+    json!({ "text": text, "source": source, "target": target }).to_string()
+}
+
+/// 从翻译接口的响应中取出译文，支持 `{"text": ...}` 和 `{"translation": ...}`
+pub fn parse_response(response: &str) -> Result<String, String> {
+    let json: Value = serde_json::from_str(response).map_err(|e| format!("响应解析失败: {}", e))?;
+    json["text"]
+        .as_str()
+        .or_else(|| json["translation"].as_str())
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| format!("响应中缺少译文: {}", response))
+}
+
+/// 向用户提供的翻译接口 POST 一条弹幕
+pub fn translate_http(
+    client: &reqwest::blocking::Client,
+    endpoint: &str,
+    text: &str,
+    source: &str,
+    target: &str,
+) -> Result<String, String> {
+    let response = client
+        .post(endpoint)
+        .header("Content-Type", "application/json")
+        .body(request_body(text, source, target))
+        .send()
+        .map_err(|e| format!("请求发送失败: {}", e))?;
+    let status = response.status();
+    let text = response
+        .text()
+        .map_err(|e| format!("响应读取失败: {}", e))?;
+    if !status.is_success() {
+        return Err(format!("翻译接口返回 HTTP {}: {}", status.as_u16(), text));
+    }
+    parse_response(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_evicts_oldest() {
+        let mut cache = TranslationCache::new(2);
+        cache.insert("你好", "en", "hello");
+        cache.insert("谢谢", "en", "thanks");
+        cache.insert("你好", "en", "hello");
+        cache.insert("再见", "en", "bye");
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("你好", "en"), None);
+        assert_eq!(cache.get("再见", "en").map(String::as_str), Some("bye"));
+        assert_eq!(cache.get("再见", "ja"), None);
+    }
+
+    #[test]
+    fn pipeline_caches_and_limits() {
+        let mut pipeline = TranslationPipeline::new("en", 1.0);
+        let danmaku = json!({ "message": "主播好厉害", "lang": "zh" });
+        assert_eq!(
+            pipeline.prepare(&json!({ "message": "nice", "lang": "en" }), 0.0),
+            Prepared::Skip
+        );
+        let Prepared::Request {
+            request_id, source, ..
+        } = pipeline.prepare(&danmaku, 0.0)
+        else {
+            panic!("expected a request");
+        };
+        assert_eq!(source, "zh");
+        assert_eq!(
+            pipeline.prepare(&json!({ "message": "好", "lang": "zh" }), 0.0),
+            Prepared::Request {
+                request_id: request_id + 1,
+                text: "好".to_string(),
+                source: "zh".to_string(),
+                target: "en".to_string(),
+            }
+        );
+        assert_eq!(
+            pipeline.prepare(&json!({ "message": "哈", "lang": "zh" }), 0.0),
+            Prepared::RateLimited
+        );
+
+        let result = Ok("so good".to_string());
+        assert_eq!(pipeline.finish(request_id, &result), Some(danmaku.clone()));
+        assert_eq!(pipeline.finish(request_id, &result), None);
+        assert_eq!(
+            pipeline.prepare(&danmaku, 0.0),
+            Prepared::Cached("so good".to_string())
+        );
+        assert_eq!(pipeline.expired(5.0), Vec::<i64>::new());
+        assert_eq!(pipeline.expired(11.0), vec![request_id + 1]);
+        assert_eq!(pipeline.stats()["rate_limited"], 1);
+    }
+
+    #[test]
+    fn parses_endpoint_response() {
+        assert_eq!(
+            parse_response(r#"{"text":"hello"}"#),
+            Ok("hello".to_string())
+        );
+        assert_eq!(
+            parse_response(r#"{"translation":"hello"}"#),
+            Ok("hello".to_string())
+        );
+        assert!(parse_response(r#"{"text":""}"#).is_err());
+        assert!(parse_response("oops").is_err());
+        assert_eq!(
+            serde_json::from_str::<Value>(&request_body("你好", "zh", "en")).unwrap(),
+            json!({ "text": "你好", "source": "zh", "target": "en" })
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// 事件触发条件，音效和场景映射共用
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TriggerRule {
+    pub event_type: String,
+    /// 只匹配该礼物名，空字符串表示任意礼物
+    pub gift_name: String,
+    /// 事件总价（千分之一元）不低于该值时才触发
+    pub min_price: i64,
+    /// 同一条规则两次触发的最短间隔（秒）
+    pub cooldown: f64,
+}
+
+impl TriggerRule {
+    pub fn matches(&self, event_type: &str, data: &Value) -> bool {
+        self.event_type == event_type
+            && (self.gift_name.is_empty() || data["gift_name"].as_str() == Some(&self.gift_name))
+            && data["price"].as_i64().unwrap_or(0) >= self.min_price
+    }
+}
+
+/// 选出要触发的规则下标：匹配且不在冷却中的规则里取 min_price 最高的一条，
+/// 相同时优先指定了礼物名的，再取靠前的
+pub fn select_trigger(
+    rules: &[TriggerRule],
+    event_type: &str,
+    data: &Value,
+    now: f64,
+    last_fired: &HashMap<usize, f64>,
+) -> Option<usize> {
+    rules
+        .iter()
+        .enumerate()
+        .filter(|(_, rule)| rule.matches(event_type, data))
+        .filter(|(index, rule)| {
+            last_fired
+                .get(index)
+                .is_none_or(|last| now - last >= rule.cooldown)
+        })
+        .min_by_key(|(index, rule)| (-rule.min_price, rule.gift_name.is_empty(), *index))
+        .map(|(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(event_type: &str, gift_name: &str, min_price: i64, cooldown: f64) -> TriggerRule {
+        TriggerRule {
+            event_type: event_type.to_string(),
+            gift_name: gift_name.to_string(),
+            min_price,
+            cooldown,
+        }
+    }
+
+    #[test]
+    fn picks_most_specific_rule_outside_cooldown() {
+        let rules = [
+            rule("gift", "", 0, 0.0),
+            rule("gift", "", 10000, 5.0),
+            rule("gift", "小电视", 0, 0.0),
+            rule("super_chat", "", 30000, 0.0),
+        ];
+        let mut last_fired = HashMap::new();
+        let big_gift = json!({ "gift_name": "辣条", "price": 52000 });
+        assert_eq!(
+            select_trigger(&rules, "gift", &big_gift, 0.0, &last_fired),
+            Some(1)
+        );
+
+        last_fired.insert(1, 0.0);
+        assert_eq!(
+            select_trigger(&rules, "gift", &big_gift, 2.0, &last_fired),
+            Some(0)
+        );
+        assert_eq!(
+            select_trigger(&rules, "gift", &big_gift, 5.0, &last_fired),
+            Some(1)
+        );
+
+        let tv = json!({ "gift_name": "小电视", "price": 0 });
+        assert_eq!(
+            select_trigger(&rules, "gift", &tv, 0.0, &last_fired),
+            Some(2)
+        );
+        let small_sc = json!({ "price": 5000 });
+        assert_eq!(
+            select_trigger(&rules, "super_chat", &small_sc, 0.0, &last_fired),
+            None
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use crate::events::{user_fields, with_user, EVENT_DANMAKU, EVENT_GIFT, EVENT_GUARD};
+use crate::source::LiveSource;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+const IRC_ADDR: &str = "irc.chat.twitch.tv:6667";
+
+/// Twitch 聊天室（IRC），以匿名身份只读接入
+///
+/// 弹幕对应 danmaku，带 bits 的弹幕对应 gift，订阅类 USERNOTICE 对应 guard。
+/// 金额单位与 B 站一致为千分之一（美元）：1 bit 记为 10。
+pub struct TwitchChat {
+    channel: String,
+}
+
+impl TwitchChat {
+    pub fn new(channel: &str) -> Self {
+        Self {
+            channel: channel.trim_start_matches('#').to_lowercase(),
+        }
+    }
+}
+
+impl LiveSource for TwitchChat {
+    fn platform(&self) -> &str {
+        "twitch"
+    }
+
+    fn run(
+        &mut self,
+        running: &AtomicBool,
+        emit: &mut dyn FnMut(&'static str, Value),
+    ) -> Result<(), String> {
+        let mut stream =
+            TcpStream::connect(IRC_ADDR).map_err(|e| format!("连接 Twitch 失败: {}", e))?;
+        // 读超时用于定期检查 running
+        stream
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .map_err(|e| format!("设置读超时失败: {}", e))?;
+        let nick = format!("justinfan{}", rand::random::<u32>() % 100000);
+        let login = format!(
+            "CAP REQ :twitch.tv/tags twitch.tv/commands\r\nPASS SCHMOOPIIE\r\nNICK {}\r\nJOIN #{}\r\n",
+            nick, self.channel
+        );
+        stream
+            .write_all(login.as_bytes())
+            .map_err(|e| format!("发送登录命令失败: {}", e))?;
+
+        let mut reader = BufReader::new(
+            stream
+                .try_clone()
+                .map_err(|e| format!("复制连接失败: {}", e))?,
+        );
+        let mut line = String::new();
+        while running.load(Ordering::SeqCst) {
+            match reader.read_line(&mut line) {
+                Ok(0) => return Err("Twitch 连接已关闭".to_string()),
+                Ok(_) => {
+                    let message = line.trim_end();
+                    if let Some(payload) = message.strip_prefix("PING") {
+                        stream
+                            .write_all(format!("PONG{}\r\n", payload).as_bytes())
+                            .map_err(|e| format!("发送 PONG 失败: {}", e))?;
+                    } else if let Some((event_type, data)) = parse_line(message) {
+                        emit(event_type, data);
+                    }
+                    line.clear();
+                }
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(e) => return Err(format!("读取 Twitch 消息失败: {}", e)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 解析一行带 tags 的 IRC 消息，不是互动事件时返回 None
+pub fn parse_line(line: &str) -> Option<(&'static str, Value)> {
+    let (tags, rest) = match line.strip_prefix('@') {
+        Some(tagged) => {
+            let (tags, rest) = tagged.split_once(' ')?;
+            (parse_tags(tags), rest)
+        }
+        None => (HashMap::new(), line),
+    };
+    let rest = rest.strip_prefix(':').unwrap_or(rest);
+    let mut parts = rest.splitn(3, ' ');
+    let prefix = parts.next()?;
+    let command = parts.next()?;
+    let text = parts
+        .next()
+        .and_then(|params| params.split_once(" :"))
+        .map(|(_, text)| text)
+        .unwrap_or_default();
+
+    let tag = |key: &str| tags.get(key).cloned().unwrap_or_default();
+    let tag_int = |key: &str| tag(key).parse::<i64>().unwrap_or(0);
+    let login = prefix.split('!').next().unwrap_or_default();
+    let uname = match tag("display-name") {
+        name if name.is_empty() => login.to_string(),
+        name => name,
+    };
+    let badges = tag("badges");
+    let subscriber = badges.split(',').any(|b| b.starts_with("subscriber/"));
+    let mut user = user_fields(
+        tag("user-id"),
+        uname,
+        String::new(),
+        0,
+        if subscriber { 3 } else { 0 },
+        tag_int("tmi-sent-ts") / 1000,
+    );
+    user["timestamp_ms"] = tag_int("tmi-sent-ts").into();
+
+    match command {
+        "PRIVMSG" => {
+            let bits = tag_int("bits");
+            if bits > 0 {
+                Some((
+                    EVENT_GIFT,
+                    with_user(
+                        user,
+                        json!({
+                            "gift_id": 0,
+                            "gift_name": "bits",
+                            "gift_num": bits,
+                            "price": bits * 10,
+                            "paid": true,
+                            "message": text,
+                        }),
+                    ),
+                ))
+            } else {
+                Some((EVENT_DANMAKU, with_user(user, json!({ "message": text }))))
+            }
+        }
+        "USERNOTICE" => {
+            if !matches!(
+                tag("msg-id").as_str(),
+                "sub" | "resub" | "subgift" | "submysterygift"
+            ) {
+                return None;
+            }
+            let mut user = user;
+            user["guard_level"] = 3.into();
+            let months = tag_int("msg-param-multimonth-duration").max(1);
+            let count = tag_int("msg-param-mass-gift-count").max(1);
+            Some((
+                EVENT_GUARD,
+                with_user(
+                    user,
+                    json!({
+                        "guard_num": months * count,
+                        "guard_unit": "月",
+                        "price": sub_price(&tag("msg-param-sub-plan")) * months * count,
+                        "message": text,
+                    }),
+                ),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// 各档订阅的月费（千分之一美元）
+fn sub_price(plan: &str) -> i64 {
+    match plan {
+        "2000" => 9990,
+        "3000" => 24990,
+        _ => 4990,
+    }
+}
+
+fn parse_tags(tags: &str) -> HashMap<String, String> {
+    tags.split(';')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (key.to_string(), unescape_tag(value)))
+        .collect()
+}
+
+fn unescape_tag(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some(':') => result.push(';'),
+            Some('r') => result.push('\r'),
+            Some('n') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => {}
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn privmsg_is_danmaku() {
+        let line = "@badges=subscriber/6;display-name=Viewer\\sOne;tmi-sent-ts=1700000000123;user-id=42 :viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #chan :hello there";
+        let (event_type, data) = parse_line(line).unwrap();
+        assert_eq!(event_type, EVENT_DANMAKU);
+        assert_eq!(data["message"], "hello there");
+        assert_eq!(data["uname"], "Viewer One");
+        assert_eq!(data["user_id"], "42");
+        assert_eq!(data["guard_level"], 3);
+        assert_eq!(data["timestamp"], 1700000000);
+    }
+
+    #[test]
+    fn bits_and_subs() {
+        let cheer =
+            "@bits=100;display-name=A;user-id=1 :a!a@a.tmi.twitch.tv PRIVMSG #chan :Cheer100";
+        let (event_type, data) = parse_line(cheer).unwrap();
+        assert_eq!(event_type, EVENT_GIFT);
+        assert_eq!(data["price"], 1000);
+
+        let sub = "@msg-id=resub;msg-param-sub-plan=2000;display-name=B;user-id=2 :tmi.twitch.tv USERNOTICE #chan :third month";
+        let (event_type, data) = parse_line(sub).unwrap();
+        assert_eq!(event_type, EVENT_GUARD);
+        assert_eq!(data["price"], 9990);
+        assert_eq!(data["guard_num"], 1);
+
+        assert!(parse_line(":tmi.twitch.tv 001 justinfan1 :Welcome").is_none());
+    }
+}
@@ -0,0 +1,164 @@
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::time::Duration;
+
+/// 两次重试之间的最长等待
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// 把选中的 `live_event` 以 JSON POST 到外部服务
+///
+/// 请求头带 `X-GDBlive-Delivery`（投递 ID，重试时不变，接收方可据此去重）、
+/// `X-GDBlive-Timestamp`（Unix 毫秒）和 `X-GDBlive-Signature`
+/// （`sha256=` + HMAC-SHA256(secret, "{timestamp}.{body}") 的十六进制）。
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    url: String,
+    secret: String,
+    /// 为空时投递所有事件
+    event_types: Vec<String>,
+    max_attempts: u32,
+    next_delivery: u64,
+}
+
+/// 一次投递，重试时原样重发
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delivery {
+    pub id: String,
+    pub event_type: String,
+    pub url: String,
+    pub body: String,
+    pub timestamp_ms: i64,
+    pub signature: String,
+    pub max_attempts: u32,
+}
+
+impl Webhook {
+    pub fn new(url: &str, secret: &str, event_types: Vec<String>, max_attempts: u32) -> Self {
+        Self {
+            url: url.to_string(),
+            secret: secret.to_string(),
+            event_types,
+            max_attempts: max_attempts.max(1),
+            next_delivery: 0,
+        }
+    }
+
+    pub fn accepts(&self, event_type: &str) -> bool {
+        self.event_types.is_empty() || self.event_types.iter().any(|t| t == event_type)
+    }
+
+    /// body 为 `{"delivery_id", "event_type", "data", "sent_ms"}`
+    pub fn delivery(&mut self, event_type: &str, data: &Value, now_ms: i64) -> Delivery {
+        self.next_delivery += 1;
+        let id = format!("{}-{}", now_ms, self.next_delivery);
+        let body = json!({
+            "delivery_id": id,
+            "event_type": event_type,
+            "data": data,
+            "sent_ms": now_ms,
+        })
+        .to_string();
+        Delivery {
+            signature: signature(&self.secret, now_ms, &body),
+            id,
+            event_type: event_type.to_string(),
+            url: self.url.clone(),
+            body,
+            timestamp_ms: now_ms,
+            max_attempts: self.max_attempts,
+        }
+    }
+}
+
+pub fn signature(secret: &str, timestamp_ms: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(format!("{}.{}", timestamp_ms, body).as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 网络错误（status 为 None）、429 和 5xx 值得重试，其余 4xx 重试也不会成功
+pub fn should_retry(status: Option<u16>) -> bool {
+    match status {
+        None => true,
+        Some(status) => status == 429 || status >= 500,
+    }
+}
+
+/// 第 attempt 次失败后的等待：1、2、4 … 秒，最长 30 秒
+pub fn retry_delay(attempt: u32) -> Duration {
+    Duration::from_secs(1u64 << attempt.saturating_sub(1).min(5)).min(MAX_RETRY_DELAY)
+}
+
+/// 发送一次，失败时返回 (是否值得重试, 错误信息)
+pub fn post(client: &reqwest::blocking::Client, delivery: &Delivery) -> Result<(), (bool, String)> {
+    let response = client
+        .post(&delivery.url)
+        .header("Content-Type", "application/json")
+        .header("X-GDBlive-Delivery", &delivery.id)
+        .header("X-GDBlive-Timestamp", delivery.timestamp_ms.to_string())
+        .header(
+            "X-GDBlive-Signature",
+            format!("sha256={}", delivery.signature),
+        )
+        .body(delivery.body.clone())
+        .send()
+        .map_err(|e| (should_retry(None), format!("请求发送失败: {}", e)))?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    Err((
+        should_retry(Some(status.as_u16())),
+        format!("接收方返回 HTTP {}", status.as_u16()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_selected_events() {
+        let mut webhook = Webhook::new(
+            "https://example.com/hook",
+            "secret",
+            vec!["gift".into(), "super_chat".into()],
+            0,
+        );
+        assert!(webhook.accepts("gift"));
+        assert!(!webhook.accepts("danmaku"));
+
+        let delivery = webhook.delivery("gift", &json!({"price": 1000}), 1_700_000_000_000);
+        let body: Value = serde_json::from_str(&delivery.body).unwrap();
+        assert_eq!(body["delivery_id"], delivery.id);
+        assert_eq!(body["data"]["price"], 1000);
+        assert_eq!(delivery.max_attempts, 1);
+        assert_eq!(
+            delivery.signature,
+            signature("secret", 1_700_000_000_000, &delivery.body)
+        );
+        assert_ne!(webhook.delivery("gift", &json!({}), 1).id, delivery.id);
+
+        assert_eq!(
+            signature("secret", 1_700_000_000_000, r#"{"a":1}"#),
+            "4ef2732b0d632a6897af3a6d02a6de287f60d6c5f15dabdb0ceb045a34e3c5a7"
+        );
+    }
+
+    #[test]
+    fn retries_with_backoff() {
+        assert!(should_retry(None));
+        assert!(should_retry(Some(503)));
+        assert!(should_retry(Some(429)));
+        assert!(!should_retry(Some(400)));
+        assert_eq!(retry_delay(1), Duration::from_secs(1));
+        assert_eq!(retry_delay(3), Duration::from_secs(4));
+        assert_eq!(retry_delay(20), MAX_RETRY_DELAY);
+    }
+}
@@ -0,0 +1,105 @@
+use futures_util::{Sink, SinkExt};
+use std::fmt::Display;
+use std::time::Duration;
+use tokio::sync::{watch, Mutex};
+use tokio::task::AbortHandle;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Message;
+
+/// 发送关闭帧最多等待的时间，超时后直接断开
+pub const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 主线程持有的停止端；调用 `stop` 或被丢弃时，连接任务都会停止
+#[derive(Debug)]
+pub struct Stopper(watch::Sender<bool>);
+
+/// 连接任务持有的接收端
+#[derive(Debug, Clone)]
+pub struct StopSignal(watch::Receiver<bool>);
+
+pub fn channel() -> (Stopper, StopSignal) {
+    let (tx, rx) = watch::channel(false);
+    (Stopper(tx), StopSignal(rx))
+}
+
+impl Stopper {
+    pub fn stop(&self) {
+        self.0.send_replace(true);
+    }
+}
+
+impl StopSignal {
+    /// 停止（或停止端被丢弃）后返回；已经停止时立即返回
+    pub async fn stopped(&mut self) {
+        let _ = self.0.wait_for(|stopped| *stopped).await;
+    }
+}
+
+/// 先中止心跳、发送队列等附属任务，再发送正常关闭帧；超过 `CLOSE_TIMEOUT` 时放弃等待
+pub async fn close<S>(write: &Mutex<S>, tasks: &[AbortHandle]) -> Result<(), String>
+where
+    S: Sink<Message> + Unpin,
+    S::Error: Display,
+{
+    for task in tasks {
+        task.abort();
+    }
+    let frame = CloseFrame {
+        code: CloseCode::Normal,
+        reason: "".into(),
+    };
+    let send = async {
+        write
+            .lock()
+            .await
+            .send(Message::Close(Some(frame)))
+            .await
+            .map_err(|e| format!("发送关闭帧失败: {}", e))
+    };
+    tokio::time::timeout(CLOSE_TIMEOUT, send)
+        .await
+        .unwrap_or_else(|_| Err("发送关闭帧超时".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+
+    #[tokio::test]
+    async fn stop_sends_close_frame_and_aborts_tasks() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+            ws.next().await
+        });
+        let (client, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr))
+            .await
+            .unwrap();
+        let (write, mut read) = client.split();
+        let write = Mutex::new(write);
+        let heartbeat = tokio::spawn(tokio::time::sleep(Duration::from_secs(30)));
+
+        // 服务器不发消息时读取循环也能立即退出
+        let (stopper, mut stop) = channel();
+        stopper.stop();
+        let stopped = tokio::select! {
+            _ = read.next() => false,
+            _ = stop.stopped() => true,
+        };
+        assert!(stopped);
+
+        close(&write, &[heartbeat.abort_handle()]).await.unwrap();
+        let received = server.await.unwrap().unwrap().unwrap();
+        assert!(matches!(received, Message::Close(Some(frame)) if frame.code == CloseCode::Normal));
+        assert!(heartbeat.await.unwrap_err().is_cancelled());
+
+        // 停止端被丢弃（节点释放）同样视为停止
+        let (stopper, mut stop) = channel();
+        drop(stopper);
+        stop.stopped().await;
+    }
+}
@@ -0,0 +1,251 @@
+use crate::events::{
+    int_of, str_of, user_fields, with_user, EVENT_DANMAKU, EVENT_GIFT, EVENT_GUARD,
+    EVENT_SUPER_CHAT,
+};
+use crate::source::LiveSource;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+const API_BASE: &str = "https://www.googleapis.com/youtube/v3";
+
+/// YouTube 直播聊天（Data API v3 轮询），需要 API Key 和直播视频 ID
+///
+/// 普通消息对应 danmaku，Super Chat 对应 super_chat，Super Sticker 对应 gift，
+/// 新会员和会员里程碑对应 guard。金额为 amountMicros / 1000，币种见 currency 字段。
+pub struct YouTubeChat {
+    api_key: String,
+    video_id: String,
+}
+
+impl YouTubeChat {
+    pub fn new(api_key: &str, video_id: &str) -> Self {
+        Self {
+            api_key: api_key.to_string(),
+            video_id: video_id.to_string(),
+        }
+    }
+
+    fn get_json(&self, client: &reqwest::blocking::Client, url: &str) -> Result<Value, String> {
+        let text = client
+            .get(url)
+            .send()
+            .map_err(|e| format!("请求发送失败: {}", e))?
+            .text()
+            .map_err(|e| format!("响应读取失败: {}", e))?;
+        let response: Value =
+            serde_json::from_str(&text).map_err(|e| format!("响应解析失败: {}", e))?;
+        match response["error"]["message"].as_str() {
+            Some(message) => Err(format!("YouTube API 错误: {}", message)),
+            None => Ok(response),
+        }
+    }
+}
+
+impl LiveSource for YouTubeChat {
+    fn platform(&self) -> &str {
+        "youtube"
+    }
+
+    fn run(
+        &mut self,
+        running: &AtomicBool,
+        emit: &mut dyn FnMut(&'static str, Value),
+    ) -> Result<(), String> {
+        let client = reqwest::blocking::Client::new();
+        let video = self.get_json(
+            &client,
+            &format!(
+                "{}/videos?part=liveStreamingDetails&id={}&key={}",
+                API_BASE, self.video_id, self.api_key
+            ),
+        )?;
+        let live_chat_id = video["items"][0]["liveStreamingDetails"]["activeLiveChatId"]
+            .as_str()
+            .ok_or_else(|| format!("视频 {} 没有进行中的直播聊天", self.video_id))?
+            .to_string();
+
+        let mut page_token = String::new();
+        // 第一页是连接前的历史消息，只用来取得 nextPageToken
+        let mut first_page = true;
+        while running.load(Ordering::SeqCst) {
+            let page = self.get_json(
+                &client,
+                &format!(
+                    "{}/liveChat/messages?liveChatId={}&part=snippet,authorDetails&key={}&pageToken={}",
+                    API_BASE, live_chat_id, self.api_key, page_token
+                ),
+            )?;
+            if page["offlineAt"].is_string() {
+                return Ok(());
+            }
+            if !first_page {
+                for item in page["items"].as_array().into_iter().flatten() {
+                    if let Some((event_type, data)) = parse_message(item) {
+                        emit(event_type, data);
+                    }
+                }
+            }
+            first_page = false;
+            page_token = str_of(&page["nextPageToken"]);
+
+            // 按服务器建议的间隔轮询，分段睡眠以便及时响应停止
+            let interval = int_of(&page["pollingIntervalMillis"]).max(1000) as u64;
+            let mut waited = 0;
+            while waited < interval && running.load(Ordering::SeqCst) {
+                std::thread::sleep(Duration::from_millis(100));
+                waited += 100;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 把一条 liveChatMessage 转换为统一事件，不关心的类型返回 None
+pub fn parse_message(item: &Value) -> Option<(&'static str, Value)> {
+    let snippet = &item["snippet"];
+    let author = &item["authorDetails"];
+    let sponsor = author["isChatSponsor"].as_bool().unwrap_or(false);
+    let user = user_fields(
+        str_of(&author["channelId"]),
+        str_of(&author["displayName"]),
+        str_of(&author["profileImageUrl"]),
+        0,
+        if sponsor { 3 } else { 0 },
+        parse_timestamp(snippet["publishedAt"].as_str().unwrap_or_default()).unwrap_or(0),
+    );
+    let amount = |details: &Value| {
+        details["amountMicros"]
+            .as_str()
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(0)
+            / 1000
+    };
+
+    let event = match snippet["type"].as_str()? {
+        "textMessageEvent" => (
+            EVENT_DANMAKU,
+            with_user(
+                user,
+                json!({ "message": str_of(&snippet["textMessageDetails"]["messageText"]) }),
+            ),
+        ),
+        "superChatEvent" => {
+            let details = &snippet["superChatDetails"];
+            (
+                EVENT_SUPER_CHAT,
+                with_user(
+                    user,
+                    json!({
+                        "message_id": 0,
+                        "message": str_of(&details["userComment"]),
+                        "price": amount(details),
+                        "currency": str_of(&details["currency"]),
+                        "duration": 0,
+                    }),
+                ),
+            )
+        }
+        "superStickerEvent" => {
+            let details = &snippet["superStickerDetails"];
+            (
+                EVENT_GIFT,
+                with_user(
+                    user,
+                    json!({
+                        "gift_id": 0,
+                        "gift_name": str_of(&details["superStickerMetadata"]["altText"]),
+                        "gift_num": 1,
+                        "price": amount(details),
+                        "currency": str_of(&details["currency"]),
+                        "paid": true,
+                    }),
+                ),
+            )
+        }
+        "newSponsorEvent" | "memberMilestoneChatEvent" => {
+            let months = int_of(&snippet["memberMilestoneChatDetails"]["memberMonth"]).max(1);
+            let mut user = user;
+            user["guard_level"] = 3.into();
+            (
+                EVENT_GUARD,
+                with_user(
+                    user,
+                    json!({ "guard_num": months, "guard_unit": "月", "price": 0 }),
+                ),
+            )
+        }
+        _ => return None,
+    };
+    Some(event)
+}
+
+/// 解析 RFC 3339 时间（如 `2024-05-01T12:00:00.123+08:00`）为 Unix 秒
+fn parse_timestamp(text: &str) -> Option<i64> {
+    let number = |range: std::ops::Range<usize>| text.get(range)?.parse::<i64>().ok();
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+
+    // 跳过小数秒后解析时区偏移
+    let zone = text[19..].trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    let offset = match zone.chars().next()? {
+        'Z' | 'z' => 0,
+        sign @ ('+' | '-') => {
+            let hours = zone.get(1..3)?.parse::<i64>().ok()?;
+            let minutes = zone.get(4..6)?.parse::<i64>().ok()?;
+            let offset = hours * 3600 + minutes * 60;
+            if sign == '+' {
+                offset
+            } else {
+                -offset
+            }
+        }
+        _ => return None,
+    };
+
+    // 公历日期到 1970-01-01 的天数
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    Some(days * 86400 + hour * 3600 + minute * 60 + second - offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_and_super_chat() {
+        let text = json!({
+            "snippet": {"type": "textMessageEvent", "publishedAt": "2023-11-14T22:13:20Z",
+                "textMessageDetails": {"messageText": "hi"}},
+            "authorDetails": {"channelId": "UC1", "displayName": "Alice", "isChatSponsor": true}
+        });
+        let (event_type, data) = parse_message(&text).unwrap();
+        assert_eq!(event_type, EVENT_DANMAKU);
+        assert_eq!(data["message"], "hi");
+        assert_eq!(data["user_id"], "UC1");
+        assert_eq!(data["guard_level"], 3);
+        assert_eq!(data["timestamp"], 1700000000);
+
+        let super_chat = json!({
+            "snippet": {"type": "superChatEvent", "publishedAt": "2023-11-15T06:13:20.5+08:00",
+                "superChatDetails": {"amountMicros": "5000000", "currency": "USD", "userComment": "gg"}},
+            "authorDetails": {"channelId": "UC2", "displayName": "Bob"}
+        });
+        let (event_type, data) = parse_message(&super_chat).unwrap();
+        assert_eq!(event_type, EVENT_SUPER_CHAT);
+        assert_eq!(data["price"], 5000);
+        assert_eq!(data["timestamp"], 1700000000);
+    }
+
+    #[test]
+    fn unknown_types_are_ignored() {
+        let item = json!({"snippet": {"type": "chatEndedEvent"}, "authorDetails": {}});
+        assert!(parse_message(&item).is_none());
+        assert_eq!(parse_timestamp("not a date"), None);
+    }
+}